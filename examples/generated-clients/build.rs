@@ -0,0 +1,47 @@
+use soapus_codegen::SoapClientGenerator;
+use std::path::{Path, PathBuf};
+
+/// The test WSDLs, each generated as an async and as a blocking client
+const WSDLS: &[&str] = &[
+    "attributes_test",
+    "calculator",
+    "countryinfo",
+    "faults",
+    "mtom",
+    "numberconversion",
+    "orders_multipart",
+    "reserved_params",
+    "session_headers",
+    "swa",
+    "ws_addressing",
+    "ws_security_policy",
+];
+
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let wsdl_dir = PathBuf::from(&manifest_dir).join("../../testdata/wsdl");
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    for name in WSDLS {
+        let wsdl_path = wsdl_dir.join(format!("{}.wsdl", name));
+        println!("cargo:rerun-if-changed={}", wsdl_path.display());
+
+        generate(&wsdl_path, &out_dir.join(name), false);
+        generate(
+            &wsdl_path,
+            &out_dir.join(format!("{}_blocking", name)),
+            true,
+        );
+    }
+}
+
+fn generate(wsdl_path: &Path, out_dir: &Path, blocking: bool) {
+    std::fs::create_dir_all(out_dir).expect("Failed to create output directory");
+    SoapClientGenerator::builder()
+        .wsdl_path(wsdl_path.to_str().expect("Invalid WSDL path"))
+        .out_dir(out_dir)
+        .unwrapped_methods(true)
+        .blocking(blocking)
+        .generate()
+        .unwrap_or_else(|e| panic!("Failed to generate {}: {}", wsdl_path.display(), e));
+}
@@ -0,0 +1,46 @@
+//! Clients generated from the test WSDLs in `testdata/wsdl`
+//!
+//! The build script generates an async and a blocking client with unwrapped
+//! methods for every test WSDL. Building this crate checks that the generated
+//! code compiles against `soapus-runtime`, which the string assertions of the
+//! generator tests cannot.
+
+// Not every generated item is used
+#![allow(dead_code, unused_imports)]
+
+macro_rules! generated {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub mod $name {
+                include!(concat!(env!("OUT_DIR"), "/", stringify!($name), "/soap_client.rs"));
+            }
+        )*
+    };
+}
+
+generated!(
+    attributes_test,
+    attributes_test_blocking,
+    calculator,
+    calculator_blocking,
+    countryinfo,
+    countryinfo_blocking,
+    faults,
+    faults_blocking,
+    mtom,
+    mtom_blocking,
+    numberconversion,
+    numberconversion_blocking,
+    orders_multipart,
+    orders_multipart_blocking,
+    reserved_params,
+    reserved_params_blocking,
+    session_headers,
+    session_headers_blocking,
+    swa,
+    swa_blocking,
+    ws_addressing,
+    ws_addressing_blocking,
+    ws_security_policy,
+    ws_security_policy_blocking,
+);
@@ -219,6 +219,9 @@ async fn root_handler() -> Html<&'static str> {
         <li><code>soap_request_duration_seconds</code> - Request duration (histogram)</li>
         <li><code>soap_response_size_bytes</code> - Response size (histogram)</li>
         <li><code>soap_errors_total</code> - Total errors (counter)</li>
+        <li><code>soap_retries_total</code> - Retried attempts (counter)</li>
+        <li><code>soap_rejected_requests_total</code> - Rejected calls (counter)</li>
+        <li><code>soap_circuit_breaker_state</code> - Circuit breaker state (gauge)</li>
     </ul>
     
     <h2>Quick Test</h2>
@@ -49,6 +49,18 @@ enum Commands {
         /// SOAP version (1.1 or 1.2)
         #[arg(short, long, value_name = "VERSION")]
         soap_version: Option<String>,
+
+        /// Also generate unwrapped methods for wrapped document/literal operations
+        #[arg(long)]
+        unwrapped: bool,
+
+        /// Generate blocking methods instead of async ones
+        #[arg(long)]
+        blocking: bool,
+
+        /// Mark an operation as safe to retry (can be repeated)
+        #[arg(long, value_name = "OPERATION")]
+        idempotent: Vec<String>,
     },
 
     /// Show information about a WSDL file
@@ -71,8 +83,19 @@ fn main() -> Result<()> {
             output,
             client_name,
             soap_version,
+            unwrapped,
+            blocking,
+            idempotent,
         } => {
-            generate_command(wsdl_path, output, client_name, soap_version)?;
+            generate_command(
+                wsdl_path,
+                output,
+                client_name,
+                soap_version,
+                unwrapped,
+                blocking,
+                idempotent,
+            )?;
         }
         Commands::Info { wsdl_path } => {
             info_command(wsdl_path)?;
@@ -124,6 +147,9 @@ fn generate_command(
     output: PathBuf,
     client_name: Option<String>,
     soap_version: Option<String>,
+    unwrapped: bool,
+    blocking: bool,
+    idempotent: Vec<String>,
 ) -> Result<()> {
     println!("🔨 Generating code from WSDL: {}", wsdl_path.display());
     println!("📂 Output directory: {}", output.display());
@@ -152,6 +178,21 @@ fn generate_command(
         println!("📌 SOAP version: {}", version);
     }
 
+    if unwrapped {
+        builder = builder.unwrapped_methods(true);
+        println!("📦 Generating unwrapped methods");
+    }
+
+    if blocking {
+        builder = builder.blocking(true);
+        println!("⏸️  Generating blocking methods");
+    }
+
+    for operation in idempotent {
+        println!("🔁 Idempotent operation: {}", operation);
+        builder = builder.idempotent_operation(operation);
+    }
+
     // Generate code
     builder.generate().context("Failed to generate code")?;
 
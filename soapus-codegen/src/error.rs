@@ -78,6 +78,10 @@ pub enum CodegenError {
     #[error("Binding '{binding_name}' not found")]
     BindingNotFound { binding_name: String },
 
+    /// Operation not found
+    #[error("Operation '{operation_name}' not found")]
+    OperationNotFound { operation_name: String },
+
     /// Port type not found
     #[error("Port type '{port_type_name}' not found")]
     PortTypeNotFound { port_type_name: String },
@@ -5,8 +5,8 @@
 pub mod rust_codegen;
 pub mod type_mapper;
 
-use crate::error::Result;
-use crate::parser::{WsdlModel, XmlSchema};
+use crate::error::{CodegenError, Result};
+use crate::parser::{SecurityPolicy, WsdlModel, XmlSchema};
 use crate::SoapClientGenerator;
 
 #[cfg(feature = "tracing")]
@@ -45,10 +45,15 @@ pub struct CodeGenerator<'a> {
 impl<'a> CodeGenerator<'a> {
     /// Create a new code generator
     pub fn new(wsdl: &'a WsdlModel, config: &'a SoapClientGenerator) -> Self {
+        let mut type_mapper = type_mapper::TypeMapper::new();
+        if rust_codegen::uses_mtom(wsdl) {
+            type_mapper.set_base64_binary_type("soapus_runtime::Binary");
+        }
+
         Self {
             wsdl,
             config,
-            type_mapper: type_mapper::TypeMapper::new(),
+            type_mapper,
         }
     }
 
@@ -72,6 +77,11 @@ impl<'a> CodeGenerator<'a> {
             output.push_str("\n\n");
         }
 
+        // Generate fault enums for operations declaring wsdl:fault
+        #[cfg(feature = "tracing")]
+        debug!("Generating fault enums");
+        output.push_str(&self.generate_fault_enums()?);
+
         // Generate client struct and implementation
         #[cfg(feature = "tracing")]
         debug!("Generating client struct");
@@ -165,6 +175,22 @@ pub const ELEMENT_FORM_QUALIFIED: bool = {};
         Ok(output)
     }
 
+    /// Generate one fault enum per operation that declares faults
+    fn generate_fault_enums(&self) -> Result<String> {
+        let mut output = String::new();
+
+        for operation in self.wsdl.operations() {
+            if let Some(fault_enum) =
+                rust_codegen::generate_fault_enum(operation, self.wsdl, &self.type_mapper)?
+            {
+                output.push_str(&fault_enum);
+                output.push_str("\n\n");
+            }
+        }
+
+        Ok(output)
+    }
+
     /// Generate the main client struct
     fn generate_client_struct(&self) -> Result<String> {
         let client_name = self
@@ -172,25 +198,155 @@ pub const ELEMENT_FORM_QUALIFIED: bool = {};
             .client_name()
             .unwrap_or_else(|| self.wsdl.service_name().unwrap_or("SoapServiceClient"));
 
+        let idempotent_operations = self.config.idempotent_operations();
+        for name in idempotent_operations {
+            if !self.wsdl.operations().any(|op| &op.name == name) {
+                return Err(CodegenError::OperationNotFound {
+                    operation_name: name.clone(),
+                });
+            }
+        }
+
+        let security_policy = self.security_policy()?;
+        let username_token = security_policy.is_some_and(|policy| policy.username_token);
+        let mut items = String::new();
+        let mut constructors = String::new();
+
+        // Operations marked as idempotent are registered on the SoapClient
+        if !idempotent_operations.is_empty() {
+            let names: Vec<String> = idempotent_operations
+                .iter()
+                .map(|name| format!("{:?}", name))
+                .collect();
+            items.push_str(&format!(
+                "/// Operations that are safe to retry\npub const IDEMPOTENT_OPERATIONS: &[&str] = &[{}];\n\n",
+                names.join(", ")
+            ));
+        }
+
+        // The WS-Security header required by the WS-SecurityPolicy of the WSDL
+        if let Some(policy) = security_policy {
+            items.push_str(&Self::generate_ws_security_fn(policy));
+        }
+
+        // Builder calls shared by all constructors, and those of `new`
+        let mut builder_calls = String::new();
+        let mut new_docs = String::new();
+        let mut new_calls = String::new();
+        if security_policy.is_some() && !username_token {
+            new_docs.push_str(
+                "\n    ///\n    /// Sends the WS-Security header returned by [`ws_security`].",
+            );
+            new_calls.push_str("\n                .ws_security(ws_security())");
+        }
+        if rust_codegen::uses_addressing(self.wsdl) {
+            new_docs.push_str(
+                "\n    ///\n    /// Sends the WS-Addressing headers required by the binding.",
+            );
+            builder_calls
+                .push_str("\n                .ws_addressing(soapus_runtime::WsAddressing::new())");
+        }
+        if rust_codegen::uses_mtom(self.wsdl) {
+            new_docs.push_str(
+                "\n    ///\n    /// Sends binary content as MTOM attachments, as required by the binding.",
+            );
+            builder_calls.push_str("\n                .mtom(true)");
+        }
+        new_calls.push_str(&builder_calls);
+
+        if !new_calls.is_empty() {
+            constructors.push_str(&format!(
+                r#"    /// Create a new SOAP client with the given endpoint URL{}
+    pub fn new(endpoint: impl Into<String>) -> Self {{
+        Self::with_client(
+            SoapClient::builder(endpoint){}
+                .build(),
+        )
+    }}"#,
+                new_docs, new_calls
+            ));
+        } else if !idempotent_operations.is_empty() {
+            constructors.push_str(
+                r#"    /// Create a new SOAP client with the given endpoint URL
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self::with_client(SoapClient::new(endpoint))
+    }"#,
+            );
+        } else {
+            constructors.push_str(
+                r#"    /// Create a new SOAP client with the given endpoint URL
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            client: SoapClient::new(endpoint),
+        }
+    }"#,
+            );
+        }
+
+        if username_token {
+            constructors.push_str(&format!(
+                r#"
+
+    /// Create a new SOAP client sending the credentials in a WS-Security header
+    ///
+    /// See [`ws_security`] for the header required by the service.
+    pub fn with_credentials(
+        endpoint: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {{
+        Self::with_client(
+            SoapClient::builder(endpoint)
+                .ws_security(ws_security(username, password)){}
+                .build(),
+        )
+    }}"#,
+                builder_calls
+            ));
+        }
+
+        if idempotent_operations.is_empty() {
+            constructors.push_str(
+                r#"
+
+    /// Create a new SOAP client with custom configuration
+    pub fn with_client(client: SoapClient) -> Self {
+        Self { client }
+    }"#,
+            );
+        } else {
+            constructors.push_str(
+                r#"
+
+    /// Create a new SOAP client with custom configuration
+    ///
+    /// Marks the [`IDEMPOTENT_OPERATIONS`] as idempotent on `client`.
+    pub fn with_client(mut client: SoapClient) -> Self {
+        for operation in IDEMPOTENT_OPERATIONS {
+            client.mark_idempotent(*operation);
+        }
+        Self { client }
+    }"#,
+            );
+        }
+
+        let client_doc = if self.config.blocking() {
+            format!(
+                "/// Blocking SOAP client for {}\n///\n/// Requires the `blocking` feature of `soapus-runtime`.",
+                client_name
+            )
+        } else {
+            format!("/// SOAP client for {}", client_name)
+        };
         let struct_def = format!(
-            r#"/// SOAP client for {}
+            r#"{}{}
 #[derive(Debug, Clone)]
 pub struct {} {{
     client: SoapClient,
 }}
 
 impl {} {{
-    /// Create a new SOAP client with the given endpoint URL
-    pub fn new(endpoint: impl Into<String>) -> Self {{
-        Self {{
-            client: SoapClient::new(endpoint),
-        }}
-    }}
-
-    /// Create a new SOAP client with custom configuration
-    pub fn with_client(client: SoapClient) -> Self {{
-        Self {{ client }}
-    }}
+{}
 
     /// Get a reference to the underlying SOAP client
     pub fn client(&self) -> &SoapClient {{
@@ -202,12 +358,70 @@ impl {} {{
         &mut self.client
     }}
 }}"#,
-            client_name, client_name, client_name
+            items, client_doc, client_name, client_name, constructors
         );
 
         Ok(struct_def)
     }
 
+    /// Get the WS-SecurityPolicy the generated client has to satisfy
+    ///
+    /// Only policies requiring a UsernameToken or a Timestamp need client support.
+    /// Without the `ws-security` feature such a policy is an error, rather than
+    /// a client that silently sends unsecured requests.
+    fn security_policy(&self) -> Result<Option<&'a SecurityPolicy>> {
+        let policy = self
+            .wsdl
+            .security_policy()
+            .filter(|policy| policy.username_token || policy.include_timestamp);
+        if policy.is_some() && !cfg!(feature = "ws-security") {
+            return Err(CodegenError::UnsupportedFeature {
+                feature: "WS-SecurityPolicy requiring a UsernameToken or Timestamp \
+                          (enable the `ws-security` feature of soapus-codegen)"
+                    .to_string(),
+            });
+        }
+        Ok(policy)
+    }
+
+    /// Generate the `ws_security` function building the required WS-Security header
+    fn generate_ws_security_fn(policy: &SecurityPolicy) -> String {
+        let mut doc = String::from(
+            "/// WS-Security header required by the WS-SecurityPolicy of the WSDL\n///\n",
+        );
+        let mut builder = String::from("    soapus_runtime::WsSecurity::new()");
+        let params = if policy.username_token {
+            let token = if policy.hash_password {
+                "digest"
+            } else {
+                "text"
+            };
+            builder.push_str(&format!(
+                "\n        .username_token(soapus_runtime::UsernameToken::{}(username, password))",
+                token
+            ));
+            "username: impl Into<String>, password: impl Into<String>"
+        } else {
+            ""
+        };
+        if policy.include_timestamp {
+            doc.push_str("/// The timestamp expires after [`WS_SECURITY_TTL`].\n");
+            builder.push_str("\n        .timestamp(WS_SECURITY_TTL)");
+        }
+
+        doc.push_str("/// Requires the `ws-security` feature of `soapus-runtime`.\n");
+
+        let mut output = String::new();
+        if policy.include_timestamp {
+            output.push_str("/// How long requests are valid after they are sent\npub const WS_SECURITY_TTL: std::time::Duration = std::time::Duration::from_secs(300);\n\n");
+        }
+        output.push_str(&format!(
+            "{}pub fn ws_security({}) -> soapus_runtime::WsSecurity {{\n{}\n}}\n\n",
+            doc, params, builder
+        ));
+        output
+    }
+
     /// Generate operation methods
     fn generate_operations(&self) -> Result<String> {
         let mut output = String::new();
@@ -223,8 +437,40 @@ impl {} {{
 
         // Generate operation methods
         for operation in self.wsdl.operations() {
-            let method_code =
-                rust_codegen::generate_operation_method(operation, self.wsdl, &self.type_mapper)?;
+            let unwrapped = if self.config.unwrapped_methods() {
+                rust_codegen::generate_unwrapped_method(
+                    operation,
+                    self.wsdl,
+                    &self.type_mapper,
+                    self.config.blocking(),
+                )?
+            } else {
+                None
+            };
+
+            let method_code = match unwrapped {
+                // The unwrapped method takes the operation name, the struct-based one gets a suffix
+                Some(unwrapped) => {
+                    let wrapped_name = format!("{}_wrapped", to_snake_case(&operation.name));
+                    let mut code = rust_codegen::generate_operation_method_named(
+                        operation,
+                        &wrapped_name,
+                        self.wsdl,
+                        &self.type_mapper,
+                        self.config.blocking(),
+                    )?;
+                    code.push('\n');
+                    code.push_str(&unwrapped);
+                    code
+                }
+                None => rust_codegen::generate_operation_method_named(
+                    operation,
+                    &to_snake_case(&operation.name),
+                    self.wsdl,
+                    &self.type_mapper,
+                    self.config.blocking(),
+                )?,
+            };
             // Replace service name placeholder
             let method_code = method_code.replace("{{service_name}}", service_name);
             output.push_str(&method_code);
@@ -293,10 +539,12 @@ pub fn sanitize_identifier(name: &str) -> String {
     match result.as_str() {
         "type" | "mod" | "fn" | "struct" | "enum" | "trait" | "impl" | "use" | "as" | "ref"
         | "mut" | "const" | "static" | "let" | "match" | "if" | "else" | "while" | "for"
-        | "loop" | "return" | "break" | "continue" | "async" | "await" | "move" | "self"
-        | "Self" | "super" | "crate" | "pub" | "in" | "where" => {
+        | "loop" | "return" | "break" | "continue" | "async" | "await" | "move" | "pub" | "in"
+        | "where" => {
             format!("r#{}", result)
         }
+        // These keywords cannot be raw identifiers
+        "self" | "Self" | "super" | "crate" => format!("{}_", result),
         _ => result,
     }
 }
@@ -356,6 +604,7 @@ mod tests {
         assert_eq!(sanitize_identifier("my_field"), "my_field");
         assert_eq!(sanitize_identifier("123field"), "_123field");
         assert_eq!(sanitize_identifier("type"), "r#type");
+        assert_eq!(sanitize_identifier("self"), "self_");
     }
 
     #[test]
@@ -1,9 +1,12 @@
 //! Rust code generation from WSDL/XSD models
 
-use crate::error::Result;
+use crate::error::{CodegenError, Result};
 use crate::generator::type_mapper::TypeMapper;
-use crate::generator::{to_pascal_case, to_snake_case};
-use crate::parser::{ComplexType, PortTypeOperation, SimpleType, WsdlModel};
+use crate::generator::{sanitize_identifier, to_pascal_case, to_snake_case};
+use crate::parser::{
+    BindingMessage, BindingOperation, ComplexType, MessagePart, PortTypeOperation, QName,
+    SimpleType, WsdlModel, XmlSchema,
+};
 
 /// Generate a Rust struct from XSD complexType
 pub fn generate_complex_type(
@@ -135,42 +138,644 @@ pub fn generate_simple_type_enum(name: &str, simple_type: &SimpleType) -> Result
     }
 }
 
-/// Generate a client method for a WSDL operation
+/// A message part as it appears in a generated operation method
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationPart {
+    /// XML element name of the part inside the SOAP body
+    pub xml_name: String,
+    /// WSDL part name
+    pub part_name: String,
+    /// Rust type of the part
+    pub rust_type: String,
+    /// Whether the part element is qualified with the target namespace
+    pub qualified: bool,
+    /// Whether the Rust type already serializes with `xml_name` as root element
+    /// (element with an inline complexType)
+    pub inline_element: bool,
+}
+
+/// Resolve the body parts of an operation's input or output message
+///
+/// Only the parts selected by `<soap:body parts="...">` are returned (all parts
+/// if the binding does not restrict them, except those bound to a `soap:header`
+/// or a `mime:content` attachment).
+/// Fails if the message or any part type cannot be resolved against the schema.
+pub fn resolve_message_parts(
+    message: Option<&QName>,
+    binding_message: Option<&BindingMessage>,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+) -> Result<Vec<OperationPart>> {
+    let Some(message_name) = message else {
+        return Ok(Vec::new());
+    };
+
+    let message = wsdl
+        .find_message(message_name)
+        .ok_or_else(|| CodegenError::MessageNotFound {
+            message_name: message_name.to_string(),
+        })?;
+
+    let selected = binding_message.and_then(|b| b.body_parts.as_ref());
+    if let Some(selected) = selected {
+        if let Some(unknown) = selected
+            .iter()
+            .find(|name| !message.parts.iter().any(|p| &p.name == *name))
+        {
+            return Err(CodegenError::InvalidWsdl(format!(
+                "soap:body references part '{}' which is not defined in message '{}'",
+                unknown, message.name
+            )));
+        }
+    }
+
+    // Parts of this message carried in a header are not part of the body
+    let header_parts: Vec<&str> = binding_message
+        .map(|b| b.headers.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|h| h.message.local_name() == message.name)
+        .map(|h| h.part.as_str())
+        .collect();
+    // Neither are MIME attachments
+    let attachment_parts: Vec<&str> = binding_message
+        .map(|b| b.attachments.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|a| a.part.as_str())
+        .collect();
+
+    let mut parts = Vec::new();
+
+    for part in &message.parts {
+        let is_selected = match selected {
+            Some(names) => names.contains(&part.name),
+            None => {
+                !header_parts.contains(&part.name.as_str())
+                    && !attachment_parts.contains(&part.name.as_str())
+            }
+        };
+        if is_selected {
+            parts.push(resolve_part(
+                part,
+                &message.name,
+                wsdl.schema(),
+                type_mapper,
+            )?);
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Resolve the header blocks of an operation's input or output
+///
+/// Each `<soap:header message="..." part="..."/>` is resolved to the referenced
+/// message part. Fails if the message or part does not exist.
+pub fn resolve_header_parts(
+    binding_message: Option<&BindingMessage>,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+) -> Result<Vec<OperationPart>> {
+    let Some(binding_message) = binding_message else {
+        return Ok(Vec::new());
+    };
+
+    let mut headers = Vec::new();
+    for header in &binding_message.headers {
+        let message =
+            wsdl.find_message(&header.message)
+                .ok_or_else(|| CodegenError::MessageNotFound {
+                    message_name: header.message.to_string(),
+                })?;
+
+        let part = message
+            .parts
+            .iter()
+            .find(|p| p.name == header.part)
+            .ok_or_else(|| {
+                CodegenError::InvalidWsdl(format!(
+                    "soap:header references part '{}' which is not defined in message '{}'",
+                    header.part, message.name
+                ))
+            })?;
+
+        headers.push(resolve_part(
+            part,
+            &message.name,
+            wsdl.schema(),
+            type_mapper,
+        )?);
+    }
+
+    Ok(headers)
+}
+
+/// Resolve a single message part to its XML name and Rust type
+fn resolve_part(
+    part: &MessagePart,
+    message_name: &str,
+    schema: Option<&XmlSchema>,
+    type_mapper: &TypeMapper,
+) -> Result<OperationPart> {
+    if let Some(element) = &part.element {
+        let inline_element = schema.is_some_and(|s| {
+            !s.elements.contains_key(element.local_name())
+                && s.complex_types.contains_key(element.local_name())
+        });
+        Ok(OperationPart {
+            xml_name: element.local_name().to_string(),
+            part_name: part.name.clone(),
+            rust_type: resolve_element_type(element, schema, type_mapper)?,
+            qualified: true,
+            inline_element,
+        })
+    } else if let Some(type_) = &part.type_ {
+        Ok(OperationPart {
+            xml_name: part.name.clone(),
+            part_name: part.name.clone(),
+            rust_type: resolve_type(type_, schema, type_mapper)?,
+            qualified: false,
+            inline_element: false,
+        })
+    } else {
+        Err(CodegenError::InvalidWsdl(format!(
+            "Part '{}' in message '{}' has neither 'element' nor 'type'",
+            part.name, message_name
+        )))
+    }
+}
+
+/// Resolve the Rust type of a top-level schema element
+fn resolve_element_type(
+    element: &QName,
+    schema: Option<&XmlSchema>,
+    type_mapper: &TypeMapper,
+) -> Result<String> {
+    let local_name = element.local_name();
+
+    if let Some(schema) = schema {
+        // Element referencing a named or built-in type
+        if let Some(schema_element) = schema.elements.get(local_name) {
+            if schema_element.type_.is_empty() {
+                // An element without type is xs:anyType
+                return Ok(type_mapper.map_type(&QName::new("anyType")));
+            }
+            return resolve_type(&schema_element.type_, Some(schema), type_mapper);
+        }
+
+        // Element with inline complexType (stored under the element name)
+        if schema.complex_types.contains_key(local_name) {
+            return Ok(to_pascal_case(local_name));
+        }
+    }
+
+    Err(CodegenError::TypeNotFound {
+        type_name: element.to_string(),
+    })
+}
+
+/// Resolve the Rust type for a named XSD type
+fn resolve_type(
+    type_: &QName,
+    schema: Option<&XmlSchema>,
+    type_mapper: &TypeMapper,
+) -> Result<String> {
+    if type_mapper.is_builtin_type(type_) {
+        return Ok(type_mapper.map_type(type_));
+    }
+
+    let local_name = type_.local_name();
+    if let Some(schema) = schema {
+        if schema.complex_types.contains_key(local_name) {
+            return Ok(type_mapper.map_type(type_));
+        }
+
+        if let Some(simple_type) = schema.simple_types.get(local_name) {
+            return match simple_type {
+                // Enumerations are generated as enums, other restrictions use their base type
+                SimpleType::Restriction { restrictions, .. }
+                    if restrictions
+                        .iter()
+                        .any(|r| matches!(r, crate::parser::Restriction::Enumeration(_))) =>
+                {
+                    Ok(type_mapper.map_type(type_))
+                }
+                SimpleType::Restriction { base, .. } => {
+                    resolve_type(base, Some(schema), type_mapper)
+                }
+                // Lists and unions are kept as their lexical representation
+                SimpleType::List { .. } | SimpleType::Union { .. } => Ok("String".to_string()),
+            };
+        }
+    }
+
+    Err(CodegenError::TypeNotFound {
+        type_name: type_.to_string(),
+    })
+}
+
+/// Rust type for a list of resolved parts
+///
+/// No parts map to `()`, a single part to its type and several parts to a tuple.
+fn parts_type(parts: &[OperationPart]) -> String {
+    match parts {
+        [] => "()".to_string(),
+        [part] => part.rust_type.clone(),
+        parts => format!(
+            "({})",
+            parts
+                .iter()
+                .map(|p| p.rust_type.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Resolved body parts and header blocks of an operation
+struct OperationParts {
+    input: Vec<OperationPart>,
+    output: Vec<OperationPart>,
+    input_headers: Vec<OperationPart>,
+    output_headers: Vec<OperationPart>,
+}
+
+/// Resolve the input and output parts of an operation
+fn resolve_operation_parts(
+    operation: &PortTypeOperation,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+) -> Result<OperationParts> {
+    let binding_operation = wsdl
+        .find_binding_operation(&operation.name)
+        .map(|(_, op)| op);
+    let binding_input = binding_operation.and_then(|op| op.input.as_ref());
+    let binding_output = binding_operation.and_then(|op| op.output.as_ref());
+
+    let input_context = || format!("Resolving input of operation '{}'", operation.name);
+    let output_context = || format!("Resolving output of operation '{}'", operation.name);
+
+    Ok(OperationParts {
+        input: resolve_message_parts(operation.input.as_ref(), binding_input, wsdl, type_mapper)
+            .map_err(|e| e.with_context(input_context()))?,
+        output: resolve_message_parts(operation.output.as_ref(), binding_output, wsdl, type_mapper)
+            .map_err(|e| e.with_context(output_context()))?,
+        input_headers: resolve_header_parts(binding_input, wsdl, type_mapper)
+            .map_err(|e| e.with_context(input_context()))?,
+        output_headers: resolve_header_parts(binding_output, wsdl, type_mapper)
+            .map_err(|e| e.with_context(output_context()))?,
+    })
+}
+
+/// Check for the wrapped document/literal pattern: a single element with
+/// inline complexType in and a single part out
+fn is_wrapped_operation(
+    operation: &PortTypeOperation,
+    wsdl: &WsdlModel,
+    input_parts: &[OperationPart],
+    output_parts: &[OperationPart],
+) -> bool {
+    wsdl.find_operation_style(&operation.name) != "rpc"
+        && input_parts.len() == 1
+        && input_parts[0].inline_element
+        && output_parts.len() == 1
+}
+
+/// Find the complexType describing a wrapper element
+///
+/// Inline complexTypes are stored under the element name, named types are
+/// looked up through the element's type reference.
+fn wrapper_complex_type<'a>(xml_name: &str, schema: &'a XmlSchema) -> Option<&'a ComplexType> {
+    match schema.elements.get(xml_name) {
+        Some(element) => schema.complex_types.get(element.type_.local_name()),
+        None => schema.complex_types.get(xml_name),
+    }
+}
+
+/// Fields of a wrapper complexType as `(field name, Rust type)`
+///
+/// Returns None for wrappers that cannot be flattened into plain parameters
+/// (choice, all or complexContent extensions).
+fn wrapper_fields(
+    complex_type: &ComplexType,
+    type_mapper: &TypeMapper,
+) -> Option<Vec<(String, String)>> {
+    if complex_type.choice.is_some()
+        || complex_type.all.is_some()
+        || complex_type.base_type.is_some()
+    {
+        return None;
+    }
+
+    // Same field order and naming as generate_complex_type
+    let mut fields = Vec::new();
+    for attr in &complex_type.attributes {
+        let rust_type = if attr.use_ == crate::parser::AttributeUse::Required {
+            type_mapper.map_type(&attr.type_)
+        } else {
+            format!("Option<{}>", type_mapper.map_type(&attr.type_))
+        };
+        fields.push((sanitize_identifier(&to_snake_case(&attr.name)), rust_type));
+    }
+    if let Some(seq) = &complex_type.sequence {
+        for elem in &seq.elements {
+            let rust_type = type_mapper.map_type_with_occurs(
+                &elem.type_,
+                Some(elem.min_occurs),
+                &elem.max_occurs,
+                elem.nillable,
+            );
+            fields.push((sanitize_identifier(&to_snake_case(&elem.name)), rust_type));
+        }
+    }
+
+    Some(fields)
+}
+
+/// Generate an unwrapped client method for a wrapped document/literal operation
+///
+/// The method takes the fields of the request wrapper as parameters and
+/// returns the single field of the response wrapper (`()` for an empty
+/// response, the response struct if it has several fields). It delegates to
+/// the struct-based method, which must be generated as `<name>_wrapped`.
+/// With `blocking`, the method is a plain `fn` like the struct-based one.
+///
+/// Returns None if the operation does not follow the wrapped pattern.
+pub fn generate_unwrapped_method(
+    operation: &PortTypeOperation,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+    blocking: bool,
+) -> Result<Option<String>> {
+    let parts = resolve_operation_parts(operation, wsdl, type_mapper)?;
+    if !is_wrapped_operation(operation, wsdl, &parts.input, &parts.output)
+        || !parts.input_headers.is_empty()
+        || !parts.output_headers.is_empty()
+        || wsdl
+            .find_binding_operation(&operation.name)
+            .is_some_and(|(_, op)| uses_swa(op))
+    {
+        return Ok(None);
+    }
+
+    let Some(schema) = wsdl.schema() else {
+        return Ok(None);
+    };
+    let input = &parts.input[0];
+    let output = &parts.output[0];
+
+    let Some(input_fields) = wrapper_complex_type(&input.xml_name, schema)
+        .and_then(|ct| wrapper_fields(ct, type_mapper))
+    else {
+        return Ok(None);
+    };
+    // An `options` field would clash with the options parameter of the `_with_options` method
+    if input_fields.iter().any(|(name, _)| name == "options") {
+        return Ok(None);
+    }
+
+    let output_fields = wrapper_complex_type(&output.xml_name, schema)
+        .and_then(|ct| wrapper_fields(ct, type_mapper));
+
+    let method_name = to_snake_case(&operation.name);
+    let mut code = String::new();
+
+    code.push_str(&format!(
+        "    /// Call the {} operation with unwrapped parameters\n",
+        operation.name
+    ));
+    if let Some(doc) = &operation.documentation {
+        code.push_str("    ///\n");
+        for line in doc.lines() {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                code.push_str(&format!("    /// {}\n", trimmed));
+            }
+        }
+    }
+    if !input_fields.is_empty() {
+        code.push_str("    ///\n    /// # Arguments\n");
+        for (name, rust_type) in &input_fields {
+            code.push_str(&format!(
+                "    /// * `{}` - The `{}.{}` field ({})\n",
+                name,
+                input.rust_type,
+                name.trim_start_matches("r#"),
+                rust_type
+            ));
+        }
+    }
+
+    let field_names: String = input_fields
+        .iter()
+        .map(|(name, _)| format!("{}, ", name))
+        .collect();
+    let (return_type, return_expr) = match output_fields.as_deref() {
+        Some([]) => ("()".to_string(), "Ok(())".to_string()),
+        Some([(name, rust_type)]) => (rust_type.clone(), format!("Ok(response.{})", name)),
+        _ => (output.rust_type.clone(), "Ok(response)".to_string()),
+    };
+
+    let signature_params: String = input_fields
+        .iter()
+        .map(|(name, rust_type)| format!(", {}: {}", name, rust_type))
+        .collect();
+    let (fn_keyword, await_suffix) = method_keywords(blocking);
+    code.push_str(&format!(
+        "    {} {}(&self{}) -> SoapResult<{}> {{\n",
+        fn_keyword, method_name, signature_params, return_type
+    ));
+    code.push_str(&format!(
+        "        self.{}_with_options({}&soapus_runtime::CallOptions::default()){}\n    }}\n\n",
+        method_name, field_names, await_suffix
+    ));
+
+    code.push_str(&format!(
+        "    /// Call the {} operation with unwrapped parameters and per-call options\n    ///\n    /// Like [`Self::{}`], with the timeout, endpoint, headers, correlation ID\n    /// and cancellation of `options` applied to this call only.\n",
+        operation.name, method_name
+    ));
+    code.push_str(&format!(
+        "    #[cfg_attr(feature = \"tracing\", tracing::instrument(skip(self, {}options)))]\n",
+        field_names
+    ));
+    code.push_str(&format!(
+        "    {} {}_with_options(&self{}, options: &soapus_runtime::CallOptions) -> SoapResult<{}> {{\n",
+        fn_keyword, method_name, signature_params, return_type
+    ));
+
+    let field_inits: Vec<&str> = input_fields.iter().map(|(name, _)| name.as_str()).collect();
+    let request = if field_inits.is_empty() {
+        format!("{} {{}}", input.rust_type)
+    } else {
+        format!("{} {{ {} }}", input.rust_type, field_inits.join(", "))
+    };
+    let binding = if return_expr == "Ok(())" {
+        "_response"
+    } else {
+        "response"
+    };
+    code.push_str(&format!(
+        "        let {} = self.{}_wrapped_with_options({}, options){}?.into_body();\n",
+        binding, method_name, request, await_suffix
+    ));
+    code.push_str(&format!("        {}\n", return_expr));
+    code.push_str("    }\n");
+
+    Ok(Some(code))
+}
+
+/// The keyword and the suffix awaiting a call of async or blocking client methods
+fn method_keywords(blocking: bool) -> (&'static str, &'static str) {
+    if blocking {
+        ("pub fn", "")
+    } else {
+        ("pub async fn", ".await")
+    }
+}
+
+/// Generate a `let <var> = vec![...];` statement serializing the given parameters
+fn body_parts_vec(var: &str, params: &[(String, &OperationPart)]) -> String {
+    let mut code = format!("        let {} = vec![\n", var);
+    for (name, part) in params {
+        let namespace = if part.qualified {
+            "Some(TARGET_NAMESPACE), ELEMENT_FORM_QUALIFIED"
+        } else {
+            "None, false"
+        };
+        code.push_str(&format!(
+            "            soapus_runtime::BodyPart::new(\"{}\", &{}, {})?,\n",
+            part.xml_name, name, namespace
+        ));
+    }
+    code.push_str("        ];\n");
+    code
+}
+
+/// Generate an async client method for a WSDL operation
 pub fn generate_operation_method(
     operation: &PortTypeOperation,
     wsdl: &WsdlModel,
-    _type_mapper: &TypeMapper,
+    type_mapper: &TypeMapper,
+) -> Result<String> {
+    generate_operation_method_named(
+        operation,
+        &to_snake_case(&operation.name),
+        wsdl,
+        type_mapper,
+        false,
+    )
+}
+
+/// Generate a client method for a WSDL operation with a custom method name
+///
+/// With `blocking`, the methods are plain `fn`s running the call with
+/// `soapus_runtime::blocking::block_on`.
+pub fn generate_operation_method_named(
+    operation: &PortTypeOperation,
+    method_name: &str,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+    blocking: bool,
 ) -> Result<String> {
     let mut output = String::new();
 
-    // Method name
-    let method_name = to_snake_case(&operation.name);
+    let binding_operation = wsdl
+        .find_binding_operation(&operation.name)
+        .map(|(_, op)| op);
+    let is_rpc = wsdl.find_operation_style(&operation.name) == "rpc";
+
+    // Resolve input and output message parts
+    let OperationParts {
+        input: input_parts,
+        output: output_parts,
+        input_headers,
+        output_headers,
+    } = resolve_operation_parts(operation, wsdl, type_mapper)?;
+    let has_headers = !input_headers.is_empty() || !output_headers.is_empty();
+
+    // SOAP with Attachments: attachments are passed alongside the body parts
+    let swa = binding_operation.is_some_and(uses_swa);
+    let binding_input = binding_operation.and_then(|op| op.input.as_ref());
+    let binding_output = binding_operation.and_then(|op| op.output.as_ref());
+    let sends_attachments = swa && binding_input.is_some_and(|b| b.multipart_related);
+    let receives_attachments = swa && binding_output.is_some_and(|b| b.multipart_related);
+    let input_attachments = binding_input
+        .map(|b| b.attachments.as_slice())
+        .unwrap_or_default();
+
+    // Response headers are optional, the service may omit them
+    let output_headers: Vec<OperationPart> = output_headers
+        .into_iter()
+        .map(|h| OperationPart {
+            rust_type: format!("Option<{}>", h.rust_type),
+            ..h
+        })
+        .collect();
+    let returned: Vec<OperationPart> = output_parts
+        .iter()
+        .chain(output_headers.iter())
+        .cloned()
+        .collect();
+    let output_type = if receives_attachments {
+        let types: Vec<&str> = returned
+            .iter()
+            .map(|p| p.rust_type.as_str())
+            .chain(std::iter::once("Vec<soapus_runtime::Attachment>"))
+            .collect();
+        match types.as_slice() {
+            [single] => single.to_string(),
+            types => format!("({})", types.join(", ")),
+        }
+    } else {
+        parts_type(&returned)
+    };
+
+    // Parameter names: a single part is always called `request`
+    let params: Vec<(String, &OperationPart)> = match input_parts.as_slice() {
+        [part] => vec![("request".to_string(), part)],
+        parts => parts
+            .iter()
+            .map(|p| (sanitize_identifier(&to_snake_case(&p.part_name)), p))
+            .collect(),
+    };
+
+    // Header parameters follow the body parameters
+    let mut header_params: Vec<(String, &OperationPart)> = Vec::new();
+    for header in &input_headers {
+        let mut name = sanitize_identifier(&to_snake_case(&header.part_name));
+        if params
+            .iter()
+            .chain(header_params.iter())
+            .any(|(existing, _)| *existing == name)
+        {
+            name = format!("{}_header", name.trim_start_matches("r#"));
+        }
+        header_params.push((name, header));
+    }
+
+    // The attachments parameter follows the header parameters
+    let attachments_param = sends_attachments.then(|| {
+        let taken = params
+            .iter()
+            .chain(header_params.iter())
+            .any(|(name, _)| name == "attachments");
+        if taken {
+            "mime_attachments"
+        } else {
+            "attachments"
+        }
+    });
 
-    // Find input and output message types
-    let input_msg = operation
-        .input
-        .as_ref()
-        .and_then(|qname| wsdl.find_message(qname));
-    let output_msg = operation
-        .output
-        .as_ref()
-        .and_then(|qname| wsdl.find_message(qname));
-
-    // For now, use generic types if we can't resolve
-    let input_type = input_msg
-        .and_then(|m| m.parts.first())
-        .and_then(|p| p.element.as_ref())
-        .map(|e| to_pascal_case(e.local_name()))
-        .unwrap_or_else(|| "()".to_string());
-
-    let output_type = output_msg
-        .and_then(|m| m.parts.first())
-        .and_then(|p| p.element.as_ref())
-        .map(|e| to_pascal_case(e.local_name()))
-        .unwrap_or_else(|| "()".to_string());
-
-    // Find SOAPAction from WSDL bindings
-    let soap_action = wsdl.find_soap_action(&operation.name);
+    // Find SOAPAction from WSDL bindings, with WS-Addressing the action of the input message
+    let soap_action = if uses_addressing(wsdl) {
+        wsdl.find_action(&operation.name)
+    } else {
+        wsdl.find_soap_action(&operation.name).map(str::to_string)
+    };
+    let soap_action_arg = soap_action
+        .map(|action| format!("Some(\"{}\")", action))
+        .unwrap_or_else(|| "None".to_string());
 
     // Generate method with better documentation
     output.push_str(&format!("    /// Call the {} operation\n", operation.name));
@@ -187,36 +792,223 @@ pub fn generate_operation_method(
         }
     }
 
-    // Add doc comment for parameters if we have type info
-    if input_type != "()" {
+    // Add doc comment for parameters
+    match params.as_slice() {
+        [] => {}
+        [(name, part)] => output.push_str(&format!(
+            "    ///\n    /// # Arguments\n    /// * `{}` - The {} request\n",
+            name, part.rust_type
+        )),
+        params => {
+            output.push_str("    ///\n    /// # Arguments\n");
+            for (name, part) in params {
+                output.push_str(&format!(
+                    "    /// * `{}` - The `{}` part ({})\n",
+                    name, part.part_name, part.rust_type
+                ));
+            }
+        }
+    }
+    if !header_params.is_empty() {
+        if params.is_empty() {
+            output.push_str("    ///\n    /// # Arguments\n");
+        }
+        for (name, header) in &header_params {
+            output.push_str(&format!(
+                "    /// * `{}` - The `{}` SOAP header ({})\n",
+                name, header.xml_name, header.rust_type
+            ));
+        }
+    }
+    if let Some(name) = attachments_param {
+        if params.is_empty() && header_params.is_empty() {
+            output.push_str("    ///\n    /// # Arguments\n");
+        }
+        if input_attachments.is_empty() {
+            output.push_str(&format!(
+                "    /// * `{}` - The MIME attachments, referenced by `cid:` URLs\n",
+                name
+            ));
+        } else {
+            let parts: Vec<String> = input_attachments
+                .iter()
+                .map(|a| match a.content_types.as_slice() {
+                    [] => format!("`{}`", a.part),
+                    types => format!("`{}` ({})", a.part, types.join(", ")),
+                })
+                .collect();
+            output.push_str(&format!(
+                "    /// * `{}` - The MIME attachments, created with `Attachment::for_part`: {}\n",
+                name,
+                parts.join(", ")
+            ));
+        }
+    }
+    if !operation.faults.is_empty() {
+        output.push_str(&format!(
+            "    ///\n    /// # Errors\n    /// Declared faults can be decoded with [`{}::from_soap_error`]\n",
+            fault_enum_name(operation, wsdl)
+        ));
+    }
+    if !output_headers.is_empty() {
+        let names: Vec<String> = output_headers
+            .iter()
+            .map(|h| format!("`{}`", h.xml_name))
+            .collect();
         output.push_str(&format!(
-            "    ///\n    /// # Arguments\n    /// * `request` - The {} request\n",
-            input_type
+            "    ///\n    /// # Returns\n    /// The response followed by the SOAP headers {} (None if not sent){}\n",
+            names.join(", "),
+            if receives_attachments {
+                " and the MIME attachments"
+            } else {
+                ""
+            }
         ));
+    } else if receives_attachments {
+        output.push_str(
+            "    ///\n    /// # Returns\n    /// The response followed by the MIME attachments of the response\n",
+        );
     }
 
-    // Add tracing instrument attribute for Send compatibility with async
-    output.push_str(
-        "    #[cfg_attr(feature = \"tracing\", tracing::instrument(skip(self, request)))]\n",
-    );
+    let arg_names: Vec<&str> = params
+        .iter()
+        .chain(header_params.iter())
+        .map(|(name, _)| name.as_str())
+        .chain(attachments_param)
+        .collect();
+    let mut signature_params: String = params
+        .iter()
+        .chain(header_params.iter())
+        .map(|(name, part)| format!(", {}: {}", name, part.rust_type))
+        .collect();
+    if let Some(name) = attachments_param {
+        signature_params.push_str(&format!(", {}: Vec<soapus_runtime::Attachment>", name));
+    }
+
+    // The plain method uses the client configuration as is and returns the body,
+    // `_with_options` takes per-call options and returns the full response
+    let args: String = arg_names.iter().map(|name| format!("{}, ", name)).collect();
+    let full_type = format!("soapus_runtime::SoapResponse<{}>", output_type);
+    let (fn_keyword, await_suffix) = method_keywords(blocking);
+    output.push_str(&format!(
+        "    {} {}(&self{}) -> SoapResult<{}> {{\n",
+        fn_keyword, method_name, signature_params, output_type
+    ));
+    output.push_str(&format!(
+        "        self.{}_with_options({}&soapus_runtime::CallOptions::default()){}\n            .map(soapus_runtime::SoapResponse::into_body)\n    }}\n\n",
+        method_name, args, await_suffix
+    ));
 
     output.push_str(&format!(
-        "    pub async fn {}(&self, request: {}) -> SoapResult<{}> {{\n",
-        method_name, input_type, output_type
+        "    /// Call the {} operation with per-call options, returning the full response\n    ///\n    /// Like [`Self::{}`], with the timeout, endpoint, headers, correlation ID\n    /// and cancellation of `options` applied to this call only. The response\n    /// holds the SOAP headers and HTTP details alongside the body.\n",
+        operation.name, method_name
     ));
 
-    // Use call_with_soap_action with namespace and optional SOAPAction
-    // Pass ELEMENT_FORM_QUALIFIED to control namespace handling for child elements
-    if let Some(action) = soap_action {
-        output.push_str(&format!(
-            "        self.client.call_with_soap_action(\"{}\", Some(\"{}\"), Some(TARGET_NAMESPACE), ELEMENT_FORM_QUALIFIED, &request).await\n",
-            operation.name, action
+    // Add tracing instrument attribute for Send compatibility with async
+    output.push_str(&format!(
+        "    #[cfg_attr(feature = \"tracing\", tracing::instrument(skip(self, {}options)))]\n",
+        args
+    ));
+    output.push_str(&format!(
+        "    {} {}_with_options(&self{}, options: &soapus_runtime::CallOptions) -> SoapResult<{}> {{\n",
+        fn_keyword, method_name, signature_params, full_type
+    ));
+
+    let mut body = String::new();
+
+    // Wrapped document/literal: a single element with inline complexType in and out
+    if !has_headers && !swa && is_wrapped_operation(operation, wsdl, &input_parts, &output_parts) {
+        // Use call_with_options with namespace and optional SOAPAction
+        // Pass ELEMENT_FORM_QUALIFIED to control namespace handling for child elements
+        body.push_str(&format!(
+            "        self.client.call_with_options(\"{}\", {}, Some(TARGET_NAMESPACE), ELEMENT_FORM_QUALIFIED, &request, options).await\n",
+            operation.name, soap_action_arg
         ));
     } else {
-        output.push_str(&format!(
-            "        self.client.call_with_soap_action(\"{}\", None, Some(TARGET_NAMESPACE), ELEMENT_FORM_QUALIFIED, &request).await\n",
-            operation.name
-        ));
+        // Bare, multi-part, RPC message or message with headers or attachments:
+        // serialize each part separately
+        body.push_str(&body_parts_vec("parts", &params));
+        if !header_params.is_empty() {
+            body.push_str(&body_parts_vec("headers", &header_params));
+        }
+
+        let unwrap = if is_rpc {
+            let rpc_namespace = binding_operation
+                .and_then(|op| op.input.as_ref())
+                .and_then(|b| b.namespace.as_deref())
+                .map(|ns| format!("\"{}\"", ns))
+                .unwrap_or_else(|| "TARGET_NAMESPACE".to_string());
+            body.push_str(&format!(
+                "        let parts = vec![soapus_runtime::BodyPart::rpc_wrapper(\"{}\", {}, &parts)];\n",
+                operation.name, rpc_namespace
+            ));
+            // Without output parts there is nothing to unwrap, the service may send an empty body
+            if output_parts.is_empty() {
+                ""
+            } else {
+                ".unwrap_rpc()?"
+            }
+        } else {
+            ""
+        };
+
+        let headers_arg = if header_params.is_empty() {
+            "&[]"
+        } else {
+            "&headers"
+        };
+        // Attachment parameters are sent with the other attachments of the options
+        if let Some(name) = attachments_param {
+            body.push_str(&format!(
+                "        let options = &options.clone().attachments({});\n",
+                name
+            ));
+        }
+        let call = format!(
+            "self.client.call_parts_with_options(\"{}\", {}, {}, &parts, options).await?{}",
+            operation.name, soap_action_arg, headers_arg, unwrap
+        );
+
+        let values: Vec<String> = output_parts
+            .iter()
+            .map(|p| format!("response.part(\"{}\")?", p.xml_name))
+            .chain(
+                output_headers
+                    .iter()
+                    .map(|h| format!("response.header(\"{}\")?", h.xml_name)),
+            )
+            .chain(receives_attachments.then(|| "response.take_attachments()".to_string()))
+            .collect();
+
+        // The header blocks and HTTP details of the response are kept around the body
+        let binding = if receives_attachments {
+            "let mut response"
+        } else {
+            "let response"
+        };
+        body.push_str(&format!("        {} = {};\n", binding, call));
+        match values.as_slice() {
+            [] => body.push_str("        Ok(response.into_response(()))\n"),
+            [value] => {
+                body.push_str(&format!("        let body = {};\n", value));
+                body.push_str("        Ok(response.into_response(body))\n");
+            }
+            values => {
+                body.push_str(&format!("        let body = ({});\n", values.join(", ")));
+                body.push_str("        Ok(response.into_response(body))\n");
+            }
+        }
+    }
+
+    if blocking {
+        // Blocking methods run the call on the shared runtime of `soapus_runtime::blocking`
+        output.push_str("        soapus_runtime::blocking::block_on(async {\n");
+        for line in body.lines() {
+            output.push_str(&format!("    {}\n", line));
+        }
+        output.push_str("        })\n");
+    } else {
+        output.push_str(&body);
     }
 
     output.push_str("    }\n");
@@ -224,6 +1016,129 @@ pub fn generate_operation_method(
     Ok(output)
 }
 
+/// Check whether the generated client sends WS-Addressing headers
+///
+/// Requires the `ws-addressing` feature; without it the WSDL's addressing
+/// requirement is ignored.
+pub fn uses_addressing(wsdl: &WsdlModel) -> bool {
+    cfg!(feature = "ws-addressing") && wsdl.uses_addressing()
+}
+
+/// Check whether the generated method of an operation exchanges MIME attachments
+///
+/// True for `mime:multipartRelated` inputs or outputs (SOAP with Attachments).
+/// Requires the `swa` feature; without it such operations only exchange the
+/// SOAP part of their messages.
+pub fn uses_swa(operation: &BindingOperation) -> bool {
+    cfg!(feature = "swa")
+        && [&operation.input, &operation.output]
+            .into_iter()
+            .flatten()
+            .any(|message| message.multipart_related)
+}
+
+/// Check whether the generated client sends binary content as MTOM attachments
+///
+/// Requires the `mtom` feature; without it `xs:base64Binary` stays `Vec<u8>`.
+pub fn uses_mtom(wsdl: &WsdlModel) -> bool {
+    cfg!(feature = "mtom") && wsdl.uses_mtom()
+}
+
+/// Name of the enum generated for the faults declared by an operation
+///
+/// `<Operation>Fault`, or `<Operation>OperationFault` if the schema already
+/// defines a type with that name.
+pub fn fault_enum_name(operation: &PortTypeOperation, wsdl: &WsdlModel) -> String {
+    let name = format!("{}Fault", to_pascal_case(&operation.name));
+    let collides = wsdl.schema().is_some_and(|schema| {
+        schema
+            .complex_types
+            .keys()
+            .chain(schema.simple_types.keys())
+            .any(|type_name| to_pascal_case(type_name) == name)
+    });
+
+    if collides {
+        format!("{}OperationFault", to_pascal_case(&operation.name))
+    } else {
+        name
+    }
+}
+
+/// Generate an enum of the faults declared for an operation
+///
+/// Each `<wsdl:fault>` becomes a variant holding the type of the fault
+/// message's part. The generated `from_soap_error` decodes the matching
+/// entry of a `SoapError::SoapFault` detail.
+///
+/// Returns None if the operation declares no faults with a part.
+pub fn generate_fault_enum(
+    operation: &PortTypeOperation,
+    wsdl: &WsdlModel,
+    type_mapper: &TypeMapper,
+) -> Result<Option<String>> {
+    let mut variants = Vec::new();
+    for fault in &operation.faults {
+        let parts =
+            resolve_message_parts(Some(&fault.message), None, wsdl, type_mapper).map_err(|e| {
+                e.with_context(format!(
+                    "Resolving fault '{}' of operation '{}'",
+                    fault.name, operation.name
+                ))
+            })?;
+        // A fault message has a single part describing the detail entry
+        if let Some(part) = parts.into_iter().next() {
+            variants.push((to_pascal_case(&fault.name), fault, part));
+        }
+    }
+
+    if variants.is_empty() {
+        return Ok(None);
+    }
+
+    let enum_name = fault_enum_name(operation, wsdl);
+    let mut code = String::new();
+
+    code.push_str(&format!(
+        "/// Faults declared for the {} operation\n",
+        operation.name
+    ));
+    code.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+    code.push_str(&format!("pub enum {} {{\n", enum_name));
+    for (variant, fault, part) in &variants {
+        code.push_str(&format!("    /// The `{}` fault\n", fault.name));
+        code.push_str(&format!("    {}({}),\n", variant, part.rust_type));
+    }
+    code.push_str("}\n\n");
+
+    code.push_str(&format!("impl {} {{\n", enum_name));
+    code.push_str("    /// Decode the fault detail of an error returned by the operation\n");
+    code.push_str("    ///\n");
+    code.push_str(
+        "    /// Returns `Ok(None)` if the error is not one of the declared faults and an\n",
+    );
+    code.push_str("    /// error if the detail of a declared fault cannot be deserialized.\n");
+    code.push_str(
+        "    pub fn from_soap_error(error: &soapus_runtime::SoapError) -> SoapResult<Option<Self>> {\n",
+    );
+    for (variant, _, part) in &variants {
+        code.push_str(&format!(
+            "        if let Some(detail) = error.fault_detail_entry(\"{}\")? {{\n",
+            part.xml_name
+        ));
+        code.push_str(&format!(
+            "            return Ok(Some(Self::{}(detail)));\n",
+            variant
+        ));
+        code.push_str("        }\n");
+    }
+    code.push_str("        Ok(None)\n");
+    code.push_str("    }\n");
+    code.push_str("}\n");
+
+    Ok(Some(code))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -367,9 +1282,7 @@ mod tests {
     #[test]
     fn test_generate_struct_with_attributes() {
         let complex_type = ComplexType {
-            sequence: Some(Sequence {
-                elements: vec![],
-            }),
+            sequence: Some(Sequence { elements: vec![] }),
             attributes: vec![
                 Attribute {
                     name: "key".to_string(),
@@ -425,22 +1338,386 @@ mod tests {
             name: "getAllVersions".to_string(),
             input: Some(QName::new("tns:getAllVersions")),
             output: Some(QName::new("tns:getAllVersionsResponse")),
+            input_action: None,
             faults: vec![],
             documentation: None,
         };
 
-        // Create a minimal WsdlModel - we don't need messages for this test
-        // since we're testing method signature generation
+        // An empty WsdlModel has no messages, so the types cannot be resolved
         let wsdl = crate::parser::WsdlModel::default();
         let type_mapper = TypeMapper::new();
 
-        let code = generate_operation_method(&operation, &wsdl, &type_mapper).unwrap();
+        let result = generate_operation_method(&operation, &wsdl, &type_mapper);
+
+        // Unresolved messages are a hard error instead of a `()` fallback
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("getAllVersions"));
+        assert!(err.contains("tns:getAllVersions"));
+    }
+
+    fn orders_wsdl() -> WsdlModel {
+        crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/orders_multipart.wsdl"))
+            .unwrap()
+    }
+
+    fn find_operation<'a>(wsdl: &'a WsdlModel, name: &str) -> &'a PortTypeOperation {
+        wsdl.operations().find(|op| op.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_generate_wrapped_operation_method() {
+        let wsdl =
+            crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/calculator.wsdl"))
+                .unwrap();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "Add"), &wsdl, &type_mapper).unwrap();
+
+        assert!(code.contains("pub async fn add(&self, request: Add) -> SoapResult<AddResponse>"));
+        assert!(code.contains(
+            "self.add_with_options(request, &soapus_runtime::CallOptions::default()).await\n            .map(soapus_runtime::SoapResponse::into_body)"
+        ));
+        assert!(code.contains("pub async fn add_with_options(&self, request: Add, options: &soapus_runtime::CallOptions) -> SoapResult<soapus_runtime::SoapResponse<AddResponse>>"));
+        assert!(code.contains("self.client.call_with_options(\"Add\""));
+        assert!(!code.contains("_full"));
+    }
+
+    #[test]
+    fn test_generate_unwrapped_method() {
+        let wsdl =
+            crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/calculator.wsdl"))
+                .unwrap();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_unwrapped_method(find_operation(&wsdl, "Add"), &wsdl, &type_mapper, false)
+                .unwrap()
+                .unwrap();
+
+        assert!(code.contains("pub async fn add(&self, int_a: i32, int_b: i32) -> SoapResult<i32>"));
+        assert!(code.contains(
+            "self.add_with_options(int_a, int_b, &soapus_runtime::CallOptions::default())"
+        ));
+        assert!(code.contains(
+            "self.add_wrapped_with_options(Add { int_a, int_b }, options).await?.into_body()"
+        ));
+        assert!(code.contains("Ok(response.add_result)"));
+        assert!(code.contains("skip(self, int_a, int_b, options)"));
+    }
+
+    #[test]
+    fn test_generate_operation_method_named() {
+        let wsdl =
+            crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/calculator.wsdl"))
+                .unwrap();
+        let type_mapper = TypeMapper::new();
+
+        let code = generate_operation_method_named(
+            find_operation(&wsdl, "Add"),
+            "add_wrapped",
+            &wsdl,
+            &type_mapper,
+            false,
+        )
+        .unwrap();
+
+        assert!(code
+            .contains("pub async fn add_wrapped(&self, request: Add) -> SoapResult<AddResponse>"));
+    }
+
+    #[test]
+    fn test_generate_blocking_methods() {
+        let wsdl =
+            crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/calculator.wsdl"))
+                .unwrap();
+        let type_mapper = TypeMapper::new();
+
+        let code = generate_operation_method_named(
+            find_operation(&wsdl, "Add"),
+            "add_wrapped",
+            &wsdl,
+            &type_mapper,
+            true,
+        )
+        .unwrap();
+        assert!(!code.contains("async fn"));
+        assert!(code.contains("pub fn add_wrapped(&self, request: Add) -> SoapResult<AddResponse>"));
+        assert!(code.contains(
+            "self.add_wrapped_with_options(request, &soapus_runtime::CallOptions::default())\n            .map(soapus_runtime::SoapResponse::into_body)"
+        ));
+        assert!(code.contains("        soapus_runtime::blocking::block_on(async {\n            self.client.call_with_options(\"Add\""));
+        assert!(code.contains(".await\n        })\n    }\n"));
+
+        let code =
+            generate_unwrapped_method(find_operation(&wsdl, "Add"), &wsdl, &type_mapper, true)
+                .unwrap()
+                .unwrap();
+        assert!(code.contains("pub fn add(&self, int_a: i32, int_b: i32) -> SoapResult<i32>"));
+        assert!(code.contains(
+            "self.add_wrapped_with_options(Add { int_a, int_b }, options)?.into_body();"
+        ));
+        assert!(!code.contains(".await"));
+
+        // Messages serialized part by part are run as a whole
+        let wsdl = orders_wsdl();
+        let code = generate_operation_method_named(
+            find_operation(&wsdl, "PlaceOrder"),
+            "place_order",
+            &wsdl,
+            &type_mapper,
+            true,
+        )
+        .unwrap();
+        assert!(code.contains("block_on(async {\n            let parts = vec![\n"));
+        assert!(code.contains("            Ok(response.into_response(body))\n        })\n"));
+    }
+
+    #[test]
+    fn test_generate_unwrapped_method_skips_non_wrapped_operations() {
+        let wsdl = orders_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        for name in ["PlaceOrder", "GetStatus", "CancelOrder"] {
+            let code =
+                generate_unwrapped_method(find_operation(&wsdl, name), &wsdl, &type_mapper, false)
+                    .unwrap();
+            assert!(code.is_none(), "{} is not a wrapped operation", name);
+        }
+    }
+
+    #[test]
+    fn test_generate_multi_part_bare_operation_method() {
+        let wsdl = orders_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "PlaceOrder"), &wsdl, &type_mapper)
+                .unwrap();
+
+        // Bare element parts use the type of the referenced element, not its name
+        assert!(code.contains(
+            "pub async fn place_order(&self, body: OrderRequest, audit: AuditInfo) -> SoapResult<(OrderConfirmation, String)>"
+        ));
+        assert!(code.contains(
+            "BodyPart::new(\"PlaceOrderRequest\", &body, Some(TARGET_NAMESPACE), ELEMENT_FORM_QUALIFIED)?"
+        ));
+        assert!(code.contains("BodyPart::new(\"AuditInfo\", &audit,"));
+        assert!(code.contains(
+            "let body = (response.part(\"OrderReceipt\")?, response.part(\"TraceId\")?);"
+        ));
+        assert!(code.contains("skip(self, body, audit, options)"));
+    }
+
+    #[test]
+    fn test_generate_rpc_operation_method() {
+        let wsdl = orders_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "GetStatus"), &wsdl, &type_mapper)
+                .unwrap();
+
+        // Type parts are named after the part and left unqualified
+        assert!(code.contains(
+            "pub async fn get_status(&self, order_id: String, verbose: bool) -> SoapResult<String>"
+        ));
+        assert!(code.contains("BodyPart::new(\"orderId\", &order_id, None, false)?"));
+        assert!(code
+            .contains("BodyPart::rpc_wrapper(\"GetStatus\", \"urn:example:orders:rpc\", &parts)"));
+        assert!(code.contains(".unwrap_rpc()?"));
+        assert!(code.contains("response.part(\"status\")"));
+
+        // Without output parts the response is not unwrapped, its body may be empty
+        let code =
+            generate_operation_method(find_operation(&wsdl, "ArchiveOrder"), &wsdl, &type_mapper)
+                .unwrap();
+        assert!(code.contains(
+            "pub async fn archive_order(&self, order_id: String, verbose: bool) -> SoapResult<()>"
+        ));
+        assert!(code.contains("BodyPart::rpc_wrapper(\"ArchiveOrder\""));
+        assert!(!code.contains(".unwrap_rpc()"));
+        assert!(code.contains("Ok(response.into_response(()))"));
+    }
+
+    #[test]
+    fn test_generate_operation_method_honours_body_parts() {
+        let wsdl = orders_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "CancelOrder"), &wsdl, &type_mapper)
+                .unwrap();
+
+        // Only the `body` part is selected by <soap:body parts="body"/>
+        assert!(code
+            .contains("pub async fn cancel_order(&self, request: OrderRequest) -> SoapResult<()>"));
+        assert!(!code.contains("AuditInfo"));
+        assert!(code.contains("Ok(response.into_response(()))"));
+    }
+
+    fn bank_wsdl() -> WsdlModel {
+        crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/session_headers.wsdl"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_generate_operation_method_with_headers() {
+        let wsdl = bank_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "GetBalance"), &wsdl, &type_mapper)
+                .unwrap();
+
+        // Input headers become parameters, output headers are returned as Option
+        assert!(code.contains(
+            "pub async fn get_balance(&self, request: GetBalance, auth: AuthHeader) -> SoapResult<(GetBalanceResponse, Option<SessionInfo>)>"
+        ));
+        assert!(code.contains("let headers = vec!["));
+        assert!(code.contains("BodyPart::new(\"AuthHeader\", &auth,"));
+        assert!(code.contains("call_parts_with_options(\"GetBalance\", Some(\"urn:example:bank/GetBalance\"), &headers, &parts, options)"));
+        assert!(code.contains(
+            "let body = (response.part(\"GetBalanceResponse\")?, response.header(\"SessionInfo\")?);"
+        ));
+        assert!(code.contains("Ok(response.into_response(body))"));
+    }
+
+    #[test]
+    fn test_generate_operation_method_with_output_header_only() {
+        let wsdl = bank_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code =
+            generate_operation_method(find_operation(&wsdl, "Login"), &wsdl, &type_mapper).unwrap();
+
+        assert!(code.contains("SoapResult<(LoginResponse, Option<SessionInfo>)>"));
+        assert!(!code.contains("let headers"));
+        assert!(code.contains("&[], &parts, options)"));
+    }
+
+    #[test]
+    fn test_header_part_is_excluded_from_body() {
+        let wsdl = bank_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code = generate_operation_method(find_operation(&wsdl, "Logout"), &wsdl, &type_mapper)
+            .unwrap();
+
+        // The auth part of LogoutIn is bound to soap:header, not to the body
+        assert!(code.contains(
+            "pub async fn logout(&self, request: Logout, auth: AuthHeader) -> SoapResult<()>"
+        ));
+        let parts_vec = code
+            .split("let parts")
+            .nth(1)
+            .and_then(|s| s.split("let headers").next())
+            .unwrap();
+        assert!(!parts_vec.contains("AuthHeader"));
+
+        // Operations with headers are not unwrapped
+        let unwrapped =
+            generate_unwrapped_method(find_operation(&wsdl, "Logout"), &wsdl, &type_mapper, false)
+                .unwrap();
+        assert!(unwrapped.is_none());
+    }
+
+    #[test]
+    fn test_resolve_header_parts_unknown_part() {
+        let wsdl = bank_wsdl();
+        let binding_message = BindingMessage {
+            headers: vec![crate::parser::BindingHeader {
+                message: QName::new("tns:AuthHeaderMsg"),
+                part: "missing".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let result = resolve_header_parts(Some(&binding_message), &wsdl, &TypeMapper::new());
+
+        assert!(matches!(result, Err(CodegenError::InvalidWsdl(msg)) if msg.contains("missing")));
+    }
+
+    #[test]
+    fn test_resolve_message_parts_unknown_element() {
+        let wsdl = crate::parser::parse_wsdl(
+            r#"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/" xmlns:tns="urn:test">
+  <types>
+    <schema xmlns="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:test"/>
+  </types>
+  <message name="In">
+    <part name="parameters" element="tns:Missing"/>
+  </message>
+</definitions>"#,
+        )
+        .unwrap();
+
+        let result =
+            resolve_message_parts(Some(&QName::new("tns:In")), None, &wsdl, &TypeMapper::new());
+
+        assert!(matches!(
+            result,
+            Err(CodegenError::TypeNotFound { type_name }) if type_name == "tns:Missing"
+        ));
+    }
+
+    fn accounts_wsdl() -> WsdlModel {
+        crate::parser::parse_wsdl(include_str!("../../../testdata/wsdl/faults.wsdl")).unwrap()
+    }
+
+    #[test]
+    fn test_generate_fault_enum() {
+        let wsdl = accounts_wsdl();
+        let type_mapper = TypeMapper::new();
+
+        let code = generate_fault_enum(find_operation(&wsdl, "Withdraw"), &wsdl, &type_mapper)
+            .unwrap()
+            .unwrap();
+
+        assert!(code.contains("pub enum WithdrawFault {"));
+        assert!(code.contains("    InsufficientFunds(InsufficientFunds),"));
+        assert!(code.contains("    AccountLocked(AccountLocked),"));
+        assert!(code.contains(
+            "pub fn from_soap_error(error: &soapus_runtime::SoapError) -> SoapResult<Option<Self>>"
+        ));
+        // Detail entries that do not deserialize are reported, not skipped
+        assert!(
+            code.contains("if let Some(detail) = error.fault_detail_entry(\"AccountLocked\")? {")
+        );
+        assert!(code.contains("return Ok(Some(Self::AccountLocked(detail)));"));
+
+        let method =
+            generate_operation_method(find_operation(&wsdl, "Withdraw"), &wsdl, &type_mapper)
+                .unwrap();
+        assert!(method.contains("[`WithdrawFault::from_soap_error`]"));
+    }
+
+    #[test]
+    fn test_fault_enum_name_avoids_schema_types() {
+        let wsdl = accounts_wsdl();
+
+        // The schema defines an AuditFault element
+        assert_eq!(
+            fault_enum_name(find_operation(&wsdl, "Audit"), &wsdl),
+            "AuditOperationFault"
+        );
+        assert_eq!(
+            fault_enum_name(find_operation(&wsdl, "Withdraw"), &wsdl),
+            "WithdrawFault"
+        );
+    }
+
+    #[test]
+    fn test_no_fault_enum_without_faults() {
+        let wsdl = accounts_wsdl();
+        let type_mapper = TypeMapper::new();
 
-        assert!(code.contains("pub async fn get_all_versions"));
-        // When messages aren't found, it falls back to type names from QName
-        assert!(code.contains("GetAllVersions") || code.contains("()"));
-        assert!(code.contains("GetAllVersionsResponse") || code.contains("()"));
-        assert!(code.contains("SoapResult"));
-        assert!(code.contains("/// Call the getAllVersions operation"));
+        let operation = find_operation(&wsdl, "GetAccount");
+        assert!(generate_fault_enum(operation, &wsdl, &type_mapper)
+            .unwrap()
+            .is_none());
+        assert!(!generate_operation_method(operation, &wsdl, &type_mapper)
+            .unwrap()
+            .contains("# Errors"));
     }
 }
@@ -7,6 +7,8 @@ use std::collections::HashMap;
 pub struct TypeMapper {
     /// Custom type mappings (QName -> Rust type)
     custom_mappings: HashMap<String, String>,
+    /// Rust type of `xs:base64Binary`
+    base64_binary_type: String,
 }
 
 impl TypeMapper {
@@ -14,6 +16,7 @@ impl TypeMapper {
     pub fn new() -> Self {
         Self {
             custom_mappings: HashMap::new(),
+            base64_binary_type: "Vec<u8>".to_string(),
         }
     }
 
@@ -23,6 +26,11 @@ impl TypeMapper {
             .insert(xsd_type.into(), rust_type.into());
     }
 
+    /// Set the Rust type of `xs:base64Binary`, e.g. `soapus_runtime::Binary` for MTOM
+    pub fn set_base64_binary_type(&mut self, rust_type: impl Into<String>) {
+        self.base64_binary_type = rust_type.into();
+    }
+
     /// Map an XSD type to a Rust type
     pub fn map_type(&self, qname: &QName) -> String {
         // Check custom mappings first
@@ -76,9 +84,12 @@ impl TypeMapper {
             "duration" => "String".to_string(), // Could use chrono::Duration
 
             // Binary types
-            "base64Binary" => "Vec<u8>".to_string(),
+            "base64Binary" => self.base64_binary_type.clone(),
             "hexBinary" => "Vec<u8>".to_string(),
 
+            // WS-I Attachments Profile: `cid:` URL of a MIME attachment
+            "swaRef" => "String".to_string(),
+
             // Other types
             "anyType" => "String".to_string(), // Generic fallback
             "anySimpleType" => "String".to_string(),
@@ -207,6 +218,7 @@ impl TypeMapper {
                 | "duration"
                 | "base64Binary"
                 | "hexBinary"
+                | "swaRef"
                 | "anyType"
                 | "anySimpleType"
         )
@@ -254,6 +266,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_base64_binary_type() {
+        let mut mapper = TypeMapper::new();
+        assert_eq!(mapper.map_type(&QName::new("xs:base64Binary")), "Vec<u8>");
+
+        mapper.set_base64_binary_type("soapus_runtime::Binary");
+        assert_eq!(
+            mapper.map_type(&QName::new("xsd:base64Binary")),
+            "soapus_runtime::Binary"
+        );
+        assert_eq!(mapper.map_type(&QName::new("xs:hexBinary")), "Vec<u8>");
+    }
+
     #[test]
     fn test_is_optional() {
         let mapper = TypeMapper::new();
@@ -345,6 +370,7 @@ mod tests {
         assert!(mapper.is_builtin_type(&QName::new("xs:string")));
         assert!(mapper.is_builtin_type(&QName::new("xsd:int")));
         assert!(mapper.is_builtin_type(&QName::new("boolean")));
+        assert!(mapper.is_builtin_type(&QName::new("ref:swaRef")));
         assert!(!mapper.is_builtin_type(&QName::new("MyCustomType")));
     }
 
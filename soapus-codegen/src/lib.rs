@@ -80,6 +80,9 @@ pub struct SoapClientGenerator {
     client_name: Option<String>,
     generate_tests: bool,
     soap_version: SoapVersion,
+    unwrapped_methods: bool,
+    blocking: bool,
+    idempotent_operations: Vec<String>,
 }
 
 /// SOAP protocol version
@@ -114,8 +117,7 @@ impl SoapClientGenerator {
             parse_wsdl(&wsdl_content).map_err(|e| CodegenError::WsdlParse(e.to_string()))?;
 
         // Generate code
-        let code = generator::generate_client_code(&wsdl_model, self)
-            .unwrap_or_else(|_| "// Code generation not yet implemented\n".to_string());
+        let code = generator::generate_client_code(&wsdl_model, self)?;
 
         // Write to output file
         let output_file = self.out_dir.join("soap_client.rs");
@@ -146,6 +148,21 @@ impl SoapClientGenerator {
     pub fn generate_tests(&self) -> bool {
         self.generate_tests
     }
+
+    /// Check if unwrapped operation methods are generated
+    pub fn unwrapped_methods(&self) -> bool {
+        self.unwrapped_methods
+    }
+
+    /// Check if blocking methods are generated instead of async ones
+    pub fn blocking(&self) -> bool {
+        self.blocking
+    }
+
+    /// Get the operations marked as safe to retry
+    pub fn idempotent_operations(&self) -> &[String] {
+        &self.idempotent_operations
+    }
 }
 
 /// Builder for configuring SOAP client generation
@@ -156,6 +173,9 @@ pub struct SoapClientGeneratorBuilder {
     client_name: Option<String>,
     generate_tests: bool,
     soap_version: SoapVersion,
+    unwrapped_methods: bool,
+    blocking: bool,
+    idempotent_operations: Vec<String>,
 }
 
 impl SoapClientGeneratorBuilder {
@@ -168,6 +188,9 @@ impl SoapClientGeneratorBuilder {
             client_name: None,
             generate_tests: false,
             soap_version: SoapVersion::Auto,
+            unwrapped_methods: false,
+            blocking: false,
+            idempotent_operations: Vec::new(),
         }
     }
 
@@ -207,6 +230,37 @@ impl SoapClientGeneratorBuilder {
         self
     }
 
+    /// Generate unwrapped methods for wrapped document/literal operations
+    ///
+    /// For an operation `Add { int_a, int_b } -> AddResponse { add_result }` this
+    /// generates `add(int_a, int_b) -> i32`. The struct-based method is still
+    /// generated, but renamed to `add_wrapped`.
+    pub fn unwrapped_methods(mut self, enable: bool) -> Self {
+        self.unwrapped_methods = enable;
+        self
+    }
+
+    /// Generate blocking methods instead of async ones
+    ///
+    /// The client methods are plain `fn`s that can be called without an async
+    /// runtime, e.g. from CLI tools. The generated code requires the `blocking`
+    /// feature of `soapus-runtime`.
+    pub fn blocking(mut self, enable: bool) -> Self {
+        self.blocking = enable;
+        self
+    }
+
+    /// Mark a WSDL operation as safe to retry
+    ///
+    /// The generated client marks the operation as idempotent on its
+    /// `SoapClient`, so a retry policy may resend it after timeouts, HTTP 5xx
+    /// responses or retryable faults. Generation fails if the WSDL does not
+    /// define the operation.
+    pub fn idempotent_operation(mut self, operation: impl Into<String>) -> Self {
+        self.idempotent_operations.push(operation.into());
+        self
+    }
+
     /// Build the generator and generate the code
     pub fn generate(self) -> Result<GeneratedCode> {
         let generator = self.build()?;
@@ -240,6 +294,9 @@ impl SoapClientGeneratorBuilder {
             client_name: self.client_name,
             generate_tests: self.generate_tests,
             soap_version: self.soap_version,
+            unwrapped_methods: self.unwrapped_methods,
+            blocking: self.blocking,
+            idempotent_operations: self.idempotent_operations,
         })
     }
 }
@@ -305,4 +362,33 @@ mod tests {
     fn test_soap_version_default() {
         assert_eq!(SoapVersion::default(), SoapVersion::Auto);
     }
+
+    #[test]
+    fn test_builder_unwrapped_methods() {
+        let generator = SoapClientGeneratorBuilder::new()
+            .wsdl_path("test.wsdl")
+            .out_dir(std::env::temp_dir())
+            .build()
+            .unwrap();
+        assert!(!generator.unwrapped_methods());
+
+        let generator = SoapClientGeneratorBuilder::new()
+            .wsdl_path("test.wsdl")
+            .out_dir(std::env::temp_dir())
+            .unwrapped_methods(true)
+            .build()
+            .unwrap();
+        assert!(generator.unwrapped_methods());
+    }
+
+    #[test]
+    fn test_builder_blocking() {
+        let generator = SoapClientGeneratorBuilder::new()
+            .wsdl_path("test.wsdl")
+            .out_dir(std::env::temp_dir())
+            .blocking(true)
+            .build()
+            .unwrap();
+        assert!(generator.blocking());
+    }
 }
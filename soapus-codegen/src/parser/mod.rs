@@ -7,8 +7,8 @@ mod xsd;
 
 pub use wsdl::parser::parse_wsdl;
 pub use wsdl::{
-    Binding, BindingOperation, Fault, Message, MessagePart, Port, PortType, PortTypeOperation,
-    Service, WsdlModel,
+    Binding, BindingHeader, BindingMessage, BindingOperation, Fault, Message, MessagePart,
+    MimePart, Port, PortType, PortTypeOperation, SecurityPolicy, Service, WsdlModel,
 };
 
 pub use xsd::parser::parse_schema;
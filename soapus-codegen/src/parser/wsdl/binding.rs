@@ -8,12 +8,22 @@ use tracing::warn;
 use crate::parser::QName;
 
 use super::parser::WsdlParser;
-use super::{Binding, BindingOperation};
+use super::policy::PolicyAssertions;
+use super::{Binding, BindingHeader, BindingMessage, BindingOperation, MimePart};
 
 // Standard SOAP namespace URIs as defined by W3C WSDL specification
 const SOAP_11_BINDING_NS: &str = "http://schemas.xmlsoap.org/wsdl/soap/";
 const SOAP_12_BINDING_NS: &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
 
+// WSDL 1.1 MIME binding, used for SOAP with Attachments
+const MIME_BINDING_NS: &str = "http://schemas.xmlsoap.org/wsdl/mime/";
+
+// WS-Addressing 1.0 WSDL Binding and the 2004/08 member submission
+pub(super) const ADDRESSING_WSDL_NAMESPACES: &[&str] = &[
+    "http://www.w3.org/2006/05/addressing/wsdl",
+    "http://schemas.xmlsoap.org/ws/2004/08/addressing/policy",
+];
+
 impl<B: std::io::BufRead> WsdlParser<B> {
     /// Parse a <binding> element
     ///
@@ -41,8 +51,11 @@ impl<B: std::io::BufRead> WsdlParser<B> {
         let mut type_ = None;
         let mut transport = None;
         let mut soap_version = None;
+        let mut style = None;
         let mut is_soap_binding = false;
         let mut operations = Vec::new();
+        let mut policies = PolicyAssertions::default();
+        let mut policy_references = Vec::new();
 
         for attr in ev.attributes().with_checks(false) {
             let attr = attr?;
@@ -81,7 +94,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                         let attr = attr?;
                         match attr.key.as_ref() {
                             b"transport" => transport = Some(attr.unescape_value()?.to_string()),
-                            b"style" => {}
+                            b"style" => style = Some(attr.unescape_value()?.to_string()),
                             b"version" => {
                                 // Allow explicit version attribute to override namespace detection
                                 if let Some(ns_uri) = self.get_namespace_uri(e.name().as_ref()) {
@@ -101,6 +114,8 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                     let mut op_name = None;
                     let mut soap_action = None;
                     let mut style = None;
+                    let mut input = None;
+                    let mut output = None;
 
                     for attr in e.attributes().with_checks(false) {
                         let attr = attr?;
@@ -135,6 +150,19 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                                     }
                                 }
                             }
+                            // <input> / <output> with nested <soap:body>
+                            Event::Start(e) if e.local_name().as_ref() == b"input" => {
+                                input = Some(self.parse_binding_message(b"input")?);
+                            }
+                            Event::Start(e) if e.local_name().as_ref() == b"output" => {
+                                output = Some(self.parse_binding_message(b"output")?);
+                            }
+                            Event::Empty(e) if e.local_name().as_ref() == b"input" => {
+                                input = Some(BindingMessage::default());
+                            }
+                            Event::Empty(e) if e.local_name().as_ref() == b"output" => {
+                                output = Some(BindingMessage::default());
+                            }
                             Event::End(e) if e.local_name().as_ref() == b"operation" => break,
                             _ => {}
                         }
@@ -159,10 +187,39 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                             name,
                             soap_action,
                             style,
+                            input,
+                            output,
                         });
                     }
                 }
 
+                // <wsaw:UsingAddressing/>
+                Event::Empty(e) | Event::Start(e)
+                    if e.local_name().as_ref() == b"UsingAddressing"
+                        && self.get_namespace_uri(e.name().as_ref()).is_some_and(|ns| {
+                            ADDRESSING_WSDL_NAMESPACES.contains(&ns.as_str())
+                        }) =>
+                {
+                    policies.addressing = true;
+                }
+
+                // Inline WS-Policy
+                Event::Start(e) if e.local_name().as_ref() == b"Policy" => {
+                    policies.merge(&self.parse_policy(&e)?);
+                }
+
+                // <wsp:PolicyReference URI="#..."/>, resolved once all policies are known
+                Event::Empty(e) | Event::Start(e)
+                    if e.local_name().as_ref() == b"PolicyReference" =>
+                {
+                    for attr in e.attributes().with_checks(false) {
+                        let attr = attr?;
+                        if attr.key.as_ref() == b"URI" {
+                            policy_references.push(attr.unescape_value()?.to_string());
+                        }
+                    }
+                }
+
                 Event::End(e) if e.local_name().as_ref() == b"binding" => break,
                 Event::Eof => break,
                 _ => {}
@@ -192,10 +249,118 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                 type_,
                 transport,
                 soap_version,
+                style,
                 operations,
+                uses_addressing: policies.addressing,
+                uses_mtom: policies.mtom,
             });
+            let index = self.model.bindings.len() - 1;
+            self.policy_references
+                .extend(policy_references.into_iter().map(|uri| (index, uri)));
         }
 
         Ok(())
     }
+
+    /// Parse the SOAP details of a binding operation's <input> or <output>
+    ///
+    /// Example:
+    /// ```xml
+    /// <input>
+    ///   <soap:body use="literal" parts="body" namespace="urn:example"/>
+    ///   <soap:header message="tns:AuthHeader" part="auth" use="literal"/>
+    /// </input>
+    /// ```
+    fn parse_binding_message(&mut self, end_tag: &[u8]) -> Result<BindingMessage, Box<dyn Error>> {
+        let mut message = BindingMessage::default();
+        let mut buf = Vec::new();
+
+        loop {
+            match self.reader.read_event_into(&mut buf)? {
+                Event::Empty(e) | Event::Start(e) if e.local_name().as_ref() == b"body" => {
+                    for attr in e.attributes().with_checks(false) {
+                        let attr = attr?;
+                        match attr.key.as_ref() {
+                            b"parts" => {
+                                message.body_parts = Some(
+                                    attr.unescape_value()?
+                                        .split_whitespace()
+                                        .map(str::to_string)
+                                        .collect(),
+                                )
+                            }
+                            b"namespace" => {
+                                message.namespace = Some(attr.unescape_value()?.to_string())
+                            }
+                            b"use" => message.use_ = Some(attr.unescape_value()?.to_string()),
+                            _ => {}
+                        }
+                    }
+                }
+                Event::Empty(e) | Event::Start(e) if e.local_name().as_ref() == b"header" => {
+                    let mut header_message = None;
+                    let mut part = None;
+                    for attr in e.attributes().with_checks(false) {
+                        let attr = attr?;
+                        match attr.key.as_ref() {
+                            b"message" => {
+                                header_message = Some(QName(attr.unescape_value()?.to_string()))
+                            }
+                            b"part" => part = Some(attr.unescape_value()?.to_string()),
+                            _ => {}
+                        }
+                    }
+                    message.headers.push(BindingHeader {
+                        message: header_message.ok_or("soap:header missing 'message'")?,
+                        part: part.ok_or("soap:header missing 'part'")?,
+                    });
+                }
+                Event::Start(e)
+                    if e.local_name().as_ref() == b"multipartRelated" && self.is_mime(&e) =>
+                {
+                    message.multipart_related = true;
+                }
+                Event::Empty(e) | Event::Start(e)
+                    if e.local_name().as_ref() == b"content" && self.is_mime(&e) =>
+                {
+                    let mut part = None;
+                    let mut content_type = None;
+                    for attr in e.attributes().with_checks(false) {
+                        let attr = attr?;
+                        match attr.key.as_ref() {
+                            b"part" => part = Some(attr.unescape_value()?.to_string()),
+                            b"type" => content_type = Some(attr.unescape_value()?.to_string()),
+                            _ => {}
+                        }
+                    }
+                    // Alternative contents of the same part are listed one after another
+                    let part = part.ok_or("mime:content missing 'part'")?;
+                    let content_types =
+                        match message.attachments.iter_mut().find(|p| p.part == part) {
+                            Some(existing) => &mut existing.content_types,
+                            None => {
+                                message.attachments.push(MimePart {
+                                    part,
+                                    content_types: Vec::new(),
+                                });
+                                &mut message.attachments.last_mut().unwrap().content_types
+                            }
+                        };
+                    content_types.extend(content_type);
+                }
+                Event::End(e) if e.local_name().as_ref() == end_tag => break,
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear()
+        }
+
+        Ok(message)
+    }
+
+    /// Check whether an element is in the WSDL MIME binding namespace
+    fn is_mime(&self, e: &BytesStart) -> bool {
+        self.get_namespace_uri(e.name().as_ref())
+            .is_some_and(|ns| ns == MIME_BINDING_NS)
+    }
 }
@@ -20,7 +20,14 @@ impl<B: std::io::BufRead> WsdlParser<B> {
     ///   <part name="parameters" element="tns:Add"/>
     /// </message>
     /// ```
-    pub(super) fn parse_message(&mut self, ev: &BytesStart) -> Result<(), Box<dyn Error>> {
+    ///
+    /// `has_content` is false for an empty `<message/>` tag, which defines a
+    /// message without parts.
+    pub(super) fn parse_message(
+        &mut self,
+        ev: &BytesStart,
+        has_content: bool,
+    ) -> Result<(), Box<dyn Error>> {
         let mut name = None;
         for attr in ev.attributes().with_checks(false) {
             let attr = attr?;
@@ -32,8 +39,9 @@ impl<B: std::io::BufRead> WsdlParser<B> {
         let name = name.ok_or("message missing name")?;
         let mut parts = Vec::new();
         let mut buf = Vec::new();
+        let mut in_message = has_content;
 
-        loop {
+        while in_message {
             match self.reader.read_event_into(&mut buf)? {
                 Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"part" => {
                     let mut part_name = None;
@@ -66,8 +74,8 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                         });
                     }
                 }
-                Event::End(e) if e.local_name().as_ref() == b"message" => break,
-                Event::Eof => break,
+                Event::End(e) if e.local_name().as_ref() == b"message" => in_message = false,
+                Event::Eof => in_message = false,
                 _ => {}
             }
             buf.clear()
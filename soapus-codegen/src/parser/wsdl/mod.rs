@@ -4,6 +4,7 @@ pub mod parser;
 mod binding;
 mod definitions;
 mod message;
+mod policy;
 mod port_type;
 mod service;
 mod types;
@@ -21,6 +22,7 @@ pub struct WsdlModel {
     bindings: Vec<Binding>,
     services: Vec<Service>,
     schema: Option<crate::parser::XmlSchema>,
+    security_policy: Option<SecurityPolicy>,
 }
 
 #[derive(Clone, Debug)]
@@ -53,6 +55,8 @@ pub struct PortTypeOperation {
     pub name: String,
     pub input: Option<QName>,
     pub output: Option<QName>,
+    /// WS-Addressing action of the input message (`wsam:Action`)
+    pub input_action: Option<String>,
     pub faults: Vec<Fault>,
     /// Documentation from WSDL <wsdl:documentation> element
     pub documentation: Option<String>,
@@ -62,9 +66,16 @@ pub struct PortTypeOperation {
 pub struct Binding {
     pub name: String,
     pub type_: QName,
-    pub transport: String,    // e.g. "http://schemas.xmlsoap.org/soap/http"
-    pub soap_version: String, // e.g. für <soap:binding style="..."> or xmlns:soap="..."
+    pub transport: String,     // e.g. "http://schemas.xmlsoap.org/soap/http"
+    pub soap_version: String,  // e.g. für <soap:binding style="..."> or xmlns:soap="..."
+    pub style: Option<String>, // default style from <soap:binding style="...">
     pub operations: Vec<BindingOperation>,
+    /// Whether the binding requires WS-Addressing (`wsaw:UsingAddressing` or
+    /// a `wsam:Addressing` policy attached to the binding)
+    pub uses_addressing: bool,
+    /// Whether the binding requires MTOM (a `wsoma:OptimizedMimeSerialization`
+    /// policy attached to the binding)
+    pub uses_mtom: bool,
 }
 
 #[derive(Debug)]
@@ -72,6 +83,57 @@ pub struct BindingOperation {
     pub name: String,
     pub soap_action: Option<String>,
     pub style: Option<String>, // "document" or "rpc" for SOAP Binding
+    pub input: Option<BindingMessage>,
+    pub output: Option<BindingMessage>,
+}
+
+/// SOAP details of a binding operation's <input> or <output>
+#[derive(Debug, Default, Clone)]
+pub struct BindingMessage {
+    /// Message parts placed in the SOAP body (`<soap:body parts="...">`), None = all parts
+    pub body_parts: Option<Vec<String>>,
+    /// Namespace for the RPC wrapper element (`<soap:body namespace="...">`)
+    pub namespace: Option<String>,
+    /// "literal" or "encoded"
+    pub use_: Option<String>,
+    /// Header blocks declared with `<soap:header message="..." part="..."/>`
+    pub headers: Vec<BindingHeader>,
+    /// Whether the message is `<mime:multipartRelated>` (SOAP with Attachments)
+    pub multipart_related: bool,
+    /// Message parts sent as MIME attachments (`<mime:content part="..." type="..."/>`)
+    pub attachments: Vec<MimePart>,
+}
+
+/// A message part bound to a MIME attachment
+#[derive(Debug, Clone, PartialEq)]
+pub struct MimePart {
+    /// Name of the message part
+    pub part: String,
+    /// Allowed content types, e.g. `image/jpeg`
+    pub content_types: Vec<String>,
+}
+
+/// A SOAP header block declared in a binding operation
+#[derive(Debug, Clone)]
+pub struct BindingHeader {
+    /// Message containing the header part
+    pub message: QName,
+    /// Name of the part within the message
+    pub part: String,
+}
+
+/// WS-SecurityPolicy assertions found in the WSDL
+///
+/// Assertions are collected from all policies of the document, regardless
+/// of which binding references them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SecurityPolicy {
+    /// A `sp:UsernameToken` is required
+    pub username_token: bool,
+    /// The password is sent as digest (`sp:HashPassword`)
+    pub hash_password: bool,
+    /// A `wsu:Timestamp` is required (`sp:IncludeTimestamp`)
+    pub include_timestamp: bool,
 }
 
 #[derive(Debug)]
@@ -108,6 +170,87 @@ impl WsdlModel {
         self.schema = Some(schema);
     }
 
+    /// Get the WS-SecurityPolicy assertions (if the WSDL has any)
+    pub fn security_policy(&self) -> Option<&SecurityPolicy> {
+        self.security_policy.as_ref()
+    }
+
+    /// Check whether the binding of the client requires WS-Addressing
+    ///
+    /// See [`Binding::uses_addressing`] and [`WsdlModel::client_binding`].
+    pub fn uses_addressing(&self) -> bool {
+        self.client_binding()
+            .is_some_and(|binding| binding.uses_addressing)
+    }
+
+    /// Get the binding the generated client talks to
+    ///
+    /// The binding of the first service port, i.e. of [`WsdlModel::endpoint_url`],
+    /// or the first binding if no port refers to a known binding.
+    pub fn client_binding(&self) -> Option<&Binding> {
+        self.services
+            .first()
+            .and_then(|s| s.ports.first())
+            .and_then(|port| self.find_binding(port.binding.local_name()))
+            .or_else(|| self.bindings.first())
+    }
+
+    /// Bindings in the order operations are looked up: the client binding first
+    fn bindings_for_client(&self) -> impl Iterator<Item = &Binding> {
+        let client = self.client_binding();
+        client.into_iter().chain(
+            self.bindings
+                .iter()
+                .filter(move |binding| !client.is_some_and(|c| std::ptr::eq(c, *binding))),
+        )
+    }
+
+    /// Check whether the binding of the client requires MTOM
+    ///
+    /// See [`Binding::uses_mtom`] and [`WsdlModel::client_binding`].
+    pub fn uses_mtom(&self) -> bool {
+        self.client_binding()
+            .is_some_and(|binding| binding.uses_mtom)
+    }
+
+    /// Get the WS-Addressing action of an operation's input message
+    ///
+    /// Uses the explicit `wsam:Action` of the portType input, then the
+    /// binding's `soapAction`, and finally the default action pattern of
+    /// WS-Addressing Metadata: `{targetNamespace}/{portType}/{operation}Request`.
+    pub fn find_action(&self, operation_name: &str) -> Option<String> {
+        let (port_type, operation) = self.port_types.iter().find_map(|pt| {
+            pt.operations
+                .iter()
+                .find(|op| op.name == operation_name)
+                .map(|op| (pt, op))
+        })?;
+
+        if let Some(action) = &operation.input_action {
+            return Some(action.clone());
+        }
+        if let Some(action) = self.find_soap_action(operation_name) {
+            if !action.is_empty() {
+                return Some(action.to_string());
+            }
+        }
+
+        let namespace = self.target_namespace.as_deref().unwrap_or_default();
+        let delimiter = if namespace.starts_with("urn:") {
+            ':'
+        } else {
+            '/'
+        };
+        let mut action = namespace.to_string();
+        if !action.is_empty() && !action.ends_with(delimiter) {
+            action.push(delimiter);
+        }
+        Some(format!(
+            "{}{}{}{}Request",
+            action, port_type.name, delimiter, operation.name
+        ))
+    }
+
     /// Find a message by QName
     pub fn find_message(&self, qname: &QName) -> Option<&Message> {
         self.messages.iter().find(|m| m.name == qname.local_name())
@@ -163,10 +306,10 @@ impl WsdlModel {
 
     /// Find the SOAPAction for a given operation name
     ///
-    /// Searches through all bindings to find the SOAPAction header value
-    /// for the specified operation.
+    /// Searches through all bindings, starting with the client binding, to
+    /// find the SOAPAction header value for the specified operation.
     pub fn find_soap_action(&self, operation_name: &str) -> Option<&str> {
-        for binding in &self.bindings {
+        for binding in self.bindings_for_client() {
             for op in &binding.operations {
                 if op.name == operation_name {
                     return op.soap_action.as_deref();
@@ -175,4 +318,30 @@ impl WsdlModel {
         }
         None
     }
+
+    /// Find the binding operation (and its binding) for a given operation name
+    ///
+    /// The client binding is searched first.
+    pub fn find_binding_operation(
+        &self,
+        operation_name: &str,
+    ) -> Option<(&Binding, &BindingOperation)> {
+        self.bindings_for_client().find_map(|binding| {
+            binding
+                .operations
+                .iter()
+                .find(|op| op.name == operation_name)
+                .map(|op| (binding, op))
+        })
+    }
+
+    /// Find the SOAP style ("document" or "rpc") of an operation
+    ///
+    /// The operation's own style takes precedence over the binding default.
+    /// Defaults to "document" as specified by WSDL 1.1.
+    pub fn find_operation_style(&self, operation_name: &str) -> &str {
+        self.find_binding_operation(operation_name)
+            .and_then(|(binding, op)| op.style.as_deref().or(binding.style.as_deref()))
+            .unwrap_or("document")
+    }
 }
@@ -10,7 +10,9 @@
 //! - `port_type` - PortType and operation definitions
 //! - `binding` - SOAP binding and operation details
 //! - `service` - Service endpoints and ports
+//! - `policy` - WS-Policy assertions
 
+use super::policy::PolicyAssertions;
 use super::WsdlModel;
 use quick_xml::events::Event;
 use quick_xml::Reader;
@@ -63,6 +65,10 @@ pub struct WsdlParser<B: std::io::BufRead> {
     pub(super) namespaces: HashMap<String, String>,
     pub(super) target_namespace: Option<String>,
     pub(super) model: WsdlModel,
+    /// Policies by the URI they are referenced with
+    pub(super) policies: HashMap<String, PolicyAssertions>,
+    /// `PolicyReference` URIs by binding index
+    pub(super) policy_references: Vec<(usize, String)>,
 }
 
 impl<B: std::io::BufRead> WsdlParser<B> {
@@ -73,6 +79,8 @@ impl<B: std::io::BufRead> WsdlParser<B> {
             namespaces: HashMap::new(),
             target_namespace: None,
             model: WsdlModel::default(),
+            policies: HashMap::new(),
+            policy_references: Vec::new(),
         }
     }
 
@@ -120,7 +128,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                     b"message" => {
                         #[cfg(feature = "tracing")]
                         debug!("Parsing message element");
-                        self.parse_message(&ev)?
+                        self.parse_message(&ev, true)?
                     }
                     b"portType" => {
                         #[cfg(feature = "tracing")]
@@ -137,14 +145,33 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                         debug!("Parsing service element");
                         self.parse_service(&ev)?
                     }
+                    b"Policy" => {
+                        #[cfg(feature = "tracing")]
+                        debug!("Parsing policy element");
+                        self.parse_policy(&ev)?;
+                    }
                     _ => {}
                 },
+                Event::Empty(ev) if ev.local_name().as_ref() == b"message" => {
+                    #[cfg(feature = "tracing")]
+                    debug!("Parsing empty message element");
+                    self.parse_message(&ev, false)?
+                }
                 Event::Eof => break,
                 _ => {}
             }
             buf.clear()
         }
 
+        // Policies may be defined after the bindings referencing them
+        for (index, uri) in &self.policy_references {
+            if let Some(assertions) = self.policies.get(uri) {
+                let binding = &mut self.model.bindings[*index];
+                binding.uses_addressing |= assertions.addressing;
+                binding.uses_mtom |= assertions.mtom;
+            }
+        }
+
         self.model.target_namespace = self.target_namespace;
         self.model.namespaces = self.namespaces;
         Ok(self.model)
@@ -154,6 +181,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::MimePart;
 
     #[test]
     fn parses_wsdl() {
@@ -362,4 +390,250 @@ mod tests {
         // Check schema
         assert!(model.schema().is_some());
     }
+
+    #[test]
+    fn parses_binding_body_parts_and_style() {
+        let wsdl = include_str!("../../../../testdata/wsdl/orders_multipart.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        let binding = &model.bindings[0];
+        assert_eq!(binding.style, Some("document".to_string()));
+
+        // Operation style overrides the binding default
+        assert_eq!(model.find_operation_style("PlaceOrder"), "document");
+        assert_eq!(model.find_operation_style("GetStatus"), "rpc");
+
+        let (_, get_status) = model.find_binding_operation("GetStatus").unwrap();
+        let input = get_status.input.as_ref().unwrap();
+        assert_eq!(input.namespace, Some("urn:example:orders:rpc".to_string()));
+        assert_eq!(input.use_, Some("literal".to_string()));
+        assert_eq!(input.body_parts, None);
+
+        let (_, cancel) = model.find_binding_operation("CancelOrder").unwrap();
+        assert_eq!(
+            cancel.input.as_ref().unwrap().body_parts,
+            Some(vec!["body".to_string()])
+        );
+
+        // Empty <message/> elements are parsed as messages without parts
+        let empty = model.find_message(&"tns:CancelOrderOut".into()).unwrap();
+        assert!(empty.parts.is_empty());
+
+        let place_order_in = model.find_message(&"tns:PlaceOrderIn".into()).unwrap();
+        assert_eq!(place_order_in.parts.len(), 2);
+    }
+
+    #[test]
+    fn parses_binding_headers() {
+        let wsdl = include_str!("../../../../testdata/wsdl/session_headers.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        let (_, login) = model.find_binding_operation("Login").unwrap();
+        assert!(login.input.as_ref().unwrap().headers.is_empty());
+        let output_headers = &login.output.as_ref().unwrap().headers;
+        assert_eq!(output_headers.len(), 1);
+        assert_eq!(output_headers[0].message.local_name(), "SessionInfoMsg");
+        assert_eq!(output_headers[0].part, "session");
+
+        let (_, logout) = model.find_binding_operation("Logout").unwrap();
+        let input_headers = &logout.input.as_ref().unwrap().headers;
+        assert_eq!(input_headers[0].message.local_name(), "LogoutIn");
+        assert_eq!(input_headers[0].part, "auth");
+    }
+
+    #[test]
+    fn parses_port_type_faults() {
+        let wsdl = include_str!("../../../../testdata/wsdl/faults.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        let withdraw = model.operations().find(|op| op.name == "Withdraw").unwrap();
+        let faults: Vec<(&str, &str)> = withdraw
+            .faults
+            .iter()
+            .map(|f| (f.name.as_str(), f.message.local_name()))
+            .collect();
+        // The second fault has nested documentation
+        assert_eq!(
+            faults,
+            vec![
+                ("InsufficientFunds", "InsufficientFundsMsg"),
+                ("AccountLocked", "AccountLockedMsg")
+            ]
+        );
+
+        let get_account = model
+            .operations()
+            .find(|op| op.name == "GetAccount")
+            .unwrap();
+        assert!(get_account.faults.is_empty());
+    }
+
+    #[test]
+    fn parses_security_policy() {
+        let wsdl = include_str!("../../../../testdata/wsdl/ws_security_policy.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        let policy = model.security_policy().unwrap();
+        assert!(policy.username_token);
+        assert!(policy.hash_password);
+        assert!(policy.include_timestamp);
+        // The binding after the policy is still parsed
+        assert!(model.find_binding_operation("GetSalary").is_some());
+
+        let calculator =
+            parse_wsdl(include_str!("../../../../testdata/wsdl/calculator.wsdl")).unwrap();
+        assert!(calculator.security_policy().is_none());
+    }
+
+    #[test]
+    fn parses_inline_binding_policy() {
+        let wsdl = r#"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/"
+             xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+             xmlns:wsp="http://schemas.xmlsoap.org/ws/2004/09/policy"
+             xmlns:sp="http://schemas.xmlsoap.org/ws/2005/07/securitypolicy"
+             xmlns:tns="urn:example"
+             targetNamespace="urn:example">
+  <binding name="Binding" type="tns:PortType">
+    <wsp:Policy>
+      <sp:SignedSupportingTokens>
+        <wsp:Policy><sp:UsernameToken/></wsp:Policy>
+      </sp:SignedSupportingTokens>
+    </wsp:Policy>
+    <soap:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+  </binding>
+</definitions>"#;
+
+        let model = parse_wsdl(wsdl).unwrap();
+        let policy = model.security_policy().unwrap();
+        assert!(policy.username_token);
+        assert!(!policy.hash_password);
+        assert!(!policy.include_timestamp);
+        assert_eq!(model.bindings().len(), 1);
+    }
+
+    #[test]
+    fn parses_ws_addressing() {
+        let wsdl = include_str!("../../../../testdata/wsdl/ws_addressing.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        assert!(model.uses_addressing());
+        assert_eq!(
+            model.find_action("GetStatus").as_deref(),
+            Some("http://tempuri.org/IStatusService/GetStatus")
+        );
+        // Empty soapAction, no wsam:Action: default action pattern
+        assert_eq!(
+            model.find_action("Ping").as_deref(),
+            Some("http://tempuri.org/IStatusService/PingRequest")
+        );
+        assert_eq!(model.find_action("Missing"), None);
+
+        let calculator =
+            parse_wsdl(include_str!("../../../../testdata/wsdl/calculator.wsdl")).unwrap();
+        assert!(!calculator.uses_addressing());
+        assert_eq!(
+            calculator.find_action("Add").as_deref(),
+            Some("http://tempuri.org/Add")
+        );
+    }
+
+    #[test]
+    fn parses_addressing_policy() {
+        let wsdl = r##"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/"
+             xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+             xmlns:wsp="http://www.w3.org/ns/ws-policy"
+             xmlns:wsu="http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd"
+             xmlns:tns="urn:example"
+             targetNamespace="urn:example">
+  <portType name="PortType">
+    <operation name="Op"><input message="tns:OpIn"/></operation>
+  </portType>
+  <binding name="Plain" type="tns:PortType">
+    <soap:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+  </binding>
+  <binding name="Addressed" type="tns:PortType">
+    <wsp:PolicyReference URI="#AddressingPolicy"/>
+    <soap:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+  </binding>
+  <service name="Service">
+    <port name="AddressedPort" binding="tns:Addressed"><soap:address location="http://a"/></port>
+    <port name="PlainPort" binding="tns:Plain"><soap:address location="http://p"/></port>
+  </service>
+  <wsp:Policy wsu:Id="AddressingPolicy">
+    <wsam:Addressing xmlns:wsam="http://www.w3.org/2007/05/addressing/metadata">
+      <wsp:Policy/>
+    </wsam:Addressing>
+  </wsp:Policy>
+</definitions>"##;
+
+        let model = parse_wsdl(wsdl).unwrap();
+        // The policy applies to the binding referencing it only
+        assert!(!model.find_binding("Plain").unwrap().uses_addressing);
+        assert!(model.find_binding("Addressed").unwrap().uses_addressing);
+        // The client talks to the binding of the first port
+        assert_eq!(model.client_binding().unwrap().name, "Addressed");
+        assert!(model.uses_addressing());
+        assert!(model.security_policy().is_none());
+
+        let plain = parse_wsdl(&wsdl.replace(
+            "<port name=\"AddressedPort\" binding=\"tns:Addressed\"><soap:address location=\"http://a\"/></port>",
+            "",
+        ))
+        .unwrap();
+        assert!(!plain.uses_addressing());
+        assert_eq!(
+            model.find_action("Op").as_deref(),
+            Some("urn:example:PortType:OpRequest")
+        );
+    }
+
+    #[test]
+    fn parses_mtom_policy() {
+        let wsdl = include_str!("../../../../testdata/wsdl/mtom.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        assert!(model.uses_mtom());
+        assert!(!model.uses_addressing());
+        assert!(model.security_policy().is_none());
+
+        // A policy no binding refers to does not apply
+        let unreferenced =
+            parse_wsdl(&wsdl.replace("<wsp:PolicyReference URI=\"#MtomPolicy\"/>", "")).unwrap();
+        assert!(!unreferenced.uses_mtom());
+
+        let calculator =
+            parse_wsdl(include_str!("../../../../testdata/wsdl/calculator.wsdl")).unwrap();
+        assert!(!calculator.uses_mtom());
+    }
+
+    #[test]
+    fn parses_mime_multipart_related() {
+        let wsdl = include_str!("../../../../testdata/wsdl/swa.wsdl");
+        let model = parse_wsdl(wsdl).unwrap();
+
+        let (_, submit) = model.find_binding_operation("SubmitClaim").unwrap();
+        let input = submit.input.as_ref().unwrap();
+        assert!(input.multipart_related);
+        assert_eq!(input.body_parts, Some(vec!["body".to_string()]));
+        assert_eq!(
+            input.attachments,
+            vec![
+                MimePart {
+                    part: "form".to_string(),
+                    content_types: vec!["image/tiff".to_string()],
+                },
+                MimePart {
+                    part: "photos".to_string(),
+                    content_types: vec!["image/jpeg".to_string(), "image/png".to_string()],
+                },
+            ]
+        );
+        assert!(!submit.output.as_ref().unwrap().multipart_related);
+
+        let (_, get) = model.find_binding_operation("GetClaimDocument").unwrap();
+        let output = get.output.as_ref().unwrap();
+        assert!(output.multipart_related);
+        assert_eq!(output.use_.as_deref(), Some("literal"));
+        assert_eq!(output.attachments[0].part, "pdf");
+    }
 }
@@ -0,0 +1,179 @@
+//! Parsing of WS-Policy elements
+//!
+//! Only WS-SecurityPolicy assertions relevant for the generated client are
+//! recorded; all other policy content is skipped. WS-Addressing and MTOM
+//! assertions apply to the bindings the policy is attached to, inline or by
+//! `wsp:PolicyReference`.
+
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::binding::ADDRESSING_WSDL_NAMESPACES;
+use super::parser::WsdlParser;
+use super::SecurityPolicy;
+
+// WS-SecurityPolicy 1.2 and the pre-standard 2005/07 version used by older WCF services
+const SECURITY_POLICY_NAMESPACES: &[&str] = &[
+    "http://docs.oasis-open.org/ws-sx/ws-securitypolicy/200702",
+    "http://schemas.xmlsoap.org/ws/2005/07/securitypolicy",
+];
+
+// WS-Addressing Metadata, home of the wsam:Addressing assertion
+const ADDRESSING_METADATA_NS: &str = "http://www.w3.org/2007/05/addressing/metadata";
+
+// Home of the wsoma:OptimizedMimeSerialization (MTOM) assertion
+const MTOM_POLICY_NS: &str =
+    "http://schemas.xmlsoap.org/ws/2004/09/policy/optimizedmimeserialization";
+
+/// Assertions of a policy that apply to the bindings it is attached to
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct PolicyAssertions {
+    /// `wsam:Addressing` or `wsaw:UsingAddressing`
+    pub addressing: bool,
+    /// `wsoma:OptimizedMimeSerialization`
+    pub mtom: bool,
+}
+
+impl PolicyAssertions {
+    /// Add the assertions of another policy
+    pub fn merge(&mut self, other: &PolicyAssertions) {
+        self.addressing |= other.addressing;
+        self.mtom |= other.mtom;
+    }
+}
+
+impl<B: std::io::BufRead> WsdlParser<B> {
+    /// Parse a <wsp:Policy> element
+    ///
+    /// Policies may appear at the top level (referenced from bindings with
+    /// `<wsp:PolicyReference>`) or inline in a binding. Nested policies are
+    /// read up to the end of the outermost `Policy` element.
+    ///
+    /// Returns the assertions for the bindings the policy applies to. A policy
+    /// with a `wsu:Id` or `Name` is also kept for resolving `PolicyReference`s.
+    ///
+    /// Example:
+    /// ```xml
+    /// <wsp:Policy wsu:Id="UsernameTokenPolicy">
+    ///   <sp:SupportingTokens>
+    ///     <wsp:Policy>
+    ///       <sp:UsernameToken sp:IncludeToken=".../AlwaysToRecipient">
+    ///         <wsp:Policy><sp:HashPassword/></wsp:Policy>
+    ///       </sp:UsernameToken>
+    ///     </wsp:Policy>
+    ///   </sp:SupportingTokens>
+    /// </wsp:Policy>
+    /// ```
+    pub(super) fn parse_policy(
+        &mut self,
+        ev: &BytesStart,
+    ) -> Result<PolicyAssertions, Box<dyn Error>> {
+        // Namespaces declared inside the policy, e.g. xmlns:sp on the Policy element
+        let mut local_namespaces = HashMap::new();
+        let mut assertions = PolicyAssertions::default();
+        self.record_policy_assertion(ev, &mut local_namespaces, &mut assertions)?;
+
+        let mut depth = 1;
+        let mut buf = Vec::new();
+        loop {
+            match self.reader.read_event_into(&mut buf)? {
+                Event::Start(e) => {
+                    depth += 1;
+                    self.record_policy_assertion(&e, &mut local_namespaces, &mut assertions)?;
+                }
+                Event::Empty(e) => {
+                    self.record_policy_assertion(&e, &mut local_namespaces, &mut assertions)?
+                }
+                Event::End(_) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        // Referenced as `#Id` (wsu:Id) or by the URI in `Name`
+        for attr in ev.attributes().with_checks(false) {
+            let attr = attr?;
+            match attr.key.local_name().as_ref() {
+                b"Id" => {
+                    let uri = format!("#{}", attr.unescape_value()?);
+                    self.policies.insert(uri, assertions);
+                }
+                b"Name" => {
+                    let uri = attr.unescape_value()?.to_string();
+                    self.policies.insert(uri, assertions);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(assertions)
+    }
+
+    /// Record a policy element if it is a known WS-SecurityPolicy, WS-Addressing or MTOM assertion
+    fn record_policy_assertion(
+        &mut self,
+        e: &BytesStart,
+        local_namespaces: &mut HashMap<String, String>,
+        assertions: &mut PolicyAssertions,
+    ) -> Result<(), Box<dyn Error>> {
+        for attr in e.attributes().with_checks(false) {
+            let attr = attr?;
+            let key = attr.key.as_ref();
+            if key == b"xmlns" {
+                local_namespaces.insert(String::new(), attr.unescape_value()?.to_string());
+            } else if let Some(prefix) = key.strip_prefix(b"xmlns:") {
+                local_namespaces.insert(
+                    String::from_utf8_lossy(prefix).to_string(),
+                    attr.unescape_value()?.to_string(),
+                );
+            }
+        }
+
+        let name = e.name();
+        let prefix = name
+            .prefix()
+            .map(|p| String::from_utf8_lossy(p.as_ref()).to_string())
+            .unwrap_or_default();
+        let namespace = local_namespaces
+            .get(&prefix)
+            .or_else(|| self.namespaces.get(&prefix))
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        match e.local_name().as_ref() {
+            b"Addressing" if namespace == ADDRESSING_METADATA_NS => {
+                assertions.addressing = true;
+            }
+            b"UsingAddressing" if ADDRESSING_WSDL_NAMESPACES.contains(&namespace) => {
+                assertions.addressing = true;
+            }
+            b"OptimizedMimeSerialization" if namespace == MTOM_POLICY_NS => {
+                assertions.mtom = true;
+            }
+            _ => {}
+        }
+        if !SECURITY_POLICY_NAMESPACES.contains(&namespace) {
+            return Ok(());
+        }
+
+        let policy = self
+            .model
+            .security_policy
+            .get_or_insert_with(SecurityPolicy::default);
+        match e.local_name().as_ref() {
+            b"UsernameToken" => policy.username_token = true,
+            b"HashPassword" => policy.hash_password = true,
+            b"IncludeTimestamp" => policy.include_timestamp = true,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
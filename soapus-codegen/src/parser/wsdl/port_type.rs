@@ -39,6 +39,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
         let mut current_op_name = None;
         let mut current_input = None;
         let mut current_output = None;
+        let mut current_input_action = None;
         let mut current_documentation = None;
 
         let mut buf = Vec::new();
@@ -49,6 +50,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                     current_op_name = None;
                     current_input = None;
                     current_output = None;
+                    current_input_action = None;
                     current_documentation = None;
 
                     for attr in e.attributes().with_checks(false) {
@@ -80,6 +82,11 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                         let attr = attr?;
                         if attr.key.as_ref() == b"message" {
                             current_input = Some(QName(attr.unescape_value()?.to_string()));
+                        } else if attr.key.local_name().as_ref() == b"Action"
+                            && attr.key.prefix().is_some()
+                        {
+                            // wsam:Action or wsaw:Action
+                            current_input_action = Some(attr.unescape_value()?.to_string());
                         }
                     }
                 }
@@ -91,27 +98,14 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                         }
                     }
                 }
-                Event::Empty(e) | Event::Start(e) if e.local_name().as_ref() == b"fault" => {
-                    let mut fault_name = None;
-                    let mut fault_message = None;
-                    for attr in e.attributes().with_checks(false) {
-                        let attr = attr?;
-                        match attr.key.as_ref() {
-                            b"name" => fault_name = Some(attr.unescape_value()?.to_string()),
-                            b"message" => {
-                                fault_message = Some(QName(attr.unescape_value()?.to_string()))
-                            }
-                            _ => {}
-                        }
-                    }
-                    while let Ok(Event::End(ref e)) = self.reader.read_event_into(&mut buf) {
-                        if e.local_name().as_ref() == b"fault" {
-                            break;
-                        }
-                    }
-                    if let (Some(name), Some(message)) = (fault_name, fault_message) {
-                        current_faults.push(Fault { name, message });
-                    }
+                Event::Empty(e) if e.local_name().as_ref() == b"fault" => {
+                    current_faults.extend(Self::parse_fault(&e)?);
+                }
+                Event::Start(e) if e.local_name().as_ref() == b"fault" => {
+                    current_faults.extend(Self::parse_fault(&e)?);
+                    // Skip nested content such as <documentation>
+                    let mut skip_buf = Vec::new();
+                    self.reader.read_to_end_into(e.name(), &mut skip_buf)?;
                 }
                 Event::End(e) if e.local_name().as_ref() == b"operation" => {
                     if current_input.is_none() && current_output.is_none() {
@@ -129,6 +123,7 @@ impl<B: std::io::BufRead> WsdlParser<B> {
                             name,
                             input: current_input.take(),
                             output: current_output.take(),
+                            input_action: current_input_action.take(),
                             faults,
                             documentation: current_documentation.take(),
                         });
@@ -144,4 +139,28 @@ impl<B: std::io::BufRead> WsdlParser<B> {
         self.model.port_types.push(PortType { name, operations });
         Ok(())
     }
+
+    /// Parse the attributes of a portType operation's <fault> element
+    ///
+    /// Example:
+    /// ```xml
+    /// <fault name="ValidationFault" message="tns:ValidationFaultMsg"/>
+    /// ```
+    fn parse_fault(ev: &BytesStart) -> Result<Option<Fault>, Box<dyn Error>> {
+        let mut fault_name = None;
+        let mut fault_message = None;
+        for attr in ev.attributes().with_checks(false) {
+            let attr = attr?;
+            match attr.key.as_ref() {
+                b"name" => fault_name = Some(attr.unescape_value()?.to_string()),
+                b"message" => fault_message = Some(QName(attr.unescape_value()?.to_string())),
+                _ => {}
+            }
+        }
+
+        Ok(match (fault_name, fault_message) {
+            (Some(name), Some(message)) => Some(Fault { name, message }),
+            _ => None,
+        })
+    }
 }
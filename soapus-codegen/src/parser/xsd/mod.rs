@@ -122,15 +122,10 @@ pub struct Attribute {
 }
 
 /// Whether an attribute is required or optional
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub enum AttributeUse {
     Required,
+    #[default]
     Optional,
     Prohibited,
 }
-
-impl Default for AttributeUse {
-    fn default() -> Self {
-        AttributeUse::Optional
-    }
-}
@@ -72,12 +72,10 @@ impl<B: std::io::BufRead> SchemaParser<B> {
         let mut buf = Vec::new();
         loop {
             match self.reader.read_event_into(&mut buf)? {
-                Event::Start(ev) => {
-                    if ev.local_name().as_ref() == b"schema" {
-                        self.parse_schema_attributes(&ev)?;
-                        self.parse_schema_content()?;
-                        break;
-                    }
+                Event::Start(ev) if ev.local_name().as_ref() == b"schema" => {
+                    self.parse_schema_attributes(&ev)?;
+                    self.parse_schema_content()?;
+                    break;
                 }
                 Event::End(ev) if ev.local_name().as_ref() == b"schema" => {
                     break;
@@ -247,10 +247,7 @@ fn test_generate_from_attributes_wsdl() {
         content.contains("pub description: String"),
         "Should have description element"
     );
-    assert!(
-        content.contains("pub price:"),
-        "Should have price element"
-    );
+    assert!(content.contains("pub price:"), "Should have price element");
 
     // Check for operations
     assert!(
@@ -263,15 +260,429 @@ fn test_generate_from_attributes_wsdl() {
     );
 }
 
+#[test]
+fn test_generate_from_orders_multipart_wsdl() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/orders_multipart.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(
+        result.is_ok(),
+        "Orders code generation failed: {:?}",
+        result.err()
+    );
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+
+    // Multi-part document/literal bare operation
+    assert!(
+        content.contains("pub async fn place_order(&self, body: OrderRequest, audit: AuditInfo)"),
+        "place_order should take one argument per part"
+    );
+    assert!(
+        content.contains("SoapResult<(OrderConfirmation, String)>"),
+        "place_order should return a tuple of output parts"
+    );
+
+    // RPC operation with type parts
+    assert!(
+        content.contains(
+            "pub async fn get_status(&self, order_id: String, verbose: bool) -> SoapResult<String>"
+        ),
+        "get_status should use the part types"
+    );
+
+    // soap:body parts="body" restricts the input to a single part
+    assert!(
+        content
+            .contains("pub async fn cancel_order(&self, request: OrderRequest) -> SoapResult<()>"),
+        "cancel_order should only take the selected body part"
+    );
+}
+
+#[test]
+fn test_generate_from_session_headers_wsdl() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/session_headers.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(
+        result.is_ok(),
+        "Headers code generation failed: {:?}",
+        result.err()
+    );
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+
+    // Header types are generated from the schema
+    assert!(content.contains("pub struct AuthHeader"));
+    assert!(content.contains("pub struct SessionInfo"));
+
+    assert!(
+        content.contains("pub async fn get_balance(&self, request: GetBalance, auth: AuthHeader)"),
+        "get_balance should take the AuthHeader"
+    );
+    assert!(
+        content.contains("SoapResult<(GetBalanceResponse, Option<SessionInfo>)>"),
+        "get_balance should return the SessionInfo header"
+    );
+    assert!(
+        content.contains("call_parts_with_options("),
+        "Operations with headers should use call_parts_with_options"
+    );
+}
+
+#[test]
+fn test_generate_from_faults_wsdl() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/faults.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(
+        result.is_ok(),
+        "Faults code generation failed: {:?}",
+        result.err()
+    );
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+
+    assert!(
+        content.contains("pub enum WithdrawFault"),
+        "Should generate a fault enum for Withdraw"
+    );
+    assert!(content.contains("InsufficientFunds(InsufficientFunds)"));
+    assert!(
+        content.contains("pub enum AuditOperationFault"),
+        "Fault enum name should not clash with the AuditFault type"
+    );
+    assert!(
+        !content.contains("GetAccountFault"),
+        "Operations without faults should not get a fault enum"
+    );
+}
+
+#[test]
+fn test_generate_unwrapped_methods() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/calculator.wsdl")
+        .out_dir(dir.path())
+        .unwrapped_methods(true)
+        .generate();
+
+    assert!(
+        result.is_ok(),
+        "Unwrapped code generation failed: {:?}",
+        result.err()
+    );
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+
+    for op in ["add", "subtract", "multiply", "divide"] {
+        assert!(
+            content.contains(&format!(
+                "pub async fn {}(&self, int_a: i32, int_b: i32) -> SoapResult<i32>",
+                op
+            )),
+            "Should generate unwrapped {} method",
+            op
+        );
+        assert!(
+            content.contains(&format!("pub async fn {}_wrapped(&self, request:", op)),
+            "Should keep struct-based {} method",
+            op
+        );
+    }
+}
+
+#[test]
+fn test_generate_unwrapped_methods_with_reserved_names() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/reserved_params.wsdl")
+        .out_dir(dir.path())
+        .unwrapped_methods(true)
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+
+    // An `options` parameter would clash with the CallOptions parameter
+    assert!(
+        !content.contains(", options: String"),
+        "Should not generate an options parameter"
+    );
+    assert!(content.contains("pub async fn search(&self, request: Search)"));
+    assert!(content.contains("#[serde(rename = \"self\")]\n    pub self_: String,"));
+    assert!(content.contains("pub async fn rename(&self, self_: String, name: String)"));
+    assert!(content.contains("pub async fn count(&self, query: String) -> SoapResult<i32>"));
+}
+
+#[test]
+fn test_generate_blocking_client() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/calculator.wsdl")
+        .out_dir(dir.path())
+        .unwrapped_methods(true)
+        .blocking(true)
+        .generate();
+
+    assert!(
+        result.is_ok(),
+        "Blocking code generation failed: {:?}",
+        result.err()
+    );
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    assert!(content.contains("/// Blocking SOAP client for"));
+    assert!(
+        !content.contains("async fn"),
+        "Should not generate async methods"
+    );
+    assert!(content.contains("pub fn add(&self, int_a: i32, int_b: i32) -> SoapResult<i32>"));
+    assert!(content.contains("pub fn add_wrapped_with_options(&self, request: Add"));
+    assert!(content.contains("soapus_runtime::blocking::block_on(async {"));
+}
+
+#[test]
+fn test_generate_idempotent_operations() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/faults.wsdl")
+        .out_dir(dir.path())
+        .idempotent_operation("GetAccount")
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    assert!(content.contains("pub const IDEMPOTENT_OPERATIONS: &[&str] = &[\"GetAccount\"];"));
+    assert!(content.contains("client.mark_idempotent(*operation);"));
+
+    let unknown = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/faults.wsdl")
+        .out_dir(dir.path())
+        .idempotent_operation("Deposit")
+        .generate();
+    assert!(
+        matches!(unknown, Err(soapus_codegen::CodegenError::OperationNotFound { ref operation_name }) if operation_name == "Deposit")
+    );
+}
+
+/// Without the extension features, the generated code builds against the default runtime
+#[cfg(not(any(
+    feature = "ws-security",
+    feature = "ws-addressing",
+    feature = "mtom",
+    feature = "swa"
+)))]
+#[test]
+fn test_generate_without_extension_features() {
+    let dir = tempdir().unwrap();
+
+    for wsdl in ["ws_addressing.wsdl", "mtom.wsdl", "swa.wsdl"] {
+        let result = SoapClientGenerator::builder()
+            .wsdl_path(format!("../testdata/wsdl/{}", wsdl))
+            .out_dir(dir.path())
+            .generate()
+            .unwrap();
+        let content = fs::read_to_string(&result.output_file).unwrap();
+        for item in [
+            "WsSecurity",
+            "WsAddressing",
+            "Binary",
+            "Attachment",
+            ".mtom(",
+        ] {
+            assert!(!content.contains(item), "{} uses {}", wsdl, item);
+        }
+    }
+}
+
+/// Without the `ws-security` feature, a WS-SecurityPolicy is not silently ignored
+#[cfg(not(feature = "ws-security"))]
+#[test]
+fn test_security_policy_requires_feature() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/ws_security_policy.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(
+        matches!(&result, Err(soapus_codegen::CodegenError::UnsupportedFeature { feature }) if feature.contains("ws-security")),
+        "{:?}",
+        result.map(|gen| gen.output_file)
+    );
+}
+
+#[cfg(feature = "ws-security")]
+#[test]
+fn test_generate_ws_security_from_policy() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/ws_security_policy.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    assert!(content.contains(
+        "pub fn ws_security(username: impl Into<String>, password: impl Into<String>) -> soapus_runtime::WsSecurity {"
+    ));
+    assert!(content.contains("soapus_runtime::UsernameToken::digest(username, password)"));
+    assert!(content.contains(".timestamp(WS_SECURITY_TTL)"));
+    assert!(content.contains("pub fn with_credentials("));
+
+    // Without a policy no WS-Security code is generated
+    let plain = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/calculator.wsdl")
+        .out_dir(dir.path())
+        .generate()
+        .unwrap();
+    let content = fs::read_to_string(&plain.output_file).unwrap();
+    assert!(!content.contains("ws_security"));
+}
+
+#[cfg(feature = "ws-addressing")]
+#[test]
+fn test_generate_ws_addressing() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/ws_addressing.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    assert!(content.contains(".ws_addressing(soapus_runtime::WsAddressing::new())"));
+    // wsam:Action wins over soapAction, the default action fills an empty soapAction
+    assert!(content.contains(r#"Some("http://tempuri.org/IStatusService/GetStatus")"#));
+    assert!(content.contains(r#"Some("http://tempuri.org/IStatusService/PingRequest")"#));
+    assert!(!content.contains("urn:legacy:GetStatus"));
+}
+
+#[cfg(feature = "mtom")]
+#[test]
+fn test_generate_mtom() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/mtom.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    assert!(content.contains(".mtom(true)"));
+    assert!(content.contains("pub content: soapus_runtime::Binary"));
+    assert!(content.contains("pub thumbnail: Option<soapus_runtime::Binary>"));
+    assert!(!content.contains("Vec<u8>"));
+}
+
+#[cfg(feature = "swa")]
+#[test]
+fn test_generate_swa() {
+    let dir = tempdir().unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path("../testdata/wsdl/swa.wsdl")
+        .out_dir(dir.path())
+        .generate();
+
+    assert!(result.is_ok(), "Code generation failed: {:?}", result.err());
+
+    let content = fs::read_to_string(&result.unwrap().output_file).unwrap();
+    // Attachment parts are not serialized into the body
+    assert!(content.contains(
+        "pub async fn submit_claim(&self, request: SubmitClaim, attachments: Vec<soapus_runtime::Attachment>) -> SoapResult<SubmitClaimResponse>"
+    ));
+    assert!(content.contains("`form` (image/tiff), `photos` (image/jpeg, image/png)"));
+    assert!(content.contains("let options = &options.clone().attachments(attachments);"));
+    assert!(content.contains("&[], &parts, options).await?"));
+    assert!(content.contains(
+        "pub async fn get_claim_document(&self, request: GetClaimDocument) -> SoapResult<(GetClaimDocumentResponse, Vec<soapus_runtime::Attachment>)>"
+    ));
+    assert!(content.contains(
+        "let body = (response.part(\"GetClaimDocumentResponse\")?, response.take_attachments());"
+    ));
+    assert!(content.contains(
+        "pub async fn get_claim_document_with_options(&self, request: GetClaimDocument, options: &soapus_runtime::CallOptions) -> SoapResult<soapus_runtime::SoapResponse<(GetClaimDocumentResponse, Vec<soapus_runtime::Attachment>)>>"
+    ));
+    // swaRef is the cid: URL of an attachment
+    assert!(content.contains("pub document: String"));
+}
+
+#[test]
+fn test_unresolved_part_type_is_an_error() {
+    let dir = tempdir().unwrap();
+    let wsdl_path = dir.path().join("broken.wsdl");
+    fs::write(
+        &wsdl_path,
+        r#"<definitions xmlns="http://schemas.xmlsoap.org/wsdl/"
+             xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+             xmlns:tns="urn:broken" targetNamespace="urn:broken">
+  <message name="In"><part name="parameters" element="tns:DoesNotExist"/></message>
+  <portType name="BrokenPort">
+    <operation name="Broken"><input message="tns:In"/></operation>
+  </portType>
+  <service name="Broken"/>
+</definitions>"#,
+    )
+    .unwrap();
+
+    let result = SoapClientGenerator::builder()
+        .wsdl_path(&wsdl_path)
+        .out_dir(dir.path())
+        .generate();
+
+    let err = result.err().expect("Unresolved part type should fail");
+    assert!(
+        err.to_string().contains("tns:DoesNotExist"),
+        "Error should name the unresolved type: {}",
+        err
+    );
+}
+
 #[test]
 fn test_all_wsdls_generate_valid_rust() {
     // Test that all WSDL files generate code that at least compiles syntactically
-    let wsdl_files = vec![
+    let mut wsdl_files = vec![
         ("../testdata/wsdl/calculator.wsdl", "Calculator"),
         ("../testdata/wsdl/countryinfo.wsdl", "CountryInfo"),
         ("../testdata/wsdl/numberconversion.wsdl", "NumberConversion"),
         ("../testdata/wsdl/attributes_test.wsdl", "AttributesTest"),
+        ("../testdata/wsdl/orders_multipart.wsdl", "OrderService"),
+        ("../testdata/wsdl/session_headers.wsdl", "BankService"),
+        ("../testdata/wsdl/faults.wsdl", "AccountService"),
+        ("../testdata/wsdl/ws_addressing.wsdl", "StatusService"),
+        ("../testdata/wsdl/mtom.wsdl", "DocumentArchive"),
+        ("../testdata/wsdl/swa.wsdl", "Claims"),
     ];
+    if cfg!(feature = "ws-security") {
+        wsdl_files.push(("../testdata/wsdl/ws_security_policy.wsdl", "PayrollService"));
+    }
 
     for (wsdl_path, expected_name) in wsdl_files {
         let dir = tempdir().unwrap();
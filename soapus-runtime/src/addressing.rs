@@ -0,0 +1,416 @@
+//! WS-Addressing 1.0
+//!
+//! [`WsAddressing`] adds the WS-Addressing 1.0 message addressing headers to
+//! every request of a [`SoapClient`](crate::SoapClient):
+//!
+//! - `wsa:Action` - the SOAPAction of the call, or the operation name
+//! - `wsa:To` - the endpoint of the call, including an endpoint set in its
+//!   [`CallOptions`](crate::CallOptions)
+//! - `wsa:MessageID` - a fresh `urn:uuid:` for every call
+//! - `wsa:ReplyTo` and `wsa:FaultTo` - where replies and faults are sent,
+//!   anonymous (the HTTP response) unless configured otherwise
+//!
+//! Replies are correlated with their request: a response whose `wsa:RelatesTo`
+//! names a different message fails with [`SoapError::InvalidResponse`].
+//! `RelatesTo` is available from the response headers with
+//! [`SoapHeaders::relates_to`].
+//!
+//! Requires the `ws-addressing` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{SoapClient, WsAddressing};
+//!
+//! let client = SoapClient::builder("http://example.com/Service.svc")
+//!     .ws_addressing(WsAddressing::new())
+//!     .build();
+//! ```
+
+use crate::envelope::{SoapEnvelope, SoapVersion};
+use crate::error::{SoapError, SoapResult};
+use crate::header::{SoapHeader, SoapHeaders};
+use crate::interceptor::{Interceptor, RawResponse, RequestContext, SoapRequest};
+use crate::random::{hex, random_bytes};
+use async_trait::async_trait;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::{NsReader, Reader};
+
+/// Namespace of WS-Addressing 1.0
+pub const WSA_NS: &str = "http://www.w3.org/2005/08/addressing";
+
+/// Address for replies sent back on the HTTP response
+pub const ANONYMOUS: &str = "http://www.w3.org/2005/08/addressing/anonymous";
+
+/// Address for messages that must not be sent at all
+pub const NONE: &str = "http://www.w3.org/2005/08/addressing/none";
+
+/// Relationship type of a reply to a request
+pub const REPLY: &str = "http://www.w3.org/2005/08/addressing/reply";
+
+/// The WS-Addressing headers sent with every request
+///
+/// Registered with
+/// [`SoapClientBuilder::ws_addressing`](crate::client::SoapClientBuilder::ws_addressing).
+/// Unless a destination is set, `wsa:To` is the endpoint of each call.
+#[derive(Debug, Clone)]
+pub struct WsAddressing {
+    to: Option<String>,
+    reply_to: Option<String>,
+    fault_to: Option<String>,
+    must_understand: bool,
+}
+
+impl WsAddressing {
+    /// Send anonymous `ReplyTo` and mark `Action` and `To` as `mustUnderstand`
+    pub fn new() -> Self {
+        Self {
+            to: None,
+            reply_to: Some(ANONYMOUS.to_string()),
+            fault_to: None,
+            must_understand: true,
+        }
+    }
+
+    /// Set the destination (`wsa:To`), if it differs from the endpoint
+    pub fn to(mut self, address: impl Into<String>) -> Self {
+        self.to = Some(address.into());
+        self
+    }
+
+    /// Ask for replies at this address (`wsa:ReplyTo`)
+    pub fn reply_to(mut self, address: impl Into<String>) -> Self {
+        self.reply_to = Some(address.into());
+        self
+    }
+
+    /// Ask for faults at this address (`wsa:FaultTo`), instead of the `ReplyTo` address
+    pub fn fault_to(mut self, address: impl Into<String>) -> Self {
+        self.fault_to = Some(address.into());
+        self
+    }
+
+    /// Set `mustUnderstand` on `Action` and `To` (default true)
+    pub fn must_understand(mut self, must_understand: bool) -> Self {
+        self.must_understand = must_understand;
+        self
+    }
+
+    /// Build the header blocks of a message
+    ///
+    /// `wsa:To` is only sent if a destination is set.
+    pub fn headers(&self, action: &str, message_id: &str) -> Vec<SoapHeader> {
+        self.headers_to(action, message_id, self.to.as_deref())
+    }
+
+    /// Build the header blocks of a message sent to `to`
+    fn headers_to(&self, action: &str, message_id: &str, to: Option<&str>) -> Vec<SoapHeader> {
+        let mut headers = vec![
+            SoapHeader::raw("Action", element("Action", action))
+                .must_understand(self.must_understand),
+            SoapHeader::raw("MessageID", element("MessageID", message_id)),
+        ];
+        if let Some(reply_to) = &self.reply_to {
+            headers.push(SoapHeader::raw(
+                "ReplyTo",
+                endpoint_reference("ReplyTo", reply_to),
+            ));
+        }
+        if let Some(fault_to) = &self.fault_to {
+            headers.push(SoapHeader::raw(
+                "FaultTo",
+                endpoint_reference("FaultTo", fault_to),
+            ));
+        }
+        if let Some(to) = to {
+            headers.push(
+                SoapHeader::raw("To", element("To", to)).must_understand(self.must_understand),
+            );
+        }
+        headers
+    }
+}
+
+impl Default for WsAddressing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Interceptor for WsAddressing {
+    async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+        let action = context
+            .soap_action
+            .clone()
+            .unwrap_or_else(|| context.operation.clone());
+        let message_id = message_id()?;
+
+        context
+            .headers
+            .retain(|header| !HEADER_NAMES.contains(&header.name()));
+        let to = self.to.as_deref().unwrap_or(&context.endpoint);
+        context
+            .headers
+            .extend(self.headers_to(&action, &message_id, Some(to)));
+        Ok(())
+    }
+
+    async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+        // SOAP 1.2 carries the action as parameter of the media type
+        if request.soap_version == SoapVersion::Soap12 {
+            if let Some(action) = header_text(&request.envelope, "Action")? {
+                let content_type = request.header("Content-Type").unwrap_or_default();
+                if !content_type.contains("action=") {
+                    let content_type = format!("{}; action=\"{}\"", content_type, action);
+                    request.set_header("Content-Type", content_type);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn after_receive(
+        &self,
+        request: &SoapRequest,
+        response: &mut RawResponse,
+    ) -> SoapResult<()> {
+        let Some(message_id) = header_text(&request.envelope, "MessageID")? else {
+            return Ok(());
+        };
+        // Malformed responses are reported when the body is parsed
+        let Ok(headers) = SoapEnvelope::parse_response_headers(&response.body) else {
+            return Ok(());
+        };
+        match headers.relates_to()? {
+            Some(relates_to) if relates_to.is_reply() && relates_to.message_id != message_id => {
+                Err(SoapError::InvalidResponse(format!(
+                    "Response relates to message '{}', expected '{}'",
+                    relates_to.message_id, message_id
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A `wsa:RelatesTo` header of a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatesTo {
+    /// The `MessageID` of the related message
+    pub message_id: String,
+    /// The relationship type, [`REPLY`] if not given
+    pub relationship_type: String,
+}
+
+impl RelatesTo {
+    /// Check whether the message is a reply to the related message
+    pub fn is_reply(&self) -> bool {
+        self.relationship_type == REPLY
+    }
+}
+
+impl SoapHeaders {
+    /// Get the WS-Addressing `RelatesTo` header block
+    ///
+    /// Returns `Ok(None)` if the response has no `RelatesTo` header.
+    pub fn relates_to(&self) -> SoapResult<Option<RelatesTo>> {
+        let Some(xml) = self.raw("RelatesTo") else {
+            return Ok(None);
+        };
+
+        let mut reader = Reader::from_str(xml);
+        let mut relationship_type = REPLY.to_string();
+        let mut message_id = String::new();
+        loop {
+            match reader.read_event().map_err(xml_error)? {
+                Event::Start(e) | Event::Empty(e) => {
+                    for attr in e.attributes().with_checks(false) {
+                        let attr = attr.map_err(xml_error)?;
+                        if attr.key.local_name().as_ref() == b"RelationshipType" {
+                            relationship_type = attr.unescape_value().map_err(xml_error)?.into();
+                        }
+                    }
+                }
+                Event::Text(e) => message_id.push_str(&e.unescape().map_err(xml_error)?),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(Some(RelatesTo {
+            message_id: message_id.trim().to_string(),
+            relationship_type,
+        }))
+    }
+}
+
+/// Generate a message id, `urn:uuid:` followed by a random UUID
+pub fn message_id() -> SoapResult<String> {
+    let mut bytes = random_bytes::<16>()?;
+    // UUID version 4, variant RFC 4122
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = hex(&bytes);
+    Ok(format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// Names of the header blocks replaced on every request
+const HEADER_NAMES: &[&str] = &["Action", "MessageID", "ReplyTo", "FaultTo", "To"];
+
+fn element(name: &str, value: &str) -> String {
+    format!(
+        "<wsa:{} xmlns:wsa=\"{}\">{}</wsa:{}>",
+        name,
+        WSA_NS,
+        escape(value),
+        name
+    )
+}
+
+fn endpoint_reference(name: &str, address: &str) -> String {
+    format!(
+        "<wsa:{} xmlns:wsa=\"{}\"><wsa:Address>{}</wsa:Address></wsa:{}>",
+        name,
+        WSA_NS,
+        escape(address),
+        name
+    )
+}
+
+/// The text of a WS-Addressing header block of an envelope, whatever its prefix
+fn header_text(envelope: &str, name: &str) -> SoapResult<Option<String>> {
+    let mut reader = NsReader::from_str(envelope);
+    let mut depth = 0;
+    let mut in_header = false;
+    let mut text: Option<String> = None;
+    loop {
+        match reader.read_resolved_event().map_err(xml_error)? {
+            (ns, Event::Start(e)) => {
+                depth += 1;
+                match depth {
+                    2 => in_header = e.local_name().as_ref() == b"Header",
+                    3 if in_header
+                        && text.is_none()
+                        && ns == ResolveResult::Bound(Namespace(WSA_NS.as_bytes()))
+                        && e.local_name().as_ref() == name.as_bytes() =>
+                    {
+                        text = Some(String::new());
+                    }
+                    _ => {}
+                }
+            }
+            (_, Event::Text(e)) => {
+                if let Some(text) = text.as_mut() {
+                    text.push_str(&e.unescape().map_err(xml_error)?);
+                }
+            }
+            (_, Event::End(_)) => {
+                if depth == 3 && text.is_some() {
+                    return Ok(text.map(|text| text.trim().to_string()));
+                }
+                if depth == 2 && in_header {
+                    return Ok(None);
+                }
+                depth -= 1;
+            }
+            (_, Event::Eof) => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+fn xml_error(e: impl std::fmt::Display) -> SoapError {
+    SoapError::XmlError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers() {
+        let addressing = WsAddressing::new().fault_to("http://client/faults");
+        let xml: Vec<String> = addressing
+            .headers_to(
+                "http://tempuri.org/IService/Op",
+                "urn:uuid:1",
+                Some("http://service/a?b&c"),
+            )
+            .iter()
+            .map(|header| header.to_xml(SoapVersion::Soap12))
+            .collect();
+
+        assert_eq!(
+            xml[0],
+            "<wsa:Action env:mustUnderstand=\"true\" xmlns:wsa=\"http://www.w3.org/2005/08/addressing\">http://tempuri.org/IService/Op</wsa:Action>"
+        );
+        assert!(xml[1].ends_with(">urn:uuid:1</wsa:MessageID>"));
+        assert!(xml[2].ends_with("><wsa:Address>http://www.w3.org/2005/08/addressing/anonymous</wsa:Address></wsa:ReplyTo>"));
+        assert!(xml[3].ends_with("><wsa:Address>http://client/faults</wsa:Address></wsa:FaultTo>"));
+        assert!(xml[4].starts_with("<wsa:To env:mustUnderstand=\"true\""));
+        assert!(xml[4].ends_with(">http://service/a?b&amp;c</wsa:To>"));
+
+        // Without a destination `wsa:To` is left out
+        let addressing = WsAddressing::new();
+        assert_eq!(addressing.headers("a", "m").len(), 3);
+        let addressing = addressing.to("urn:dest");
+        assert!(addressing.headers("a", "m")[3]
+            .to_xml(SoapVersion::Soap11)
+            .contains(">urn:dest<"));
+    }
+
+    #[test]
+    fn test_message_id() {
+        let id = message_id().unwrap();
+        assert_eq!(id.len(), 45);
+        assert!(id.starts_with("urn:uuid:"));
+        assert_eq!(&id[23..24], "4");
+        assert_ne!(id, message_id().unwrap());
+    }
+
+    #[test]
+    fn test_header_text() {
+        let envelope = "<s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\"><s:Header><Action>urn:other</Action><a:MessageID xmlns:a=\"http://www.w3.org/2005/08/addressing\"> urn:uuid:1 </a:MessageID><a:Action xmlns:a=\"http://www.w3.org/2005/08/addressing\">urn:a&amp;b</a:Action></s:Header><s:Body><a:To xmlns:a=\"http://www.w3.org/2005/08/addressing\">urn:body</a:To></s:Body></s:Envelope>";
+
+        assert_eq!(
+            header_text(envelope, "Action").unwrap().as_deref(),
+            Some("urn:a&b")
+        );
+        assert_eq!(
+            header_text(envelope, "MessageID").unwrap().as_deref(),
+            Some("urn:uuid:1")
+        );
+        // Only header blocks count
+        assert_eq!(header_text(envelope, "To").unwrap(), None);
+    }
+
+    #[test]
+    fn test_relates_to() {
+        let headers = SoapHeaders::new(vec![(
+            "RelatesTo".to_string(),
+            "<a:RelatesTo xmlns:a=\"http://www.w3.org/2005/08/addressing\"> urn:uuid:1 </a:RelatesTo>"
+                .to_string(),
+        )]);
+        let relates_to = headers.relates_to().unwrap().unwrap();
+        assert_eq!(relates_to.message_id, "urn:uuid:1");
+        assert!(relates_to.is_reply());
+
+        let headers = SoapHeaders::new(vec![(
+            "RelatesTo".to_string(),
+            "<RelatesTo RelationshipType=\"urn:other\">urn:uuid:2</RelatesTo>".to_string(),
+        )]);
+        assert!(!headers.relates_to().unwrap().unwrap().is_reply());
+        assert_eq!(SoapHeaders::default().relates_to().unwrap(), None);
+    }
+}
@@ -0,0 +1,1223 @@
+//! HTTP authentication
+//!
+//! [`HttpAuth`] adds HTTP authentication to every request of a
+//! [`SoapClient`](crate::SoapClient), independent of the transport:
+//!
+//! - **Basic** - credentials are sent with every request
+//! - **Digest** (RFC 7616) - MD5 and SHA-256, `qop=auth` and `auth-int`; the
+//!   challenge of a `401` response is answered and reused for later requests
+//! - **NTLMv2** - the negotiate/challenge/authenticate handshake is run for
+//!   every request, as expected by IIS, on one connection opened with
+//!   [`SoapTransport::connection`]; transports without connections, such as
+//!   a [`ReqwestTransport::with_client`](crate::transport::ReqwestTransport::with_client),
+//!   fail with [`SoapError::AuthError`]
+//! - **Bearer** - tokens from a [`TokenProvider`], refreshed once when the
+//!   server answers `401`
+//!
+//! Streamed requests (feature `streaming`) can't be sent twice: they are
+//! buffered for Digest and NTLM, and Bearer tokens aren't refreshed for them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{HttpAuth, SoapClient};
+//!
+//! let client = SoapClient::builder("http://intranet/Service.svc")
+//!     .auth(HttpAuth::ntlm("CORP", "svc-soap", "secret"))
+//!     .build();
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use crate::random::{hex, random_bytes};
+#[cfg(feature = "streaming")]
+use crate::transport::{send_buffered, StreamingRequest, StreamingResponse};
+use crate::transport::{SoapConnection, SoapTransport, TransportRequest, TransportResponse};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use md4::Md4;
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Provides bearer tokens
+///
+/// Use [`macro@async_trait`](crate::async_trait) on the impl block.
+#[async_trait]
+pub trait TokenProvider: Send + Sync {
+    /// Get the token to send
+    async fn token(&self) -> SoapResult<String>;
+
+    /// Get a new token after the server rejected the current one
+    async fn refresh(&self) -> SoapResult<String>;
+}
+
+/// A token that never changes
+struct StaticToken(String);
+
+#[async_trait]
+impl TokenProvider for StaticToken {
+    async fn token(&self) -> SoapResult<String> {
+        Ok(self.0.clone())
+    }
+
+    async fn refresh(&self) -> SoapResult<String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// HTTP authentication scheme and credentials
+#[derive(Clone)]
+pub struct HttpAuth {
+    scheme: Scheme,
+}
+
+#[derive(Clone)]
+enum Scheme {
+    Basic(Credentials),
+    Digest(Credentials),
+    Ntlm {
+        credentials: Credentials,
+        domain: String,
+        workstation: String,
+    },
+    Bearer(Arc<dyn TokenProvider>),
+}
+
+#[derive(Clone)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl HttpAuth {
+    /// HTTP Basic authentication
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            scheme: Scheme::Basic(Credentials::new(username, password)),
+        }
+    }
+
+    /// HTTP Digest authentication (RFC 7616)
+    pub fn digest(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            scheme: Scheme::Digest(Credentials::new(username, password)),
+        }
+    }
+
+    /// NTLMv2 authentication for a Windows domain account
+    pub fn ntlm(
+        domain: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        Self {
+            scheme: Scheme::Ntlm {
+                credentials: Credentials::new(username, password),
+                domain: domain.into(),
+                workstation: String::new(),
+            },
+        }
+    }
+
+    /// Set the workstation name sent with NTLM authentication
+    pub fn workstation(mut self, name: impl Into<String>) -> Self {
+        if let Scheme::Ntlm { workstation, .. } = &mut self.scheme {
+            *workstation = name.into();
+        }
+        self
+    }
+
+    /// Bearer authentication with a fixed token
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::bearer_provider(StaticToken(token.into()))
+    }
+
+    /// Bearer authentication with tokens from a provider
+    pub fn bearer_provider(provider: impl TokenProvider + 'static) -> Self {
+        Self {
+            scheme: Scheme::Bearer(Arc::new(provider)),
+        }
+    }
+}
+
+impl fmt::Debug for HttpAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print passwords or tokens
+        match &self.scheme {
+            Scheme::Basic(c) => write!(f, "HttpAuth::Basic({})", c.username),
+            Scheme::Digest(c) => write!(f, "HttpAuth::Digest({})", c.username),
+            Scheme::Ntlm {
+                credentials,
+                domain,
+                ..
+            } => write!(f, "HttpAuth::Ntlm({}\\{})", domain, credentials.username),
+            Scheme::Bearer(_) => f.write_str("HttpAuth::Bearer"),
+        }
+    }
+}
+
+impl Credentials {
+    fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+}
+
+/// Transport adding HTTP authentication to an inner transport
+pub(crate) struct AuthTransport {
+    inner: Arc<dyn SoapTransport>,
+    auth: HttpAuth,
+    /// The last Digest challenge, reused for later requests
+    digest: Mutex<Option<DigestSession>>,
+}
+
+impl AuthTransport {
+    pub(crate) fn new(inner: Arc<dyn SoapTransport>, auth: HttpAuth) -> Self {
+        Self {
+            inner,
+            auth,
+            digest: Mutex::new(None),
+        }
+    }
+
+    async fn send_bearer(
+        &self,
+        provider: &dyn TokenProvider,
+        mut request: TransportRequest,
+    ) -> SoapResult<TransportResponse> {
+        let token = provider.token().await?;
+        set_header(&mut request.headers, "Bearer", &token);
+        let response = self.inner.send(request.clone()).await?;
+        if response.status != 401 {
+            return Ok(response);
+        }
+
+        let token = provider.refresh().await?;
+        set_header(&mut request.headers, "Bearer", &token);
+        self.inner.send(request).await
+    }
+
+    async fn send_digest(
+        &self,
+        credentials: &Credentials,
+        mut request: TransportRequest,
+    ) -> SoapResult<TransportResponse> {
+        // Answer the last challenge right away to save a round trip
+        let sent_nonce = {
+            let mut session = self.lock_digest();
+            session.as_mut().map(|session| {
+                session.nc += 1;
+                let header = session.authorization(credentials, &request);
+                set_header(&mut request.headers, "Digest", &header);
+                session.challenge.nonce.clone()
+            })
+        };
+
+        let response = self.inner.send(request.clone()).await?;
+        if response.status != 401 {
+            return Ok(response);
+        }
+        let Some(challenge) = DigestChallenge::select(&response) else {
+            return Ok(response);
+        };
+        // The same nonce without `stale` means the credentials were rejected
+        if sent_nonce.as_ref() == Some(&challenge.nonce) && !challenge.stale {
+            return Ok(response);
+        }
+
+        let header = {
+            let mut session = self.lock_digest();
+            let session = session.insert(DigestSession { challenge, nc: 1 });
+            session.authorization(credentials, &request)
+        };
+        set_header(&mut request.headers, "Digest", &header);
+        self.inner.send(request).await
+    }
+
+    async fn send_ntlm(
+        &self,
+        credentials: &Credentials,
+        domain: &str,
+        workstation: &str,
+        mut request: TransportRequest,
+    ) -> SoapResult<TransportResponse> {
+        // The handshake authenticates the connection, not the request
+        let mut connection = self.ntlm_connection()?;
+        set_header(
+            &mut request.headers,
+            "NTLM",
+            &BASE64.encode(ntlm::negotiate_message()),
+        );
+        let response = connection.send(request.clone()).await?;
+        if response.status != 401 {
+            return Ok(response);
+        }
+        let Some(token) = challenges(&response, "NTLM").find(|token| !token.is_empty()) else {
+            return Ok(response);
+        };
+
+        let message = BASE64
+            .decode(token)
+            .map_err(|e| SoapError::AuthError(format!("Invalid NTLM challenge: {}", e)))?;
+        let challenge = ntlm::Challenge::parse(&message)?;
+        let authenticate = ntlm::authenticate_message(
+            &challenge,
+            domain,
+            &credentials.username,
+            &credentials.password,
+            workstation,
+            ntlm::timestamp(),
+            random_bytes()?,
+        );
+
+        set_header(&mut request.headers, "NTLM", &BASE64.encode(authenticate));
+        connection.send(request).await
+    }
+
+    fn ntlm_connection(&self) -> SoapResult<Box<dyn SoapConnection>> {
+        self.inner.connection()?.ok_or_else(|| {
+            SoapError::AuthError(
+                "NTLM requires a transport that can keep a connection open".to_string(),
+            )
+        })
+    }
+
+    fn lock_digest(&self) -> std::sync::MutexGuard<'_, Option<DigestSession>> {
+        self.digest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl SoapTransport for AuthTransport {
+    async fn send(&self, mut request: TransportRequest) -> SoapResult<TransportResponse> {
+        match &self.auth.scheme {
+            Scheme::Basic(credentials) => {
+                let token =
+                    BASE64.encode(format!("{}:{}", credentials.username, credentials.password));
+                set_header(&mut request.headers, "Basic", &token);
+                self.inner.send(request).await
+            }
+            Scheme::Digest(credentials) => self.send_digest(credentials, request).await,
+            Scheme::Ntlm {
+                credentials,
+                domain,
+                workstation,
+            } => {
+                self.send_ntlm(credentials, domain, workstation, request)
+                    .await
+            }
+            Scheme::Bearer(provider) => self.send_bearer(provider.as_ref(), request).await,
+        }
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn send_streaming(&self, mut request: StreamingRequest) -> SoapResult<StreamingResponse> {
+        match &self.auth.scheme {
+            Scheme::Basic(credentials) => {
+                let token =
+                    BASE64.encode(format!("{}:{}", credentials.username, credentials.password));
+                set_header(&mut request.headers, "Basic", &token);
+                self.inner.send_streaming(request).await
+            }
+            Scheme::Bearer(provider) => {
+                let token = provider.token().await?;
+                set_header(&mut request.headers, "Bearer", &token);
+                self.inner.send_streaming(request).await
+            }
+            // The handshakes send the request more than once
+            Scheme::Digest(_) | Scheme::Ntlm { .. } => send_buffered(self, request).await,
+        }
+    }
+}
+
+/// Set the `Authorization` header to `{scheme} {value}`
+fn set_header(headers: &mut Vec<(String, String)>, scheme: &str, value: &str) {
+    headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Authorization"));
+    headers.push(("Authorization".to_string(), format!("{} {}", scheme, value)));
+}
+
+/// The parameters of all `WWW-Authenticate` challenges for a scheme
+fn challenges<'a>(
+    response: &'a TransportResponse,
+    scheme: &'a str,
+) -> impl Iterator<Item = &'a str> + 'a {
+    response
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("WWW-Authenticate"))
+        .filter_map(move |(_, value)| {
+            let value = value.trim();
+            let (name, rest) = value.split_once(' ').unwrap_or((value, ""));
+            name.eq_ignore_ascii_case(scheme).then(|| rest.trim())
+        })
+}
+
+/// Parse `name=value, name="quoted value"` auth parameters, names lowercased
+fn parse_params(input: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut rest = input;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        let Some((name, after)) = rest.split_once('=') else {
+            break;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let after = after.trim_start();
+
+        let value;
+        if let Some(quoted) = after.strip_prefix('"') {
+            let mut unescaped = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            unescaped.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    _ => unescaped.push(c),
+                }
+            }
+            value = unescaped;
+            rest = &quoted[end..];
+        } else {
+            let end = after.find(',').unwrap_or(after.len());
+            value = after[..end].trim().to_string();
+            rest = &after[end..];
+        }
+        params.push((name, value));
+    }
+
+    params
+}
+
+/// The path and query of an endpoint URL, used as Digest `uri`
+fn request_uri(endpoint: &str) -> &str {
+    let without_scheme = endpoint
+        .split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(endpoint);
+    let uri = without_scheme
+        .find('/')
+        .map(|i| &without_scheme[i..])
+        .unwrap_or("/");
+    uri.split('#').next().unwrap_or(uri)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    fn hash(self, data: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 => hex(&Md5::digest(data.as_bytes())),
+            DigestAlgorithm::Sha256 => hex(&Sha256::digest(data.as_bytes())),
+        }
+    }
+
+    fn hash_bytes(self, data: &[u8]) -> String {
+        match self {
+            DigestAlgorithm::Md5 => hex(&Md5::digest(data)),
+            DigestAlgorithm::Sha256 => hex(&Sha256::digest(data)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Qop {
+    Auth,
+    AuthInt,
+}
+
+/// A `WWW-Authenticate: Digest` challenge
+#[derive(Debug, Clone, PartialEq)]
+struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: DigestAlgorithm,
+    /// The `-sess` variant of the algorithm
+    session: bool,
+    qop: Option<Qop>,
+    stale: bool,
+}
+
+impl DigestChallenge {
+    fn parse(params: &str) -> Option<Self> {
+        let params = parse_params(params);
+        let get = |name: &str| {
+            params
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        let algorithm_name = get("algorithm").unwrap_or("MD5");
+        let (algorithm, session) = match algorithm_name.to_ascii_uppercase().as_str() {
+            "MD5" => (DigestAlgorithm::Md5, false),
+            "MD5-SESS" => (DigestAlgorithm::Md5, true),
+            "SHA-256" => (DigestAlgorithm::Sha256, false),
+            "SHA-256-SESS" => (DigestAlgorithm::Sha256, true),
+            _ => return None,
+        };
+
+        let qop = get("qop").and_then(|qop| {
+            let options: Vec<&str> = qop.split(',').map(str::trim).collect();
+            if options.contains(&"auth") {
+                Some(Qop::Auth)
+            } else if options.contains(&"auth-int") {
+                Some(Qop::AuthInt)
+            } else {
+                None
+            }
+        });
+
+        Some(Self {
+            realm: get("realm").unwrap_or_default().to_string(),
+            nonce: get("nonce")?.to_string(),
+            opaque: get("opaque").map(str::to_string),
+            algorithm,
+            session,
+            qop,
+            stale: get("stale").is_some_and(|s| s.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    /// The strongest supported Digest challenge of a response
+    fn select(response: &TransportResponse) -> Option<Self> {
+        challenges(response, "Digest")
+            .filter_map(Self::parse)
+            .max_by_key(|c| c.algorithm == DigestAlgorithm::Sha256)
+    }
+
+    fn algorithm_name(&self) -> &'static str {
+        match (self.algorithm, self.session) {
+            (DigestAlgorithm::Md5, false) => "MD5",
+            (DigestAlgorithm::Md5, true) => "MD5-sess",
+            (DigestAlgorithm::Sha256, false) => "SHA-256",
+            (DigestAlgorithm::Sha256, true) => "SHA-256-sess",
+        }
+    }
+
+    /// Compute the `response` value (RFC 7616, section 3.4.1)
+    fn response(
+        &self,
+        credentials: &Credentials,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+        nc: &str,
+        cnonce: &str,
+    ) -> String {
+        let h = |data: &str| self.algorithm.hash(data);
+
+        let mut ha1 = h(&format!(
+            "{}:{}:{}",
+            credentials.username, self.realm, credentials.password
+        ));
+        if self.session {
+            ha1 = h(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+
+        let ha2 = match self.qop {
+            Some(Qop::AuthInt) => h(&format!(
+                "{}:{}:{}",
+                method,
+                uri,
+                self.algorithm.hash_bytes(body)
+            )),
+            _ => h(&format!("{}:{}", method, uri)),
+        };
+
+        match self.qop {
+            Some(qop) => h(&format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1,
+                self.nonce,
+                nc,
+                cnonce,
+                match qop {
+                    Qop::Auth => "auth",
+                    Qop::AuthInt => "auth-int",
+                },
+                ha2
+            )),
+            None => h(&format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        }
+    }
+}
+
+/// A Digest challenge and the number of requests that answered it
+struct DigestSession {
+    challenge: DigestChallenge,
+    nc: u32,
+}
+
+impl DigestSession {
+    /// Build the `Authorization` parameters for a request
+    fn authorization(&self, credentials: &Credentials, request: &TransportRequest) -> String {
+        let challenge = &self.challenge;
+        let uri = request_uri(&request.endpoint);
+        let nc = format!("{:08x}", self.nc);
+        let cnonce = random_bytes::<16>()
+            .map(|bytes| hex(&bytes))
+            .unwrap_or_else(|_| format!("{:016x}", self.nc));
+        let response = challenge.response(credentials, "POST", uri, &request.body, &nc, &cnonce);
+
+        let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut header = format!(
+            "username=\"{}\", realm=\"{}\", uri=\"{}\", algorithm={}, nonce=\"{}\"",
+            quote(&credentials.username),
+            quote(&challenge.realm),
+            quote(uri),
+            challenge.algorithm_name(),
+            quote(&challenge.nonce),
+        );
+        if let Some(qop) = challenge.qop {
+            header.push_str(&format!(
+                ", nc={}, cnonce=\"{}\", qop={}",
+                nc,
+                cnonce,
+                match qop {
+                    Qop::Auth => "auth",
+                    Qop::AuthInt => "auth-int",
+                }
+            ));
+        }
+        header.push_str(&format!(", response=\"{}\"", response));
+        if let Some(opaque) = &challenge.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        header
+    }
+}
+
+/// NTLMv2 messages (MS-NLMP)
+mod ntlm {
+    use super::*;
+
+    pub(super) const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+    const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+    const REQUEST_TARGET: u32 = 0x0000_0004;
+    const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+    const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+    const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+    const NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+    const NEGOTIATE_128: u32 = 0x2000_0000;
+    const NEGOTIATE_56: u32 = 0x8000_0000;
+
+    const FLAGS: u32 = NEGOTIATE_UNICODE
+        | REQUEST_TARGET
+        | NEGOTIATE_NTLM
+        | NEGOTIATE_ALWAYS_SIGN
+        | NEGOTIATE_EXTENDED_SESSIONSECURITY
+        | NEGOTIATE_TARGET_INFO
+        | NEGOTIATE_128
+        | NEGOTIATE_56;
+
+    /// AV pair id of the server timestamp in the target info
+    const MSV_AV_TIMESTAMP: u16 = 7;
+
+    /// Seconds between 1601-01-01 and 1970-01-01
+    const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+    /// The NEGOTIATE_MESSAGE, without domain and workstation
+    pub(super) fn negotiate_message() -> Vec<u8> {
+        let mut message = Vec::with_capacity(32);
+        message.extend_from_slice(SIGNATURE);
+        message.extend_from_slice(&1u32.to_le_bytes());
+        message.extend_from_slice(&FLAGS.to_le_bytes());
+        // Empty domain and workstation fields
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&[0; 8]);
+        message
+    }
+
+    /// A CHALLENGE_MESSAGE from the server
+    #[derive(Debug, Clone, PartialEq)]
+    pub(super) struct Challenge {
+        pub(super) flags: u32,
+        pub(super) server_challenge: [u8; 8],
+        pub(super) target_info: Vec<u8>,
+    }
+
+    impl Challenge {
+        pub(super) fn parse(message: &[u8]) -> SoapResult<Self> {
+            let invalid = || SoapError::AuthError("Invalid NTLM challenge message".to_string());
+
+            if message.len() < 32 || &message[..8] != SIGNATURE || read_u32(message, 8) != 2 {
+                return Err(invalid());
+            }
+
+            let flags = read_u32(message, 20);
+            let mut server_challenge = [0u8; 8];
+            server_challenge.copy_from_slice(&message[24..32]);
+
+            let target_info = if message.len() >= 48 {
+                let len = read_u16(message, 40) as usize;
+                let offset = read_u32(message, 44) as usize;
+                message
+                    .get(offset..offset + len)
+                    .ok_or_else(invalid)?
+                    .to_vec()
+            } else {
+                Vec::new()
+            };
+
+            Ok(Self {
+                flags,
+                server_challenge,
+                target_info,
+            })
+        }
+
+        /// The server timestamp from the target info, if any
+        fn timestamp(&self) -> Option<[u8; 8]> {
+            let info = &self.target_info;
+            let mut pos = 0;
+            while pos + 4 <= info.len() {
+                let id = read_u16(info, pos);
+                let len = read_u16(info, pos + 2) as usize;
+                let value = info.get(pos + 4..pos + 4 + len)?;
+                match id {
+                    0 => return None,
+                    MSV_AV_TIMESTAMP if len == 8 => {
+                        let mut timestamp = [0u8; 8];
+                        timestamp.copy_from_slice(value);
+                        return Some(timestamp);
+                    }
+                    _ => pos += 4 + len,
+                }
+            }
+            None
+        }
+    }
+
+    /// The current time in 100ns intervals since 1601-01-01
+    pub(super) fn timestamp() -> [u8; 8] {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let intervals = (since_epoch.as_secs() + FILETIME_UNIX_OFFSET) * 10_000_000
+            + u64::from(since_epoch.subsec_nanos() / 100);
+        intervals.to_le_bytes()
+    }
+
+    fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+        let mut mac = Hmac::<Md5>::new_from_slice(key).expect("HMAC accepts any key length");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    pub(super) fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    /// NTOWFv2: the NTLMv2 response key
+    pub(super) fn ntowf_v2(domain: &str, username: &str, password: &str) -> [u8; 16] {
+        let nt_hash = Md4::digest(utf16(password));
+        let identity = format!("{}{}", username.to_uppercase(), domain);
+        hmac_md5(&nt_hash, &[&utf16(&identity)])
+    }
+
+    /// The LMv2 and NTLMv2 challenge responses
+    pub(super) fn responses(
+        key: &[u8; 16],
+        server_challenge: &[u8; 8],
+        client_challenge: &[u8; 8],
+        timestamp: &[u8; 8],
+        target_info: &[u8],
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut temp = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        temp.extend_from_slice(timestamp);
+        temp.extend_from_slice(client_challenge);
+        temp.extend_from_slice(&[0; 4]);
+        temp.extend_from_slice(target_info);
+        temp.extend_from_slice(&[0; 4]);
+
+        let proof = hmac_md5(key, &[server_challenge, &temp]);
+        let mut nt_response = proof.to_vec();
+        nt_response.extend_from_slice(&temp);
+
+        let mut lm_response = hmac_md5(key, &[server_challenge, client_challenge]).to_vec();
+        lm_response.extend_from_slice(client_challenge);
+
+        (lm_response, nt_response)
+    }
+
+    /// The AUTHENTICATE_MESSAGE answering a challenge
+    pub(super) fn authenticate_message(
+        challenge: &Challenge,
+        domain: &str,
+        username: &str,
+        password: &str,
+        workstation: &str,
+        timestamp: [u8; 8],
+        client_challenge: [u8; 8],
+    ) -> Vec<u8> {
+        let key = ntowf_v2(domain, username, password);
+        let server_timestamp = challenge.timestamp();
+        let (mut lm_response, nt_response) = responses(
+            &key,
+            &challenge.server_challenge,
+            &client_challenge,
+            &server_timestamp.unwrap_or(timestamp),
+            &challenge.target_info,
+        );
+        // With a server timestamp the LMv2 response must not be sent
+        if server_timestamp.is_some() {
+            lm_response = vec![0; 24];
+        }
+
+        let fields = [
+            lm_response,
+            nt_response,
+            utf16(domain),
+            utf16(username),
+            utf16(workstation),
+            Vec::new(),
+        ];
+
+        const HEADER_LEN: usize = 64;
+        let mut message =
+            Vec::with_capacity(HEADER_LEN + fields.iter().map(Vec::len).sum::<usize>());
+        message.extend_from_slice(SIGNATURE);
+        message.extend_from_slice(&3u32.to_le_bytes());
+
+        let mut offset = HEADER_LEN;
+        for field in &fields {
+            let len = field.len() as u16;
+            message.extend_from_slice(&len.to_le_bytes());
+            message.extend_from_slice(&len.to_le_bytes());
+            message.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += field.len();
+        }
+        let flags = (challenge.flags & FLAGS) | NEGOTIATE_UNICODE | NEGOTIATE_NTLM;
+        message.extend_from_slice(&flags.to_le_bytes());
+
+        for field in &fields {
+            message.extend_from_slice(field);
+        }
+        message
+    }
+
+    pub(super) fn read_u16(data: &[u8], pos: usize) -> u16 {
+        u16::from_le_bytes([data[pos], data[pos + 1]])
+    }
+
+    pub(super) fn read_u32(data: &[u8], pos: usize) -> u32 {
+        u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn unhex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn request() -> TransportRequest {
+        TransportRequest {
+            endpoint: "http://example.com/service.asmx?wsdl".to_string(),
+            headers: Vec::new(),
+            body: b"<Envelope/>".to_vec(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    fn authorization(request: &TransportRequest) -> Option<&str> {
+        request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Authorization")
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn auth_transport(stub: &MemoryTransport, auth: HttpAuth) -> AuthTransport {
+        AuthTransport::new(Arc::new(stub.clone()), auth)
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let params = parse_params(
+            r#"realm="a \"b\", c", qop="auth,auth-int", algorithm=SHA-256, stale=TRUE"#,
+        );
+        assert_eq!(
+            params,
+            vec![
+                ("realm".to_string(), "a \"b\", c".to_string()),
+                ("qop".to_string(), "auth,auth-int".to_string()),
+                ("algorithm".to_string(), "SHA-256".to_string()),
+                ("stale".to_string(), "TRUE".to_string()),
+            ]
+        );
+        assert_eq!(request_uri("http://host:8080/a/b?x=1#f"), "/a/b?x=1");
+        assert_eq!(request_uri("https://host"), "/");
+    }
+
+    #[test]
+    fn test_digest_rfc7616_example() {
+        // RFC 7616, section 3.9.1
+        let credentials = Credentials::new("Mufasa", "Circle of Life");
+        let params = r#"realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+        let mut challenge = DigestChallenge::parse(params).unwrap();
+        assert_eq!(challenge.qop, Some(Qop::Auth));
+
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        assert_eq!(
+            challenge.response(
+                &credentials,
+                "GET",
+                "/dir/index.html",
+                b"",
+                "00000001",
+                cnonce
+            ),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+
+        challenge.algorithm = DigestAlgorithm::Md5;
+        assert_eq!(
+            challenge.response(
+                &credentials,
+                "GET",
+                "/dir/index.html",
+                b"",
+                "00000001",
+                cnonce
+            ),
+            "8ca523f5e9506fed4657c9700eebdbec"
+        );
+    }
+
+    #[test]
+    fn test_ntlmv2_ms_nlmp_example() {
+        // MS-NLMP, section 4.2.4
+        let key = ntlm::ntowf_v2("Domain", "User", "Password");
+        assert_eq!(key.to_vec(), unhex("0c868a403bfd7a93a3001ef22ef02e3f"));
+
+        let mut target_info = vec![0x02, 0x00, 0x0c, 0x00];
+        target_info.extend(ntlm::utf16("Domain"));
+        target_info.extend([0x01, 0x00, 0x0c, 0x00]);
+        target_info.extend(ntlm::utf16("Server"));
+        target_info.extend([0x00, 0x00, 0x00, 0x00]);
+
+        let (lm, nt) = ntlm::responses(
+            &key,
+            &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+            &[0xaa; 8],
+            &[0; 8],
+            &target_info,
+        );
+        assert_eq!(
+            lm,
+            unhex("86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa")
+        );
+        assert_eq!(nt[..16].to_vec(), unhex("68cd0ab851e51c96aabc927bebef6a1c"));
+    }
+
+    #[tokio::test]
+    async fn test_basic() {
+        let stub = MemoryTransport::with_handler(|request| {
+            assert_eq!(authorization(request), Some("Basic dXNlcjpwYXNz"));
+            Ok(TransportResponse::new(200, "ok"))
+        });
+        let transport = auth_transport(&stub, HttpAuth::basic("user", "pass"));
+
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_digest_challenge_flow() {
+        let stub = MemoryTransport::with_handler(|request| match authorization(request) {
+            Some(auth) if auth.starts_with("Digest ") => Ok(TransportResponse::new(200, "ok")),
+            _ => Ok(TransportResponse::new(401, "").with_header(
+                "WWW-Authenticate",
+                r#"Digest realm="soap", qop="auth", nonce="abc", opaque="xyz""#,
+            )),
+        });
+        let transport = auth_transport(&stub, HttpAuth::digest("Mufasa", "Circle of Life"));
+
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+
+        // Challenge, answer, then the cached challenge is answered right away
+        let requests = stub.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(authorization(&requests[0]), None);
+        let answer = authorization(&requests[1]).unwrap();
+        assert!(answer.contains(r#"username="Mufasa""#));
+        assert!(answer.contains(r#"uri="/service.asmx?wsdl""#));
+        assert!(answer.contains("algorithm=MD5"));
+        assert!(answer.contains("nc=00000001"));
+        assert!(answer.contains(r#"opaque="xyz""#));
+        assert!(authorization(&requests[2]).unwrap().contains("nc=00000002"));
+    }
+
+    #[tokio::test]
+    async fn test_digest_rejected_credentials() {
+        let stub = MemoryTransport::with_handler(|_| {
+            Ok(TransportResponse::new(401, "")
+                .with_header("WWW-Authenticate", r#"Digest realm="soap", nonce="abc""#))
+        });
+        let transport = auth_transport(&stub, HttpAuth::digest("user", "wrong"));
+
+        assert_eq!(transport.send(request()).await.unwrap().status, 401);
+        assert_eq!(transport.send(request()).await.unwrap().status, 401);
+        // The second call does not retry the nonce that was already rejected
+        assert_eq!(stub.requests().len(), 3);
+    }
+
+    /// A `WWW-Authenticate` header with a CHALLENGE_MESSAGE without target info
+    fn ntlm_challenge() -> String {
+        let mut challenge = ntlm::SIGNATURE.to_vec();
+        challenge.extend(2u32.to_le_bytes());
+        challenge.extend([0; 8]);
+        challenge.extend(0x0000_0201u32.to_le_bytes());
+        challenge.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+        challenge.extend([0; 8]);
+        format!("NTLM {}", BASE64.encode(challenge))
+    }
+
+    /// The NTLM message of an `Authorization` header
+    fn ntlm_message(authorization: &str) -> Vec<u8> {
+        BASE64
+            .decode(authorization.strip_prefix("NTLM ").unwrap())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ntlm_handshake() {
+        let stub = MemoryTransport::with_handler(|request| {
+            let message = ntlm_message(authorization(request).unwrap());
+            match ntlm::read_u32(&message, 8) {
+                1 => Ok(TransportResponse::new(401, "")
+                    .with_header("WWW-Authenticate", ntlm_challenge())),
+                3 => {
+                    // The user name is the fourth field of the message
+                    let len = ntlm::read_u16(&message, 36) as usize;
+                    let offset = ntlm::read_u32(&message, 40) as usize;
+                    assert_eq!(
+                        &message[offset..offset + len],
+                        ntlm::utf16("svc").as_slice()
+                    );
+                    Ok(TransportResponse::new(200, "ok"))
+                }
+                other => panic!("unexpected NTLM message type {}", other),
+            }
+        });
+        let transport = auth_transport(&stub, HttpAuth::ntlm("CORP", "svc", "secret"));
+
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+        assert_eq!(stub.requests().len(), 2);
+    }
+
+    /// A transport that can't keep requests on one connection
+    struct Stateless(MemoryTransport);
+
+    #[async_trait]
+    impl SoapTransport for Stateless {
+        async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+            self.0.send(request).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ntlm_requires_connection() {
+        let stub = MemoryTransport::new();
+        let transport = AuthTransport::new(
+            Arc::new(Stateless(stub.clone())),
+            HttpAuth::ntlm("CORP", "svc", "secret"),
+        );
+
+        let result = transport.send(request()).await;
+        assert!(matches!(result, Err(SoapError::AuthError(_))));
+        assert!(stub.requests().is_empty());
+    }
+
+    /// Connection index and NTLM message type of the requests to [`ntlm_server`]
+    #[cfg(any(feature = "std-http", feature = "reqwest"))]
+    type RequestLog = Arc<Mutex<Vec<(usize, u32)>>>;
+
+    /// Serve NTLM handshakes over HTTP/1.1 on a local port
+    ///
+    /// Returns the endpoint and the log of requests. An AUTHENTICATE_MESSAGE is only accepted on a connection
+    /// that sent a NEGOTIATE_MESSAGE before.
+    #[cfg(any(feature = "std-http", feature = "reqwest"))]
+    fn ntlm_server() -> (String, RequestLog) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/Service.svc", listener.local_addr().unwrap());
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let server_log = log.clone();
+        std::thread::spawn(move || {
+            for (connection, stream) in listener.incoming().enumerate() {
+                let log = server_log.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.unwrap());
+                    let mut negotiated = false;
+                    loop {
+                        let mut authorization = String::new();
+                        let mut length = 0;
+                        let mut line = String::new();
+                        loop {
+                            line.clear();
+                            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            let Some((name, value)) = line.trim_end().split_once(':') else {
+                                if line.trim_end().is_empty() {
+                                    break;
+                                }
+                                continue;
+                            };
+                            if name.eq_ignore_ascii_case("Authorization") {
+                                authorization = value.trim().to_string();
+                            } else if name.eq_ignore_ascii_case("Content-Length") {
+                                length = value.trim().parse().unwrap();
+                            }
+                        }
+                        let mut body = vec![0; length];
+                        reader.read_exact(&mut body).unwrap();
+
+                        let message_type = ntlm::read_u32(&ntlm_message(&authorization), 8);
+                        log.lock().unwrap().push((connection, message_type));
+                        let response = match message_type {
+                            1 => {
+                                negotiated = true;
+                                format!(
+                                    "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: {}\r\nContent-Length: 0\r\n\r\n",
+                                    ntlm_challenge()
+                                )
+                            }
+                            3 if negotiated => {
+                                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string()
+                            }
+                            _ => {
+                                "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n".to_string()
+                            }
+                        };
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        (endpoint, log)
+    }
+
+    #[test]
+    #[cfg(feature = "std-http")]
+    fn test_ntlm_connection_std_http() {
+        let (endpoint, log) = ntlm_server();
+        let transport = AuthTransport::new(
+            Arc::new(crate::transport::StdHttpTransport::new()),
+            HttpAuth::ntlm("CORP", "svc", "secret"),
+        );
+
+        for _ in 0..2 {
+            let response = futures_executor::block_on(transport.send(TransportRequest {
+                endpoint: endpoint.clone(),
+                ..request()
+            }))
+            .unwrap();
+            assert_eq!(response.status, 200);
+        }
+        // Each handshake runs on a connection of its own
+        assert_eq!(*log.lock().unwrap(), vec![(0, 1), (0, 3), (1, 1), (1, 3)]);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "reqwest")]
+    async fn test_ntlm_connection_reqwest() {
+        use crate::transport::ReqwestTransport;
+
+        let (endpoint, log) = ntlm_server();
+        let transport = AuthTransport::new(
+            Arc::new(ReqwestTransport::new()),
+            HttpAuth::ntlm("CORP", "svc", "secret"),
+        );
+        let response = transport
+            .send(TransportRequest {
+                endpoint: endpoint.clone(),
+                ..request()
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(*log.lock().unwrap(), vec![(0, 1), (0, 3)]);
+
+        // A configured client has no connections to offer
+        let transport = AuthTransport::new(
+            Arc::new(ReqwestTransport::with_client(reqwest::Client::new())),
+            HttpAuth::ntlm("CORP", "svc", "secret"),
+        );
+        let result = transport
+            .send(TransportRequest {
+                endpoint,
+                ..request()
+            })
+            .await;
+        assert!(matches!(result, Err(SoapError::AuthError(_))));
+    }
+
+    struct RotatingToken(AtomicUsize);
+
+    #[async_trait]
+    impl TokenProvider for RotatingToken {
+        async fn token(&self) -> SoapResult<String> {
+            Ok(format!("token-{}", self.0.load(Ordering::SeqCst)))
+        }
+
+        async fn refresh(&self) -> SoapResult<String> {
+            Ok(format!(
+                "token-{}",
+                self.0.fetch_add(1, Ordering::SeqCst) + 1
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bearer_refresh() {
+        let stub = MemoryTransport::with_handler(|request| match authorization(request) {
+            Some("Bearer token-1") => Ok(TransportResponse::new(200, "ok")),
+            _ => Ok(TransportResponse::new(401, "")),
+        });
+        let transport = auth_transport(
+            &stub,
+            HttpAuth::bearer_provider(RotatingToken(AtomicUsize::new(0))),
+        );
+
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+        assert_eq!(transport.send(request()).await.unwrap().status, 200);
+        // Only the first call needed a refresh
+        assert_eq!(stub.requests().len(), 3);
+    }
+}
@@ -0,0 +1,344 @@
+//! Batches of concurrent calls
+//!
+//! [`Batch`] runs one call per item with a bounded number of calls in flight
+//! and an optional rate limit, and returns the results in the order of the
+//! items. It works with any operation method of a generated client, or with
+//! [`SoapClient`](crate::SoapClient) directly, and with any executor.
+//!
+//! With the `tracing` feature, the batch runs in a `soap_batch` span and
+//! each call in a `soap_batch_item` span with the index of its item, so the
+//! spans of the call itself are nested below.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::Batch;
+//! use std::time::Duration;
+//!
+//! # #[derive(serde::Serialize)] struct GetCustomer { id: u32 }
+//! # #[derive(serde::Deserialize)] struct GetCustomerResponse {}
+//! # struct CustomerServiceClient(soapus_runtime::SoapClient);
+//! # impl CustomerServiceClient {
+//! #     async fn get_customer(&self, request: GetCustomer) -> soapus_runtime::SoapResult<GetCustomerResponse> {
+//! #         self.0.call("GetCustomer", &request).await
+//! #     }
+//! # }
+//! # async fn example(client: CustomerServiceClient) {
+//! // `client` is a generated client
+//! let ids: Vec<u32> = (1..=5000).collect();
+//!
+//! let results = Batch::new()
+//!     .concurrency(16)
+//!     .rate_limit(100, Duration::from_secs(1))
+//!     .run(ids, |id| client.get_customer(GetCustomer { id }))
+//!     .await;
+//!
+//! for (index, result) in results.iter().enumerate() {
+//!     if let Err(e) = result {
+//!         eprintln!("customer {} failed: {}", index + 1, e);
+//!     }
+//! }
+//! # }
+//! ```
+
+use crate::error::SoapResult;
+use crate::executor;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tracing")]
+use tracing::{debug, instrument};
+
+/// Number of calls in flight if not configured
+pub const DEFAULT_CONCURRENCY: usize = 10;
+
+/// Runs many calls with bounded concurrency and an optional rate limit
+///
+/// Calls are started in the order of the items. A failed call does not stop
+/// the batch: its error is returned in place of its result.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    concurrency: usize,
+    rate_limit: Option<Duration>,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Batch {
+    /// Create a batch with [`DEFAULT_CONCURRENCY`] calls in flight and no rate limit
+    pub fn new() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            rate_limit: None,
+        }
+    }
+
+    /// Maximum number of calls in flight (at least 1)
+    ///
+    /// Keep it at or below the client's `max_in_flight`, otherwise calls
+    /// beyond that limit fail with `SoapError::Rejected`.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Start at most `calls` calls per `period`
+    ///
+    /// Calls are spread evenly over the period, e.g. 100 calls per second
+    /// start 10ms apart.
+    pub fn rate_limit(mut self, calls: u32, period: Duration) -> Self {
+        self.rate_limit = Some(period / calls.max(1));
+        self
+    }
+
+    /// Maximum number of calls in flight
+    pub fn max_concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Minimum interval between the start of two calls, if rate limited
+    pub fn interval(&self) -> Option<Duration> {
+        self.rate_limit
+    }
+
+    /// Call `call` for every item and collect the results in the order of the items
+    ///
+    /// Items are taken from the iterator only when their call starts.
+    #[cfg_attr(feature = "tracing", instrument(name = "soap_batch", skip_all, fields(concurrency = self.concurrency)))]
+    pub async fn run<I, F, Fut, R>(&self, items: I, mut call: F) -> Vec<SoapResult<R>>
+    where
+        I: IntoIterator,
+        F: FnMut(I::Item) -> Fut,
+        Fut: Future<Output = SoapResult<R>>,
+    {
+        let mut items = items.into_iter().peekable();
+        let mut results: Vec<Option<SoapResult<R>>> = Vec::new();
+        let mut running: Vec<Running<Fut>> = Vec::new();
+        let mut next_start: Option<Instant> = None;
+        let mut delay: Option<Pin<Box<dyn Future<Output = ()> + Send>>> = None;
+
+        poll_fn(|cx| loop {
+            // Start calls while there is capacity and the rate limit allows it
+            while running.len() < self.concurrency && items.peek().is_some() {
+                if let Some(sleep) = delay.as_mut() {
+                    if sleep.as_mut().poll(cx).is_pending() {
+                        break;
+                    }
+                    delay = None;
+                }
+                if let Some(interval) = self.rate_limit {
+                    let now = Instant::now();
+                    match next_start {
+                        Some(start) if start > now => {
+                            delay = Some(Box::pin(executor::sleep(start - now)));
+                            continue;
+                        }
+                        _ => next_start = Some(now + interval),
+                    }
+                }
+
+                let Some(item) = items.next() else {
+                    break;
+                };
+                let index = results.len();
+                results.push(None);
+                running.push(Running::start(index, || call(item)));
+            }
+
+            let mut completed = false;
+            running.retain_mut(|running| match running.poll(cx) {
+                Poll::Ready(result) => {
+                    results[running.index] = Some(result);
+                    completed = true;
+                    false
+                }
+                Poll::Pending => true,
+            });
+
+            if running.is_empty() && items.peek().is_none() {
+                return Poll::Ready(());
+            }
+            if !completed {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|result| result.expect("every started call completes"))
+            .collect();
+
+        #[cfg(feature = "tracing")]
+        debug!(
+            calls = results.len(),
+            failed = results.iter().filter(|result| result.is_err()).count(),
+            "Batch completed"
+        );
+
+        results
+    }
+}
+
+/// A call in flight
+struct Running<Fut> {
+    index: usize,
+    future: Pin<Box<Fut>>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl<Fut: Future> Running<Fut> {
+    fn start(index: usize, call: impl FnOnce() -> Fut) -> Self {
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::info_span!("soap_batch_item", index);
+            // Spans created by the call, e.g. by `SoapClient::call`, become children of the item span
+            let future = Box::pin(span.in_scope(call));
+            Self {
+                index,
+                future,
+                span,
+            }
+        }
+        #[cfg(not(feature = "tracing"))]
+        {
+            Self {
+                index,
+                future: Box::pin(call()),
+            }
+        }
+    }
+
+    fn poll(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Fut::Output> {
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.enter();
+        self.future.as_mut().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SoapError;
+    use crate::transport::{MemoryTransport, TransportResponse};
+    use crate::SoapClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_builder() {
+        let batch = Batch::new();
+        assert_eq!(batch.max_concurrency(), DEFAULT_CONCURRENCY);
+        assert_eq!(batch.interval(), None);
+
+        let batch = Batch::new()
+            .concurrency(0)
+            .rate_limit(100, Duration::from_secs(1));
+        assert_eq!(batch.max_concurrency(), 1);
+        assert_eq!(batch.interval(), Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_results_in_order() {
+        let results = futures_executor::block_on(Batch::new().concurrency(3).run(
+            [30u64, 10, 20, 0, 5],
+            |millis| async move {
+                executor::sleep(Duration::from_millis(millis)).await;
+                if millis == 20 {
+                    Err(SoapError::TransportError("unavailable".to_string()))
+                } else {
+                    Ok(millis)
+                }
+            },
+        ));
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap(), &30);
+        assert_eq!(results[1].as_ref().unwrap(), &10);
+        assert!(matches!(results[2], Err(SoapError::TransportError(_))));
+        assert_eq!(results[3].as_ref().unwrap(), &0);
+        assert_eq!(results[4].as_ref().unwrap(), &5);
+
+        let results: Vec<SoapResult<()>> =
+            futures_executor::block_on(Batch::new().run(Vec::<u32>::new(), |_| async { Ok(()) }));
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_concurrency_limit() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        let results = futures_executor::block_on(Batch::new().concurrency(4).run(0..20, |i| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                executor::sleep(Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(i)
+            }
+        }));
+
+        assert_eq!(results.len(), 20);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let start = Instant::now();
+        let results = futures_executor::block_on(
+            Batch::new()
+                .rate_limit(50, Duration::from_secs(1))
+                .run(0..5, |i| async move { Ok(i) }),
+        );
+
+        assert_eq!(results.len(), 5);
+        // Five calls 20ms apart
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+
+    #[derive(serde::Serialize)]
+    struct GetCustomer {
+        id: u32,
+    }
+
+    #[tokio::test]
+    async fn test_batch_client_calls() {
+        let transport = MemoryTransport::with_handler(|request| {
+            let body = String::from_utf8_lossy(&request.body);
+            if body.contains("<id>2</id>") {
+                return Ok(TransportResponse::new(
+                    500,
+                    r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>Unknown customer</faultstring></soap:Fault></soap:Body></soap:Envelope>"#,
+                ));
+            }
+            Ok(TransportResponse::new(
+                200,
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><GetCustomerResponse>found</GetCustomerResponse></soap:Body></soap:Envelope>"#,
+            ))
+        });
+        let client = SoapClient::builder("memory://customers")
+            .transport(transport.clone())
+            .build();
+
+        let results = Batch::new()
+            .concurrency(2)
+            .run([1, 2, 3], |id| {
+                let client = &client;
+                async move { client.call_raw("GetCustomer", &GetCustomer { id }).await }
+            })
+            .await;
+
+        assert_eq!(transport.requests().len(), 3);
+        assert!(results[0].as_ref().unwrap().contains("found"));
+        assert!(matches!(results[1], Err(SoapError::SoapFault(_))));
+        assert!(results[2].as_ref().unwrap().contains("found"));
+    }
+}
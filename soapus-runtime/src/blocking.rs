@@ -0,0 +1,167 @@
+//! Blocking calls
+//!
+//! For programs without an async runtime, e.g. CLI batch jobs or extensions
+//! embedded in other languages. [`block_on`] runs a call on a runtime shared
+//! by the process and waits for its result. Generated clients with blocking
+//! methods (`SoapClientGenerator::builder().blocking(true)`) use it for every
+//! operation.
+//!
+//! Inside a multi-threaded tokio runtime, `block_on` runs the call on that
+//! runtime and blocks the calling worker thread until it is done, while the
+//! other workers carry on. Prefer the async methods there. Inside a
+//! `current_thread` runtime the call could never make progress, so `block_on`
+//! fails with [`SoapError::InvalidConfig`] instead of deadlocking.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{blocking, SoapClient};
+//!
+//! # #[derive(serde::Serialize)] struct GetReport {}
+//! # #[derive(serde::Deserialize)] struct GetReportResponse {}
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let client = SoapClient::new("http://example.com/soap");
+//!     let report: GetReportResponse = blocking::block_on(client.call("GetReport", &GetReport {}))?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use std::future::Future;
+use std::sync::OnceLock;
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
+
+/// Run a call to completion, blocking the current thread
+///
+/// Outside a tokio runtime the call runs on the shared runtime, where
+/// connections stay open between calls, so later calls reuse them. Inside a
+/// multi-threaded runtime it runs on that runtime, so transports and tasks
+/// bound to it keep working.
+///
+/// # Errors
+///
+/// Returns [`SoapError::InvalidConfig`] when called from a `current_thread`
+/// runtime: blocking its only thread would stop everything the call waits for.
+///
+/// # Panics
+///
+/// Panics if the shared runtime cannot be started.
+pub fn block_on<T, F>(future: F) -> SoapResult<T>
+where
+    F: Future<Output = SoapResult<T>>,
+{
+    let Ok(handle) = Handle::try_current() else {
+        return runtime().block_on(future);
+    };
+
+    if handle.runtime_flavor() == RuntimeFlavor::CurrentThread {
+        return Err(SoapError::InvalidConfig(
+            "blocking calls can't run inside a current_thread runtime, use the async methods"
+                .to_string(),
+        ));
+    }
+    // The other workers take over the tasks of this one while it blocks
+    tokio::task::block_in_place(|| handle.block_on(future))
+}
+
+/// The runtime shared by all blocking calls
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("soapus-blocking")
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime for blocking calls")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{MemoryTransport, SoapTransport, TransportRequest, TransportResponse};
+    use crate::SoapClient;
+
+    #[derive(serde::Serialize)]
+    struct Ping {}
+
+    fn transport() -> MemoryTransport {
+        MemoryTransport::with_handler(|_| {
+            Ok(TransportResponse::new(
+                200,
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><PingResponse>pong</PingResponse></soap:Body></soap:Envelope>"#,
+            ))
+        })
+    }
+
+    fn client() -> SoapClient {
+        SoapClient::builder("memory://service")
+            .transport(transport())
+            .build()
+    }
+
+    /// Sends requests from a task of the runtime it was created in
+    struct RuntimeBoundTransport {
+        handle: Handle,
+        inner: MemoryTransport,
+    }
+
+    #[async_trait::async_trait]
+    impl SoapTransport for RuntimeBoundTransport {
+        async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+            let inner = self.inner.clone();
+            self.handle
+                .spawn(async move { inner.send(request).await })
+                .await
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_block_on() {
+        let client = client();
+        let response = block_on(client.call_raw("Ping", &Ping {})).unwrap();
+        assert!(response.contains("pong"));
+
+        // The shared runtime is reused
+        let response = block_on(client.call_raw("Ping", &Ping {})).unwrap();
+        assert!(response.contains("pong"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_block_on_inside_runtime() {
+        // The transport needs a task on the runtime of the test
+        let client = SoapClient::builder("memory://service")
+            .transport(RuntimeBoundTransport {
+                handle: Handle::current(),
+                inner: transport(),
+            })
+            .build();
+        let response = block_on(client.call_raw("Ping", &Ping {})).unwrap();
+        assert!(response.contains("pong"));
+
+        // Called from its only worker, which blocks until the call is done
+        let response = tokio::spawn(async move { block_on(client.call_raw("Ping", &Ping {})) })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(response.contains("pong"));
+    }
+
+    #[tokio::test]
+    async fn test_block_on_inside_current_thread_runtime() {
+        let client = client();
+        let result = block_on(client.call_raw("Ping", &Ping {}));
+        assert!(matches!(result, Err(SoapError::InvalidConfig(_))));
+    }
+
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn test_block_on_inside_runtime_panics() {
+        Runtime::new()
+            .unwrap()
+            .block_on(async { block_on::<(), _>(async { panic!("boom") }) })
+            .unwrap();
+    }
+}
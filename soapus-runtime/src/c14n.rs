@@ -0,0 +1,559 @@
+//! Exclusive XML canonicalization
+//!
+//! Implements [Exclusive XML Canonicalization 1.0](https://www.w3.org/TR/xml-exc-c14n/)
+//! without comments, as required for XML signatures in WS-Security. An
+//! element is written in its canonical form:
+//!
+//! - empty elements are written with start and end tag
+//! - only namespace declarations used by the element or its attributes are
+//!   written, and only where an output ancestor did not declare them already
+//! - namespace declarations and attributes are sorted
+//! - line breaks are normalized to `\n` and whitespace in attribute values
+//!   to spaces, as by an XML parser
+//! - character and entity references are replaced, special characters escaped
+//! - comments and the XML declaration are removed
+//!
+//! Namespaces listed as inclusive prefixes (`InclusiveNamespaces PrefixList`,
+//! `#default` for the default namespace) are written whenever they are in scope.
+//!
+//! Requires the `ws-security` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use soapus_runtime::c14n;
+//!
+//! let xml = r#"<a:Root xmlns:a="urn:a" xmlns:b="urn:b"><a:Item z="1" id="2"/></a:Root>"#;
+//! let canonical = c14n::canonicalize(xml, &[]).unwrap();
+//! assert_eq!(
+//!     canonical,
+//!     r#"<a:Root xmlns:a="urn:a"><a:Item id="2" z="1"></a:Item></a:Root>"#
+//! );
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace URIs by prefix, `""` is the default namespace
+pub(crate) type Namespaces = BTreeMap<String, String>;
+
+/// Canonicalize the root element of a document
+pub fn canonicalize(xml: &str, inclusive_prefixes: &[&str]) -> SoapResult<String> {
+    let root = parse(xml)?;
+    Ok(root.canonicalize(&Namespaces::new(), inclusive_prefixes))
+}
+
+/// Canonicalize the element with the given `Id` attribute, e.g. `wsu:Id`
+///
+/// Returns `Ok(None)` if no element has this id.
+pub fn canonicalize_id(
+    xml: &str,
+    id: &str,
+    inclusive_prefixes: &[&str],
+) -> SoapResult<Option<String>> {
+    let root = parse(xml)?;
+    Ok(root
+        .find(&Namespaces::new(), &|element| element.id() == Some(id))
+        .map(|(element, scope)| element.canonicalize(&scope, inclusive_prefixes)))
+}
+
+/// An element of a parsed document
+#[derive(Debug, Clone)]
+pub(crate) struct Element {
+    pub(crate) prefix: String,
+    pub(crate) local_name: String,
+    /// Resolved namespace URI, empty if the element has none
+    pub(crate) namespace: String,
+    /// Namespace declarations made on this element
+    pub(crate) declarations: Vec<(String, String)>,
+    pub(crate) attributes: Vec<Attribute>,
+    pub(crate) children: Vec<Node>,
+    /// Byte offset of the start tag in the parsed document
+    pub(crate) start: usize,
+    /// Byte offset of the content, right after the start tag
+    pub(crate) content_start: usize,
+    /// Byte offset of the end tag, or of the end of an empty element tag
+    pub(crate) end: usize,
+    /// Byte offset right after the element
+    pub(crate) outer_end: usize,
+}
+
+/// An attribute other than a namespace declaration
+#[derive(Debug, Clone)]
+pub(crate) struct Attribute {
+    pub(crate) prefix: String,
+    pub(crate) local_name: String,
+    /// Resolved namespace URI, empty for unprefixed attributes
+    pub(crate) namespace: String,
+    pub(crate) value: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+    ProcessingInstruction(String),
+}
+
+impl Element {
+    /// Get the qualified name as written in the document
+    pub(crate) fn qname(&self) -> String {
+        if self.prefix.is_empty() {
+            self.local_name.clone()
+        } else {
+            format!("{}:{}", self.prefix, self.local_name)
+        }
+    }
+
+    /// Check the namespace and local name
+    pub(crate) fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.namespace == namespace && self.local_name == local_name
+    }
+
+    /// Get the value of the attribute with the given namespace and local name
+    pub(crate) fn attribute(&self, namespace: &str, local_name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.namespace == namespace && attr.local_name == local_name)
+            .map(|attr| attr.value.as_str())
+    }
+
+    /// Get the `Id` attribute in any namespace, e.g. `wsu:Id`
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.local_name == "Id")
+            .map(|attr| attr.value.as_str())
+    }
+
+    /// Iterate over the child elements
+    pub(crate) fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            _ => None,
+        })
+    }
+
+    /// Get the first child element with the given namespace and local name
+    pub(crate) fn child(&self, namespace: &str, local_name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.is(namespace, local_name))
+    }
+
+    /// Get the concatenated text content
+    pub(crate) fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match node {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(e) => text.push_str(&e.text()),
+                Node::ProcessingInstruction(_) => {}
+            }
+        }
+        text
+    }
+
+    /// Namespaces in scope for the children of this element
+    pub(crate) fn scope(&self, parent_scope: &Namespaces) -> Namespaces {
+        let mut scope = parent_scope.clone();
+        for (prefix, uri) in &self.declarations {
+            scope.insert(prefix.clone(), uri.clone());
+        }
+        scope
+    }
+
+    /// Find the first element (this one or a descendant) matching `predicate`
+    ///
+    /// Returns the element with the namespaces in scope for it from its ancestors.
+    pub(crate) fn find<'a>(
+        &'a self,
+        parent_scope: &Namespaces,
+        predicate: &dyn Fn(&Element) -> bool,
+    ) -> Option<(&'a Element, Namespaces)> {
+        if predicate(self) {
+            return Some((self, parent_scope.clone()));
+        }
+        let scope = self.scope(parent_scope);
+        self.elements()
+            .find_map(|child| child.find(&scope, predicate))
+    }
+
+    /// Count this element and the descendants matching `predicate`
+    pub(crate) fn count(&self, predicate: &dyn Fn(&Element) -> bool) -> usize {
+        usize::from(predicate(self))
+            + self
+                .elements()
+                .map(|child| child.count(predicate))
+                .sum::<usize>()
+    }
+
+    /// Write the element in exclusive canonical form
+    ///
+    /// `parent_scope` holds the namespaces declared by the ancestors of the
+    /// element in the document.
+    pub(crate) fn canonicalize(
+        &self,
+        parent_scope: &Namespaces,
+        inclusive_prefixes: &[&str],
+    ) -> String {
+        let inclusive: Vec<&str> = inclusive_prefixes
+            .iter()
+            .map(|prefix| if *prefix == "#default" { "" } else { prefix })
+            .collect();
+        let mut output = String::new();
+        self.write_canonical(&mut output, parent_scope, &Namespaces::new(), &inclusive);
+        output
+    }
+
+    fn write_canonical(
+        &self,
+        output: &mut String,
+        parent_scope: &Namespaces,
+        rendered: &Namespaces,
+        inclusive: &[&str],
+    ) {
+        let scope = self.scope(parent_scope);
+
+        // Visibly utilized prefixes, sorted with the default namespace first
+        let mut prefixes: BTreeSet<&str> = BTreeSet::new();
+        prefixes.insert(&self.prefix);
+        for attr in &self.attributes {
+            if !attr.prefix.is_empty() && attr.prefix != "xml" {
+                prefixes.insert(&attr.prefix);
+            }
+        }
+        for prefix in inclusive {
+            if scope.contains_key(*prefix) {
+                prefixes.insert(prefix);
+            }
+        }
+
+        let mut rendered_here = rendered.clone();
+        let mut declarations = String::new();
+        for prefix in prefixes {
+            let uri = scope.get(prefix).map(String::as_str).unwrap_or("");
+            let previous = rendered.get(prefix).map(String::as_str).unwrap_or("");
+            if uri == previous || (uri.is_empty() && !prefix.is_empty()) {
+                continue;
+            }
+            if prefix.is_empty() {
+                declarations.push_str(&format!(" xmlns=\"{}\"", escape_attribute(uri)));
+            } else {
+                declarations.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_attribute(uri)));
+            }
+            rendered_here.insert(prefix.to_string(), uri.to_string());
+        }
+
+        let mut attributes: Vec<&Attribute> = self.attributes.iter().collect();
+        attributes.sort_by(|a, b| {
+            (a.namespace.as_str(), a.local_name.as_str())
+                .cmp(&(b.namespace.as_str(), b.local_name.as_str()))
+        });
+
+        let qname = self.qname();
+        output.push('<');
+        output.push_str(&qname);
+        output.push_str(&declarations);
+        for attr in attributes {
+            output.push(' ');
+            if !attr.prefix.is_empty() {
+                output.push_str(&attr.prefix);
+                output.push(':');
+            }
+            output.push_str(&attr.local_name);
+            output.push_str("=\"");
+            output.push_str(&escape_attribute(&attr.value));
+            output.push('"');
+        }
+        output.push('>');
+
+        for node in &self.children {
+            match node {
+                Node::Element(child) => {
+                    child.write_canonical(output, &scope, &rendered_here, inclusive)
+                }
+                Node::Text(text) => output.push_str(&escape_text(text)),
+                Node::ProcessingInstruction(content) => {
+                    output.push_str("<?");
+                    output.push_str(content);
+                    output.push_str("?>");
+                }
+            }
+        }
+
+        output.push_str("</");
+        output.push_str(&qname);
+        output.push('>');
+    }
+}
+
+/// Parse a document into its root element
+pub(crate) fn parse(xml: &str) -> SoapResult<Element> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut scopes: Vec<Namespaces> = vec![Namespaces::new()];
+    let mut root = None;
+
+    loop {
+        let position = reader.buffer_position() as usize;
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => {
+                let mut element = parse_element(&e, position, &mut scopes)?;
+                element.content_start = reader.buffer_position() as usize;
+                stack.push(element);
+            }
+            Event::Empty(e) => {
+                let mut element = parse_element(&e, position, &mut scopes)?;
+                scopes.pop();
+                element.content_start = reader.buffer_position() as usize;
+                element.end = element.content_start;
+                element.outer_end = element.content_start;
+                push_node(&mut stack, &mut root, Node::Element(element));
+            }
+            Event::End(_) => {
+                let mut element = stack
+                    .pop()
+                    .ok_or_else(|| SoapError::XmlError("Unexpected end tag".to_string()))?;
+                scopes.pop();
+                element.end = position;
+                element.outer_end = reader.buffer_position() as usize;
+                push_node(&mut stack, &mut root, Node::Element(element));
+            }
+            Event::Text(e) => {
+                if let Some(parent) = stack.last_mut() {
+                    let raw = std::str::from_utf8(&e).map_err(xml_error)?;
+                    let text = unescape(&normalize_line_breaks(raw))
+                        .map_err(xml_error)?
+                        .into_owned();
+                    parent.children.push(Node::Text(text));
+                }
+            }
+            Event::CData(e) => {
+                if let Some(parent) = stack.last_mut() {
+                    let text = String::from_utf8_lossy(&e.into_inner()).into_owned();
+                    parent
+                        .children
+                        .push(Node::Text(normalize_line_breaks(&text).into_owned()));
+                }
+            }
+            Event::PI(e) => {
+                if let Some(parent) = stack.last_mut() {
+                    let content = String::from_utf8_lossy(&e).into_owned();
+                    parent.children.push(Node::ProcessingInstruction(content));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(SoapError::XmlError("Unclosed element".to_string()));
+    }
+    root.ok_or_else(|| SoapError::XmlError("Document has no root element".to_string()))
+}
+
+fn push_node(stack: &mut [Element], root: &mut Option<Element>, node: Node) {
+    match (stack.last_mut(), node) {
+        (Some(parent), node) => parent.children.push(node),
+        (None, Node::Element(element)) => *root = Some(element),
+        (None, _) => {}
+    }
+}
+
+/// Parse a start tag and push the namespaces in scope for its content
+fn parse_element(
+    e: &BytesStart,
+    position: usize,
+    scopes: &mut Vec<Namespaces>,
+) -> SoapResult<Element> {
+    let mut scope = scopes.last().cloned().unwrap_or_default();
+    let mut declarations = Vec::new();
+    let mut raw_attributes = Vec::new();
+
+    for attr in e.attributes().with_checks(false) {
+        let attr = attr.map_err(xml_error)?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).into_owned();
+        let raw = std::str::from_utf8(&attr.value).map_err(xml_error)?;
+        let value = unescape(&normalize_attribute(raw))
+            .map_err(xml_error)?
+            .into_owned();
+        if key == "xmlns" {
+            declarations.push((String::new(), value));
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            declarations.push((prefix.to_string(), value));
+        } else {
+            raw_attributes.push((key, value));
+        }
+    }
+    for (prefix, uri) in &declarations {
+        scope.insert(prefix.clone(), uri.clone());
+    }
+
+    let attributes = raw_attributes
+        .into_iter()
+        .map(|(key, value)| {
+            let (prefix, local_name) = split_qname(&key);
+            let namespace = match prefix {
+                "" => String::new(),
+                "xml" => XML_NS.to_string(),
+                prefix => resolve(&scope, prefix)?,
+            };
+            Ok(Attribute {
+                prefix: prefix.to_string(),
+                local_name: local_name.to_string(),
+                namespace,
+                value,
+            })
+        })
+        .collect::<SoapResult<Vec<_>>>()?;
+
+    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    let (prefix, local_name) = split_qname(&name);
+    let namespace = if prefix.is_empty() {
+        scope.get("").cloned().unwrap_or_default()
+    } else {
+        resolve(&scope, prefix)?
+    };
+
+    let element = Element {
+        prefix: prefix.to_string(),
+        local_name: local_name.to_string(),
+        namespace,
+        declarations,
+        attributes,
+        children: Vec::new(),
+        start: position,
+        content_start: position,
+        end: position,
+        outer_end: position,
+    };
+    scopes.push(scope);
+    Ok(element)
+}
+
+fn resolve(scope: &Namespaces, prefix: &str) -> SoapResult<String> {
+    scope
+        .get(prefix)
+        .cloned()
+        .ok_or_else(|| SoapError::XmlError(format!("Undeclared namespace prefix '{}'", prefix)))
+}
+
+fn split_qname(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("", name))
+}
+
+/// Replace `\r\n` and single `\r` line breaks by `\n`
+fn normalize_line_breaks(raw: &str) -> Cow<'_, str> {
+    if raw.contains('\r') {
+        Cow::Owned(raw.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(raw)
+    }
+}
+
+/// Replace literal tabs and line breaks of an attribute value by spaces
+///
+/// Whitespace written as character reference, e.g. `&#xA;`, is kept.
+fn normalize_attribute(raw: &str) -> String {
+    normalize_line_breaks(raw).replace(['\t', '\n'], " ")
+}
+
+pub(crate) fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push_str("&#x9;"),
+            '\n' => escaped.push_str("&#xA;"),
+            '\r' => escaped.push_str("&#xD;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn xml_error(e: impl std::fmt::Display) -> SoapError {
+    SoapError::XmlError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_syntax() {
+        let xml = "<?xml version=\"1.0\"?>\n<!-- comment --><r b='&quot;x&quot;' a=\"1&#9;2\"><e/><!-- c --><t>&lt;1 &amp; 2&gt; &#x41;</t><![CDATA[<raw>]]></r>";
+        assert_eq!(
+            canonicalize(xml, &[]).unwrap(),
+            "<r a=\"1&#x9;2\" b=\"&quot;x&quot;\"><e></e><t>&lt;1 &amp; 2&gt; A</t>&lt;raw&gt;</r>"
+        );
+    }
+
+    #[test]
+    fn test_normalizes_whitespace() {
+        let xml = "<r a=\"x\r\n\ty\" b=\"1&#xA;2\">one\r\ntwo\rthree&#xD;<![CDATA[\r\n]]></r>";
+        assert_eq!(
+            canonicalize(xml, &[]).unwrap(),
+            "<r a=\"x  y\" b=\"1&#xA;2\">one\ntwo\nthree&#xD;\n</r>"
+        );
+    }
+
+    #[test]
+    fn test_renders_only_used_namespaces() {
+        let xml = r#"<soap:Envelope xmlns:soap="urn:soap" xmlns:wsu="urn:wsu" xmlns:unused="urn:unused"><soap:Body wsu:Id="body"><m:Op xmlns:m="urn:m" xmlns="urn:default"><value>1</value><m:x xmlns:m="urn:m"/></m:Op></soap:Body></soap:Envelope>"#;
+
+        assert_eq!(
+            canonicalize_id(xml, "body", &[]).unwrap().unwrap(),
+            r#"<soap:Body xmlns:soap="urn:soap" xmlns:wsu="urn:wsu" wsu:Id="body"><m:Op xmlns:m="urn:m"><value xmlns="urn:default">1</value><m:x></m:x></m:Op></soap:Body>"#
+        );
+        assert!(canonicalize_id(xml, "missing", &[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sorts_attributes_by_namespace() {
+        let xml = r#"<r xmlns:b="urn:b" xmlns:a="urn:z" b:y="1" a:x="2" z="3" c="4"/>"#;
+        assert_eq!(
+            canonicalize(xml, &[]).unwrap(),
+            r#"<r xmlns:a="urn:z" xmlns:b="urn:b" c="4" z="3" b:y="1" a:x="2"></r>"#
+        );
+    }
+
+    #[test]
+    fn test_inclusive_prefixes() {
+        let xml = r#"<a:Root xmlns:a="urn:a" xmlns:b="urn:b" xmlns="urn:d"><a:Child>b:value</a:Child></a:Root>"#;
+        assert_eq!(
+            canonicalize(xml, &["b", "#default"]).unwrap(),
+            r#"<a:Root xmlns="urn:d" xmlns:a="urn:a" xmlns:b="urn:b"><a:Child>b:value</a:Child></a:Root>"#
+        );
+    }
+
+    #[test]
+    fn test_undeclared_prefix_is_an_error() {
+        assert!(matches!(
+            canonicalize("<x:Root/>", &[]),
+            Err(SoapError::XmlError(_))
+        ));
+    }
+}
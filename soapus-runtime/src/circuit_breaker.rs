@@ -0,0 +1,357 @@
+//! Circuit breaker
+//!
+//! A [`CircuitBreaker`] stops sending requests to an endpoint that keeps
+//! failing, so callers fail fast with [`SoapError::Rejected`] instead of
+//! waiting for timeouts:
+//!
+//! - **Closed** - requests are sent, consecutive failures are counted
+//! - **Open** - after `failure_threshold` failures in a row all requests are
+//!   rejected for `open_duration`
+//! - **Half-open** - afterwards a limited number of trial requests is sent; a
+//!   success closes the circuit, a failure opens it again
+//!
+//! Connection errors, timeouts and HTTP 5xx responses count as failures. SOAP
+//! faults do not, the service answered.
+//!
+//! Every endpoint has its own circuit, so calls sent to another endpoint with
+//! [`CallOptions::endpoint`](crate::CallOptions::endpoint) neither trip nor
+//! are rejected by the circuit of the client's endpoint.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{CircuitBreaker, SoapClient};
+//! use std::time::Duration;
+//!
+//! let client = SoapClient::builder("http://example.com/soap")
+//!     .circuit_breaker(CircuitBreaker::new(5, Duration::from_secs(30)))
+//!     .max_in_flight(32)
+//!     .build();
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tracing")]
+use tracing::{info, warn};
+
+/// The state of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// Requests are rejected
+    Open,
+    /// Trial requests are sent to probe the endpoint
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half-open",
+        })
+    }
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trials_in_flight: u32,
+}
+
+impl BreakerState {
+    /// The closed circuit of an endpoint seen for the first time
+    fn new(endpoint: &str) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("soap_circuit_breaker_state", gauge_value(CircuitState::Closed), "endpoint" => endpoint.to_string());
+
+        #[cfg(not(feature = "metrics"))]
+        let _ = endpoint;
+
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            trials_in_flight: 0,
+        }
+    }
+}
+
+/// Circuit breaker for the endpoints of a client
+///
+/// Keeps a separate circuit per endpoint. Clones share their state, so all
+/// clones of a [`SoapClient`](crate::SoapClient) see the same circuits.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_max_calls: u32,
+    circuits: Arc<Mutex<HashMap<String, BreakerState>>>,
+}
+
+impl CircuitBreaker {
+    /// Create a circuit breaker that opens after `failure_threshold`
+    /// consecutive failures and stays open for `open_duration`
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            half_open_max_calls: 1,
+            circuits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set how many trial requests may be in flight while half-open (default 1)
+    pub fn half_open_max_calls(mut self, calls: u32) -> Self {
+        self.half_open_max_calls = calls.max(1);
+        self
+    }
+
+    /// Get the current state of the circuit of `endpoint`
+    ///
+    /// An open circuit whose open duration has passed is reported as half-open.
+    /// Endpoints no request was sent to yet are closed.
+    pub fn state(&self, endpoint: &str) -> CircuitState {
+        match self.lock().get(endpoint) {
+            Some(state) if self.open_expired(state) => CircuitState::HalfOpen,
+            Some(state) => state.state,
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Ask to send a request to `endpoint`, `None` if the circuit is open
+    ///
+    /// The returned permit must be given the outcome of the request with
+    /// [`CircuitPermit::record`].
+    pub(crate) fn acquire<'a>(&'a self, endpoint: &'a str) -> Option<CircuitPermit<'a>> {
+        let mut circuits = self.lock();
+        let state = circuits
+            .entry(endpoint.to_string())
+            .or_insert_with(|| BreakerState::new(endpoint));
+        self.refresh(state, endpoint);
+
+        match state.state {
+            CircuitState::Closed => {}
+            CircuitState::Open => return None,
+            CircuitState::HalfOpen => {
+                if state.trials_in_flight >= self.half_open_max_calls {
+                    return None;
+                }
+                state.trials_in_flight += 1;
+            }
+        }
+
+        Some(CircuitPermit {
+            breaker: self,
+            endpoint,
+            trial: state.state == CircuitState::HalfOpen,
+            recorded: false,
+        })
+    }
+
+    /// Move from open to half-open once the open duration has passed
+    fn refresh(&self, state: &mut BreakerState, endpoint: &str) {
+        if self.open_expired(state) {
+            transition(state, CircuitState::HalfOpen, endpoint);
+        }
+    }
+
+    fn open_expired(&self, state: &BreakerState) -> bool {
+        state.state == CircuitState::Open
+            && state
+                .opened_at
+                .is_some_and(|at| at.elapsed() >= self.open_duration)
+    }
+
+    fn record(&self, endpoint: &str, trial: bool, success: bool) {
+        let mut circuits = self.lock();
+        let Some(state) = circuits.get_mut(endpoint) else {
+            return;
+        };
+        if trial {
+            state.trials_in_flight = state.trials_in_flight.saturating_sub(1);
+        }
+
+        if success {
+            state.consecutive_failures = 0;
+            if state.state == CircuitState::HalfOpen {
+                transition(state, CircuitState::Closed, endpoint);
+            }
+            return;
+        }
+
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        let open = match state.state {
+            CircuitState::Closed => state.consecutive_failures >= self.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if open {
+            state.opened_at = Some(Instant::now());
+            transition(state, CircuitState::Open, endpoint);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, BreakerState>> {
+        self.circuits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn transition(state: &mut BreakerState, to: CircuitState, endpoint: &str) {
+    #[cfg(feature = "tracing")]
+    match to {
+        CircuitState::Open => {
+            warn!(endpoint = %endpoint, from = %state.state, to = %to, failures = state.consecutive_failures, "Circuit breaker state changed")
+        }
+        _ => {
+            info!(endpoint = %endpoint, from = %state.state, to = %to, "Circuit breaker state changed")
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::gauge!("soap_circuit_breaker_state", gauge_value(to), "endpoint" => endpoint.to_string());
+        metrics::increment_counter!("soap_circuit_breaker_transitions_total", "endpoint" => endpoint.to_string(), "state" => to.to_string());
+    }
+
+    #[cfg(not(any(feature = "tracing", feature = "metrics")))]
+    let _ = endpoint;
+
+    if to == CircuitState::Closed {
+        state.opened_at = None;
+    }
+    state.state = to;
+}
+
+/// The value of a state in the `soap_circuit_breaker_state` gauge
+#[cfg(feature = "metrics")]
+fn gauge_value(state: CircuitState) -> f64 {
+    match state {
+        CircuitState::Closed => 0.0,
+        CircuitState::HalfOpen => 1.0,
+        CircuitState::Open => 2.0,
+    }
+}
+
+/// Whether an error means the endpoint is unhealthy
+pub(crate) fn is_failure(error: &SoapError) -> bool {
+    match error {
+        SoapError::HttpStatus(status) => *status >= 500,
+        SoapError::TransportError(_) | SoapError::ConnectError(_) | SoapError::Timeout(_) => true,
+        #[cfg(feature = "reqwest")]
+        SoapError::HttpError(_) => true,
+        _ => false,
+    }
+}
+
+/// Permission to send one request through a [`CircuitBreaker`]
+///
+/// A permit dropped without an outcome, e.g. because the call was
+/// cancelled, frees its trial slot without changing the state.
+pub(crate) struct CircuitPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    endpoint: &'a str,
+    trial: bool,
+    recorded: bool,
+}
+
+impl CircuitPermit<'_> {
+    /// Record the outcome of the request
+    pub(crate) fn record<T>(mut self, result: &SoapResult<T>) {
+        self.recorded = true;
+        let success = match result {
+            Ok(_) => true,
+            Err(error) => !is_failure(error),
+        };
+        self.breaker.record(self.endpoint, self.trial, success);
+    }
+}
+
+impl Drop for CircuitPermit<'_> {
+    fn drop(&mut self) {
+        if !self.recorded && self.trial {
+            if let Some(state) = self.breaker.lock().get_mut(self.endpoint) {
+                state.trials_in_flight = state.trials_in_flight.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE: &str = "memory://service";
+
+    fn fail(breaker: &CircuitBreaker) {
+        breaker
+            .acquire(SERVICE)
+            .unwrap()
+            .record::<()>(&Err(SoapError::HttpStatus(503)));
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        fail(&breaker);
+        breaker.acquire(SERVICE).unwrap().record(&Ok(()));
+        fail(&breaker);
+        assert_eq!(breaker.state(SERVICE), CircuitState::Closed);
+
+        fail(&breaker);
+        assert_eq!(breaker.state(SERVICE), CircuitState::Open);
+        assert!(breaker.acquire(SERVICE).is_none());
+    }
+
+    #[test]
+    fn test_half_open_trials() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker);
+        assert_eq!(breaker.state(SERVICE), CircuitState::HalfOpen);
+
+        // Only one trial at a time, a dropped permit frees the slot
+        let trial = breaker.acquire(SERVICE).unwrap();
+        assert!(breaker.acquire(SERVICE).is_none());
+        drop(trial);
+
+        // A failed trial opens the circuit again, a successful one closes it
+        fail(&breaker);
+        assert_eq!(breaker.state(SERVICE), CircuitState::HalfOpen);
+        breaker.acquire(SERVICE).unwrap().record(&Ok(()));
+        assert_eq!(breaker.state(SERVICE), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_faults_are_not_failures() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker
+            .acquire(SERVICE)
+            .unwrap()
+            .record::<()>(&Err(crate::fault::SoapFault::default().into()));
+        assert_eq!(breaker.state(SERVICE), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_circuit_per_endpoint() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        fail(&breaker);
+        assert_eq!(breaker.state(SERVICE), CircuitState::Open);
+
+        assert_eq!(breaker.state("memory://backup"), CircuitState::Closed);
+        breaker
+            .acquire("memory://backup")
+            .unwrap()
+            .record::<()>(&Err(SoapError::Timeout("no response".to_string())));
+        assert_eq!(breaker.state("memory://backup"), CircuitState::Open);
+        assert!(breaker.acquire("memory://other").is_some());
+    }
+}
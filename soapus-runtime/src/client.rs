@@ -3,11 +3,36 @@
 //! This module provides the main `SoapClient` for making SOAP requests over HTTP.
 //! It handles envelope construction, HTTP communication, and response parsing.
 
-use crate::envelope::{SoapEnvelope, SoapVersion};
-use crate::error::{SoapError, SoapResult};
-use reqwest::{Client, StatusCode};
+#[cfg(feature = "auth")]
+use crate::auth::{AuthTransport, HttpAuth};
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+#[cfg(feature = "compression")]
+use crate::compression;
+use crate::envelope::{BodyPart, ResponseParts, SoapEnvelope, SoapVersion};
+use crate::error::{Rejection, SoapError, SoapResult};
+use crate::executor;
+use crate::header::{HttpMetadata, SoapHeader, SoapResponse};
+use crate::interceptor::find_header;
+use crate::interceptor::{
+    Interceptor, InterceptorChain, ParsedResponse, RawResponse, RequestContext, SoapRequest,
+};
+use crate::mime;
+#[cfg(feature = "mtom")]
+use crate::mtom::{self, Attachment};
+use crate::options::{CallOptions, CORRELATION_ID_HEADER};
+use crate::retry::RetryPolicy;
+use crate::session::{CookieJar, SessionTransport};
+#[cfg(feature = "streaming")]
+use crate::streaming::{self, IncomingStream, Stream};
+#[cfg(feature = "streaming")]
+use crate::transport::StreamingRequest;
+use crate::transport::{SoapTransport, TransportRequest};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "tracing")]
 use tracing::{debug, info, instrument, warn};
@@ -49,14 +74,37 @@ use tracing::{debug, info, instrument, warn};
 pub struct SoapClient {
     /// The SOAP endpoint URL
     endpoint: String,
-    /// HTTP client for making requests
-    http_client: Client,
+    /// Transport sending the requests
+    transport: Arc<dyn SoapTransport>,
     /// SOAP protocol version to use
     soap_version: SoapVersion,
     /// SOAPAction header value (optional)
     soap_action: Option<String>,
     /// Request timeout
     timeout: Duration,
+    /// Header blocks sent with every request
+    headers: Vec<SoapHeader>,
+    /// Interceptors run for every call
+    interceptors: InterceptorChain,
+    /// When failed calls are retried
+    retry_policy: RetryPolicy,
+    /// Operations that are safe to retry
+    idempotent_operations: HashSet<String>,
+    /// Circuit breaker for the endpoint (optional)
+    circuit_breaker: Option<CircuitBreaker>,
+    /// Limit of requests in flight, shared by all clones (optional)
+    in_flight: Option<Arc<InFlightLimit>>,
+    /// Cookies of the session, shared by all clones (optional)
+    cookie_jar: Option<CookieJar>,
+    /// Whether requests are sent as MTOM messages
+    #[cfg(feature = "mtom")]
+    mtom: bool,
+    /// Whether compressed responses are accepted and decoded
+    #[cfg(feature = "compression")]
+    response_compression: bool,
+    /// Whether request bodies are gzipped
+    #[cfg(feature = "compression")]
+    request_compression: bool,
 }
 
 impl SoapClient {
@@ -70,10 +118,23 @@ impl SoapClient {
     pub fn new(endpoint: impl Into<String>) -> Self {
         Self {
             endpoint: endpoint.into(),
-            http_client: Client::new(),
+            transport: crate::transport::default_transport(),
             soap_version: SoapVersion::Soap11,
             soap_action: None,
             timeout: Duration::from_secs(30),
+            headers: Vec::new(),
+            interceptors: InterceptorChain::default(),
+            retry_policy: RetryPolicy::none(),
+            idempotent_operations: HashSet::new(),
+            circuit_breaker: None,
+            in_flight: None,
+            cookie_jar: None,
+            #[cfg(feature = "mtom")]
+            mtom: false,
+            #[cfg(feature = "compression")]
+            response_compression: true,
+            #[cfg(feature = "compression")]
+            request_compression: false,
         }
     }
 
@@ -109,6 +170,84 @@ impl SoapClient {
         self.timeout = timeout;
     }
 
+    /// Add a header block that is sent with every request
+    pub fn add_header(&mut self, header: SoapHeader) {
+        self.headers.push(header);
+    }
+
+    /// Remove all persistent header blocks with the given element name
+    ///
+    /// Returns true if a header was removed.
+    pub fn remove_header(&mut self, name: &str) -> bool {
+        let len = self.headers.len();
+        self.headers.retain(|h| h.name() != name);
+        self.headers.len() != len
+    }
+
+    /// Remove all persistent header blocks
+    pub fn clear_headers(&mut self) {
+        self.headers.clear();
+    }
+
+    /// Get the header blocks sent with every request
+    pub fn headers(&self) -> &[SoapHeader] {
+        &self.headers
+    }
+
+    /// Get the cookie jar of the session, if cookies are enabled
+    ///
+    /// See the [`session`](crate::session) module.
+    pub fn cookie_jar(&self) -> Option<&CookieJar> {
+        self.cookie_jar.as_ref()
+    }
+
+    /// End the session by removing all cookies, e.g. before logging in again
+    ///
+    /// Affects all clones of the client. Does nothing if cookies are not enabled.
+    pub fn reset_session(&self) {
+        if let Some(jar) = &self.cookie_jar {
+            jar.clear();
+        }
+    }
+
+    /// Add an interceptor that runs for every call
+    ///
+    /// See the [`interceptor`](crate::interceptor) module for the order of the hooks.
+    pub fn add_interceptor(&mut self, interceptor: impl Interceptor + 'static) {
+        self.interceptors.push(Arc::new(interceptor));
+    }
+
+    /// Set the retry policy
+    ///
+    /// See the [`retry`](crate::retry) module for which errors are retried.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Get the retry policy
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Mark an operation as safe to retry after the request may have reached the server
+    pub fn mark_idempotent(&mut self, operation: impl Into<String>) {
+        self.idempotent_operations.insert(operation.into());
+    }
+
+    /// Check whether an operation is marked as idempotent
+    pub fn is_idempotent(&self, operation: &str) -> bool {
+        self.idempotent_operations.contains(operation)
+    }
+
+    /// Get the state of the circuit of the client's endpoint, if a circuit breaker is configured
+    ///
+    /// Endpoints set per call have circuits of their own, see [`CircuitBreaker::state`].
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit_breaker
+            .as_ref()
+            .map(|breaker| breaker.state(&self.endpoint))
+    }
+
     /// Make a SOAP call
     ///
     /// This method performs the complete SOAP request/response cycle:
@@ -200,11 +339,259 @@ impl SoapClient {
         Req: Serialize,
         Resp: for<'de> Deserialize<'de>,
     {
+        self.call_with_options(
+            operation,
+            soap_action,
+            namespace,
+            element_form_qualified,
+            request,
+            &CallOptions::default(),
+        )
+        .await
+        .map(SoapResponse::into_body)
+    }
+
+    /// Call a SOAP operation with per-call options, returning the full response
+    ///
+    /// Like [`SoapClient::call_with_soap_action`], but the timeout, endpoint,
+    /// headers, correlation ID, cancellation and attachments of `options`
+    /// apply to this call only, see the [`options`](crate::options) module.
+    /// The returned [`SoapResponse`] holds the HTTP status and headers, SOAP
+    /// header blocks and elapsed time of the response alongside the
+    /// deserialized body, and its raw body if [`CallOptions::raw_body`] is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The SOAP operation name
+    /// * `soap_action` - The SOAPAction header value (if None, uses operation name)
+    /// * `namespace` - The XML namespace for the request body element (if None, no namespace is added)
+    /// * `request` - The request body to send
+    /// * `options` - The options of this call
+    ///
+    /// # Returns
+    ///
+    /// The full response or a `SoapError`
+    #[cfg_attr(feature = "tracing", instrument(skip(self, request, options), fields(endpoint = %self.endpoint, soap_version = ?self.soap_version)))]
+    pub async fn call_with_options<Req, Resp>(
+        &self,
+        operation: &str,
+        soap_action: Option<&str>,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+        request: &Req,
+        options: &CallOptions,
+    ) -> SoapResult<SoapResponse<Resp>>
+    where
+        Req: Serialize,
+        Resp: for<'de> Deserialize<'de>,
+    {
+        let context = self.request_context(operation, soap_action, &[], options);
+
+        self.execute(
+            context,
+            options,
+            |context| self.build_envelope(context, namespace, element_form_qualified, request),
+            Self::parse_response,
+        )
+        .await
+        .map(|(response, received)| received.into_response(response))
+    }
+
+    /// Call a SOAP operation whose messages consist of individual body parts
+    ///
+    /// This is used for multi-part messages, document/literal bare messages and
+    /// RPC style operations. Each request part is written as a child of the SOAP
+    /// `Body`, and the response body is returned split into its parts.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The SOAP operation name
+    /// * `soap_action` - The SOAPAction header value (if None, uses operation name)
+    /// * `parts` - The serialized request body parts, in message order
+    ///
+    /// # Returns
+    ///
+    /// The response body parts or a `SoapError`
+    #[cfg_attr(feature = "tracing", instrument(skip(self, parts), fields(endpoint = %self.endpoint, soap_version = ?self.soap_version)))]
+    pub async fn call_with_parts(
+        &self,
+        operation: &str,
+        soap_action: Option<&str>,
+        parts: &[BodyPart],
+    ) -> SoapResult<ResponseParts> {
+        self.call_parts_with_options(operation, soap_action, &[], parts, &CallOptions::default())
+            .await
+    }
+
+    /// Call a SOAP operation with header blocks, body parts and per-call options
+    ///
+    /// Like [`SoapClient::call_with_parts`], but additionally writes the given
+    /// header blocks into the SOAP `Header`, after the persistent client
+    /// headers, and applies `options` to this call only. Header blocks,
+    /// attachments and HTTP details of the response are available from the
+    /// returned [`ResponseParts`].
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The SOAP operation name
+    /// * `soap_action` - The SOAPAction header value (if None, uses operation name)
+    /// * `headers` - The serialized SOAP header blocks
+    /// * `parts` - The serialized request body parts, in message order
+    /// * `options` - The options of this call
+    ///
+    /// # Returns
+    ///
+    /// The response body parts and header blocks or a `SoapError`
+    #[cfg_attr(feature = "tracing", instrument(skip(self, headers, parts, options), fields(endpoint = %self.endpoint, soap_version = ?self.soap_version)))]
+    pub async fn call_parts_with_options(
+        &self,
+        operation: &str,
+        soap_action: Option<&str>,
+        headers: &[BodyPart],
+        parts: &[BodyPart],
+        options: &CallOptions,
+    ) -> SoapResult<ResponseParts> {
+        let headers = part_headers(headers);
+        let context = self.request_context(operation, soap_action, &headers, options);
+
+        self.execute(
+            context,
+            options,
+            |context| self.build_parts_envelope(context, parts),
+            Self::parse_response_parts,
+        )
+        .await
+        .map(|(parts, received)| received.into_parts(parts))
+    }
+
+    /// Call a SOAP operation, streaming binary content
+    ///
+    /// Like [`SoapClient::call_with_soap_action`], but binary content is
+    /// exchanged as [`BinaryStream`](crate::streaming::BinaryStream) without
+    /// holding complete envelopes in memory. The response is returned once its
+    /// envelope is read; streamed content is read from the response afterwards.
+    /// The timeout, endpoint, headers, correlation ID and cancellation of
+    /// `options` apply as for [`SoapClient::call_with_options`]; attachments
+    /// of `options` are not sent. Streamed calls are never retried, and
+    /// WS-Security signatures and encryption don't cover streamed content.
+    /// See the [`streaming`] module.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The SOAP operation name
+    /// * `soap_action` - The SOAPAction header value (if None, uses operation name)
+    /// * `namespace` - The XML namespace for the request body element (if None, no namespace is added)
+    /// * `request` - The request body to send
+    /// * `options` - The options of this call
+    ///
+    /// # Returns
+    ///
+    /// The deserialized response or a `SoapError`
+    #[cfg(feature = "streaming")]
+    #[cfg_attr(feature = "tracing", instrument(skip(self, request, options), fields(endpoint = %self.endpoint, soap_version = ?self.soap_version)))]
+    pub async fn call_streaming<Req, Resp>(
+        &self,
+        operation: &str,
+        soap_action: Option<&str>,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+        request: &Req,
+        options: &CallOptions,
+    ) -> SoapResult<Resp>
+    where
+        Req: Serialize,
+        Resp: for<'de> Deserialize<'de>,
+    {
+        let mut context = self.request_context(operation, soap_action, &[], options);
+        let start = Instant::now();
+
+        let result = options
+            .cancellable(async {
+                self.interceptors.before_serialize(&mut context).await?;
+
+                let mut streams = Vec::new();
+                let message = self.serialize(|| {
+                    let (envelope, collected) = streaming::collect(|| {
+                        SoapEnvelope::build_with_soap_headers(
+                            &context.headers,
+                            request,
+                            self.soap_version,
+                            namespace,
+                            element_form_qualified,
+                        )
+                    })?;
+                    streams = collected;
+                    if self.mtom {
+                        return Ok(streaming::include(envelope, &streams));
+                    }
+                    Ok(envelope)
+                })?;
+
+                let _in_flight = self.acquire_in_flight(&context)?;
+                let (response, mut http) = self
+                    .through_breaker(
+                        &context,
+                        self.send_stream(&context, options, message, streams),
+                    )
+                    .await?;
+                http.elapsed = start.elapsed();
+
+                let parsed = self.parsed_response(&response.envelope, &http)?;
+                let result = response.parse(SoapEnvelope::parse_response)?;
+                if let Some(parsed) = parsed {
+                    self.interceptors.after_parse(&context, &parsed).await?;
+                }
+                Ok(result)
+            })
+            .await;
+
+        if let Err(error) = &result {
+            self.interceptors.on_error(&context, error).await;
+        }
+
+        result
+    }
+
+    /// Create the interceptor context of a call
+    fn request_context(
+        &self,
+        operation: &str,
+        soap_action: Option<&str>,
+        headers: &[SoapHeader],
+        options: &CallOptions,
+    ) -> RequestContext {
+        RequestContext {
+            operation: operation.to_string(),
+            soap_action: soap_action.map(str::to_string),
+            endpoint: options
+                .endpoint
+                .clone()
+                .unwrap_or_else(|| self.endpoint.clone()),
+            headers: self
+                .headers
+                .iter()
+                .chain(headers)
+                .chain(&options.soap_headers)
+                .cloned()
+                .collect(),
+            correlation_id: options.correlation_id.clone(),
+        }
+    }
+
+    /// Build the envelope of a request body
+    fn build_envelope<Req: Serialize>(
+        &self,
+        context: &RequestContext,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+        request: &Req,
+    ) -> SoapResult<String> {
         #[cfg(feature = "tracing")]
-        debug!(operation = %operation, soap_action = ?soap_action, namespace = ?namespace, element_form_qualified = %element_form_qualified, "Building SOAP envelope");
+        debug!(operation = %context.operation, soap_action = ?context.soap_action, namespace = ?namespace, element_form_qualified = %element_form_qualified, header_count = context.headers.len(), "Building SOAP envelope");
 
         // Build SOAP envelope with namespace if provided
-        let envelope = SoapEnvelope::build_with_namespace(
+        let envelope = SoapEnvelope::build_with_soap_headers(
+            &context.headers,
             request,
             self.soap_version,
             namespace,
@@ -214,179 +601,747 @@ impl SoapClient {
         #[cfg(feature = "tracing")]
         debug!(envelope_size = envelope.len(), "SOAP envelope built");
 
-        // Prepare HTTP request
-        let mut http_request = self
-            .http_client
-            .post(&self.endpoint)
-            .timeout(self.timeout)
-            .body(envelope);
+        Ok(envelope)
+    }
+
+    /// Deserialize a response body together with its header blocks
+    fn parse_response<Resp>(response_text: &str) -> SoapResult<SoapResponse<Resp>>
+    where
+        Resp: for<'de> Deserialize<'de>,
+    {
+        // Parse response
+        #[cfg(feature = "tracing")]
+        debug!("Parsing SOAP response");
 
-        // Set Content-Type based on SOAP version
-        http_request = match self.soap_version {
-            SoapVersion::Soap11 => http_request.header("Content-Type", "text/xml; charset=utf-8"),
-            SoapVersion::Soap12 => {
-                http_request.header("Content-Type", "application/soap+xml; charset=utf-8")
-            }
-        };
+        let parsed_response = SoapEnvelope::parse_response(response_text)?;
+        let response_headers = SoapEnvelope::parse_response_headers(response_text)?;
 
-        // Set SOAPAction header for SOAP 1.1
-        if self.soap_version == SoapVersion::Soap11 {
-            let action = soap_action
-                .or(self.soap_action.as_deref())
-                .unwrap_or(operation);
-            http_request = http_request.header("SOAPAction", format!("\"{}\"", action));
-        }
+        #[cfg(feature = "tracing")]
+        debug!(
+            header_count = response_headers.len(),
+            "SOAP response parsed successfully"
+        );
 
-        // Send request
+        Ok(SoapResponse::new(parsed_response, response_headers))
+    }
+
+    /// Build the envelope of a request from the header blocks of the context and body parts
+    fn build_parts_envelope(
+        &self,
+        context: &RequestContext,
+        parts: &[BodyPart],
+    ) -> SoapResult<String> {
         #[cfg(feature = "tracing")]
-        debug!(endpoint = %self.endpoint, "Sending HTTP POST request");
+        debug!(operation = %context.operation, soap_action = ?context.soap_action, header_count = context.headers.len(), part_count = parts.len(), "Building SOAP envelope from parts");
+
+        let headers: Vec<BodyPart> = context
+            .headers
+            .iter()
+            .map(|h| h.to_body_part(self.soap_version))
+            .collect();
+        Ok(SoapEnvelope::build_with_headers(
+            &headers,
+            parts,
+            self.soap_version,
+        ))
+    }
 
-        let response = match http_request.send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                #[cfg(feature = "tracing")]
-                warn!(endpoint = %self.endpoint, error = %e, "HTTP request failed");
-                return Err(e.into());
-            }
-        };
+    /// Split a response body into its parts and header blocks
+    fn parse_response_parts(response_text: &str) -> SoapResult<ResponseParts> {
+        // Parts keep their XML, so attachments are inlined as base64
+        #[cfg(feature = "mtom")]
+        let response_text = &mtom::inline(response_text);
 
-        // Check HTTP status
-        let status = response.status();
+        SoapEnvelope::parse_response_parts(response_text)
+    }
 
-        #[cfg(feature = "tracing")]
-        debug!(status = %status, "Received HTTP response");
-        if !status.is_success() && status != StatusCode::INTERNAL_SERVER_ERROR {
-            // SOAP faults can come with 500 status, so we allow that
-            return Err(SoapError::HttpError(
-                response.error_for_status().unwrap_err(),
-            ));
+    /// Run a call through the interceptor chain
+    ///
+    /// `build_envelope` serializes the request once the `before_serialize`
+    /// hooks have run; the attachments of `options` are sent along with it.
+    /// `parse_response` turns the response body into the result, which is
+    /// returned with the HTTP details and attachments of the response.
+    async fn execute<T>(
+        &self,
+        context: RequestContext,
+        options: &CallOptions,
+        build_envelope: impl FnOnce(&RequestContext) -> SoapResult<String>,
+        parse_response: impl FnOnce(&str) -> SoapResult<T>,
+    ) -> SoapResult<(T, Received)> {
+        self.execute_message(
+            context,
+            options,
+            |context| {
+                #[cfg_attr(not(feature = "swa"), allow(unused_mut))]
+                let mut message = self.serialize(|| build_envelope(context))?;
+                #[cfg(feature = "swa")]
+                message
+                    .attachments
+                    .extend(options.attachments.iter().cloned());
+                Ok(message)
+            },
+            |mut response| {
+                let received = Received {
+                    http: std::mem::take(&mut response.http),
+                    // XOP attachments are resolved into the body
+                    #[cfg(feature = "swa")]
+                    attachments: if response.xop {
+                        Vec::new()
+                    } else {
+                        std::mem::take(&mut response.attachments)
+                    },
+                };
+                Ok((response.parse(parse_response)?, received))
+            },
+        )
+        .await
+    }
+
+    /// Run a serialized call through the interceptor chain and handle its response
+    ///
+    /// The whole call is aborted once the cancellation of `options` fires. The
+    /// elapsed time of the response is measured from the start of the call.
+    async fn execute_message<T>(
+        &self,
+        mut context: RequestContext,
+        options: &CallOptions,
+        build_message: impl FnOnce(&RequestContext) -> SoapResult<OutgoingMessage>,
+        handle_response: impl FnOnce(IncomingMessage) -> SoapResult<T>,
+    ) -> SoapResult<T> {
+        let start = Instant::now();
+        let result = options
+            .cancellable(async {
+                self.interceptors.before_serialize(&mut context).await?;
+                let message = build_message(&context)?;
+                let mut response = self.send_with_retry(&context, options, &message).await?;
+                response.http.elapsed = start.elapsed();
+
+                let parsed = self.parsed_response(&response.text, &response.http)?;
+                let result = handle_response(response)?;
+                if let Some(parsed) = parsed {
+                    self.interceptors.after_parse(&context, &parsed).await?;
+                }
+                Ok(result)
+            })
+            .await;
+
+        if let Err(error) = &result {
+            self.interceptors.on_error(&context, error).await;
         }
 
-        // Get response body
-        let response_text = response.text().await?;
+        result
+    }
 
-        #[cfg(feature = "tracing")]
-        debug!(
-            response_size = response_text.len(),
-            "Received response body"
-        );
+    /// Describe a response for the `after_parse` hooks, if there are any
+    fn parsed_response(
+        &self,
+        text: &str,
+        http: &HttpMetadata,
+    ) -> SoapResult<Option<ParsedResponse>> {
+        if self.interceptors.is_empty() {
+            return Ok(None);
+        }
 
-        #[cfg(feature = "metrics")]
-        metrics::histogram!("soap_response_size_bytes", response_text.len() as f64);
+        Ok(Some(ParsedResponse {
+            status: http.status,
+            http_headers: http.headers.clone(),
+            headers: SoapEnvelope::parse_response_headers(text)?,
+            elapsed: http.elapsed,
+        }))
+    }
 
-        // Check for SOAP faults
-        if let Err(e) = SoapEnvelope::check_for_fault(&response_text) {
-            #[cfg(feature = "tracing")]
-            warn!(error = %e, "SOAP fault detected in response");
-            return Err(e);
+    /// Build the envelope of a request, collecting its attachments if MTOM is enabled
+    fn serialize(
+        &self,
+        build_envelope: impl FnOnce() -> SoapResult<String>,
+    ) -> SoapResult<OutgoingMessage> {
+        #[cfg(feature = "mtom")]
+        if self.mtom {
+            let (envelope, attachments) = mtom::collect(build_envelope)?;
+            return Ok(OutgoingMessage {
+                envelope,
+                attachments,
+            });
         }
 
-        // Parse response
-        #[cfg(feature = "tracing")]
-        debug!("Parsing SOAP response");
+        Ok(OutgoingMessage {
+            envelope: build_envelope()?,
+            #[cfg(feature = "mtom")]
+            attachments: Vec::new(),
+        })
+    }
+
+    /// Send a SOAP envelope, retrying failed attempts according to the retry policy
+    async fn send_with_retry(
+        &self,
+        context: &RequestContext,
+        options: &CallOptions,
+        message: &OutgoingMessage,
+    ) -> SoapResult<IncomingMessage> {
+        // Held until the last attempt is done
+        let _in_flight = self.acquire_in_flight(context)?;
+
+        let idempotent = self.is_idempotent(&context.operation);
+        let mut attempt = 1;
+
+        loop {
+            match self.send_attempt(context, options, message).await {
+                Err(error)
+                    if self.retry_policy.should_retry(
+                        &context.operation,
+                        idempotent,
+                        attempt,
+                        &error,
+                    ) =>
+                {
+                    let delay = self.retry_policy.delay_for(attempt);
+
+                    #[cfg(feature = "tracing")]
+                    warn!(operation = %context.operation, attempt, delay_ms = delay.as_millis() as u64, error = %error, "Retrying SOAP call");
+
+                    #[cfg(feature = "metrics")]
+                    metrics::increment_counter!("soap_retries_total", "operation" => context.operation.clone());
+
+                    executor::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Take a slot of the in-flight limit, if one is configured
+    fn acquire_in_flight(
+        &self,
+        context: &RequestContext,
+    ) -> SoapResult<Option<InFlightPermit<'_>>> {
+        match &self.in_flight {
+            Some(limit) => limit
+                .try_acquire()
+                .map(Some)
+                .ok_or_else(|| self.reject(context, Rejection::ConcurrencyLimit)),
+            None => Ok(None),
+        }
+    }
 
-        let parsed_response = SoapEnvelope::parse_response(&response_text)?;
+    /// Send a SOAP envelope once, through the circuit breaker if one is configured
+    async fn send_attempt(
+        &self,
+        context: &RequestContext,
+        options: &CallOptions,
+        message: &OutgoingMessage,
+    ) -> SoapResult<IncomingMessage> {
+        self.through_breaker(context, self.send_envelope(context, options, message))
+            .await
+    }
 
-        #[cfg(feature = "tracing")]
-        debug!("SOAP response parsed successfully");
+    /// Run a request through the circuit breaker if one is configured
+    async fn through_breaker<T>(
+        &self,
+        context: &RequestContext,
+        send: impl Future<Output = SoapResult<T>>,
+    ) -> SoapResult<T> {
+        let Some(breaker) = &self.circuit_breaker else {
+            return send.await;
+        };
 
-        Ok(parsed_response)
+        let permit = breaker
+            .acquire(&context.endpoint)
+            .ok_or_else(|| self.reject(context, Rejection::CircuitOpen))?;
+        let result = send.await;
+        permit.record(&result);
+        result
     }
 
-    /// Make a SOAP call without deserializing the response
-    ///
-    /// This is useful for debugging or when you want to handle the raw XML response yourself.
-    ///
-    /// # Arguments
-    ///
-    /// * `operation` - The SOAP operation name
-    /// * `request` - The request body to send
-    ///
-    /// # Returns
-    ///
-    /// The raw XML response as a string
-    #[cfg_attr(feature = "tracing", instrument(skip(self, request), fields(endpoint = %self.endpoint)))]
-    pub async fn call_raw<Req>(&self, operation: &str, request: &Req) -> SoapResult<String>
-    where
-        Req: Serialize,
-    {
+    /// Create the error for a request rejected without being sent
+    fn reject(&self, context: &RequestContext, reason: Rejection) -> SoapError {
         #[cfg(feature = "tracing")]
-        debug!(operation = %operation, "Building SOAP envelope for raw call");
+        warn!(operation = %context.operation, endpoint = %context.endpoint, reason = %reason, "SOAP request rejected");
 
-        // Build SOAP envelope
-        let envelope = SoapEnvelope::build(request, self.soap_version)?;
+        #[cfg(feature = "metrics")]
+        metrics::increment_counter!("soap_rejected_requests_total", "operation" => context.operation.clone(), "reason" => reason.to_string());
 
-        // Prepare HTTP request
-        let mut http_request = self
-            .http_client
-            .post(&self.endpoint)
-            .timeout(self.timeout)
-            .body(envelope);
+        #[cfg(not(any(feature = "tracing", feature = "metrics")))]
+        let _ = context;
 
-        // Set Content-Type based on SOAP version
-        http_request = match self.soap_version {
-            SoapVersion::Soap11 => http_request.header("Content-Type", "text/xml; charset=utf-8"),
-            SoapVersion::Soap12 => {
-                http_request.header("Content-Type", "application/soap+xml; charset=utf-8")
-            }
+        SoapError::Rejected(reason)
+    }
+
+    /// Send a complete SOAP envelope and return the response body text
+    ///
+    /// Sets the transport headers for the configured SOAP version, runs the
+    /// `before_send` and `after_receive` hooks, checks the HTTP status and
+    /// detects SOAP faults in the response. MTOM and SwA messages are packaged after
+    /// `before_send` and unpacked before `after_receive`, so interceptors
+    /// always see the plain envelope.
+    async fn send_envelope(
+        &self,
+        context: &RequestContext,
+        options: &CallOptions,
+        message: &OutgoingMessage,
+    ) -> SoapResult<IncomingMessage> {
+        let mut request = self.soap_request(context, options, &message.envelope);
+        #[cfg(feature = "compression")]
+        if self.response_compression {
+            request.set_header("Accept-Encoding", compression::ACCEPT_ENCODING);
+        }
+        self.interceptors.before_send(&mut request).await?;
+
+        #[cfg_attr(not(any(feature = "mtom", feature = "compression")), allow(unused_mut))]
+        let mut transport_request = TransportRequest {
+            endpoint: request.endpoint.clone(),
+            headers: request.http_headers.clone(),
+            body: request.envelope.clone().into_bytes(),
+            timeout: options.timeout.unwrap_or(self.timeout),
         };
 
-        // Set SOAPAction header for SOAP 1.1
-        if self.soap_version == SoapVersion::Soap11 {
-            let soap_action = self.soap_action.as_deref().unwrap_or(operation);
-            http_request = http_request.header("SOAPAction", format!("\"{}\"", soap_action));
+        #[cfg(feature = "mtom")]
+        if self.mtom {
+            let (content_type, body) = mtom::encode(
+                &request.envelope,
+                request.header("Content-Type").unwrap_or_default(),
+                mtom::Root::Xop,
+                &message.attachments,
+            )?;
+            set_content_type(&mut transport_request.headers, content_type);
+            transport_request.body = body;
+        }
+        #[cfg(feature = "swa")]
+        if !self.mtom && !message.attachments.is_empty() {
+            let (content_type, body) = mtom::encode(
+                &request.envelope,
+                request.header("Content-Type").unwrap_or_default(),
+                mtom::Root::Plain,
+                &message.attachments,
+            )?;
+            set_content_type(&mut transport_request.headers, content_type);
+            transport_request.body = body;
+        }
+        #[cfg(feature = "compression")]
+        if self.request_compression {
+            transport_request.body = compression::compress(&transport_request.body)?;
+            transport_request
+                .headers
+                .push(("Content-Encoding".to_string(), "gzip".to_string()));
         }
 
         // Send request
         #[cfg(feature = "tracing")]
-        debug!(endpoint = %self.endpoint, "Sending HTTP POST request (raw call)");
+        debug!(endpoint = %request.endpoint, correlation_id = ?context.correlation_id, "Sending HTTP POST request");
 
-        let response = match http_request.send().await {
+        #[cfg_attr(not(feature = "compression"), allow(unused_mut))]
+        let mut response = match self.transport.send(transport_request).await {
             Ok(resp) => resp,
             Err(e) => {
                 #[cfg(feature = "tracing")]
-                warn!(endpoint = %self.endpoint, error = %e, "HTTP request failed (raw call)");
-                return Err(e.into());
+                warn!(endpoint = %request.endpoint, error = %e, "HTTP request failed");
+                return Err(e);
             }
         };
 
-        // Check HTTP status
-        let status = response.status();
-
         #[cfg(feature = "tracing")]
-        debug!(status = %status, "Received HTTP response (raw call)");
-        if !status.is_success() && status != StatusCode::INTERNAL_SERVER_ERROR {
-            return Err(SoapError::HttpError(
-                response.error_for_status().unwrap_err(),
-            ));
-        }
+        debug!(status = %response.status, "Received HTTP response");
 
-        // Get response body
-        let response_text = response.text().await?;
+        #[cfg(feature = "compression")]
+        if self.response_compression {
+            if let Some(encoding) = take_header(&mut response.headers, "Content-Encoding") {
+                #[cfg(feature = "tracing")]
+                let compressed_size = response.body.len();
 
-        #[cfg(feature = "tracing")]
-        debug!(
-            response_size = response_text.len(),
-            "Received response body (raw call)"
-        );
+                response.body = compression::decompress(&encoding, response.body)?;
 
-        // Check for SOAP faults
-        if let Err(e) = SoapEnvelope::check_for_fault(&response_text) {
-            #[cfg(feature = "tracing")]
-            warn!(error = %e, "SOAP fault detected in raw response");
-            return Err(e);
+                #[cfg(feature = "tracing")]
+                debug!(encoding = %encoding, compressed_size, size = response.body.len(), "Decompressed response body");
+            }
         }
 
-        Ok(response_text)
+        // Get response body, the root part of MTOM and SwA responses
+        #[cfg(feature = "mtom")]
+        let (body, attachments, xop) = match find_header(&response.headers, "Content-Type") {
+            Some(content_type) if mtom::is_multipart(content_type) => {
+                let (body, attachments) = mtom::decode(content_type, &response.body)?;
+                (body, attachments, mtom::is_xop(content_type))
+            }
+            content_type => (
+                mime::decode_xml(content_type, &response.body),
+                Vec::new(),
+                false,
+            ),
+        };
+        #[cfg(not(feature = "mtom"))]
+        let body = mime::decode_xml(
+            find_header(&response.headers, "Content-Type"),
+            &response.body,
+        );
+
+        let checked = self
+            .check_response(&request, response.status, response.headers, body)
+            .await?;
+
+        Ok(IncomingMessage {
+            text: checked.body,
+            http: HttpMetadata {
+                status: checked.status,
+                headers: checked.http_headers,
+                elapsed: Duration::ZERO,
+                raw_body: if options.raw_body {
+                    response.body
+                } else {
+                    Vec::new()
+                },
+            },
+            #[cfg(feature = "mtom")]
+            attachments,
+            #[cfg(feature = "mtom")]
+            xop,
+        })
     }
-}
 
-/// Builder for configuring a SOAP client
-///
-/// Provides a fluent interface for setting up a SOAP client with custom settings.
-///
-/// # Example
-///
+    /// Send a request with streamed content and read the response envelope
+    ///
+    /// Like [`SoapClient::send_envelope`], but the body is assembled from the
+    /// streams while it is sent, and the response is only read up to the end
+    /// of its envelope.
+    #[cfg(feature = "streaming")]
+    async fn send_stream(
+        &self,
+        context: &RequestContext,
+        options: &CallOptions,
+        message: OutgoingMessage,
+        streams: Vec<Stream>,
+    ) -> SoapResult<(IncomingStream, HttpMetadata)> {
+        let mut request = self.soap_request(context, options, &message.envelope);
+        self.interceptors.before_send(&mut request).await?;
+
+        let (content_type, body) = streaming::body(
+            &request.envelope,
+            request.header("Content-Type").unwrap_or_default(),
+            message.attachments,
+            streams,
+            self.mtom,
+        )?;
+        let mut headers = request.http_headers.clone();
+        set_content_type(&mut headers, content_type);
+
+        #[cfg(feature = "tracing")]
+        debug!(endpoint = %request.endpoint, correlation_id = ?context.correlation_id, "Sending streamed HTTP POST request");
+
+        let response = match self
+            .transport
+            .send_streaming(StreamingRequest {
+                endpoint: request.endpoint.clone(),
+                headers,
+                body,
+                timeout: options.timeout.unwrap_or(self.timeout),
+            })
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                warn!(endpoint = %request.endpoint, error = %e, "HTTP request failed");
+                return Err(e);
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        debug!(status = %response.status, "Received HTTP response");
+
+        let content_type = find_header(&response.headers, "Content-Type").map(str::to_string);
+        let mut incoming = streaming::receive(content_type.as_deref(), response.body).await?;
+        if !(200..300).contains(&response.status) {
+            // Faults are reported with all their text
+            incoming.inline_texts();
+        }
+
+        let checked = self
+            .check_response(
+                &request,
+                response.status,
+                response.headers,
+                std::mem::take(&mut incoming.envelope),
+            )
+            .await?;
+        incoming.envelope = checked.body;
+        Ok((
+            incoming,
+            HttpMetadata {
+                status: checked.status,
+                headers: checked.http_headers,
+                ..HttpMetadata::default()
+            },
+        ))
+    }
+
+    /// Create the request of an envelope with the transport headers of the SOAP version
+    ///
+    /// The HTTP headers of the call options are set last and replace the defaults.
+    fn soap_request(
+        &self,
+        context: &RequestContext,
+        options: &CallOptions,
+        envelope: &str,
+    ) -> SoapRequest {
+        let mut request = SoapRequest {
+            endpoint: context.endpoint.clone(),
+            operation: context.operation.clone(),
+            soap_version: self.soap_version,
+            http_headers: Vec::new(),
+            envelope: envelope.to_string(),
+        };
+
+        // Set Content-Type based on SOAP version
+        request.set_header(
+            "Content-Type",
+            match self.soap_version {
+                SoapVersion::Soap11 => "text/xml; charset=utf-8",
+                SoapVersion::Soap12 => "application/soap+xml; charset=utf-8",
+            },
+        );
+
+        // Set SOAPAction header for SOAP 1.1
+        if self.soap_version == SoapVersion::Soap11 {
+            let action = context
+                .soap_action
+                .as_deref()
+                .or(self.soap_action.as_deref())
+                .unwrap_or(&context.operation);
+            request.set_header("SOAPAction", format!("\"{}\"", action));
+        }
+
+        if let Some(id) = &context.correlation_id {
+            request.set_header(CORRELATION_ID_HEADER, id.as_str());
+        }
+        for (name, value) in &options.http_headers {
+            request.set_header(name.as_str(), value.as_str());
+        }
+
+        request
+    }
+
+    /// Run the `after_receive` hooks, check the HTTP status and detect SOAP faults
+    ///
+    /// Returns the response as changed by the interceptors.
+    async fn check_response(
+        &self,
+        request: &SoapRequest,
+        status: u16,
+        http_headers: Vec<(String, String)>,
+        body: String,
+    ) -> SoapResult<RawResponse> {
+        let mut raw_response = RawResponse {
+            status,
+            http_headers,
+            body,
+        };
+
+        self.interceptors
+            .after_receive(request, &mut raw_response)
+            .await?;
+
+        // SOAP faults can come with 500 status, so we allow that
+        if !(200..300).contains(&status) && status != 500 {
+            return Err(SoapError::HttpStatus(status));
+        }
+
+        let response_text = &raw_response.body;
+
+        #[cfg(feature = "tracing")]
+        debug!(
+            response_size = response_text.len(),
+            "Received response body"
+        );
+
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("soap_response_size_bytes", response_text.len() as f64);
+
+        // Check for SOAP faults
+        if let Err(e) = SoapEnvelope::check_for_fault(response_text) {
+            #[cfg(feature = "tracing")]
+            warn!(error = %e, "SOAP fault detected in response");
+            return Err(e);
+        }
+
+        // A 500 without a SOAP fault, e.g. the error page of a proxy
+        if status == 500 {
+            return Err(SoapError::HttpStatus(status));
+        }
+
+        Ok(raw_response)
+    }
+
+    /// Make a SOAP call without deserializing the response
+    ///
+    /// This is useful for debugging or when you want to handle the raw XML response yourself.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The SOAP operation name
+    /// * `request` - The request body to send
+    ///
+    /// # Returns
+    ///
+    /// The raw XML response as a string
+    #[cfg_attr(feature = "tracing", instrument(skip(self, request), fields(endpoint = %self.endpoint)))]
+    pub async fn call_raw<Req>(&self, operation: &str, request: &Req) -> SoapResult<String>
+    where
+        Req: Serialize,
+    {
+        let options = CallOptions::default();
+        let context = self.request_context(operation, None, &[], &options);
+
+        self.execute(
+            context,
+            &options,
+            |context| {
+                #[cfg(feature = "tracing")]
+                debug!(operation = %operation, "Building SOAP envelope for raw call");
+
+                // Build SOAP envelope
+                SoapEnvelope::build_with_soap_headers(
+                    &context.headers,
+                    request,
+                    self.soap_version,
+                    None,
+                    true,
+                )
+            },
+            |response_text| {
+                #[cfg(feature = "mtom")]
+                return Ok(mtom::inline(response_text));
+
+                #[cfg(not(feature = "mtom"))]
+                Ok(response_text.to_string())
+            },
+        )
+        .await
+        .map(|(text, _)| text)
+    }
+}
+
+/// Header parts of a WSDL message, sent like per-call header blocks
+fn part_headers(headers: &[BodyPart]) -> Vec<SoapHeader> {
+    headers
+        .iter()
+        .map(|part| SoapHeader::raw(part.name(), part.xml()))
+        .collect()
+}
+
+/// Remove a header from transport headers, returning its value
+#[cfg(feature = "compression")]
+fn take_header(headers: &mut Vec<(String, String)>, name: &str) -> Option<String> {
+    let index = headers
+        .iter()
+        .position(|(header, _)| header.eq_ignore_ascii_case(name))?;
+    Some(headers.remove(index).1)
+}
+
+/// Replace the `Content-Type` of transport headers
+#[cfg(feature = "mtom")]
+fn set_content_type(headers: &mut Vec<(String, String)>, content_type: String) {
+    headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Content-Type"));
+    headers.push(("Content-Type".to_string(), content_type));
+}
+
+/// Limit of requests in flight
+#[derive(Debug)]
+struct InFlightLimit {
+    max: usize,
+    current: AtomicUsize,
+}
+
+impl InFlightLimit {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            current: AtomicUsize::new(0),
+        }
+    }
+
+    /// Take a slot, unless all are taken
+    fn try_acquire(&self) -> Option<InFlightPermit<'_>> {
+        self.current
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                (current < self.max).then_some(current + 1)
+            })
+            .ok()
+            .map(|_| InFlightPermit(self))
+    }
+}
+
+/// A slot of the in-flight limit, released when dropped
+struct InFlightPermit<'a>(&'a InFlightLimit);
+
+impl Drop for InFlightPermit<'_> {
+    fn drop(&mut self) {
+        self.0.current.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A serialized request
+struct OutgoingMessage {
+    envelope: String,
+    /// Attachments referenced from the envelope with `xop:Include`, or sent
+    /// alongside it with SOAP with Attachments
+    #[cfg(feature = "mtom")]
+    attachments: Vec<Attachment>,
+}
+
+/// A response body that is not a SOAP fault
+struct IncomingMessage {
+    text: String,
+    http: HttpMetadata,
+    /// Attachments referenced from the body with `cid:` references
+    #[cfg(feature = "mtom")]
+    attachments: Vec<Attachment>,
+    /// Whether the references replace `xop:Include` elements, rather than
+    /// being part of the content (e.g. SwA `swaRef`s)
+    #[cfg(feature = "mtom")]
+    xop: bool,
+}
+
+impl IncomingMessage {
+    /// Parse the body, resolving attachment references
+    fn parse<T>(self, parse_response: impl FnOnce(&str) -> SoapResult<T>) -> SoapResult<T> {
+        #[cfg(feature = "mtom")]
+        if self.xop {
+            return mtom::resolve(self.attachments, || parse_response(&self.text));
+        }
+
+        parse_response(&self.text)
+    }
+}
+
+/// What a call returns besides the parsed response body
+struct Received {
+    http: HttpMetadata,
+    /// Attachments of a SwA response, not referenced through XOP
+    #[cfg(feature = "swa")]
+    attachments: Vec<Attachment>,
+}
+
+impl Received {
+    /// Attach the HTTP details and attachments to a deserialized response
+    fn into_response<T>(self, response: SoapResponse<T>) -> SoapResponse<T> {
+        #[cfg(feature = "swa")]
+        let response = response.with_attachments(self.attachments);
+        response.with_http(self.http)
+    }
+
+    /// Attach the HTTP details and attachments to the parts of a response
+    fn into_parts(self, parts: ResponseParts) -> ResponseParts {
+        #[cfg(feature = "swa")]
+        let parts = parts.with_attachments(self.attachments);
+        parts.with_http(self.http)
+    }
+}
+
+/// Builder for configuring a SOAP client
+///
+/// Provides a fluent interface for setting up a SOAP client with custom settings.
+///
+/// # Example
+///
 /// ```no_run
 /// use soapus_runtime::{SoapClient, SoapVersion};
 /// use std::time::Duration;
@@ -402,7 +1357,22 @@ pub struct SoapClientBuilder {
     soap_version: SoapVersion,
     soap_action: Option<String>,
     timeout: Duration,
-    http_client: Option<Client>,
+    transport: Option<Arc<dyn SoapTransport>>,
+    headers: Vec<SoapHeader>,
+    interceptors: InterceptorChain,
+    retry_policy: RetryPolicy,
+    idempotent_operations: HashSet<String>,
+    circuit_breaker: Option<CircuitBreaker>,
+    max_in_flight: Option<usize>,
+    #[cfg(feature = "auth")]
+    auth: Option<HttpAuth>,
+    cookie_jar: Option<CookieJar>,
+    #[cfg(feature = "mtom")]
+    mtom: bool,
+    #[cfg(feature = "compression")]
+    response_compression: bool,
+    #[cfg(feature = "compression")]
+    request_compression: bool,
 }
 
 impl SoapClientBuilder {
@@ -413,7 +1383,22 @@ impl SoapClientBuilder {
             soap_version: SoapVersion::Soap11,
             soap_action: None,
             timeout: Duration::from_secs(30),
-            http_client: None,
+            transport: None,
+            headers: Vec::new(),
+            interceptors: InterceptorChain::default(),
+            retry_policy: RetryPolicy::none(),
+            idempotent_operations: HashSet::new(),
+            circuit_breaker: None,
+            max_in_flight: None,
+            #[cfg(feature = "auth")]
+            auth: None,
+            cookie_jar: None,
+            #[cfg(feature = "mtom")]
+            mtom: false,
+            #[cfg(feature = "compression")]
+            response_compression: true,
+            #[cfg(feature = "compression")]
+            request_compression: false,
         }
     }
 
@@ -439,19 +1424,190 @@ impl SoapClientBuilder {
     ///
     /// This allows you to configure the underlying reqwest client with custom settings
     /// such as proxies, authentication, or custom certificates.
-    pub fn http_client(mut self, client: Client) -> Self {
-        self.http_client = Some(client);
+    #[cfg(feature = "reqwest")]
+    pub fn http_client(self, client: reqwest::Client) -> Self {
+        self.transport(crate::transport::ReqwestTransport::with_client(client))
+    }
+
+    /// Set the transport used to send requests
+    ///
+    /// Defaults to [`ReqwestTransport`](crate::transport::ReqwestTransport)
+    /// if the `reqwest` feature is enabled.
+    pub fn transport(mut self, transport: impl SoapTransport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Authenticate requests with HTTP Basic, Digest, NTLM or bearer tokens
+    ///
+    /// Works with any transport; `401` challenges are answered transparently.
+    /// See the [`auth`](crate::auth) module.
+    #[cfg(feature = "auth")]
+    pub fn auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Store cookies set by responses and send them with later requests
+    ///
+    /// Disabled by default. Enable it for session-based services, e.g. ASP.NET
+    /// `.asmx` services or services with a login operation. See the
+    /// [`session`](crate::session) module.
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        self.cookie_jar = match enabled {
+            true => Some(self.cookie_jar.unwrap_or_default()),
+            false => None,
+        };
+        self
+    }
+
+    /// Use a cookie jar for the session, e.g. with cookies imported after a restart
+    ///
+    /// The jar stays shared with the caller, so the session can be exported later.
+    pub fn cookie_jar(mut self, jar: CookieJar) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Send a WS-Security header with every request
+    ///
+    /// The header is built anew for each attempt, including retries. Content
+    /// streamed by `SoapClient::call_streaming` is neither signed nor
+    /// encrypted. See the [`ws_security`](crate::ws_security) module.
+    #[cfg(feature = "ws-security")]
+    pub fn ws_security(self, security: crate::ws_security::WsSecurity) -> Self {
+        self.interceptor(security)
+    }
+
+    /// Send WS-Addressing headers with every request
+    ///
+    /// `wsa:To` defaults to the endpoint of each call. See the
+    /// [`addressing`](crate::addressing) module.
+    #[cfg(feature = "ws-addressing")]
+    pub fn ws_addressing(self, addressing: crate::addressing::WsAddressing) -> Self {
+        self.interceptor(addressing)
+    }
+
+    /// Send requests as MTOM messages
+    ///
+    /// [`Binary`](crate::mtom::Binary) content is sent as `multipart/related`
+    /// attachment instead of base64 text. MTOM responses are understood either
+    /// way. See the [`mtom`] module.
+    #[cfg(feature = "mtom")]
+    pub fn mtom(mut self, enabled: bool) -> Self {
+        self.mtom = enabled;
+        self
+    }
+
+    /// Accept compressed responses
+    ///
+    /// Enabled by default: requests carry `Accept-Encoding: gzip, deflate, br`
+    /// and compressed responses are decoded. See the [`compression`] module.
+    #[cfg(feature = "compression")]
+    pub fn response_compression(mut self, enabled: bool) -> Self {
+        self.response_compression = enabled;
+        self
+    }
+
+    /// Send request bodies gzipped, with `Content-Encoding: gzip`
+    ///
+    /// Disabled by default, since not every server accepts compressed requests.
+    #[cfg(feature = "compression")]
+    pub fn request_compression(mut self, enabled: bool) -> Self {
+        self.request_compression = enabled;
+        self
+    }
+
+    /// Add a header block that is sent with every request
+    pub fn header(mut self, header: SoapHeader) -> Self {
+        self.headers.push(header);
+        self
+    }
+
+    /// Add an interceptor that runs for every call
+    ///
+    /// Interceptors run in the order they are added before a request is sent,
+    /// and in reverse order on the response.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Set the retry policy
+    ///
+    /// By default failed calls are not retried. Calls made with
+    /// `SoapClient::call_streaming` are never retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Mark an operation as safe to retry after the request may have reached the server
+    ///
+    /// Generated clients mark the operations configured as idempotent in the generator.
+    pub fn idempotent_operation(mut self, operation: impl Into<String>) -> Self {
+        self.idempotent_operations.insert(operation.into());
+        self
+    }
+
+    /// Fail fast while the endpoint keeps failing
+    ///
+    /// See the [`circuit_breaker`](crate::circuit_breaker) module for the states.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Limit the number of requests in flight
+    ///
+    /// Further calls fail immediately with [`Rejection::ConcurrencyLimit`].
+    /// The limit is shared by all clones of the client; retries of a call do
+    /// not count separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0, which would reject every call.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        assert!(max > 0, "max_in_flight must be at least 1");
+        self.max_in_flight = Some(max);
         self
     }
 
     /// Build the SOAP client
     pub fn build(self) -> SoapClient {
+        let mut transport = self
+            .transport
+            .unwrap_or_else(crate::transport::default_transport);
+        // Below authentication, so cookies of challenge responses are sent with the answer
+        if let Some(jar) = &self.cookie_jar {
+            transport = Arc::new(SessionTransport::new(transport, jar.clone()));
+        }
+        #[cfg(feature = "auth")]
+        if let Some(auth) = self.auth {
+            transport = Arc::new(AuthTransport::new(transport, auth));
+        }
+
         SoapClient {
             endpoint: self.endpoint,
-            http_client: self.http_client.unwrap_or_default(),
+            transport,
             soap_version: self.soap_version,
             soap_action: self.soap_action,
             timeout: self.timeout,
+            headers: self.headers,
+            interceptors: self.interceptors,
+            retry_policy: self.retry_policy,
+            idempotent_operations: self.idempotent_operations,
+            circuit_breaker: self.circuit_breaker,
+            in_flight: self
+                .max_in_flight
+                .map(|max| Arc::new(InFlightLimit::new(max))),
+            cookie_jar: self.cookie_jar,
+            #[cfg(feature = "mtom")]
+            mtom: self.mtom,
+            #[cfg(feature = "compression")]
+            response_compression: self.response_compression,
+            #[cfg(feature = "compression")]
+            request_compression: self.request_compression,
         }
     }
 }
@@ -459,6 +1615,8 @@ impl SoapClientBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::CancellationToken;
+    use crate::transport::{MemoryTransport, TransportResponse};
 
     #[test]
     fn test_client_creation() {
@@ -498,6 +1656,1026 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_persistent_headers() {
+        let mut client = SoapClient::builder("http://example.com/soap")
+            .header(SoapHeader::raw("Token", "<Token>abc</Token>"))
+            .build();
+        client.add_header(SoapHeader::raw("Trace", "<Trace>1</Trace>"));
+        assert_eq!(client.headers().len(), 2);
+
+        assert!(client.remove_header("Token"));
+        assert!(!client.remove_header("Token"));
+        assert_eq!(client.headers()[0].name(), "Trace");
+
+        client.clear_headers();
+        assert!(client.headers().is_empty());
+    }
+
+    /// A client answering every call with the given response
+    fn memory_client(status: u16, body: &'static str) -> (SoapClient, MemoryTransport) {
+        let transport = MemoryTransport::new();
+        transport.push_response(TransportResponse::new(status, body));
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .build();
+        (client, transport)
+    }
+
+    #[derive(Serialize)]
+    struct Ping {}
+
+    #[derive(Default)]
+    struct TestInterceptor {
+        errors: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Interceptor for TestInterceptor {
+        async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+            context
+                .headers
+                .push(SoapHeader::raw("Audit", "<Audit>on</Audit>"));
+            Ok(())
+        }
+
+        async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+            request.set_header("Authorization", "Bearer token");
+            Ok(())
+        }
+
+        async fn after_receive(
+            &self,
+            _request: &SoapRequest,
+            response: &mut RawResponse,
+        ) -> SoapResult<()> {
+            response.body = response.body.replace("secret", "[redacted]");
+            Ok(())
+        }
+
+        async fn on_error(&self, context: &RequestContext, error: &SoapError) {
+            self.errors
+                .lock()
+                .unwrap()
+                .push(format!("{}: {}", context.operation, error));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_hooks() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct PingResponse {
+            value: String,
+        }
+
+        let (mut client, transport) = memory_client(
+            200,
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><PingResponse><value>secret</value></PingResponse></soap:Body></soap:Envelope>"#,
+        );
+        let interceptor = Arc::new(TestInterceptor::default());
+        client.add_interceptor(interceptor.clone());
+
+        let response: PingResponse = client.call("Ping", &Ping {}).await.unwrap();
+        assert_eq!(response.value, "[redacted]");
+
+        let requests = transport.requests();
+        assert_eq!(requests[0].endpoint, "memory://service");
+        assert!(requests[0]
+            .headers
+            .contains(&("Authorization".to_string(), "Bearer token".to_string())));
+        assert!(requests[0]
+            .headers
+            .contains(&("SOAPAction".to_string(), "\"Ping\"".to_string())));
+        assert!(String::from_utf8_lossy(&requests[0].body).contains("<Audit>on</Audit>"));
+        assert!(interceptor.errors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_on_error() {
+        let (mut client, _) = memory_client(
+            500,
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>Boom</faultstring></soap:Fault></soap:Body></soap:Envelope>"#,
+        );
+        let interceptor = Arc::new(TestInterceptor::default());
+        client.add_interceptor(interceptor.clone());
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+
+        assert!(result.unwrap_err().is_server_fault());
+        assert_eq!(
+            *interceptor.errors.lock().unwrap(),
+            vec!["Ping: SOAP fault: soap:Server - Boom"]
+        );
+    }
+
+    /// Records the hooks of a call under its name
+    struct HookRecorder {
+        name: &'static str,
+        log: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl HookRecorder {
+        fn record(&self, hook: impl std::fmt::Display) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", hook, self.name));
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Interceptor for HookRecorder {
+        async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+            let names: Vec<_> = context.headers.iter().map(SoapHeader::name).collect();
+            self.record(format!("serialize [{}]", names.join(",")));
+            // WSDL header parts can be replaced like any other header block
+            context.headers.retain(|header| header.name() != "Session");
+            context
+                .headers
+                .push(SoapHeader::raw("Session", "<Session>renewed</Session>"));
+            Ok(())
+        }
+
+        async fn before_send(&self, _request: &mut SoapRequest) -> SoapResult<()> {
+            self.record("send");
+            Ok(())
+        }
+
+        async fn after_receive(
+            &self,
+            _request: &SoapRequest,
+            _response: &mut RawResponse,
+        ) -> SoapResult<()> {
+            self.record("receive");
+            Ok(())
+        }
+
+        async fn after_parse(
+            &self,
+            _context: &RequestContext,
+            response: &ParsedResponse,
+        ) -> SoapResult<()> {
+            self.record(format!(
+                "parse {} [{}]",
+                response.status,
+                response.headers.names().collect::<Vec<_>>().join(",")
+            ));
+            match response.headers.raw("Deprecated") {
+                Some(_) if self.name == "strict" => Err(SoapError::InvalidResponse(
+                    "operation is deprecated".to_string(),
+                )),
+                _ => Ok(()),
+            }
+        }
+
+        async fn on_error(&self, _context: &RequestContext, _error: &SoapError) {
+            self.record("error");
+        }
+    }
+
+    fn recording_client(
+        body: &'static str,
+        names: &[&'static str],
+    ) -> (
+        SoapClient,
+        MemoryTransport,
+        Arc<std::sync::Mutex<Vec<String>>>,
+    ) {
+        let (mut client, transport) = memory_client(200, body);
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        for &name in names {
+            client.add_interceptor(HookRecorder {
+                name,
+                log: log.clone(),
+            });
+        }
+        (client, transport, log)
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_hook_order() {
+        let (client, transport, log) = recording_client(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Header><Trace>1</Trace></soap:Header><soap:Body><PingResponse/></soap:Body></soap:Envelope>"#,
+            &["a", "b"],
+        );
+
+        let response = client
+            .call_parts_with_options(
+                "Ping",
+                None,
+                &[BodyPart::raw("Session", "<Session>old</Session>")],
+                &[BodyPart::raw("Ping", "<Ping/>")],
+                &CallOptions::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.raw("PingResponse"), Some("<PingResponse/>"));
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "serialize [Session] a",
+                "serialize [Session] b",
+                "send a",
+                "send b",
+                "receive b",
+                "receive a",
+                "parse 200 [Trace] b",
+                "parse 200 [Trace] a",
+            ]
+        );
+        let body = String::from_utf8_lossy(&transport.requests()[0].body).into_owned();
+        assert!(body.contains("<Session>renewed</Session>"));
+        assert!(!body.contains("<Session>old</Session>"));
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_after_parse_error() {
+        let (client, _, log) = recording_client(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Header><Deprecated/></soap:Header><soap:Body><PingResponse/></soap:Body></soap:Envelope>"#,
+            &["a", "strict"],
+        );
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+
+        assert!(matches!(result, Err(SoapError::InvalidResponse(_))));
+        assert_eq!(
+            log.lock().unwrap()[6..],
+            ["parse 200 [Deprecated] strict", "error strict", "error a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_after_parse_skipped_on_fault() {
+        let (client, _, log) = recording_client(
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><soap:Fault><faultcode>soap:Server</faultcode><faultstring>Boom</faultstring></soap:Fault></soap:Body></soap:Envelope>"#,
+            &["a"],
+        );
+
+        assert!(client.call_raw("Ping", &Ping {}).await.is_err());
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["serialize [] a", "send a", "receive a", "error a"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_call_options() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct PingResponse {
+            value: String,
+        }
+
+        let (client, transport) = memory_client(
+            200,
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><PingResponse><value>pong</value></PingResponse></soap:Body></soap:Envelope>"#,
+        );
+        let options = CallOptions::new()
+            .timeout(Duration::from_secs(5))
+            .endpoint("memory://other")
+            .http_header("SOAPAction", "\"urn:Ping\"")
+            .http_header("X-Tenant", "acme")
+            .soap_header(SoapHeader::raw("Trace", "<Trace>on</Trace>"))
+            .correlation_id("order-4711");
+        let response = client
+            .call_with_options::<_, PingResponse>("Ping", None, None, true, &Ping {}, &options)
+            .await
+            .unwrap();
+        assert_eq!(response.body().value, "pong");
+
+        let request = &transport.requests()[0];
+        assert_eq!(request.endpoint, "memory://other");
+        assert_eq!(request.timeout, Duration::from_secs(5));
+        assert_eq!(
+            find_header(&request.headers, "SOAPAction"),
+            Some("\"urn:Ping\"")
+        );
+        assert_eq!(find_header(&request.headers, "X-Tenant"), Some("acme"));
+        assert_eq!(
+            find_header(&request.headers, CORRELATION_ID_HEADER),
+            Some("order-4711")
+        );
+        assert!(String::from_utf8_lossy(&request.body).contains("<Trace>on</Trace>"));
+
+        // The client configuration is left unchanged
+        let _: SoapResult<PingResponse> = client.call("Ping", &Ping {}).await;
+        let request = &transport.requests()[1];
+        assert_eq!(request.endpoint, "memory://service");
+        assert_eq!(request.timeout, Duration::from_secs(30));
+        assert_eq!(find_header(&request.headers, CORRELATION_ID_HEADER), None);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_session() {
+        let transport = MemoryTransport::new();
+        let envelope = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><PingResponse/></soap:Body></soap:Envelope>"#;
+        transport.push_response(
+            TransportResponse::new(200, envelope)
+                .with_header("Set-Cookie", "ASP.NET_SessionId=abc123; path=/; HttpOnly"),
+        );
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(200, envelope));
+        }
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .cookie_store(true)
+            .build();
+
+        client.call_raw("Login", &Ping {}).await.unwrap();
+        client.clone().call_raw("Ping", &Ping {}).await.unwrap();
+        assert_eq!(
+            client
+                .cookie_jar()
+                .unwrap()
+                .get("ASP.NET_SessionId")
+                .as_deref(),
+            Some("abc123")
+        );
+        client.clone().reset_session();
+        client.call_raw("Ping", &Ping {}).await.unwrap();
+
+        let requests = transport.requests();
+        assert_eq!(find_header(&requests[0].headers, "Cookie"), None);
+        assert_eq!(
+            find_header(&requests[1].headers, "Cookie"),
+            Some("ASP.NET_SessionId=abc123")
+        );
+        assert_eq!(find_header(&requests[2].headers, "Cookie"), None);
+
+        // Cookies are opt-in
+        assert!(SoapClient::new("memory://service").cookie_jar().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_response() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct PingResponse {
+            value: String,
+        }
+
+        const ENVELOPE: &str = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Header><Session><id>7</id></Session></soap:Header><soap:Body><PingResponse><value>pong</value></PingResponse></soap:Body></soap:Envelope>"#;
+        let transport = MemoryTransport::new();
+        for _ in 0..2 {
+            transport.push_response(
+                TransportResponse::new(200, ENVELOPE)
+                    .with_header("Set-Cookie", "session=7")
+                    .with_header("Set-Cookie", "lang=en")
+                    .with_header("Server-Timing", "db;dur=53"),
+            );
+        }
+        let client = SoapClient::builder("memory://service")
+            .transport(transport)
+            .build();
+
+        let options = CallOptions::new().raw_body(true);
+        let response: SoapResponse<PingResponse> = client
+            .call_with_options("Ping", None, None, true, &Ping {}, &options)
+            .await
+            .unwrap();
+        assert_eq!(response.body().value, "pong");
+        let http = response.http();
+        assert_eq!(http.status(), 200);
+        assert_eq!(http.header("server-timing"), Some("db;dur=53"));
+        let cookies: Vec<&str> = http
+            .headers()
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Set-Cookie"))
+            .map(|(_, value)| value.as_str())
+            .collect();
+        assert_eq!(cookies, ["session=7", "lang=en"]);
+        assert_eq!(http.raw_body(), ENVELOPE.as_bytes());
+        assert!(http.elapsed() > Duration::ZERO);
+        assert!(response.headers().raw("Session").is_some());
+
+        // The raw body is only kept on request
+        let parts = client
+            .call_parts_with_options("Ping", None, &[], &[], &CallOptions::new())
+            .await
+            .unwrap();
+        let value: PingResponse = parts.part("PingResponse").unwrap();
+        let response = parts.into_response(value);
+        assert_eq!(response.body().value, "pong");
+        assert_eq!(response.http().status(), 200);
+        assert!(response.http().raw_body().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_call_options_cancellation() {
+        let (client, transport) = memory_client(200, "");
+        let token = CancellationToken::new();
+        token.cancel();
+        let options = CallOptions::new().cancellation(token);
+
+        let result = client
+            .call_parts_with_options("Ping", None, &[], &[], &options)
+            .await;
+        assert!(matches!(result, Err(SoapError::Cancelled)));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_http_status_error() {
+        let (client, _) = memory_client(503, "Service Unavailable");
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+        assert!(matches!(result, Err(SoapError::HttpStatus(503))));
+
+        let (client, _) = memory_client(500, "<html><body>Internal Server Error</body></html>");
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+        assert!(matches!(result, Err(SoapError::HttpStatus(500))));
+    }
+
+    #[tokio::test]
+    async fn test_response_charset() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct PingResponse {
+            value: String,
+        }
+
+        let transport = MemoryTransport::new();
+        let envelope = "<soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\"><soap:Body><PingResponse><value>Gr\u{fc}\u{df}e</value></PingResponse></soap:Body></soap:Envelope>";
+        let (latin1, _, _) = encoding_rs::WINDOWS_1252.encode(envelope);
+        transport.push_response(
+            TransportResponse::new(200, latin1.into_owned())
+                .with_header("Content-Type", "text/xml; charset=ISO-8859-1"),
+        );
+        let utf16: Vec<u8> = format!(
+            "\u{feff}<?xml version=\"1.0\" encoding=\"UTF-16\"?>{}",
+            envelope
+        )
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+        transport.push_response(TransportResponse::new(200, utf16));
+        let client = SoapClient::builder("memory://service")
+            .transport(transport)
+            .build();
+
+        let response: PingResponse = client.call("Ping", &Ping {}).await.unwrap();
+        assert_eq!(response.value, "Grüße");
+        let response: PingResponse = client.call("Ping", &Ping {}).await.unwrap();
+        assert_eq!(response.value, "Grüße");
+    }
+
+    #[tokio::test]
+    async fn test_retry_idempotent_operation() {
+        let transport = MemoryTransport::new();
+        transport.push_response(TransportResponse::new(503, "Service Unavailable"));
+        transport.push_response(TransportResponse::new(
+            200,
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><Pong/></soap:Body></soap:Envelope>"#,
+        ));
+        let interceptor = Arc::new(TestInterceptor::default());
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .interceptor(interceptor.clone())
+            .retry_policy(RetryPolicy::new(3).backoff(Duration::ZERO, Duration::ZERO))
+            .idempotent_operation("Ping")
+            .build();
+
+        let response = client.call_raw("Ping", &Ping {}).await.unwrap();
+        assert!(response.contains("<Pong/>"));
+
+        // The same envelope is sent again, before_send runs for every attempt
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].body, requests[1].body);
+        assert!(requests[1]
+            .headers
+            .iter()
+            .any(|(name, _)| name == "Authorization"));
+        assert!(interceptor.errors.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_for_non_idempotent_operation() {
+        let (mut client, transport) = memory_client(503, "Service Unavailable");
+        client.set_retry_policy(RetryPolicy::new(3).backoff(Duration::ZERO, Duration::ZERO));
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+        assert!(matches!(result, Err(SoapError::HttpStatus(503))));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast() {
+        let transport = MemoryTransport::with_handler(|_| {
+            Ok(TransportResponse::new(503, "Service Unavailable"))
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .circuit_breaker(CircuitBreaker::new(2, Duration::from_secs(60)))
+            .build();
+
+        for _ in 0..2 {
+            let result = client.call_raw("Ping", &Ping {}).await;
+            assert!(matches!(result, Err(SoapError::HttpStatus(503))));
+        }
+        assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+
+        let result = client.call_raw("Ping", &Ping {}).await;
+        assert!(matches!(
+            result,
+            Err(SoapError::Rejected(Rejection::CircuitOpen))
+        ));
+        assert_eq!(transport.requests().len(), 2);
+
+        // Other endpoints have circuits of their own
+        let options = CallOptions::new().endpoint("memory://backup");
+        let result: SoapResult<SoapResponse<String>> = client
+            .call_with_options("Ping", None, None, true, &Ping {}, &options)
+            .await;
+        assert!(matches!(result, Err(SoapError::HttpStatus(503))));
+        assert_eq!(transport.requests().len(), 3);
+        assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+    }
+
+    #[test]
+    #[should_panic(expected = "max_in_flight must be at least 1")]
+    fn test_max_in_flight_zero() {
+        SoapClient::builder("memory://service").max_in_flight(0);
+    }
+
+    /// Holds every request until released
+    #[derive(Default)]
+    struct GateTransport {
+        entered: tokio::sync::Notify,
+        release: tokio::sync::Notify,
+    }
+
+    #[async_trait::async_trait]
+    impl SoapTransport for GateTransport {
+        async fn send(
+            &self,
+            _request: TransportRequest,
+        ) -> SoapResult<crate::transport::TransportResponse> {
+            self.entered.notify_one();
+            self.release.notified().await;
+            Ok(TransportResponse::new(
+                200,
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body/></soap:Envelope>"#,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight() {
+        let transport = Arc::new(GateTransport::default());
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .max_in_flight(1)
+            .build();
+
+        let first = tokio::spawn({
+            let client = client.clone();
+            async move { client.call_raw("Ping", &Ping {}).await }
+        });
+        transport.entered.notified().await;
+
+        let second = client.call_raw("Ping", &Ping {}).await;
+        assert!(matches!(
+            second,
+            Err(SoapError::Rejected(Rejection::ConcurrencyLimit))
+        ));
+
+        transport.release.notify_one();
+        assert!(first.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "auth")]
+    async fn test_auth_wraps_transport() {
+        let transport = MemoryTransport::with_handler(|request| {
+            let authorized = request
+                .headers
+                .iter()
+                .any(|(name, value)| name == "Authorization" && value == "Bearer abc");
+            Ok(TransportResponse::new(
+                if authorized { 200 } else { 401 },
+                r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body/></soap:Envelope>"#,
+            ))
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .auth(HttpAuth::bearer("abc"))
+            .build();
+
+        assert!(client.call_raw("Ping", &Ping {}).await.is_ok());
+    }
+
+    #[cfg(feature = "ws-security")]
+    #[tokio::test]
+    async fn test_ws_security_header() {
+        use crate::ws_security::{UsernameToken, WsSecurity};
+
+        let (_, transport) = memory_client(200, "");
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .ws_security(WsSecurity::new().username_token(UsernameToken::text("alice", "secret")))
+            .build();
+        let _ = client.call_raw("Ping", &Ping {}).await;
+
+        let envelope = String::from_utf8(transport.requests()[0].body.clone()).unwrap();
+        assert!(envelope.contains("<soap:Header><wsse:Security soap:mustUnderstand=\"1\""));
+        assert!(envelope.contains("<wsse:Username>alice</wsse:Username>"));
+    }
+
+    #[cfg(feature = "ws-addressing")]
+    #[tokio::test]
+    async fn test_ws_addressing() {
+        use crate::addressing::WsAddressing;
+
+        #[derive(Deserialize)]
+        struct PingResponse {}
+
+        // Replies relating to the MessageID of the request
+        let transport = MemoryTransport::with_handler(|request| {
+            let envelope = String::from_utf8(request.body.clone()).unwrap();
+            let start = envelope.find("urn:uuid:").unwrap();
+            let message_id = &envelope[start..start + 45];
+            let relates_to = if envelope.contains("Other") {
+                "urn:uuid:0"
+            } else {
+                message_id
+            };
+            Ok(TransportResponse::new(
+                200,
+                format!(
+                    "<s:Envelope xmlns:s=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:a=\"http://www.w3.org/2005/08/addressing\"><s:Header><a:RelatesTo>{}</a:RelatesTo></s:Header><s:Body><PingResponse/></s:Body></s:Envelope>",
+                    relates_to
+                ),
+            ))
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .soap_version(SoapVersion::Soap12)
+            .ws_addressing(WsAddressing::new())
+            .build();
+        client
+            .call_with_soap_action::<_, PingResponse>(
+                "Ping",
+                Some("http://tempuri.org/IService/Ping"),
+                None,
+                false,
+                &Ping {},
+            )
+            .await
+            .unwrap();
+
+        let request = &transport.requests()[0];
+        let envelope = String::from_utf8(request.body.clone()).unwrap();
+        assert!(envelope.contains(">http://tempuri.org/IService/Ping</wsa:Action>"));
+        assert!(envelope.contains(">memory://service</wsa:To>"));
+        assert!(envelope.contains("<wsa:ReplyTo"));
+        let content_type = request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Content-Type")
+            .map(|(_, value)| value.as_str());
+        assert_eq!(
+            content_type,
+            Some(
+                "application/soap+xml; charset=utf-8; action=\"http://tempuri.org/IService/Ping\""
+            )
+        );
+
+        // `wsa:To` follows the endpoint of the call
+        let options = CallOptions::new().endpoint("memory://other");
+        client
+            .call_with_options::<_, PingResponse>("Ping", None, None, false, &Ping {}, &options)
+            .await
+            .unwrap();
+        let request = &transport.requests()[1];
+        assert_eq!(request.endpoint, "memory://other");
+        let envelope = String::from_utf8(request.body.clone()).unwrap();
+        assert!(envelope.contains(">memory://other</wsa:To>"));
+
+        let client = SoapClient::builder("memory://service")
+            .transport(transport)
+            .ws_addressing(WsAddressing::new())
+            .build();
+        let error = client.call_raw("Other", &Ping {}).await.unwrap_err();
+        assert!(matches!(error, SoapError::InvalidResponse(_)));
+    }
+
+    #[cfg(feature = "mtom")]
+    #[tokio::test]
+    async fn test_mtom() {
+        use crate::mtom::Binary;
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Upload {
+            name: String,
+            content: Binary,
+        }
+
+        // Echoes the request, so the response is an MTOM message as well
+        let transport = MemoryTransport::with_handler(|request| {
+            let mut response = TransportResponse::new(200, request.body.clone());
+            response.headers = request.headers.clone();
+            Ok(response)
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .mtom(true)
+            .build();
+        let upload = Upload {
+            name: "scan.pdf".to_string(),
+            content: Binary::new((0..=255).rev().collect::<Vec<u8>>()),
+        };
+        let response: Upload = client.call("Upload", &upload).await.unwrap();
+        assert_eq!(response, upload);
+
+        let request = &transport.requests()[0];
+        let content_type = request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "Content-Type")
+            .map(|(_, value)| value.as_str())
+            .unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"application/xop+xml\""));
+        assert!(content_type.ends_with("start-info=\"text/xml\""));
+        let body = String::from_utf8_lossy(&request.body);
+        assert!(body.contains("<content><xop:Include xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:1."));
+        assert!(request
+            .body
+            .windows(256)
+            .any(|window| window == upload.content.as_bytes()));
+
+        // Without MTOM the content is sent as base64 text
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .build();
+        let response: Upload = client.call("Upload", &upload).await.unwrap();
+        assert_eq!(response, upload);
+        assert!(String::from_utf8(transport.requests()[1].body.clone())
+            .unwrap()
+            .contains("<content>//79/Pv6"));
+    }
+
+    #[cfg(feature = "swa")]
+    #[tokio::test]
+    async fn test_swa() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Claim {
+            form: String,
+        }
+
+        // Echoes the request, so the response carries the attachment as well
+        let transport = MemoryTransport::with_handler(|request| {
+            let mut response = TransportResponse::new(200, request.body.clone());
+            response.headers = request.headers.clone();
+            Ok(response)
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .build();
+        let form = Attachment::for_part("form", "image/tiff", b"II*\0".to_vec());
+        let claim = Claim {
+            form: form.reference(),
+        };
+        let options = CallOptions::new().attachment(form.clone());
+        let response = client
+            .call_with_options::<_, Claim>("SubmitClaim", None, None, true, &claim, &options)
+            .await
+            .unwrap();
+        assert_eq!(response.body(), &claim);
+        assert_eq!(response.attachments(), std::slice::from_ref(&form));
+        assert_eq!(response.attachment(&response.body().form), Some(&form));
+
+        let request = &transport.requests()[0];
+        let content_type = find_header(&request.headers, "Content-Type").unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"text/xml\""));
+        assert!(String::from_utf8_lossy(&request.body)
+            .contains("Content-Type: text/xml; charset=utf-8\r\n"));
+
+        let parts = client
+            .call_parts_with_options(
+                "SubmitClaim",
+                None,
+                &[],
+                &[BodyPart::raw("Claim", "<Claim><form>cid:x</form></Claim>")],
+                &CallOptions::new().attachments([Attachment::new("x", "text/plain", "hello")]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            parts.raw("Claim"),
+            Some("<Claim><form>cid:x</form></Claim>")
+        );
+        assert_eq!(parts.attachment("cid:x").unwrap().data(), b"hello");
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compression() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Report {
+            rows: Vec<String>,
+        }
+
+        // Echoes the gzipped request, Content-Encoding included
+        let transport = MemoryTransport::with_handler(|request| {
+            let mut response = TransportResponse::new(200, request.body.clone());
+            response.headers = request.headers.clone();
+            Ok(response)
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .request_compression(true)
+            .build();
+        let report = Report {
+            rows: vec!["quarterly".to_string(); 50],
+        };
+        let response: Report = client.call("GetReport", &report).await.unwrap();
+        assert_eq!(response, report);
+
+        let request = &transport.requests()[0];
+        assert_eq!(
+            find_header(&request.headers, "Accept-Encoding"),
+            Some(crate::compression::ACCEPT_ENCODING)
+        );
+        assert_eq!(
+            find_header(&request.headers, "Content-Encoding"),
+            Some("gzip")
+        );
+        assert_eq!(&request.body[..2], &[0x1f, 0x8b]);
+
+        // The raw body is decompressed, and Content-Encoding no longer applies
+        let options = CallOptions::new().raw_body(true);
+        let response = client
+            .call_with_options::<_, Report>("GetReport", None, None, true, &report, &options)
+            .await
+            .unwrap();
+        assert!(
+            String::from_utf8_lossy(response.http().raw_body()).contains("<rows>quarterly</rows>")
+        );
+        assert_eq!(response.http().header("Content-Encoding"), None);
+        assert_eq!(
+            response.http().header("Content-Type"),
+            Some("text/xml; charset=utf-8")
+        );
+
+        // Without response compression, encoded bodies are left alone
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .request_compression(true)
+            .response_compression(false)
+            .build();
+        let result: SoapResult<Report> = client.call("GetReport", &report).await;
+        assert!(result.is_err());
+        assert_eq!(
+            find_header(&transport.requests()[2].headers, "Accept-Encoding"),
+            None
+        );
+    }
+
+    #[cfg(feature = "streaming")]
+    #[tokio::test]
+    async fn test_streaming() {
+        use crate::streaming::BinaryStream;
+        use tokio::io::AsyncReadExt;
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Upload {
+            name: String,
+            content: BinaryStream,
+        }
+
+        // Echoes the request, so the response streams the content back
+        let transport = MemoryTransport::with_handler(|request| {
+            let mut response = TransportResponse::new(200, request.body.clone());
+            response.headers = request.headers.clone();
+            Ok(response)
+        });
+        let data: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+        for mtom in [true, false] {
+            let client = SoapClient::builder("memory://service")
+                .transport(transport.clone())
+                .mtom(mtom)
+                .build();
+            let upload = Upload {
+                name: "scan.pdf".to_string(),
+                content: BinaryStream::from_bytes(data.clone()),
+            };
+            let mut response: Upload = client
+                .call_streaming("Upload", None, None, true, &upload, &CallOptions::new())
+                .await
+                .unwrap();
+            assert_eq!(response.name, "scan.pdf");
+            let mut content = Vec::new();
+            response.content.read_to_end(&mut content).await.unwrap();
+            assert_eq!(content, data);
+        }
+
+        let requests = transport.requests();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("<content><xop:Include xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:stream-1."));
+        let body = String::from_utf8_lossy(&requests[1].body);
+        assert!(body.contains("<content>AAECAwQF"));
+
+        // Plain calls can't send streams
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .build();
+        let upload = Upload {
+            name: "scan.pdf".to_string(),
+            content: BinaryStream::from_bytes(data),
+        };
+        let error = client
+            .call::<_, Upload>("Upload", &upload)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("call_streaming"));
+    }
+
+    #[cfg(feature = "ws-security")]
+    #[tokio::test]
+    async fn test_ws_security_signature() {
+        use crate::ws_security::WsSecurity;
+
+        // Echoes the signed request, so the response carries a valid signature
+        let transport = MemoryTransport::with_handler(|request| {
+            Ok(TransportResponse::new(200, request.body.clone()))
+        });
+        let credential = crate::dsig::tests::credential();
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .ws_security(
+                WsSecurity::new()
+                    .timestamp(Duration::from_secs(60))
+                    .sign_with(credential.clone())
+                    .verify_with(credential.certificate().clone()),
+            )
+            .build();
+        client.call_raw("Ping", &Ping {}).await.unwrap();
+
+        let envelope = String::from_utf8(transport.requests()[0].body.clone()).unwrap();
+        assert!(envelope.contains("<ds:SignatureValue>"));
+
+        // Unsigned responses are rejected
+        let transport = MemoryTransport::with_handler(|_| {
+            Ok(TransportResponse::new(
+                200,
+                "<soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\"><soap:Body/></soap:Envelope>",
+            ))
+        });
+        let client = SoapClient::builder("memory://service")
+            .transport(transport)
+            .ws_security(WsSecurity::new().verify_with(credential.certificate().clone()))
+            .build();
+        let error = client.call_raw("Ping", &Ping {}).await.unwrap_err();
+        assert!(matches!(error, SoapError::SecurityError(_)));
+    }
+
+    #[cfg(feature = "ws-security")]
+    #[tokio::test]
+    async fn test_ws_security_encryption() {
+        use crate::dsig::tests::{credential, other_credential};
+        use crate::ws_security::WsSecurity;
+
+        let echo = |status| {
+            MemoryTransport::with_handler(move |request| {
+                Ok(TransportResponse::new(status, request.body.clone()))
+            })
+        };
+        let credential = credential();
+        let transport = echo(200);
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .ws_security(
+                WsSecurity::new()
+                    .sign_with(credential.clone())
+                    .verify_with(credential.certificate().clone())
+                    .encrypt_for(credential.certificate().clone())
+                    .decrypt_with(credential.clone()),
+            )
+            .build();
+        let response = client.call_raw("Ping", &Ping {}).await.unwrap();
+        assert!(response.contains("<Ping></Ping>"));
+
+        let envelope = String::from_utf8(transport.requests()[0].body.clone()).unwrap();
+        assert!(envelope.contains("<xenc:EncryptedKey"));
+        assert!(!envelope.contains("Ping>"));
+
+        // An encrypted fault for another key
+        let client = SoapClient::builder("memory://service")
+            .transport(echo(500))
+            .ws_security(
+                WsSecurity::new()
+                    .encrypt_for(credential.certificate().clone())
+                    .decrypt_with(other_credential()),
+            )
+            .build();
+        let error = client.call_raw("Ping", &Ping {}).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "WS-Security error: Decryption failed: the content key is encrypted for a different certificate (SOAP fault response, HTTP 500)"
+        );
+    }
+
     #[test]
     fn test_set_timeout() {
         let mut client = SoapClient::new("http://example.com/soap");
@@ -0,0 +1,141 @@
+//! HTTP compression of request and response bodies
+//!
+//! With response compression enabled (the default), requests announce
+//! `Accept-Encoding: gzip, deflate, br` and compressed responses are decoded
+//! before interceptors and the envelope parser see them. Request compression
+//! gzips request bodies and marks them with `Content-Encoding: gzip`; it is
+//! off by default, since not every server accepts compressed requests.
+//!
+//! Both are configured on the builder with
+//! [`SoapClientBuilder::response_compression`](crate::client::SoapClientBuilder::response_compression)
+//! and [`SoapClientBuilder::request_compression`](crate::client::SoapClientBuilder::request_compression).
+//! Streamed calls are not compressed.
+//!
+//! Requires the `compression` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::SoapClient;
+//!
+//! let client = SoapClient::builder("http://example.com/reports")
+//!     .request_compression(true)
+//!     .build();
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
+
+/// The `Accept-Encoding` of requests with response compression enabled
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+
+/// Compress a request body with gzip
+pub(crate) fn compress(body: &[u8]) -> SoapResult<Vec<u8>> {
+    let mut encoder = GzEncoder::new(
+        Vec::with_capacity(body.len() / 4),
+        flate2::Compression::default(),
+    );
+    encoder
+        .write_all(body)
+        .and_then(|()| encoder.finish())
+        .map_err(|e| SoapError::SerializationError(format!("Failed to gzip request: {}", e)))
+}
+
+/// Decode a response body according to its `Content-Encoding`
+///
+/// Encodings are listed in the order they were applied, so they are
+/// decoded from last to first.
+pub(crate) fn decompress(content_encoding: &str, mut body: Vec<u8>) -> SoapResult<Vec<u8>> {
+    for encoding in content_encoding.rsplit(',').map(str::trim) {
+        body = match encoding.to_ascii_lowercase().as_str() {
+            "" | "identity" => body,
+            "gzip" | "x-gzip" => decode(MultiGzDecoder::new(body.as_slice()), encoding)?,
+            // HTTP deflate is zlib-wrapped, some servers send raw deflate data
+            "deflate" if is_zlib(&body) => decode(ZlibDecoder::new(body.as_slice()), encoding)?,
+            "deflate" => decode(DeflateDecoder::new(body.as_slice()), encoding)?,
+            "br" => decode(brotli::Decompressor::new(body.as_slice(), 4096), encoding)?,
+            _ => {
+                return Err(SoapError::InvalidResponse(format!(
+                    "Unsupported Content-Encoding '{}'",
+                    encoding
+                )))
+            }
+        };
+    }
+    Ok(body)
+}
+
+fn decode(mut decoder: impl Read, encoding: &str) -> SoapResult<Vec<u8>> {
+    let mut decoded = Vec::new();
+    decoder.read_to_end(&mut decoded).map_err(|e| {
+        SoapError::InvalidResponse(format!("Invalid {} response body: {}", encoding, e))
+    })?;
+    Ok(decoded)
+}
+
+/// Check for a zlib header (RFC 1950): deflate method and a valid check value
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, ZlibEncoder};
+
+    const ENVELOPE: &[u8] = b"<soap:Envelope><soap:Body><ListResponse><item>1</item><item>2</item></ListResponse></soap:Body></soap:Envelope>";
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let compressed = compress(ENVELOPE).unwrap();
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+        assert_eq!(decompress("gzip", compressed).unwrap(), ENVELOPE);
+    }
+
+    #[test]
+    fn test_deflate_zlib_and_raw() {
+        let mut zlib = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        zlib.write_all(ENVELOPE).unwrap();
+        assert_eq!(
+            decompress("deflate", zlib.finish().unwrap()).unwrap(),
+            ENVELOPE
+        );
+
+        let mut raw = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        raw.write_all(ENVELOPE).unwrap();
+        assert_eq!(
+            decompress("Deflate", raw.finish().unwrap()).unwrap(),
+            ENVELOPE
+        );
+    }
+
+    #[test]
+    fn test_brotli() {
+        let mut compressed = Vec::new();
+        brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22)
+            .write_all(ENVELOPE)
+            .unwrap();
+        assert_eq!(decompress("br", compressed).unwrap(), ENVELOPE);
+    }
+
+    #[test]
+    fn test_multiple_and_unsupported_encodings() {
+        let twice = compress(&compress(ENVELOPE).unwrap()).unwrap();
+        assert_eq!(decompress("gzip, gzip", twice).unwrap(), ENVELOPE);
+        assert_eq!(decompress("identity", ENVELOPE.to_vec()).unwrap(), ENVELOPE);
+
+        assert!(matches!(
+            decompress("zstd", ENVELOPE.to_vec()),
+            Err(SoapError::InvalidResponse(msg)) if msg.contains("zstd")
+        ));
+        assert!(matches!(
+            decompress("gzip", ENVELOPE.to_vec()),
+            Err(SoapError::InvalidResponse(msg)) if msg.contains("gzip")
+        ));
+    }
+}
@@ -0,0 +1,665 @@
+//! XML signatures for WS-Security
+//!
+//! Signs outgoing envelopes and verifies signed responses following the
+//! WS-Security X.509 Certificate Token Profile:
+//!
+//! - the signing certificate is sent as `wsse:BinarySecurityToken`
+//! - `soap:Body` and `wsu:Timestamp` are referenced by their `wsu:Id`,
+//!   canonicalized with exclusive C14N (see [`c14n`](crate::c14n)) and
+//!   digested with SHA-256
+//! - `ds:SignedInfo` is signed with RSA-SHA256 and refers to the token
+//!   through a `wsse:SecurityTokenReference`
+//!
+//! Responses are accepted with RSA-SHA1 or RSA-SHA256 signatures and SHA-1
+//! or SHA-256 digests. The signature has to cover the `Body`, and the signing
+//! certificate has to be one of the trusted certificates: certificates are
+//! pinned, not validated against a CA. A signed `wsu:Timestamp` must not be
+//! expired, allowing for the configured clock skew.
+//!
+//! Signing and verification are enabled on [`WsSecurity`](crate::WsSecurity)
+//! with [`sign_with`](crate::WsSecurity::sign_with) and
+//! [`verify_with`](crate::WsSecurity::verify_with). Requires the
+//! `ws-security` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{SoapClient, WsSecurity, X509Certificate, X509Credential};
+//! use std::time::Duration;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let credential = X509Credential::from_pem(
+//!     &std::fs::read_to_string("client.crt")?,
+//!     &std::fs::read_to_string("client.key")?,
+//! )?;
+//! let partner = X509Certificate::from_pem(&std::fs::read_to_string("partner.crt")?)?;
+//!
+//! let client = SoapClient::builder("https://partner.example.com/soap")
+//!     .ws_security(
+//!         WsSecurity::new()
+//!             .timestamp(Duration::from_secs(300))
+//!             .sign_with(credential)
+//!             .verify_with(partner),
+//!     )
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::c14n::{self, Element, Namespaces};
+use crate::error::{SoapError, SoapResult};
+use crate::ws_security::{parse_timestamp, BASE64_BINARY, WSSE_NS, WSU_NS};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::Certificate;
+
+/// Namespace of the XML signature elements
+pub const DS_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const RSA_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#rsa-sha1";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const DIGEST_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+const DIGEST_SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Value type of X.509 binary security tokens
+pub const X509_TOKEN: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-x509-token-profile-1.0#X509v3";
+
+/// An X.509 certificate with an RSA public key
+#[derive(Clone, PartialEq)]
+pub struct X509Certificate {
+    pub(crate) der: Vec<u8>,
+    pub(crate) public_key: RsaPublicKey,
+}
+
+impl X509Certificate {
+    /// Read a DER encoded certificate
+    pub fn from_der(der: &[u8]) -> SoapResult<Self> {
+        let certificate = Certificate::from_der(der).map_err(security_error)?;
+        Self::from_certificate(&certificate, der.to_vec())
+    }
+
+    /// Read a PEM encoded certificate (`-----BEGIN CERTIFICATE-----`)
+    pub fn from_pem(pem: &str) -> SoapResult<Self> {
+        let certificate = Certificate::from_pem(pem).map_err(security_error)?;
+        let der = certificate.to_der().map_err(security_error)?;
+        Self::from_certificate(&certificate, der)
+    }
+
+    fn from_certificate(certificate: &Certificate, der: Vec<u8>) -> SoapResult<Self> {
+        let spki = certificate
+            .tbs_certificate
+            .subject_public_key_info
+            .to_der()
+            .map_err(security_error)?;
+        let public_key = RsaPublicKey::from_public_key_der(&spki).map_err(|e| {
+            SoapError::SecurityError(format!("Certificate has no RSA public key: {}", e))
+        })?;
+        Ok(Self { der, public_key })
+    }
+
+    /// Get the DER encoding of the certificate
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+impl fmt::Debug for X509Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X509Certificate")
+            .field("der_len", &self.der.len())
+            .finish()
+    }
+}
+
+/// An X.509 certificate with its RSA private key, used to sign requests
+#[derive(Clone)]
+pub struct X509Credential {
+    certificate: X509Certificate,
+    pub(crate) private_key: RsaPrivateKey,
+}
+
+impl X509Credential {
+    /// Create a credential from a DER certificate and a PKCS#8 DER private key
+    pub fn from_der(certificate: &[u8], private_key: &[u8]) -> SoapResult<Self> {
+        let private_key = RsaPrivateKey::from_pkcs8_der(private_key).map_err(security_error)?;
+        Self::new(X509Certificate::from_der(certificate)?, private_key)
+    }
+
+    /// Create a credential from a PEM certificate and a PEM private key
+    ///
+    /// The key may be PKCS#8 (`BEGIN PRIVATE KEY`) or PKCS#1 (`BEGIN RSA PRIVATE KEY`).
+    pub fn from_pem(certificate: &str, private_key: &str) -> SoapResult<Self> {
+        let private_key = if private_key.contains("BEGIN RSA PRIVATE KEY") {
+            RsaPrivateKey::from_pkcs1_pem(private_key).map_err(security_error)?
+        } else {
+            RsaPrivateKey::from_pkcs8_pem(private_key).map_err(security_error)?
+        };
+        Self::new(X509Certificate::from_pem(certificate)?, private_key)
+    }
+
+    fn new(certificate: X509Certificate, private_key: RsaPrivateKey) -> SoapResult<Self> {
+        if RsaPublicKey::from(&private_key) != certificate.public_key {
+            return Err(SoapError::InvalidConfig(
+                "Private key does not match the certificate".to_string(),
+            ));
+        }
+        Ok(Self {
+            certificate,
+            private_key,
+        })
+    }
+
+    /// Get the certificate
+    pub fn certificate(&self) -> &X509Certificate {
+        &self.certificate
+    }
+
+    /// The `wsse:BinarySecurityToken` carrying the certificate
+    pub(crate) fn token_xml(&self, id: &str) -> String {
+        format!(
+            "<wsse:BinarySecurityToken EncodingType=\"{}\" ValueType=\"{}\" wsu:Id=\"{}\">{}</wsse:BinarySecurityToken>",
+            BASE64_BINARY,
+            X509_TOKEN,
+            id,
+            BASE64.encode(&self.certificate.der)
+        )
+    }
+}
+
+impl fmt::Debug for X509Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X509Credential")
+            .field("certificate", &self.certificate)
+            .field("private_key", &"***")
+            .finish()
+    }
+}
+
+/// Sign the `Body` and `Timestamp` of an envelope
+///
+/// The envelope must contain a `wsse:Security` header with the
+/// `wsse:BinarySecurityToken` of `credential`. The `Body` gets a `wsu:Id` if
+/// it has none, the `ds:Signature` is appended to the security header.
+pub(crate) fn sign_envelope(
+    envelope: &str,
+    credential: &X509Credential,
+    body_id: &str,
+) -> SoapResult<String> {
+    let mut envelope = envelope.to_string();
+    let root = c14n::parse(&envelope)?;
+    let unsigned_body = body(&root)?;
+    if unsigned_body.id().is_none() {
+        let (_, scope) = root
+            .find(&Namespaces::new(), &|e| std::ptr::eq(e, unsigned_body))
+            .ok_or_else(|| missing("Body"))?;
+        let mut attributes = String::new();
+        if unsigned_body.scope(&scope).get("wsu").map(String::as_str) != Some(WSU_NS) {
+            attributes.push_str(&format!(" xmlns:wsu=\"{}\"", WSU_NS));
+        }
+        attributes.push_str(&format!(" wsu:Id=\"{}\"", body_id));
+        envelope.insert_str(
+            unsigned_body.start + 1 + unsigned_body.qname().len(),
+            &attributes,
+        );
+    }
+
+    let root = c14n::parse(&envelope)?;
+    let security = security(&root)?;
+    let token_id = security
+        .child(WSSE_NS, "BinarySecurityToken")
+        .and_then(Element::id)
+        .ok_or_else(|| missing("BinarySecurityToken"))?;
+
+    let mut ids = vec![body(&root)?.id().ok_or_else(|| missing("Body Id"))?];
+    if let Some(timestamp) = security.child(WSU_NS, "Timestamp") {
+        ids.push(timestamp.id().ok_or_else(|| missing("Timestamp Id"))?);
+    }
+
+    let mut references = String::new();
+    for id in ids {
+        let canonical = c14n::canonicalize_id(&envelope, id, &[])?.ok_or_else(|| missing(id))?;
+        references.push_str(&format!(
+            "<ds:Reference URI=\"#{}\"><ds:Transforms><ds:Transform Algorithm=\"{}\"/></ds:Transforms><ds:DigestMethod Algorithm=\"{}\"/><ds:DigestValue>{}</ds:DigestValue></ds:Reference>",
+            id,
+            EXC_C14N,
+            DIGEST_SHA256,
+            BASE64.encode(Sha256::digest(canonical.as_bytes()))
+        ));
+    }
+
+    let signed_info = format!(
+        "<ds:SignedInfo><ds:CanonicalizationMethod Algorithm=\"{}\"/><ds:SignatureMethod Algorithm=\"{}\"/>{}</ds:SignedInfo>",
+        EXC_C14N, RSA_SHA256, references
+    );
+    // Canonicalized on its own, SignedInfo declares the ds prefix itself
+    let canonical = c14n::canonicalize(
+        &signed_info.replacen(
+            "<ds:SignedInfo>",
+            &format!("<ds:SignedInfo xmlns:ds=\"{}\">", DS_NS),
+            1,
+        ),
+        &[],
+    )?;
+    let signature = credential
+        .private_key
+        .sign(
+            Pkcs1v15Sign::new::<Sha256>(),
+            &Sha256::digest(canonical.as_bytes()),
+        )
+        .map_err(security_error)?;
+
+    let signature_xml = format!(
+        "<ds:Signature xmlns:ds=\"{}\">{}<ds:SignatureValue>{}</ds:SignatureValue><ds:KeyInfo><wsse:SecurityTokenReference><wsse:Reference URI=\"#{}\" ValueType=\"{}\"/></wsse:SecurityTokenReference></ds:KeyInfo></ds:Signature>",
+        DS_NS,
+        signed_info,
+        BASE64.encode(signature),
+        token_id,
+        X509_TOKEN
+    );
+    envelope.insert_str(security.end, &signature_xml);
+    Ok(envelope)
+}
+
+/// Verify the signature of a response envelope
+///
+/// Fails unless the envelope carries a valid signature covering the `Body`,
+/// made with one of the `trusted` certificates. A signed timestamp has to be
+/// valid at `now`, give or take `clock_skew`.
+pub(crate) fn verify_envelope(
+    envelope: &str,
+    trusted: &[X509Certificate],
+    now: SystemTime,
+    clock_skew: Duration,
+) -> SoapResult<()> {
+    let root = c14n::parse(envelope)?;
+    let security = security(&root)?;
+    let (signature, scope) = root
+        .find(&Namespaces::new(), &|e| {
+            e.is(DS_NS, "Signature") && security.elements().any(|child| std::ptr::eq(child, e))
+        })
+        .ok_or_else(|| missing("Signature"))?;
+    let signature_scope = signature.scope(&scope);
+    let signed_info = signature
+        .child(DS_NS, "SignedInfo")
+        .ok_or_else(|| missing("SignedInfo"))?;
+
+    // Every reference must match its element
+    let mut referenced = Vec::new();
+    for reference in signed_info.elements().filter(|e| e.is(DS_NS, "Reference")) {
+        let uri = reference.attribute("", "URI").unwrap_or("");
+        let id = uri
+            .strip_prefix('#')
+            .ok_or_else(|| invalid(format!("Unsupported reference URI '{}'", uri)))?;
+
+        let mut inclusive = Vec::new();
+        if let Some(transforms) = reference.child(DS_NS, "Transforms") {
+            for transform in transforms.elements() {
+                inclusive.extend(exclusive_c14n_prefixes(transform)?);
+            }
+        }
+
+        // An id used twice could point the signature to a different element
+        if root.count(&|e| e.id() == Some(id)) != 1 {
+            return Err(invalid(format!("Reference '{}' is not unique", uri)));
+        }
+        let (element, element_scope) = root
+            .find(&Namespaces::new(), &|e| e.id() == Some(id))
+            .ok_or_else(|| missing(id))?;
+        let inclusive: Vec<&str> = inclusive.iter().map(String::as_str).collect();
+        let canonical = element.canonicalize(&element_scope, &inclusive);
+
+        let digest_method = reference
+            .child(DS_NS, "DigestMethod")
+            .and_then(|e| e.attribute("", "Algorithm"))
+            .unwrap_or("");
+        let digest = match digest_method {
+            DIGEST_SHA256 => Sha256::digest(canonical.as_bytes()).to_vec(),
+            DIGEST_SHA1 => Sha1::digest(canonical.as_bytes()).to_vec(),
+            other => return Err(invalid(format!("Unsupported digest method '{}'", other))),
+        };
+        let expected = reference
+            .child(DS_NS, "DigestValue")
+            .map(|e| decode_base64(&e.text()))
+            .transpose()?
+            .ok_or_else(|| missing("DigestValue"))?;
+        if digest != expected {
+            return Err(invalid(format!("Digest of '{}' does not match", uri)));
+        }
+        referenced.push(id);
+    }
+
+    let body_id = body(&root)?.id();
+    if !body_id.is_some_and(|id| referenced.contains(&id)) {
+        return Err(invalid("The signature does not cover the Body".to_string()));
+    }
+
+    // SignedInfo itself
+    let method = signed_info
+        .child(DS_NS, "CanonicalizationMethod")
+        .ok_or_else(|| missing("CanonicalizationMethod"))?;
+    let inclusive = exclusive_c14n_prefixes(method)?;
+    let inclusive: Vec<&str> = inclusive.iter().map(String::as_str).collect();
+    let canonical = signed_info.canonicalize(&signature_scope, &inclusive);
+
+    let signature_method = signed_info
+        .child(DS_NS, "SignatureMethod")
+        .and_then(|e| e.attribute("", "Algorithm"))
+        .unwrap_or("");
+    let (scheme, hashed) = match signature_method {
+        RSA_SHA256 => (
+            Pkcs1v15Sign::new::<Sha256>(),
+            Sha256::digest(canonical.as_bytes()).to_vec(),
+        ),
+        RSA_SHA1 => (
+            Pkcs1v15Sign::new::<Sha1>(),
+            Sha1::digest(canonical.as_bytes()).to_vec(),
+        ),
+        other => return Err(invalid(format!("Unsupported signature method '{}'", other))),
+    };
+    let signature_value = signature
+        .child(DS_NS, "SignatureValue")
+        .map(|e| decode_base64(&e.text()))
+        .transpose()?
+        .ok_or_else(|| missing("SignatureValue"))?;
+
+    let candidates: Vec<&X509Certificate> = match token(signature, security)? {
+        Some(der) => trusted.iter().filter(|c| c.der == der).collect(),
+        None => trusted.iter().collect(),
+    };
+    if candidates.is_empty() {
+        return Err(invalid(
+            "The signing certificate is not trusted".to_string(),
+        ));
+    }
+    let valid = candidates.iter().any(|certificate| {
+        certificate
+            .public_key
+            .verify(scheme.clone(), &hashed, &signature_value)
+            .is_ok()
+    });
+    if !valid {
+        return Err(invalid("Signature value does not match".to_string()));
+    }
+
+    // Only a signed timestamp can be trusted
+    if let Some(timestamp) = security
+        .child(WSU_NS, "Timestamp")
+        .filter(|timestamp| timestamp.id().is_some_and(|id| referenced.contains(&id)))
+    {
+        check_timestamp(timestamp, now, clock_skew)?;
+    }
+
+    Ok(())
+}
+
+/// Reject a `wsu:Timestamp` that is expired or created in the future
+fn check_timestamp(timestamp: &Element, now: SystemTime, clock_skew: Duration) -> SoapResult<()> {
+    let time = |name: &str| {
+        timestamp
+            .child(WSU_NS, name)
+            .map(|e| {
+                parse_timestamp(&e.text())
+                    .ok_or_else(|| invalid(format!("Invalid Timestamp {} '{}'", name, e.text())))
+            })
+            .transpose()
+    };
+
+    if let Some(expires) = time("Expires")? {
+        if expires + clock_skew < now {
+            return Err(invalid("The Timestamp has expired".to_string()));
+        }
+    }
+    if let Some(created) = time("Created")? {
+        if created > now + clock_skew {
+            return Err(invalid(
+                "The Timestamp was created in the future".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The certificate of the binary security token referenced by the signature, if any
+fn token(signature: &Element, security: &Element) -> SoapResult<Option<Vec<u8>>> {
+    let Some(uri) = signature
+        .child(DS_NS, "KeyInfo")
+        .and_then(|key_info| key_info.child(WSSE_NS, "SecurityTokenReference"))
+        .and_then(|reference| reference.child(WSSE_NS, "Reference"))
+        .and_then(|reference| reference.attribute("", "URI"))
+    else {
+        return Ok(None);
+    };
+
+    let id = uri.trim_start_matches('#');
+    security
+        .elements()
+        .find(|e| e.is(WSSE_NS, "BinarySecurityToken") && e.id() == Some(id))
+        .map(|token| decode_base64(&token.text()))
+        .transpose()
+}
+
+/// The inclusive prefixes of an exclusive C14N transform or canonicalization method
+fn exclusive_c14n_prefixes(method: &Element) -> SoapResult<Vec<String>> {
+    let algorithm = method.attribute("", "Algorithm").unwrap_or("");
+    if algorithm != EXC_C14N {
+        return Err(invalid(format!("Unsupported transform '{}'", algorithm)));
+    }
+    Ok(method
+        .elements()
+        .find(|e| e.local_name == "InclusiveNamespaces" && e.namespace == EXC_C14N)
+        .and_then(|e| e.attribute("", "PrefixList"))
+        .map(|list| list.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+/// The `Body` element of an envelope
+pub(crate) fn body(root: &Element) -> SoapResult<&Element> {
+    root.elements()
+        .find(|e| e.local_name == "Body" && e.namespace == root.namespace)
+        .ok_or_else(|| missing("Body"))
+}
+
+/// The `wsse:Security` header block of an envelope
+pub(crate) fn security(root: &Element) -> SoapResult<&Element> {
+    root.elements()
+        .find(|e| e.local_name == "Header" && e.namespace == root.namespace)
+        .and_then(|header| header.child(WSSE_NS, "Security"))
+        .ok_or_else(|| missing("Security header"))
+}
+
+pub(crate) fn decode_base64(text: &str) -> SoapResult<Vec<u8>> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    BASE64.decode(compact).map_err(security_error)
+}
+
+fn missing(what: &str) -> SoapError {
+    SoapError::SecurityError(format!("Missing {}", what))
+}
+
+fn invalid(message: String) -> SoapError {
+    SoapError::SecurityError(message)
+}
+
+pub(crate) fn security_error(e: impl fmt::Display) -> SoapError {
+    SoapError::SecurityError(e.to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::envelope::{SoapEnvelope, SoapVersion};
+    use crate::ws_security::WsSecurity;
+    use crate::ws_security::DEFAULT_CLOCK_SKEW;
+    use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+    use serde::Serialize;
+    use std::str::FromStr;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use x509_cert::builder::{Builder, CertificateBuilder, Profile};
+    use x509_cert::der::EncodePem;
+    use x509_cert::name::Name;
+    use x509_cert::serial_number::SerialNumber;
+    use x509_cert::spki::SubjectPublicKeyInfoOwned;
+    use x509_cert::time::Validity;
+
+    /// A self-signed certificate and its key in PEM
+    fn generate(name: &str) -> (String, String) {
+        let key = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap();
+        let spki = SubjectPublicKeyInfoOwned::from_key(RsaPublicKey::from(&key)).unwrap();
+        let signer = rsa::pkcs1v15::SigningKey::<Sha256>::new(key.clone());
+        let certificate = CertificateBuilder::new(
+            Profile::Root,
+            SerialNumber::from(1u32),
+            Validity::from_now(Duration::from_secs(3600)).unwrap(),
+            Name::from_str(&format!("CN={}", name)).unwrap(),
+            spki,
+            &signer,
+        )
+        .unwrap()
+        .build::<rsa::pkcs1v15::Signature>()
+        .unwrap();
+
+        (
+            certificate.to_pem(LineEnding::LF).unwrap(),
+            key.to_pkcs8_pem(LineEnding::LF).unwrap().to_string(),
+        )
+    }
+
+    /// A credential shared by all tests, key generation is slow
+    pub(crate) fn credential() -> X509Credential {
+        static PEM: OnceLock<(String, String)> = OnceLock::new();
+        let (certificate, key) = PEM.get_or_init(|| generate("client"));
+        X509Credential::from_pem(certificate, key).unwrap()
+    }
+
+    pub(crate) fn other_credential() -> X509Credential {
+        static PEM: OnceLock<(String, String)> = OnceLock::new();
+        let (certificate, key) = PEM.get_or_init(|| generate("other"));
+        X509Credential::from_pem(certificate, key).unwrap()
+    }
+
+    /// Verify a signed envelope at the current time
+    pub(crate) fn verify(envelope: &str, trusted: &[X509Certificate]) -> SoapResult<()> {
+        verify_envelope(envelope, trusted, SystemTime::now(), DEFAULT_CLOCK_SKEW)
+    }
+
+    #[derive(Serialize)]
+    struct GetSalary {
+        #[serde(rename = "EmployeeId")]
+        employee_id: String,
+    }
+
+    fn signed_envelope(credential: &X509Credential) -> String {
+        let security = WsSecurity::new()
+            .timestamp(Duration::from_secs(300))
+            .sign_with(credential.clone());
+        let envelope = SoapEnvelope::build_with_soap_headers(
+            &[security.header().unwrap()],
+            &GetSalary {
+                employee_id: "E-1".to_string(),
+            },
+            SoapVersion::Soap11,
+            Some("http://example.com/payroll"),
+            true,
+        )
+        .unwrap();
+        sign_envelope(&envelope, credential, "id-body").unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let credential = credential();
+        let envelope = signed_envelope(&credential);
+
+        assert!(envelope.contains("wsu:Id=\"id-body\""));
+        assert!(envelope.contains("<ds:Reference URI=\"#id-body\">"));
+        assert!(envelope.contains("<ds:Reference URI=\"#TS-"));
+        assert!(envelope.contains("<wsse:Reference URI=\"#X509-"));
+        assert!(envelope.contains("</ds:Signature></wsse:Security>"));
+
+        verify(&envelope, &[credential.certificate().clone()]).unwrap();
+    }
+
+    #[test]
+    fn test_verify_rejects_tampering() {
+        let credential = credential();
+        let trusted = [credential.certificate().clone()];
+        let envelope = signed_envelope(&credential);
+
+        let tampered = envelope.replace("E-1", "E-2");
+        let error = verify(&tampered, &trusted).unwrap_err();
+        assert!(error.to_string().contains("Digest of '#id-body'"));
+
+        // A second element with the signed id could be taken for the signed Body
+        let wrapped = envelope.replace(
+            "</wsse:Security>",
+            "</wsse:Security><x wsu:Id=\"id-body\"/>",
+        );
+        assert!(verify(&wrapped, &trusted).is_err());
+
+        let expired = envelope.replacen("<wsu:Expires>", "<wsu:Expires>2", 1);
+        assert!(verify(&expired, &trusted).is_err());
+
+        let unsigned = envelope.replace(
+            "<ds:Reference URI=\"#id-body\">",
+            "<ds:Reference URI=\"#other\">",
+        );
+        assert!(verify(&unsigned, &trusted).is_err());
+    }
+
+    #[test]
+    fn test_verify_checks_timestamp() {
+        let credential = credential();
+        let trusted = [credential.certificate().clone()];
+        // Signed with a 300 seconds timestamp
+        let envelope = signed_envelope(&credential);
+        let now = SystemTime::now();
+        let skew = Duration::from_secs(60);
+
+        verify_envelope(&envelope, &trusted, now + Duration::from_secs(330), skew).unwrap();
+        let error =
+            verify_envelope(&envelope, &trusted, now + Duration::from_secs(400), skew).unwrap_err();
+        assert!(error.to_string().contains("expired"));
+
+        verify_envelope(&envelope, &trusted, now - Duration::from_secs(30), skew).unwrap();
+        let error =
+            verify_envelope(&envelope, &trusted, now - Duration::from_secs(90), skew).unwrap_err();
+        assert!(error.to_string().contains("future"));
+    }
+
+    #[test]
+    fn test_verify_requires_trusted_certificate() {
+        let envelope = signed_envelope(&credential());
+        let error = verify(&envelope, &[other_credential().certificate().clone()]).unwrap_err();
+        assert!(error.to_string().contains("not trusted"));
+    }
+
+    #[test]
+    fn test_credential_key_must_match_certificate() {
+        let (certificate, _) = generate("mismatch");
+        let key = other_credential()
+            .private_key
+            .to_pkcs8_pem(LineEnding::LF)
+            .unwrap();
+        let error = X509Credential::from_pem(&certificate, &key).unwrap_err();
+        assert!(matches!(error, SoapError::InvalidConfig(_)));
+
+        let credential = credential();
+        let der = credential.certificate().der();
+        assert_eq!(
+            &X509Certificate::from_der(der).unwrap(),
+            credential.certificate()
+        );
+        assert!(!format!("{:?}", credential).contains("PRIVATE"));
+    }
+}
@@ -4,6 +4,9 @@
 //! It serializes request bodies to XML and wraps them in the appropriate SOAP envelope structure.
 
 use crate::error::{SoapError, SoapResult};
+use crate::header::{HttpMetadata, SoapHeader, SoapHeaders, SoapResponse};
+#[cfg(feature = "swa")]
+use crate::mtom::Attachment;
 use serde::Serialize;
 
 #[cfg(feature = "tracing")]
@@ -23,6 +26,214 @@ pub enum SoapVersion {
     Soap12,
 }
 
+/// A single serialized part of a SOAP message body
+///
+/// Document/literal messages place each part directly inside `Body`, while
+/// RPC style messages wrap all parts in an element named after the operation
+/// (see [`BodyPart::rpc_wrapper`]). Header blocks are serialized the same way
+/// and passed to [`SoapEnvelope::build_with_headers`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyPart {
+    name: String,
+    xml: String,
+}
+
+impl BodyPart {
+    /// Serialize a value as a body part element
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The XML element name of the part
+    /// * `value` - The part content
+    /// * `namespace` - Namespace of the part element (None for unqualified parts)
+    /// * `element_form_qualified` - Whether child elements inherit the namespace
+    pub fn new<T>(
+        name: impl Into<String>,
+        value: &T,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+    ) -> SoapResult<Self>
+    where
+        T: Serialize,
+    {
+        let name = name.into();
+        let xml =
+            SoapEnvelope::serialize_body(value, Some(&name), namespace, element_form_qualified)?;
+        Ok(Self { name, xml })
+    }
+
+    /// Create a part from already serialized XML
+    pub fn raw(name: impl Into<String>, xml: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            xml: xml.into(),
+        }
+    }
+
+    /// Wrap parts in an RPC operation element
+    ///
+    /// Produces `<ns:Operation xmlns:ns="...">...parts...</ns:Operation>`.
+    pub fn rpc_wrapper(operation: &str, namespace: &str, parts: &[BodyPart]) -> Self {
+        let content: String = parts.iter().map(|p| p.xml.as_str()).collect();
+        Self {
+            name: operation.to_string(),
+            xml: format!(
+                "<ns:{op} xmlns:ns=\"{ns}\">{content}</ns:{op}>",
+                op = operation,
+                ns = namespace,
+                content = content
+            ),
+        }
+    }
+
+    /// Get the element name of this part
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the serialized XML of this part
+    pub fn xml(&self) -> &str {
+        &self.xml
+    }
+}
+
+/// Body parts and header blocks extracted from a SOAP response
+///
+/// Each direct child of the response `Body` (and `Header`) element is kept as
+/// a separate XML fragment, so multi-part responses can be deserialized part by part.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponseParts {
+    parts: Vec<(String, String)>,
+    headers: SoapHeaders,
+    #[cfg(feature = "swa")]
+    attachments: Vec<Attachment>,
+    http: HttpMetadata,
+}
+
+impl ResponseParts {
+    /// Number of parts in the response body
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Check whether the response body was empty
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Get the raw XML of the part with the given element name
+    ///
+    /// The name is compared against the local name, ignoring any namespace prefix.
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.parts
+            .iter()
+            .find(|(local_name, _)| local_name == name)
+            .map(|(_, xml)| xml.as_str())
+    }
+
+    /// Deserialize the part with the given element name
+    pub fn part<T>(&self, name: &str) -> SoapResult<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let xml = self
+            .raw(name)
+            .ok_or_else(|| SoapError::MissingField(name.to_string()))?;
+
+        quick_xml::de::from_str(xml).map_err(|e| SoapError::DeserializationError(e.to_string()))
+    }
+
+    /// Get the raw XML of the header block with the given element name
+    pub fn raw_header(&self, name: &str) -> Option<&str> {
+        self.headers.raw(name)
+    }
+
+    /// Deserialize the header block with the given element name
+    ///
+    /// Returns `Ok(None)` if the response does not contain the header.
+    pub fn header<T>(&self, name: &str) -> SoapResult<Option<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        self.headers.get(name)
+    }
+
+    /// Get all header blocks of the response
+    pub fn headers(&self) -> &SoapHeaders {
+        &self.headers
+    }
+
+    /// Get the MIME attachments of a `multipart/related` response
+    #[cfg(feature = "swa")]
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// Get the attachment a `cid:` URL (e.g. of a `swaRef`) or Content-ID refers to
+    #[cfg(feature = "swa")]
+    pub fn attachment(&self, reference: &str) -> Option<&Attachment> {
+        crate::swa::find(&self.attachments, reference)
+    }
+
+    /// Take the MIME attachments of the response
+    #[cfg(feature = "swa")]
+    pub fn into_attachments(self) -> Vec<Attachment> {
+        self.attachments
+    }
+
+    /// Take the MIME attachments out of the response, leaving none behind
+    #[cfg(feature = "swa")]
+    pub fn take_attachments(&mut self) -> Vec<Attachment> {
+        std::mem::take(&mut self.attachments)
+    }
+
+    #[cfg(feature = "swa")]
+    pub(crate) fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    pub(crate) fn with_http(mut self, http: HttpMetadata) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Get the HTTP status, headers, elapsed time and raw body of the response
+    pub fn http(&self) -> &HttpMetadata {
+        &self.http
+    }
+
+    /// Turn into a [`SoapResponse`] with the given body, e.g. the deserialized parts
+    ///
+    /// The header blocks, attachments and HTTP details are kept.
+    pub fn into_response<T>(self, body: T) -> SoapResponse<T> {
+        let response = SoapResponse::new(body, self.headers).with_http(self.http);
+        #[cfg(feature = "swa")]
+        let response = response.with_attachments(self.attachments);
+        response
+    }
+
+    /// Unwrap an RPC style response
+    ///
+    /// RPC responses contain a single wrapper element (usually `OperationResponse`)
+    /// whose children are the message parts.
+    pub fn unwrap_rpc(self) -> SoapResult<ResponseParts> {
+        match self.parts.as_slice() {
+            [(_, xml)] => Ok(ResponseParts {
+                parts: SoapEnvelope::split_elements(xml, 1)?,
+                headers: self.headers,
+                #[cfg(feature = "swa")]
+                attachments: self.attachments,
+                http: self.http,
+            }),
+            _ => Err(SoapError::InvalidResponse(format!(
+                "Expected a single RPC response wrapper element, found {}",
+                self.parts.len()
+            ))),
+        }
+    }
+}
+
 /// SOAP envelope builder
 pub struct SoapEnvelope;
 
@@ -93,31 +304,12 @@ impl SoapEnvelope {
         #[cfg(feature = "tracing")]
         debug!("Serializing request body to XML");
 
-        let body_xml = if let Some(ns) = namespace {
-            if element_form_qualified {
-                // Serialize with namespace - quick-xml will qualify all child elements
-                Self::serialize_to_xml_with_namespace(body, ns)?
-            } else {
-                // Serialize without namespace, then add namespace PREFIX to root element
-                // Using a prefix (ns:element) instead of default namespace (xmlns="...")
-                // prevents child elements from inheriting the namespace
-                let xml = Self::serialize_to_xml(body)?;
-                Self::add_namespace_prefix_to_root(&xml, ns, "ns")
-            }
-        } else {
-            Self::serialize_to_xml(body)?
-        };
+        let body_xml = Self::serialize_body(body, None, namespace, element_form_qualified)?;
 
         #[cfg(feature = "tracing")]
         debug!(body_xml_size = body_xml.len(), "Building SOAP 1.1 envelope");
 
-        // Build envelope manually to avoid escaping the body XML
-        let envelope = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?><soap:Envelope xmlns:soap="{}"><soap:Body>{}</soap:Body></soap:Envelope>"#,
-            SOAP_11_ENVELOPE_NS, body_xml
-        );
-
-        Ok(envelope)
+        Ok(Self::wrap_body(&body_xml, SoapVersion::Soap11))
     }
 
     /// Build a SOAP 1.2 envelope
@@ -142,31 +334,134 @@ impl SoapEnvelope {
         #[cfg(feature = "tracing")]
         debug!("Serializing request body to XML");
 
-        let body_xml = if let Some(ns) = namespace {
-            if element_form_qualified {
-                // Serialize with namespace - quick-xml will qualify all child elements
-                Self::serialize_to_xml_with_namespace(body, ns)?
-            } else {
-                // Serialize without namespace, then add namespace PREFIX to root element
-                // Using a prefix (ns:element) instead of default namespace (xmlns="...")
-                // prevents child elements from inheriting the namespace
-                let xml = Self::serialize_to_xml(body)?;
-                Self::add_namespace_prefix_to_root(&xml, ns, "ns")
-            }
-        } else {
-            Self::serialize_to_xml(body)?
-        };
+        let body_xml = Self::serialize_body(body, None, namespace, element_form_qualified)?;
 
         #[cfg(feature = "tracing")]
         debug!(body_xml_size = body_xml.len(), "Building SOAP 1.2 envelope");
 
+        Ok(Self::wrap_body(&body_xml, SoapVersion::Soap12))
+    }
+
+    /// Build a SOAP envelope whose body consists of several message parts
+    ///
+    /// Each part is written as a direct child of the `Body` element, in order.
+    /// This is used for multi-part and document/literal bare messages.
+    pub fn build_with_parts(parts: &[BodyPart], version: SoapVersion) -> String {
+        Self::build_with_headers(&[], parts, version)
+    }
+
+    /// Build a SOAP envelope with header blocks and body parts
+    ///
+    /// The `Header` element is only written if at least one header block is given.
+    ///
+    /// Format (SOAP 1.1):
+    /// ```xml
+    /// <soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+    ///   <soap:Header>
+    ///     <!-- header blocks -->
+    ///   </soap:Header>
+    ///   <soap:Body>
+    ///     <!-- body parts -->
+    ///   </soap:Body>
+    /// </soap:Envelope>
+    /// ```
+    pub fn build_with_headers(
+        headers: &[BodyPart],
+        parts: &[BodyPart],
+        version: SoapVersion,
+    ) -> String {
+        #[cfg(feature = "tracing")]
+        debug!(soap_version = ?version, header_count = headers.len(), part_count = parts.len(), "Building SOAP envelope from body parts");
+
+        let header_xml: String = headers.iter().map(|h| h.xml.as_str()).collect();
+        let body_xml: String = parts.iter().map(|p| p.xml.as_str()).collect();
+        Self::wrap(&header_xml, &body_xml, version)
+    }
+
+    /// Build a SOAP envelope with generic header blocks and a serialized body
+    ///
+    /// Like [`SoapEnvelope::build_with_namespace`], but additionally writes the
+    /// given header blocks into the SOAP `Header`.
+    pub fn build_with_soap_headers<T>(
+        headers: &[SoapHeader],
+        body: &T,
+        version: SoapVersion,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+    ) -> SoapResult<String>
+    where
+        T: Serialize,
+    {
+        #[cfg(feature = "tracing")]
+        debug!(soap_version = ?version, header_count = headers.len(), namespace = ?namespace, "Building SOAP envelope with headers");
+
+        let header_xml: String = headers.iter().map(|h| h.to_xml(version)).collect();
+        let body_xml = Self::serialize_body(body, None, namespace, element_form_qualified)?;
+        Ok(Self::wrap(&header_xml, &body_xml, version))
+    }
+
+    /// Wrap already serialized body XML in a SOAP envelope
+    fn wrap_body(body_xml: &str, version: SoapVersion) -> String {
+        Self::wrap("", body_xml, version)
+    }
+
+    /// Wrap already serialized header and body XML in a SOAP envelope
+    ///
+    /// An empty `header_xml` omits the `Header` element.
+    fn wrap(header_xml: &str, body_xml: &str, version: SoapVersion) -> String {
+        let prefix = match version {
+            SoapVersion::Soap11 => "soap",
+            SoapVersion::Soap12 => "env",
+        };
+        let namespace = match version {
+            SoapVersion::Soap11 => SOAP_11_ENVELOPE_NS,
+            SoapVersion::Soap12 => SOAP_12_ENVELOPE_NS,
+        };
+        let header = if header_xml.is_empty() {
+            String::new()
+        } else {
+            format!("<{p}:Header>{}</{p}:Header>", header_xml, p = prefix)
+        };
+
         // Build envelope manually to avoid escaping the body XML
-        let envelope = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?><env:Envelope xmlns:env="{}"><env:Body>{}</env:Body></env:Envelope>"#,
-            SOAP_12_ENVELOPE_NS, body_xml
-        );
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?><{p}:Envelope xmlns:{p}="{}">{}<{p}:Body>{}</{p}:Body></{p}:Envelope>"#,
+            namespace,
+            header,
+            body_xml,
+            p = prefix
+        )
+    }
 
-        Ok(envelope)
+    /// Serialize a body element, optionally overriding its root element name
+    ///
+    /// * `root` - Element name to use instead of the serde type name
+    /// * `element_form_qualified` - If false, namespace is only added to root element,
+    ///   not inherited by children (for elementFormDefault="unqualified" in XSD)
+    fn serialize_body<T>(
+        value: &T,
+        root: Option<&str>,
+        namespace: Option<&str>,
+        element_form_qualified: bool,
+    ) -> SoapResult<String>
+    where
+        T: Serialize,
+    {
+        let xml = match root {
+            Some(root) => quick_xml::se::to_string_with_root(root, value)
+                .map_err(|e| SoapError::SerializationError(e.to_string()))?,
+            None => Self::serialize_to_xml(value)?,
+        };
+
+        Ok(match namespace {
+            // Serialize with namespace - quick-xml will qualify all child elements
+            Some(ns) if element_form_qualified => Self::add_namespace_to_root(&xml, ns),
+            // Add namespace PREFIX to root element
+            // Using a prefix (ns:element) instead of default namespace (xmlns="...")
+            // prevents child elements from inheriting the namespace
+            Some(ns) => Self::add_namespace_prefix_to_root(&xml, ns, "ns"),
+            None => xml,
+        })
     }
 
     /// Serialize a value to XML string using quick-xml
@@ -317,6 +612,69 @@ impl SoapEnvelope {
         #[cfg(feature = "tracing")]
         debug!(response_size = xml.len(), "Parsing SOAP response");
 
+        let body_content = Self::extract_body(xml)?;
+
+        if body_content.is_empty() {
+            return Err(SoapError::InvalidResponse(
+                "No body content found in SOAP response".to_string(),
+            ));
+        }
+
+        #[cfg(feature = "tracing")]
+        debug!(
+            body_content_size = body_content.len(),
+            "Extracted body content from SOAP response"
+        );
+
+        // Fix unescaped ampersands in body content before deserialization
+        let fixed_body_content = Self::fix_unescaped_ampersands(&body_content);
+
+        // Deserialize the body content
+        quick_xml::de::from_str(&fixed_body_content)
+            .map_err(|e| SoapError::DeserializationError(e.to_string()))
+    }
+
+    /// Parse a SOAP response into its individual body parts
+    ///
+    /// Unlike [`SoapEnvelope::parse_response`], an empty body is not an error:
+    /// it yields empty [`ResponseParts`] (e.g. for operations without output parts).
+    pub fn parse_response_parts(xml: &str) -> SoapResult<ResponseParts> {
+        #[cfg(feature = "tracing")]
+        debug!(response_size = xml.len(), "Parsing SOAP response parts");
+
+        if xml.trim().is_empty() {
+            return Ok(ResponseParts::default());
+        }
+
+        let body_content = Self::extract_body(xml)?;
+
+        Ok(ResponseParts {
+            parts: Self::split_elements(&body_content, 0)?,
+            headers: Self::parse_response_headers(xml)?,
+            #[cfg(feature = "swa")]
+            attachments: Vec::new(),
+            http: HttpMetadata::default(),
+        })
+    }
+
+    /// Parse the header blocks of a SOAP response
+    ///
+    /// A response without `Header` element yields empty [`SoapHeaders`].
+    pub fn parse_response_headers(xml: &str) -> SoapResult<SoapHeaders> {
+        let header_content = Self::extract_section(xml, b"Header")?;
+        Ok(SoapHeaders::new(Self::split_elements(&header_content, 0)?))
+    }
+
+    /// Extract the serialized content of the `Body` element
+    fn extract_body(xml: &str) -> SoapResult<String> {
+        Self::extract_section(xml, b"Body")
+    }
+
+    /// Extract the serialized content of a direct child of the envelope
+    /// (`Header` or `Body`)
+    ///
+    /// Returns an empty string if the section does not exist.
+    fn extract_section(xml: &str, section: &[u8]) -> SoapResult<String> {
         // Fix invalid XML: replace unescaped & with &amp;
         // This handles server bugs where text contains unescaped ampersands
         let fixed_xml = Self::fix_unescaped_ampersands(xml);
@@ -330,43 +688,32 @@ impl SoapEnvelope {
         let mut in_body = false;
         let mut body_content = String::new();
         let mut depth = 0;
+        // Nesting level outside the section, the envelope children are at level 1
+        let mut outer_depth = 0;
 
         loop {
             match reader.read_event_into(&mut buf) {
                 Ok(Event::Start(e)) => {
-                    let name = e.name();
-                    let local_name = name.as_ref();
-
-                    // Check if this is a Body element (SOAP 1.1 or 1.2)
-                    if local_name.ends_with(b"Body") {
+                    // Check if this is the section element (SOAP 1.1 or 1.2)
+                    if !in_body && outer_depth == 1 && e.local_name().as_ref() == section {
                         in_body = true;
                         depth = 0;
-                    } else if in_body {
+                    } else if !in_body {
+                        outer_depth += 1;
+                    } else {
                         depth += 1;
                         // Capture the start tag
-                        let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                        body_content.push('<');
-                        body_content.push_str(&tag);
-
-                        // Add attributes
-                        for attr in e.attributes().flatten() {
-                            body_content.push(' ');
-                            body_content.push_str(&String::from_utf8_lossy(attr.key.as_ref()));
-                            body_content.push_str("=\"");
-                            body_content.push_str(&String::from_utf8_lossy(&attr.value));
-                            body_content.push('"');
-                        }
+                        Self::push_start_tag(&mut body_content, &e);
                         body_content.push('>');
                     }
                 }
                 Ok(Event::End(e)) => {
-                    let name = e.name();
-                    let local_name = name.as_ref();
-
-                    if local_name.ends_with(b"Body") && in_body && depth == 0 {
-                        // End of Body - we're done
+                    if in_body && depth == 0 {
+                        // End of the section - we're done
                         break;
-                    } else if in_body {
+                    } else if !in_body {
+                        outer_depth -= 1;
+                    } else {
                         depth -= 1;
                         let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
                         body_content.push_str("</");
@@ -375,21 +722,12 @@ impl SoapEnvelope {
                     }
                 }
                 Ok(Event::Text(e)) if in_body => {
-                    body_content.push_str(&e.unescape().unwrap_or_default());
+                    // Keep text escaped so the extracted content stays well-formed XML
+                    let text = e.unescape().unwrap_or_default();
+                    body_content.push_str(&quick_xml::escape::escape(text.as_ref()));
                 }
                 Ok(Event::Empty(e)) if in_body => {
-                    let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    body_content.push('<');
-                    body_content.push_str(&tag);
-
-                    // Add attributes
-                    for attr in e.attributes().flatten() {
-                        body_content.push(' ');
-                        body_content.push_str(&String::from_utf8_lossy(attr.key.as_ref()));
-                        body_content.push_str("=\"");
-                        body_content.push_str(&String::from_utf8_lossy(&attr.value));
-                        body_content.push('"');
-                    }
+                    Self::push_start_tag(&mut body_content, &e);
                     body_content.push_str("/>");
                 }
                 Ok(Event::Eof) => break,
@@ -399,91 +737,90 @@ impl SoapEnvelope {
             buf.clear();
         }
 
-        if body_content.is_empty() {
-            return Err(SoapError::InvalidResponse(
-                "No body content found in SOAP response".to_string(),
-            ));
-        }
-
-        #[cfg(feature = "tracing")]
-        debug!(
-            body_content_size = body_content.len(),
-            "Extracted body content from SOAP response"
-        );
-
-        // Fix unescaped ampersands in body content before deserialization
-        let fixed_body_content = Self::fix_unescaped_ampersands(&body_content);
-
-        // Deserialize the body content
-        quick_xml::de::from_str(&fixed_body_content)
-            .map_err(|e| SoapError::DeserializationError(e.to_string()))
+        Ok(body_content)
     }
 
-    /// Check if a SOAP response contains a fault
-    pub fn check_for_fault(xml: &str) -> SoapResult<()> {
-        #[cfg(feature = "tracing")]
-        debug!("Checking SOAP response for faults");
+    /// Write an opening tag (without the closing `>`) including its attributes
+    fn push_start_tag(out: &mut String, e: &quick_xml::events::BytesStart) {
+        out.push('<');
+        out.push_str(&String::from_utf8_lossy(e.name().as_ref()));
+
+        // Add attributes
+        for attr in e.attributes().flatten() {
+            out.push(' ');
+            out.push_str(&String::from_utf8_lossy(attr.key.as_ref()));
+            out.push_str("=\"");
+            out.push_str(&String::from_utf8_lossy(&attr.value));
+            out.push('"');
+        }
+    }
 
+    /// Split an XML fragment into the elements found at the given nesting level
+    ///
+    /// Level 0 yields the top-level elements, level 1 the children of each
+    /// top-level element (used to unwrap RPC responses). Namespace declarations
+    /// from enclosing elements are not carried over, which is fine since
+    /// deserialization only looks at local names.
+    pub(crate) fn split_elements(xml: &str, level: usize) -> SoapResult<Vec<(String, String)>> {
         use quick_xml::events::Event;
         use quick_xml::Reader;
 
-        let mut reader = Reader::from_str(xml);
+        let fixed_xml = Self::fix_unescaped_ampersands(xml);
+        let mut reader = Reader::from_str(&fixed_xml);
 
-        let mut buf = Vec::new();
-        let mut in_fault = false;
-        let mut fault_code = String::new();
-        let mut fault_string = String::new();
-        let mut in_faultcode = false;
-        let mut in_faultstring = false;
+        let mut parts = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0usize;
+        let mut local_name = String::new();
 
         loop {
-            match reader.read_event_into(&mut buf) {
+            let position = reader.buffer_position() as usize;
+            match reader.read_event() {
                 Ok(Event::Start(e)) => {
-                    let name = e.name();
-                    let local_name = name.as_ref();
-
-                    if local_name.ends_with(b"Fault") {
-                        in_fault = true;
-                    } else if in_fault {
-                        if local_name.ends_with(b"faultcode") || local_name.ends_with(b"Code") {
-                            in_faultcode = true;
-                        } else if local_name.ends_with(b"faultstring")
-                            || local_name.ends_with(b"Reason")
-                        {
-                            in_faultstring = true;
-                        }
+                    if depth == level {
+                        start = position;
+                        local_name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
                     }
+                    depth += 1;
                 }
-                Ok(Event::Text(e)) => {
-                    if in_faultcode {
-                        fault_code = e.unescape().unwrap_or_default().to_string();
-                        in_faultcode = false;
-                    } else if in_faultstring {
-                        fault_string = e.unescape().unwrap_or_default().to_string();
-                        in_faultstring = false;
+                Ok(Event::End(_)) => {
+                    depth = depth.saturating_sub(1);
+                    if depth == level {
+                        let end = reader.buffer_position() as usize;
+                        parts.push((
+                            std::mem::take(&mut local_name),
+                            fixed_xml[start..end].to_string(),
+                        ));
                     }
                 }
-                Ok(Event::End(e)) => {
-                    let name = e.name();
-                    let local_name = name.as_ref();
-
-                    if local_name.ends_with(b"Fault") {
-                        // We found a fault - return error
-                        return Err(SoapError::SoapFault {
-                            code: fault_code,
-                            message: fault_string,
-                            detail: None,
-                        });
-                    }
+                Ok(Event::Empty(e)) if depth == level => {
+                    let end = reader.buffer_position() as usize;
+                    parts.push((
+                        String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+                        fixed_xml[position..end].to_string(),
+                    ));
                 }
                 Ok(Event::Eof) => break,
                 Err(e) => return Err(SoapError::XmlError(e.to_string())),
                 _ => {}
             }
-            buf.clear();
         }
 
-        Ok(())
+        Ok(parts)
+    }
+
+    /// Check if a SOAP response contains a fault
+    ///
+    /// A fault is returned as [`SoapError::SoapFault`] carrying the parsed
+    /// [`SoapFault`](crate::SoapFault).
+    pub fn check_for_fault(xml: &str) -> SoapResult<()> {
+        #[cfg(feature = "tracing")]
+        debug!("Checking SOAP response for faults");
+
+        match crate::fault::SoapFault::from_xml(xml)? {
+            Some(fault) => Err(fault.into()),
+            None => Ok(()),
+        }
     }
 }
 
@@ -617,14 +954,76 @@ mod tests {
         let result = SoapEnvelope::check_for_fault(fault_xml);
         assert!(result.is_err());
 
-        if let Err(SoapError::SoapFault { code, message, .. }) = result {
-            assert_eq!(code, "soap:Server");
-            assert_eq!(message, "Internal Server Error");
+        if let Err(SoapError::SoapFault(fault)) = result {
+            assert_eq!(fault.code, "soap:Server");
+            assert_eq!(fault.message(), "Internal Server Error");
         } else {
             panic!("Expected SoapFault error");
         }
     }
 
+    #[test]
+    fn test_check_for_fault_with_detail() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct InsufficientFundsFault {
+            balance: f64,
+        }
+
+        let fault_xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <soap:Fault>
+      <faultcode>soap:Client</faultcode>
+      <faultstring>Insufficient funds</faultstring>
+      <detail><ns:InsufficientFundsFault xmlns:ns="urn:bank"><ns:balance>12.5</ns:balance></ns:InsufficientFundsFault></detail>
+    </soap:Fault>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        let error = SoapEnvelope::check_for_fault(fault_xml).unwrap_err();
+        assert_eq!(
+            error.fault_detail(),
+            Some(
+                r#"<ns:InsufficientFundsFault xmlns:ns="urn:bank"><ns:balance>12.5</ns:balance></ns:InsufficientFundsFault>"#
+            )
+        );
+
+        // The detail entry name ends in "Fault" but must not end fault parsing
+        let fault = error.fault().expect("Expected SoapFault error");
+        assert_eq!(fault.code, "soap:Client");
+        assert_eq!(fault.message(), "Insufficient funds");
+        assert!(error.is_client_fault());
+
+        let entry: Option<InsufficientFundsFault> =
+            error.fault_detail_entry("InsufficientFundsFault").unwrap();
+        assert_eq!(entry, Some(InsufficientFundsFault { balance: 12.5 }));
+        assert_eq!(
+            error
+                .fault_detail_entry::<InsufficientFundsFault>("Other")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_for_fault_soap12_detail() {
+        let fault_xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Body>
+    <env:Fault>
+      <env:Code><env:Value>env:Sender</env:Value></env:Code>
+      <env:Reason><env:Text xml:lang="en">Bad request</env:Text></env:Reason>
+      <env:Detail><Problem>missing id</Problem></env:Detail>
+    </env:Fault>
+  </env:Body>
+</env:Envelope>"#;
+
+        let error = SoapEnvelope::check_for_fault(fault_xml).unwrap_err();
+        assert_eq!(error.fault_detail(), Some("<Problem>missing id</Problem>"));
+        let fault = error.fault().unwrap();
+        assert_eq!(fault.code, "env:Sender");
+        assert_eq!(fault.message(), "Bad request");
+        assert_eq!(fault.version, SoapVersion::Soap12);
+    }
+
     #[test]
     fn test_default_soap_version() {
         assert_eq!(SoapVersion::default(), SoapVersion::Soap11);
@@ -661,6 +1060,167 @@ mod tests {
         assert!(envelope_unqualified.contains("<user_name>admin</user_name>"));
         assert!(envelope_unqualified.contains("<password>secret</password>"));
     }
+
+    #[test]
+    fn test_build_with_parts() {
+        let request = TestRequest {
+            name: "test".to_string(),
+            value: 42,
+        };
+
+        let parts = vec![
+            BodyPart::new("Customer", &request, Some("urn:test"), true).unwrap(),
+            BodyPart::new("limit", &10, None, false).unwrap(),
+        ];
+        let envelope = SoapEnvelope::build_with_parts(&parts, SoapVersion::Soap11);
+
+        assert!(envelope.contains(
+            "<soap:Body><Customer xmlns=\"urn:test\"><name>test</name><value>42</value></Customer><limit>10</limit></soap:Body>"
+        ));
+    }
+
+    #[test]
+    fn test_build_with_rpc_wrapper() {
+        let parts = vec![
+            BodyPart::new("a", &5, None, false).unwrap(),
+            BodyPart::new("b", &3, None, false).unwrap(),
+        ];
+        let wrapper = BodyPart::rpc_wrapper("Add", "urn:calc", &parts);
+        let envelope = SoapEnvelope::build_with_parts(&[wrapper], SoapVersion::Soap12);
+
+        assert!(envelope.contains(
+            "<env:Body><ns:Add xmlns:ns=\"urn:calc\"><a>5</a><b>3</b></ns:Add></env:Body>"
+        ));
+    }
+
+    #[test]
+    fn test_parse_response_parts() {
+        let response_xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <ns:TestResponse xmlns:ns="urn:test"><ns:result>a &amp; b</ns:result></ns:TestResponse>
+    <count>7</count>
+    <empty/>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        let parts = SoapEnvelope::parse_response_parts(response_xml).unwrap();
+        assert_eq!(parts.len(), 3);
+
+        let response: TestResponse = parts.part("TestResponse").unwrap();
+        assert_eq!(response.result, "a & b");
+        let count: i32 = parts.part("count").unwrap();
+        assert_eq!(count, 7);
+        assert_eq!(parts.raw("empty"), Some("<empty/>"));
+        assert!(matches!(
+            parts.part::<i32>("missing"),
+            Err(SoapError::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_response_parts_rpc() {
+        let response_xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <ns:AddResponse xmlns:ns="urn:calc"><result>8</result><remainder>0</remainder></ns:AddResponse>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        let parts = SoapEnvelope::parse_response_parts(response_xml)
+            .unwrap()
+            .unwrap_rpc()
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts.part::<i32>("result").unwrap(), 8);
+        assert_eq!(parts.part::<i32>("remainder").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_build_with_headers() {
+        let headers = vec![BodyPart::new("AuthHeader", &"token", Some("urn:test"), true).unwrap()];
+        let parts = vec![BodyPart::new("limit", &10, None, false).unwrap()];
+
+        let envelope = SoapEnvelope::build_with_headers(&headers, &parts, SoapVersion::Soap11);
+        assert!(envelope.contains(
+            "<soap:Header><AuthHeader xmlns=\"urn:test\">token</AuthHeader></soap:Header><soap:Body><limit>10</limit></soap:Body>"
+        ));
+
+        let envelope = SoapEnvelope::build_with_headers(&headers, &parts, SoapVersion::Soap12);
+        assert!(envelope.contains("<env:Header><AuthHeader"));
+
+        // No Header element without header blocks
+        let envelope = SoapEnvelope::build_with_headers(&[], &parts, SoapVersion::Soap11);
+        assert!(!envelope.contains("Header"));
+    }
+
+    #[test]
+    fn test_parse_response_headers() {
+        let response_xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Header>
+    <ns:SessionInfo xmlns:ns="urn:test"><ns:result>abc</ns:result></ns:SessionInfo>
+  </soap:Header>
+  <soap:Body>
+    <ns:AddResponse xmlns:ns="urn:calc"><result>8</result></ns:AddResponse>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        let parts = SoapEnvelope::parse_response_parts(response_xml).unwrap();
+        assert_eq!(parts.len(), 1);
+        let session: Option<TestResponse> = parts.header("SessionInfo").unwrap();
+        assert_eq!(session.unwrap().result, "abc");
+        assert_eq!(parts.header::<TestResponse>("Missing").unwrap(), None);
+
+        // Headers survive unwrapping an RPC response
+        let parts = parts.unwrap_rpc().unwrap();
+        assert_eq!(parts.part::<i32>("result").unwrap(), 8);
+        assert!(parts.raw_header("SessionInfo").is_some());
+    }
+
+    #[test]
+    fn test_build_with_soap_headers() {
+        let request = TestRequest {
+            name: "test".to_string(),
+            value: 1,
+        };
+        let headers = vec![
+            SoapHeader::raw("Token", "<Token>abc</Token>").must_understand(true),
+            SoapHeader::new("Trace", &"t-1", Some("urn:trace")).unwrap(),
+        ];
+
+        let envelope = SoapEnvelope::build_with_soap_headers(
+            &headers,
+            &request,
+            SoapVersion::Soap12,
+            Some("urn:test"),
+            true,
+        )
+        .unwrap();
+
+        assert!(envelope.contains(
+            "<env:Header><Token env:mustUnderstand=\"true\">abc</Token><Trace xmlns=\"urn:trace\">t-1</Trace></env:Header>"
+        ));
+        assert!(envelope.contains("<env:Body><TestRequest xmlns=\"urn:test\">"));
+    }
+
+    #[test]
+    fn test_parse_response_body_named_header() {
+        // A payload element called Header is not the SOAP Header
+        let response_xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><Header>1</Header></soap:Body></soap:Envelope>"#;
+
+        let parts = SoapEnvelope::parse_response_parts(response_xml).unwrap();
+        assert_eq!(parts.part::<i32>("Header").unwrap(), 1);
+        assert!(parts.raw_header("Header").is_none());
+    }
+
+    #[test]
+    fn test_parse_response_parts_empty_body() {
+        assert!(SoapEnvelope::parse_response_parts("").unwrap().is_empty());
+
+        let response_xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body/></soap:Envelope>"#;
+        assert!(SoapEnvelope::parse_response_parts(response_xml)
+            .unwrap()
+            .is_empty());
+    }
 }
 
 #[test]
@@ -1,5 +1,6 @@
 //! Error types for SOAP client runtime
 
+use crate::fault::SoapFault;
 use thiserror::Error;
 
 /// Result type for SOAP operations
@@ -9,20 +10,33 @@ pub type SoapResult<T> = std::result::Result<T, SoapError>;
 #[derive(Error, Debug)]
 pub enum SoapError {
     /// HTTP request failed
+    #[cfg(feature = "reqwest")]
     #[error("HTTP request failed: {0}")]
     HttpError(#[from] reqwest::Error),
 
+    /// The server answered with an unexpected HTTP status code
+    #[error("HTTP error status: {0}")]
+    HttpStatus(u16),
+
+    /// A transport failed to deliver the request
+    #[error("Transport error: {0}")]
+    TransportError(String),
+
+    /// A transport could not connect to the server, so the request was not sent
+    #[error("Connection failed: {0}")]
+    ConnectError(String),
+
+    /// No response arrived within the timeout of the request
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
     /// XML parsing error
     #[error("XML parsing error: {0}")]
     XmlError(String),
 
     /// SOAP fault received from server
-    #[error("SOAP fault: {code} - {message}")]
-    SoapFault {
-        code: String,
-        message: String,
-        detail: Option<String>,
-    },
+    #[error("SOAP fault: {0}")]
+    SoapFault(Box<SoapFault>),
 
     /// Serialization error
     #[error("Serialization error: {0}")]
@@ -44,11 +58,105 @@ pub enum SoapError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
+    /// HTTP authentication failed
+    #[error("Authentication error: {0}")]
+    AuthError(String),
+
+    /// Building or checking WS-Security headers failed
+    #[error("WS-Security error: {0}")]
+    SecurityError(String),
+
+    /// The request was rejected by the client without being sent
+    #[error("Request rejected: {0}")]
+    Rejected(Rejection),
+
+    /// The call was cancelled through its [`CallOptions`](crate::CallOptions)
+    #[error("Call cancelled")]
+    Cancelled,
+
     /// Other errors
     #[error(transparent)]
     Other(#[from] Box<dyn std::error::Error + Send + Sync>),
 }
 
+/// Why a request was rejected without being sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The circuit breaker of the endpoint is open
+    CircuitOpen,
+    /// The maximum number of requests in flight is reached
+    ConcurrencyLimit,
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Rejection::CircuitOpen => "circuit breaker is open",
+            Rejection::ConcurrencyLimit => "too many requests in flight",
+        })
+    }
+}
+
+impl SoapError {
+    /// Get the SOAP fault if this error is one
+    pub fn fault(&self) -> Option<&SoapFault> {
+        match self {
+            SoapError::SoapFault(fault) => Some(fault),
+            _ => None,
+        }
+    }
+
+    /// Check whether this is a SOAP fault caused by the request
+    ///
+    /// See [`SoapFault::is_client_fault`].
+    pub fn is_client_fault(&self) -> bool {
+        self.fault().is_some_and(SoapFault::is_client_fault)
+    }
+
+    /// Check whether this is a SOAP fault caused by the server
+    ///
+    /// See [`SoapFault::is_server_fault`].
+    pub fn is_server_fault(&self) -> bool {
+        self.fault().is_some_and(SoapFault::is_server_fault)
+    }
+
+    /// Get the raw XML content of the fault `<detail>` element
+    ///
+    /// Returns None if this is not a SOAP fault or the fault has no detail.
+    pub fn fault_detail(&self) -> Option<&str> {
+        self.fault().and_then(|fault| fault.detail.as_deref())
+    }
+
+    /// Deserialize the fault detail entry with the given element name
+    ///
+    /// The fault `<detail>` may contain several entries; the one whose local
+    /// name matches `name` is deserialized. Returns `Ok(None)` if this is not
+    /// a SOAP fault or no such entry exists.
+    pub fn fault_detail_entry<T>(&self, name: &str) -> SoapResult<Option<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let Some(detail) = self.fault_detail() else {
+            return Ok(None);
+        };
+
+        crate::envelope::SoapEnvelope::split_elements(detail, 0)?
+            .into_iter()
+            .find(|(local_name, _)| local_name == name)
+            .map(|(_, xml)| {
+                quick_xml::de::from_str(&xml)
+                    .map_err(|e| SoapError::DeserializationError(e.to_string()))
+            })
+            .transpose()
+    }
+}
+
+impl From<SoapFault> for SoapError {
+    fn from(fault: SoapFault) -> Self {
+        SoapError::SoapFault(Box::new(fault))
+    }
+}
+
 impl From<quick_xml::Error> for SoapError {
     fn from(err: quick_xml::Error) -> Self {
         SoapError::XmlError(err.to_string())
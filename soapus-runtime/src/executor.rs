@@ -0,0 +1,260 @@
+//! Executor-agnostic waiting
+//!
+//! The runtime does not require a particular async executor. Sleeps are
+//! completed by the shared timer thread of `futures-timer`, or by tokio's
+//! timer inside a tokio runtime. Blocking I/O runs on a bounded pool of
+//! background threads. Both work with tokio, smol, async-std or a
+//! hand-written executor alike.
+
+#[cfg(feature = "std-http")]
+use std::future::Future;
+use std::time::Duration;
+
+/// Wait for `duration` without blocking the executor
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::time::sleep(duration).await;
+    }
+    futures_timer::Delay::new(duration).await
+}
+
+/// Run a blocking function on the shared thread pool and wait for its result
+///
+/// Panics of the function are re-raised when the future is polled. If the
+/// future is dropped before the function started, it does not run.
+#[cfg(feature = "std-http")]
+pub(crate) fn on_thread<T, F>(f: F) -> impl Future<Output = T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    pool::POOL.run(f)
+}
+
+#[cfg(feature = "std-http")]
+mod pool {
+    use std::collections::VecDeque;
+    use std::future::Future;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::pin::Pin;
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::Duration;
+
+    /// The pool shared by all blocking calls
+    pub(super) static POOL: Pool = Pool::new(64);
+
+    /// How long an idle thread waits for work before it exits
+    const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+    type Job = Box<dyn FnOnce() + Send>;
+
+    /// Threads running blocking jobs, at most `max_threads` at a time
+    ///
+    /// Threads are started on demand and exit after [`KEEP_ALIVE`] without
+    /// work. Jobs wait in a queue while all threads are busy.
+    pub(super) struct Pool {
+        state: Mutex<PoolState>,
+        available: Condvar,
+        max_threads: usize,
+    }
+
+    struct PoolState {
+        queue: VecDeque<Job>,
+        threads: usize,
+        idle: usize,
+    }
+
+    impl Pool {
+        pub(super) const fn new(max_threads: usize) -> Self {
+            Self {
+                state: Mutex::new(PoolState {
+                    queue: VecDeque::new(),
+                    threads: 0,
+                    idle: 0,
+                }),
+                available: Condvar::new(),
+                max_threads,
+            }
+        }
+
+        /// Queue `f` and return a future of its result
+        pub(super) fn run<T, F>(&'static self, f: F) -> OnThread<T>
+        where
+            F: FnOnce() -> T + Send + 'static,
+            T: Send + 'static,
+        {
+            let shared = Arc::new(Mutex::new(Shared {
+                result: None,
+                waker: None,
+                abandoned: false,
+            }));
+            let completion = shared.clone();
+            self.submit(Box::new(move || {
+                if lock(&completion).abandoned {
+                    return;
+                }
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                let mut shared = lock(&completion);
+                shared.result = Some(result);
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+            }));
+            OnThread { shared }
+        }
+
+        fn submit(&'static self, job: Job) {
+            let mut state = self.lock();
+            state.queue.push_back(job);
+            if state.queue.len() > state.idle && state.threads < self.max_threads {
+                state.threads += 1;
+                thread::spawn(move || self.work());
+            } else {
+                self.available.notify_one();
+            }
+        }
+
+        fn work(&self) {
+            let mut state = self.lock();
+            loop {
+                if let Some(job) = state.queue.pop_front() {
+                    drop(state);
+                    job();
+                    state = self.lock();
+                    continue;
+                }
+                state.idle += 1;
+                let (next, wait) = self
+                    .available
+                    .wait_timeout(state, KEEP_ALIVE)
+                    .unwrap_or_else(|e| e.into_inner());
+                state = next;
+                state.idle -= 1;
+                if wait.timed_out() && state.queue.is_empty() {
+                    state.threads -= 1;
+                    return;
+                }
+            }
+        }
+
+        fn lock(&self) -> MutexGuard<'_, PoolState> {
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    struct Shared<T> {
+        result: Option<thread::Result<T>>,
+        waker: Option<Waker>,
+        /// The future was dropped, so the job no longer needs to run
+        abandoned: bool,
+    }
+
+    /// The result of a function running on the pool
+    pub(super) struct OnThread<T> {
+        shared: Arc<Mutex<Shared<T>>>,
+    }
+
+    impl<T> Future for OnThread<T> {
+        type Output = T;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+            let mut shared = lock(&self.shared);
+            match shared.result.take() {
+                Some(Ok(value)) => Poll::Ready(value),
+                Some(Err(panic)) => panic::resume_unwind(panic),
+                None => {
+                    shared.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    impl<T> Drop for OnThread<T> {
+        fn drop(&mut self) {
+            lock(&self.shared).abandoned = true;
+        }
+    }
+
+    fn lock<T>(shared: &Mutex<Shared<T>>) -> MutexGuard<'_, Shared<T>> {
+        shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[test]
+        fn test_bounded() {
+            static SMALL: Pool = Pool::new(2);
+            static RUNNING: AtomicUsize = AtomicUsize::new(0);
+            static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+            let jobs: Vec<_> = (0..8)
+                .map(|i| {
+                    SMALL.run(move || {
+                        let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+                        MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(10));
+                        RUNNING.fetch_sub(1, Ordering::SeqCst);
+                        i
+                    })
+                })
+                .collect();
+            let results: Vec<_> = jobs.into_iter().map(futures_executor::block_on).collect();
+
+            assert_eq!(results, (0..8).collect::<Vec<_>>());
+            assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 2);
+            assert!(SMALL.lock().threads <= 2);
+        }
+
+        #[test]
+        fn test_dropped_job_does_not_run() {
+            static SINGLE: Pool = Pool::new(1);
+            static RAN: AtomicUsize = AtomicUsize::new(0);
+
+            let busy = SINGLE.run(|| thread::sleep(Duration::from_millis(50)));
+            drop(SINGLE.run(|| RAN.fetch_add(1, Ordering::SeqCst)));
+            futures_executor::block_on(busy);
+            futures_executor::block_on(SINGLE.run(|| ()));
+            assert_eq!(RAN.load(Ordering::SeqCst), 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_without_runtime() {
+        let start = Instant::now();
+        futures_executor::block_on(sleep(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn test_inside_tokio() {
+        let start = Instant::now();
+        sleep(Duration::from_millis(20)).await;
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[cfg(feature = "std-http")]
+    #[test]
+    fn test_on_thread() {
+        assert_eq!(futures_executor::block_on(on_thread(|| 6 * 7)), 42);
+    }
+
+    #[cfg(feature = "std-http")]
+    #[test]
+    #[should_panic(expected = "boom")]
+    fn test_on_thread_panic() {
+        futures_executor::block_on(on_thread(|| panic!("boom")));
+    }
+}
@@ -0,0 +1,420 @@
+//! SOAP fault model
+//!
+//! This module provides [`SoapFault`], a structured representation of the
+//! `Fault` element of a SOAP 1.1 or SOAP 1.2 response. It is carried by
+//! [`SoapError::SoapFault`](crate::SoapError::SoapFault).
+//!
+//! | Field | SOAP 1.1 | SOAP 1.2 |
+//! |-------|----------|----------|
+//! | `code` | `faultcode` | `Code/Value` |
+//! | `subcodes` | - | `Code/Subcode/Value` (outermost first) |
+//! | `reasons` | `faultstring` | `Reason/Text` (one per language) |
+//! | `node` | - | `Node` |
+//! | `role` | `faultactor` | `Role` |
+//! | `detail` | `detail` | `Detail` |
+
+use crate::envelope::SoapVersion;
+use crate::error::{SoapError, SoapResult};
+use quick_xml::events::Event;
+use quick_xml::name::{Namespace, ResolveResult};
+use quick_xml::NsReader;
+use std::fmt;
+
+#[cfg(feature = "tracing")]
+use tracing::debug;
+
+const SOAP11_ENVELOPE_NS: &[u8] = b"http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP12_ENVELOPE_NS: &[u8] = b"http://www.w3.org/2003/05/soap-envelope";
+
+/// A human readable fault reason in one language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultReason {
+    /// The reason text
+    pub text: String,
+    /// The `xml:lang` of the text, if given
+    pub lang: Option<String>,
+}
+
+/// A SOAP fault returned by the server
+///
+/// # Example
+///
+/// ```
+/// use soapus_runtime::{SoapFault, SoapVersion};
+///
+/// let xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+///   <env:Body>
+///     <env:Fault>
+///       <env:Code>
+///         <env:Value>env:Sender</env:Value>
+///         <env:Subcode><env:Value>m:InvalidAccount</env:Value></env:Subcode>
+///       </env:Code>
+///       <env:Reason>
+///         <env:Text xml:lang="en">Unknown account</env:Text>
+///         <env:Text xml:lang="de">Unbekanntes Konto</env:Text>
+///       </env:Reason>
+///     </env:Fault>
+///   </env:Body>
+/// </env:Envelope>"#;
+///
+/// let fault = SoapFault::from_xml(xml).unwrap().unwrap();
+/// assert_eq!(fault.version, SoapVersion::Soap12);
+/// assert_eq!(fault.code, "env:Sender");
+/// assert_eq!(fault.subcodes, vec!["m:InvalidAccount"]);
+/// assert_eq!(fault.reason("de"), Some("Unbekanntes Konto"));
+/// assert!(fault.is_client_fault());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoapFault {
+    /// SOAP version of the envelope carrying the fault
+    pub version: SoapVersion,
+    /// Fault code as it appears in the response (e.g. `soap:Server`)
+    pub code: String,
+    /// Subcodes of a SOAP 1.2 fault, outermost first
+    pub subcodes: Vec<String>,
+    /// Fault reasons; SOAP 1.2 allows one per language
+    pub reasons: Vec<FaultReason>,
+    /// URI of the SOAP node that generated the fault (SOAP 1.2 only)
+    pub node: Option<String>,
+    /// Role of the node that generated the fault (`faultactor` in SOAP 1.1)
+    pub role: Option<String>,
+    /// Raw XML content of the fault detail element
+    pub detail: Option<String>,
+}
+
+impl SoapFault {
+    /// Parse the `Fault` element of a SOAP response
+    ///
+    /// Returns `Ok(None)` if the response does not contain a fault. Any
+    /// element named `Fault` in the SOAP 1.1 or SOAP 1.2 envelope namespace
+    /// directly inside the `Body` is a fault, even if it is empty.
+    pub fn from_xml(xml: &str) -> SoapResult<Option<Self>> {
+        let mut reader = NsReader::from_str(xml);
+        // Nesting level of the open elements and of the Body's children
+        let mut depth = 0;
+        let mut body_depth = None;
+
+        loop {
+            let (ns, event) = reader.read_resolved_event()?;
+            let version = match ns {
+                ResolveResult::Bound(Namespace(SOAP11_ENVELOPE_NS)) => Some(SoapVersion::Soap11),
+                ResolveResult::Bound(Namespace(SOAP12_ENVELOPE_NS)) => Some(SoapVersion::Soap12),
+                _ => None,
+            };
+
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e)
+                    if body_depth == Some(depth) && e.local_name().as_ref() == b"Fault" =>
+                {
+                    let Some(version) = version else {
+                        continue;
+                    };
+
+                    #[cfg(feature = "tracing")]
+                    debug!(soap_version = ?version, "Parsing SOAP fault");
+
+                    if matches!(event, Event::Empty(_)) {
+                        return Ok(Some(SoapFault {
+                            version,
+                            ..Default::default()
+                        }));
+                    }
+                    return Self::parse_fault(&mut reader, xml, version).map(Some);
+                }
+                Event::Start(e) => {
+                    depth += 1;
+                    if body_depth.is_none()
+                        && depth == 2
+                        && version.is_some()
+                        && e.local_name().as_ref() == b"Body"
+                    {
+                        body_depth = Some(depth);
+                    }
+                }
+                Event::End(_) => {
+                    if body_depth == Some(depth) {
+                        return Ok(None);
+                    }
+                    depth -= 1;
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse the children of a `Fault` element up to its end tag
+    fn parse_fault(
+        reader: &mut NsReader<&[u8]>,
+        xml: &str,
+        version: SoapVersion,
+    ) -> SoapResult<Self> {
+        let mut fault = SoapFault {
+            version,
+            ..Default::default()
+        };
+        // Local names of the open elements below Fault
+        let mut path: Vec<String> = Vec::new();
+        let mut text = String::new();
+        let mut lang = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e)
+                    if path.is_empty()
+                        && e.local_name().as_ref().eq_ignore_ascii_case(b"detail") =>
+                {
+                    // Detail entries are application defined, keep them as XML
+                    let span = reader.read_to_end(e.name())?;
+                    let content = xml[span.start as usize..span.end as usize].trim();
+                    if !content.is_empty() {
+                        fault.detail = Some(content.to_string());
+                    }
+                }
+                Event::Start(e) => {
+                    path.push(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+                    text.clear();
+                    lang = e
+                        .try_get_attribute("xml:lang")?
+                        .map(|attr| attr.unescape_value().map(|v| v.into_owned()))
+                        .transpose()?;
+                }
+                Event::Text(e) => text.push_str(&e.unescape()?),
+                Event::CData(e) => text.push_str(&String::from_utf8_lossy(&e)),
+                Event::End(_) if path.is_empty() => return Ok(fault),
+                Event::End(_) => {
+                    let value = text.trim().to_string();
+                    let names: Vec<&str> = path.iter().map(String::as_str).collect();
+                    match names.as_slice() {
+                        ["faultcode"] | ["Code", "Value"] => fault.code = value,
+                        ["faultstring"] | ["Reason", "Text"] => fault.reasons.push(FaultReason {
+                            text: value,
+                            lang: lang.take(),
+                        }),
+                        ["faultactor"] | ["Role"] => fault.role = Some(value),
+                        ["Node"] => fault.node = Some(value),
+                        ["Code", subcodes @ .., "Value"]
+                            if subcodes.iter().all(|name| *name == "Subcode") =>
+                        {
+                            fault.subcodes.push(value)
+                        }
+                        _ => {}
+                    }
+                    path.pop();
+                    text.clear();
+                }
+                Event::Eof => {
+                    return Err(SoapError::XmlError(
+                        "Unexpected end of document inside SOAP fault".to_string(),
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Get the fault message: the first reason text, or an empty string
+    pub fn message(&self) -> &str {
+        self.reasons.first().map(|r| r.text.as_str()).unwrap_or("")
+    }
+
+    /// Get the reason text for a language
+    ///
+    /// A language tag like `en` also matches region variants like `en-US`.
+    pub fn reason(&self, lang: &str) -> Option<&str> {
+        let matches = |reason: &&FaultReason| {
+            reason.lang.as_deref().is_some_and(|l| {
+                l.eq_ignore_ascii_case(lang)
+                    || l.len() > lang.len()
+                        && l[..lang.len()].eq_ignore_ascii_case(lang)
+                        && l.as_bytes()[lang.len()] == b'-'
+            })
+        };
+        self.reasons.iter().find(matches).map(|r| r.text.as_str())
+    }
+
+    /// Get the fault code without its namespace prefix
+    pub fn code_local_name(&self) -> &str {
+        local_name(&self.code)
+    }
+
+    /// Check whether the fault was caused by the request (`Client` in SOAP 1.1, `Sender` in SOAP 1.2)
+    pub fn is_client_fault(&self) -> bool {
+        self.has_code("Client") || self.has_code("Sender")
+    }
+
+    /// Check whether the fault was caused by the server (`Server` in SOAP 1.1, `Receiver` in SOAP 1.2)
+    pub fn is_server_fault(&self) -> bool {
+        self.has_code("Server") || self.has_code("Receiver")
+    }
+
+    /// Check whether the server rejected the envelope version
+    pub fn is_version_mismatch(&self) -> bool {
+        self.has_code("VersionMismatch")
+    }
+
+    /// Check whether the server did not understand a `mustUnderstand` header
+    pub fn is_must_understand(&self) -> bool {
+        self.has_code("MustUnderstand")
+    }
+
+    /// Match the code's local name, including SOAP 1.1 dotted specializations like `Client.Authentication`
+    fn has_code(&self, code: &str) -> bool {
+        let name = self.code_local_name();
+        name == code
+            || name
+                .strip_prefix(code)
+                .is_some_and(|rest| rest.starts_with('.'))
+    }
+}
+
+impl fmt::Display for SoapFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} - {}", self.code, self.message())
+    }
+}
+
+/// Strip the namespace prefix of a qualified name
+pub(crate) fn local_name(name: &str) -> &str {
+    name.rsplit_once(':')
+        .map(|(_, local)| local)
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_soap11_fault() {
+        let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <soap:Fault>
+      <faultcode>soap:Client.Authentication</faultcode>
+      <faultstring xml:lang="en">Invalid &amp; expired token</faultstring>
+      <faultactor>http://example.com/gateway</faultactor>
+      <detail><Reason>expired</Reason></detail>
+    </soap:Fault>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        let fault = SoapFault::from_xml(xml).unwrap().unwrap();
+        assert_eq!(fault.version, SoapVersion::Soap11);
+        assert_eq!(fault.code, "soap:Client.Authentication");
+        assert_eq!(fault.code_local_name(), "Client.Authentication");
+        assert_eq!(fault.message(), "Invalid & expired token");
+        assert_eq!(fault.reason("en"), Some("Invalid & expired token"));
+        assert_eq!(fault.role.as_deref(), Some("http://example.com/gateway"));
+        assert_eq!(fault.node, None);
+        assert_eq!(fault.detail.as_deref(), Some("<Reason>expired</Reason>"));
+        assert!(fault.is_client_fault());
+        assert!(!fault.is_server_fault());
+        assert_eq!(
+            fault.to_string(),
+            "soap:Client.Authentication - Invalid & expired token"
+        );
+    }
+
+    #[test]
+    fn test_parse_soap12_fault() {
+        let xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Body>
+    <env:Fault>
+      <env:Code>
+        <env:Value>env:Receiver</env:Value>
+        <env:Subcode>
+          <env:Value>m:Database</env:Value>
+          <env:Subcode><env:Value>m:Timeout</env:Value></env:Subcode>
+        </env:Subcode>
+      </env:Code>
+      <env:Reason>
+        <env:Text xml:lang="en-US">Database timeout</env:Text>
+        <env:Text xml:lang="fr">Délai dépassé</env:Text>
+      </env:Reason>
+      <env:Node>http://example.com/node</env:Node>
+      <env:Role>http://www.w3.org/2003/05/soap-envelope/role/ultimateReceiver</env:Role>
+      <env:Detail>
+        <m:Info xmlns:m="urn:m">retry later</m:Info>
+      </env:Detail>
+    </env:Fault>
+  </env:Body>
+</env:Envelope>"#;
+
+        let fault = SoapFault::from_xml(xml).unwrap().unwrap();
+        assert_eq!(fault.version, SoapVersion::Soap12);
+        assert_eq!(fault.code, "env:Receiver");
+        assert_eq!(fault.subcodes, vec!["m:Database", "m:Timeout"]);
+        assert_eq!(fault.reasons.len(), 2);
+        assert_eq!(fault.message(), "Database timeout");
+        assert_eq!(fault.reason("en"), Some("Database timeout"));
+        assert_eq!(fault.reason("FR"), Some("Délai dépassé"));
+        assert_eq!(fault.reason("e"), None);
+        assert_eq!(fault.node.as_deref(), Some("http://example.com/node"));
+        assert_eq!(
+            fault.role.as_deref(),
+            Some("http://www.w3.org/2003/05/soap-envelope/role/ultimateReceiver")
+        );
+        assert_eq!(
+            fault.detail.as_deref(),
+            Some(r#"<m:Info xmlns:m="urn:m">retry later</m:Info>"#)
+        );
+        assert!(fault.is_server_fault());
+        assert!(!fault.is_client_fault());
+    }
+
+    #[test]
+    fn test_parse_empty_fault() {
+        let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body><soap:Fault/></soap:Body>
+</soap:Envelope>"#;
+
+        let fault = SoapFault::from_xml(xml).unwrap().unwrap();
+        assert_eq!(fault.version, SoapVersion::Soap11);
+        assert_eq!(fault.code, "");
+        assert_eq!(fault.message(), "");
+
+        let xml = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+  <env:Header/>
+  <env:Body><env:Fault></env:Fault></env:Body>
+</env:Envelope>"#;
+
+        let fault = SoapFault::from_xml(xml).unwrap().unwrap();
+        assert_eq!(fault.version, SoapVersion::Soap12);
+    }
+
+    #[test]
+    fn test_no_fault() {
+        // Application elements named Fault are not SOAP faults
+        let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <ReportResponse xmlns="urn:r"><Fault>none</Fault></ReportResponse>
+  </soap:Body>
+</soap:Envelope>"#;
+
+        assert_eq!(SoapFault::from_xml(xml).unwrap(), None);
+
+        // Faults only occur in the Body
+        let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Header><h:Audit xmlns:h="urn:h"><soap:Fault/></h:Audit></soap:Header>
+  <soap:Body><ReportResponse xmlns="urn:r"/></soap:Body>
+</soap:Envelope>"#;
+
+        assert_eq!(SoapFault::from_xml(xml).unwrap(), None);
+    }
+
+    #[test]
+    fn test_fault_code_helpers() {
+        let fault = |code: &str| SoapFault {
+            code: code.to_string(),
+            ..Default::default()
+        };
+
+        assert!(fault("soap:Server").is_server_fault());
+        assert!(fault("Server.Database").is_server_fault());
+        assert!(!fault("soap:ServerBusy").is_server_fault());
+        assert!(fault("env:Sender").is_client_fault());
+        assert!(fault("env:VersionMismatch").is_version_mismatch());
+        assert!(fault("soap:MustUnderstand").is_must_understand());
+        assert_eq!(fault("env:Sender").message(), "");
+    }
+}
@@ -0,0 +1,446 @@
+//! SOAP header blocks
+//!
+//! This module provides [`SoapHeader`] for attaching arbitrary header elements to
+//! a request and [`SoapHeaders`] for reading the header blocks of a response.
+//! Header blocks can be set once on the [`SoapClient`](crate::SoapClient) or
+//! passed per call.
+
+use crate::envelope::{BodyPart, SoapVersion};
+use crate::error::{SoapError, SoapResult};
+use crate::interceptor::find_header;
+#[cfg(feature = "swa")]
+use crate::mtom::Attachment;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::time::Duration;
+
+/// A header block to send in the SOAP `Header`
+///
+/// The SOAP processing attributes are written with the envelope prefix of the
+/// SOAP version used for the request:
+///
+/// | Setting | SOAP 1.1 | SOAP 1.2 |
+/// |---------|----------|----------|
+/// | `must_understand` | `soap:mustUnderstand="1"` | `env:mustUnderstand="true"` |
+/// | `role` | `soap:actor="..."` | `env:role="..."` |
+/// | `relay` | not written | `env:relay="true"` |
+///
+/// # Example
+///
+/// ```
+/// use soapus_runtime::{SoapHeader, SoapVersion};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Session {
+///     id: String,
+/// }
+///
+/// let header = SoapHeader::new("Session", &Session { id: "42".into() }, Some("urn:example"))
+///     .unwrap()
+///     .must_understand(true);
+///
+/// assert_eq!(
+///     header.to_xml(SoapVersion::Soap11),
+///     r#"<Session soap:mustUnderstand="1" xmlns="urn:example"><id>42</id></Session>"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoapHeader {
+    name: String,
+    xml: String,
+    must_understand: bool,
+    role: Option<String>,
+    relay: bool,
+}
+
+impl SoapHeader {
+    /// Serialize a value as a header block
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The XML element name of the header block
+    /// * `value` - The header content
+    /// * `namespace` - Namespace of the header element (child elements inherit it)
+    pub fn new<T>(name: impl Into<String>, value: &T, namespace: Option<&str>) -> SoapResult<Self>
+    where
+        T: Serialize,
+    {
+        let part = BodyPart::new(name, value, namespace, true)?;
+        Ok(Self::raw(part.name(), part.xml()))
+    }
+
+    /// Create a header block from already serialized XML
+    ///
+    /// `xml` must be a single element. `name` identifies the header, e.g. for
+    /// [`SoapClient::remove_header`](crate::SoapClient::remove_header).
+    pub fn raw(name: impl Into<String>, xml: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            xml: xml.into(),
+            must_understand: false,
+            role: None,
+            relay: false,
+        }
+    }
+
+    /// Require the receiver to process this header (`mustUnderstand`)
+    pub fn must_understand(mut self, must_understand: bool) -> Self {
+        self.must_understand = must_understand;
+        self
+    }
+
+    /// Target the header at a specific node (`actor` in SOAP 1.1, `role` in SOAP 1.2)
+    pub fn role(mut self, role: impl Into<String>) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+    /// Relay the header if it is not processed (SOAP 1.2 only)
+    pub fn relay(mut self, relay: bool) -> Self {
+        self.relay = relay;
+        self
+    }
+
+    /// Get the element name of this header
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The XML of the header element, without SOAP attributes
+    #[cfg(feature = "ws-security")]
+    pub(crate) fn xml(&self) -> &str {
+        &self.xml
+    }
+
+    /// Serialize the header block for the given SOAP version
+    pub fn to_xml(&self, version: SoapVersion) -> String {
+        let mut attributes = String::new();
+        match version {
+            SoapVersion::Soap11 => {
+                if self.must_understand {
+                    attributes.push_str(" soap:mustUnderstand=\"1\"");
+                }
+                if let Some(role) = &self.role {
+                    attributes.push_str(&format!(
+                        " soap:actor=\"{}\"",
+                        quick_xml::escape::escape(role.as_str())
+                    ));
+                }
+            }
+            SoapVersion::Soap12 => {
+                if self.must_understand {
+                    attributes.push_str(" env:mustUnderstand=\"true\"");
+                }
+                if let Some(role) = &self.role {
+                    attributes.push_str(&format!(
+                        " env:role=\"{}\"",
+                        quick_xml::escape::escape(role.as_str())
+                    ));
+                }
+                if self.relay {
+                    attributes.push_str(" env:relay=\"true\"");
+                }
+            }
+        }
+
+        insert_root_attributes(&self.xml, &attributes)
+    }
+
+    /// Convert the header block into a part for [`SoapEnvelope::build_with_headers`](crate::SoapEnvelope::build_with_headers)
+    pub fn to_body_part(&self, version: SoapVersion) -> BodyPart {
+        BodyPart::raw(self.name.as_str(), self.to_xml(version))
+    }
+}
+
+/// Insert attributes right after the element name of the root tag
+///
+/// An XML declaration, comments and processing instructions before the root
+/// element are skipped.
+fn insert_root_attributes(xml: &str, attributes: &str) -> String {
+    if attributes.is_empty() {
+        return xml.to_string();
+    }
+
+    let mut reader = Reader::from_str(xml);
+    let name_end = loop {
+        let start = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                // The tag starts at `start` unless whitespace preceded it
+                let tag_start = start + xml[start..].find('<').unwrap_or_default();
+                break tag_start + 1 + e.name().as_ref().len();
+            }
+            Ok(Event::Eof) | Err(_) => return xml.to_string(),
+            Ok(_) => {}
+        }
+    };
+
+    let mut result = String::with_capacity(xml.len() + attributes.len());
+    result.push_str(&xml[..name_end]);
+    result.push_str(attributes);
+    result.push_str(&xml[name_end..]);
+    result
+}
+
+/// Header blocks extracted from a SOAP response
+///
+/// Each direct child of the response `Header` element is kept as a separate
+/// XML fragment and can be deserialized by its element name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SoapHeaders {
+    entries: Vec<(String, String)>,
+}
+
+impl SoapHeaders {
+    pub(crate) fn new(entries: Vec<(String, String)>) -> Self {
+        Self { entries }
+    }
+
+    /// Number of header blocks
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the response had no header blocks
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the local names of all header blocks
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Get the raw XML of the header block with the given element name
+    ///
+    /// The name is compared against the local name, ignoring any namespace prefix.
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(local_name, _)| local_name == name)
+            .map(|(_, xml)| xml.as_str())
+    }
+
+    /// Deserialize the header block with the given element name
+    ///
+    /// Returns `Ok(None)` if the response does not contain the header.
+    pub fn get<T>(&self, name: &str) -> SoapResult<Option<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        self.raw(name)
+            .map(|xml| {
+                quick_xml::de::from_str(xml)
+                    .map_err(|e| SoapError::DeserializationError(e.to_string()))
+            })
+            .transpose()
+    }
+}
+
+/// HTTP details of a received response
+///
+/// Available from [`SoapResponse::http`] and
+/// [`ResponseParts::http`](crate::envelope::ResponseParts::http).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpMetadata {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) elapsed: Duration,
+    pub(crate) raw_body: Vec<u8>,
+}
+
+impl HttpMetadata {
+    /// Get the HTTP status code
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Get all HTTP response headers, e.g. every `Set-Cookie`
+    ///
+    /// These are the headers as left by the `after_receive` interceptor hooks.
+    /// The `Content-Encoding` header of a decompressed body is removed.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Get the first value of an HTTP response header (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Get the time from the start of the call until the response was received
+    ///
+    /// Includes serialization, interceptors and retries, but not deserialization.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Get the HTTP response body, e.g. for auditing
+    ///
+    /// Only captured for calls with
+    /// [`CallOptions::raw_body`](crate::options::CallOptions::raw_body), empty
+    /// otherwise. The body is taken as the transport received it, before the
+    /// `after_receive` interceptor hooks run, but after decompression; for
+    /// MTOM and SwA responses this is the complete `multipart/related` message.
+    pub fn raw_body(&self) -> &[u8] {
+        &self.raw_body
+    }
+}
+
+/// A deserialized response body with the SOAP header blocks and HTTP details of the response
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoapResponse<T> {
+    body: T,
+    headers: SoapHeaders,
+    #[cfg(feature = "swa")]
+    attachments: Vec<Attachment>,
+    http: HttpMetadata,
+}
+
+impl<T> SoapResponse<T> {
+    pub(crate) fn new(body: T, headers: SoapHeaders) -> Self {
+        Self {
+            body,
+            headers,
+            #[cfg(feature = "swa")]
+            attachments: Vec::new(),
+            http: HttpMetadata::default(),
+        }
+    }
+
+    #[cfg(feature = "swa")]
+    pub(crate) fn with_attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    pub(crate) fn with_http(mut self, http: HttpMetadata) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Get the deserialized response body
+    pub fn body(&self) -> &T {
+        &self.body
+    }
+
+    /// Get the response header blocks
+    pub fn headers(&self) -> &SoapHeaders {
+        &self.headers
+    }
+
+    /// Deserialize the response header block with the given element name
+    pub fn header<H>(&self, name: &str) -> SoapResult<Option<H>>
+    where
+        H: for<'de> serde::Deserialize<'de>,
+    {
+        self.headers.get(name)
+    }
+
+    /// Get the MIME attachments of a `multipart/related` response
+    #[cfg(feature = "swa")]
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// Get the attachment a `cid:` URL (e.g. of a `swaRef`) or Content-ID refers to
+    #[cfg(feature = "swa")]
+    pub fn attachment(&self, reference: &str) -> Option<&Attachment> {
+        crate::swa::find(&self.attachments, reference)
+    }
+
+    /// Get the HTTP status, headers, elapsed time and raw body of the response
+    pub fn http(&self) -> &HttpMetadata {
+        &self.http
+    }
+
+    /// Take the response body, dropping the headers
+    pub fn into_body(self) -> T {
+        self.body
+    }
+
+    /// Split into body and headers
+    pub fn into_parts(self) -> (T, SoapHeaders) {
+        (self.body, self.headers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Session {
+        id: String,
+    }
+
+    #[test]
+    fn test_header_attributes_soap11() {
+        let header = SoapHeader::raw(
+            "Session",
+            "<ns:Session xmlns:ns=\"urn:s\"><id>1</id></ns:Session>",
+        )
+        .must_understand(true)
+        .role("http://example.com/actor?a=1&b=2")
+        .relay(true);
+
+        assert_eq!(
+            header.to_xml(SoapVersion::Soap11),
+            "<ns:Session soap:mustUnderstand=\"1\" soap:actor=\"http://example.com/actor?a=1&amp;b=2\" xmlns:ns=\"urn:s\"><id>1</id></ns:Session>"
+        );
+    }
+
+    #[test]
+    fn test_header_attributes_soap12() {
+        let header = SoapHeader::raw("Token", "<Token/>")
+            .must_understand(true)
+            .role("http://www.w3.org/2003/05/soap-envelope/role/next")
+            .relay(true);
+
+        assert_eq!(
+            header.to_xml(SoapVersion::Soap12),
+            "<Token env:mustUnderstand=\"true\" env:role=\"http://www.w3.org/2003/05/soap-envelope/role/next\" env:relay=\"true\"/>"
+        );
+    }
+
+    #[test]
+    fn test_header_attributes_after_prolog() {
+        let header = SoapHeader::raw(
+            "Token",
+            "<?xml version=\"1.0\"?>\n<!-- <Not the root> -->\n<?pi data?><t:Token xmlns:t=\"urn:t\">1</t:Token>",
+        )
+        .must_understand(true);
+
+        assert_eq!(
+            header.to_xml(SoapVersion::Soap11),
+            "<?xml version=\"1.0\"?>\n<!-- <Not the root> -->\n<?pi data?><t:Token soap:mustUnderstand=\"1\" xmlns:t=\"urn:t\">1</t:Token>"
+        );
+    }
+
+    #[test]
+    fn test_header_without_attributes() {
+        let header = SoapHeader::new("Session", &Session { id: "7".into() }, None).unwrap();
+        assert_eq!(header.name(), "Session");
+        assert_eq!(
+            header.to_xml(SoapVersion::Soap12),
+            "<Session><id>7</id></Session>"
+        );
+    }
+
+    #[test]
+    fn test_soap_headers_get() {
+        let headers = SoapHeaders::new(vec![(
+            "Session".to_string(),
+            "<ns:Session xmlns:ns=\"urn:s\"><ns:id>9</ns:id></ns:Session>".to_string(),
+        )]);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.names().collect::<Vec<_>>(), vec!["Session"]);
+        let session: Option<Session> = headers.get("Session").unwrap();
+        assert_eq!(session, Some(Session { id: "9".into() }));
+        assert_eq!(headers.get::<Session>("Other").unwrap(), None);
+    }
+}
@@ -0,0 +1,393 @@
+//! Request interceptors
+//!
+//! An [`Interceptor`] hooks into every call made by a
+//! [`SoapClient`](crate::SoapClient). Interceptors are registered with
+//! [`SoapClientBuilder::interceptor`](crate::client::SoapClientBuilder::interceptor)
+//! and run in registration order before a request is sent and in reverse
+//! order once a response or error comes back:
+//!
+//! 1. [`Interceptor::before_serialize`] - add or remove SOAP header blocks
+//! 2. [`Interceptor::before_send`] - modify the envelope and HTTP headers
+//! 3. [`Interceptor::after_receive`] - inspect or modify the raw response
+//! 4. [`Interceptor::after_parse`] - inspect the response once its body is parsed
+//! 5. [`Interceptor::on_error`] - observe any error of the call
+//!
+//! An error returned by a hook aborts the call with that error.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{async_trait, Interceptor, SoapClient, SoapRequest, SoapResult};
+//!
+//! struct BearerToken(String);
+//!
+//! #[async_trait]
+//! impl Interceptor for BearerToken {
+//!     async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+//!         request.set_header("Authorization", format!("Bearer {}", self.0));
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let client = SoapClient::builder("http://example.com/soap")
+//!     .interceptor(BearerToken("secret".to_string()))
+//!     .build();
+//! ```
+
+use crate::envelope::SoapVersion;
+use crate::error::{SoapError, SoapResult};
+use crate::header::{SoapHeader, SoapHeaders};
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hooks into the request/response cycle of a SOAP call
+///
+/// All hooks have a default implementation that does nothing, so only the
+/// needed ones have to be implemented. Use [`macro@async_trait`] on the impl block.
+#[async_trait]
+pub trait Interceptor: Send + Sync {
+    /// Called before the request envelope is built
+    ///
+    /// Header blocks in `context.headers` are written into the SOAP `Header`,
+    /// including the header parts of the WSDL message.
+    async fn before_serialize(&self, _context: &mut RequestContext) -> SoapResult<()> {
+        Ok(())
+    }
+
+    /// Called with the serialized request right before it is sent
+    async fn before_send(&self, _request: &mut SoapRequest) -> SoapResult<()> {
+        Ok(())
+    }
+
+    /// Called with the raw response before the HTTP status and SOAP faults are checked
+    async fn after_receive(
+        &self,
+        _request: &SoapRequest,
+        _response: &mut RawResponse,
+    ) -> SoapResult<()> {
+        Ok(())
+    }
+
+    /// Called once the response body was parsed without errors
+    ///
+    /// Returning an error fails the call, e.g. if a required response header is missing.
+    async fn after_parse(
+        &self,
+        _context: &RequestContext,
+        _response: &ParsedResponse,
+    ) -> SoapResult<()> {
+        Ok(())
+    }
+
+    /// Called when the call fails, including errors returned by other hooks
+    async fn on_error(&self, _context: &RequestContext, _error: &SoapError) {}
+}
+
+/// Shared interceptors, e.g. to keep a handle for inspecting collected state
+#[async_trait]
+impl<T: Interceptor + ?Sized> Interceptor for Arc<T> {
+    async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+        (**self).before_serialize(context).await
+    }
+
+    async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+        (**self).before_send(request).await
+    }
+
+    async fn after_receive(
+        &self,
+        request: &SoapRequest,
+        response: &mut RawResponse,
+    ) -> SoapResult<()> {
+        (**self).after_receive(request, response).await
+    }
+
+    async fn after_parse(
+        &self,
+        context: &RequestContext,
+        response: &ParsedResponse,
+    ) -> SoapResult<()> {
+        (**self).after_parse(context, response).await
+    }
+
+    async fn on_error(&self, context: &RequestContext, error: &SoapError) {
+        (**self).on_error(context, error).await
+    }
+}
+
+/// A call before its envelope is built
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestContext {
+    /// The SOAP operation name
+    pub operation: String,
+    /// The SOAPAction passed to the call, if any
+    pub soap_action: Option<String>,
+    /// The endpoint URL of the call, from its [`CallOptions`](crate::CallOptions) or the client
+    pub endpoint: String,
+    /// Header blocks to send: the persistent client headers followed by the per-call headers
+    pub headers: Vec<SoapHeader>,
+    /// The correlation ID set in the [`CallOptions`](crate::CallOptions) of the call, if any
+    pub correlation_id: Option<String>,
+}
+
+/// A serialized request about to be sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoapRequest {
+    /// The endpoint URL the request is sent to
+    pub endpoint: String,
+    /// The SOAP operation name
+    pub operation: String,
+    /// The SOAP version of the envelope
+    pub soap_version: SoapVersion,
+    /// HTTP headers, including `Content-Type` and (SOAP 1.1) `SOAPAction`
+    pub http_headers: Vec<(String, String)>,
+    /// The complete SOAP envelope
+    pub envelope: String,
+}
+
+impl SoapRequest {
+    /// Get an HTTP header value (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.http_headers, name)
+    }
+
+    /// Set an HTTP header, replacing any existing value (case-insensitive)
+    pub fn set_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.http_headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        self.http_headers.push((name, value.into()));
+    }
+}
+
+/// A received HTTP response before it is parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// HTTP response headers
+    pub http_headers: Vec<(String, String)>,
+    /// Response body
+    pub body: String,
+}
+
+impl RawResponse {
+    /// Get an HTTP header value (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.http_headers, name)
+    }
+}
+
+/// A received response whose body was parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// HTTP response headers, as left by the `after_receive` hooks
+    pub http_headers: Vec<(String, String)>,
+    /// SOAP header blocks of the response
+    pub headers: SoapHeaders,
+    /// Time from the start of the call until the response was received
+    pub elapsed: Duration,
+}
+
+impl ParsedResponse {
+    /// Get an HTTP header value (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.http_headers, name)
+    }
+}
+
+pub(crate) fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// The interceptors registered on a client
+#[derive(Clone, Default)]
+pub(crate) struct InterceptorChain {
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl InterceptorChain {
+    pub(crate) fn push(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    pub(crate) async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+        for interceptor in &self.interceptors {
+            interceptor.before_serialize(context).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+        for interceptor in &self.interceptors {
+            interceptor.before_send(request).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after_receive(
+        &self,
+        request: &SoapRequest,
+        response: &mut RawResponse,
+    ) -> SoapResult<()> {
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_receive(request, response).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after_parse(
+        &self,
+        context: &RequestContext,
+        response: &ParsedResponse,
+    ) -> SoapResult<()> {
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.after_parse(context, response).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn on_error(&self, context: &RequestContext, error: &SoapError) {
+        for interceptor in self.interceptors.iter().rev() {
+            interceptor.on_error(context, error).await;
+        }
+    }
+}
+
+impl fmt::Debug for InterceptorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptorChain")
+            .field("len", &self.interceptors.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records the hooks it sees under its name
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn record(&self, hook: &str) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", hook, self.name));
+        }
+    }
+
+    #[async_trait]
+    impl Interceptor for Recorder {
+        async fn before_serialize(&self, _context: &mut RequestContext) -> SoapResult<()> {
+            self.record("serialize");
+            Ok(())
+        }
+
+        async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+            self.record("send");
+            request.set_header("X-Trace", self.name);
+            Ok(())
+        }
+
+        async fn after_receive(
+            &self,
+            _request: &SoapRequest,
+            _response: &mut RawResponse,
+        ) -> SoapResult<()> {
+            self.record("receive");
+            Ok(())
+        }
+
+        async fn after_parse(
+            &self,
+            _context: &RequestContext,
+            _response: &ParsedResponse,
+        ) -> SoapResult<()> {
+            self.record("parse");
+            Ok(())
+        }
+
+        async fn on_error(&self, _context: &RequestContext, _error: &SoapError) {
+            self.record("error");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut chain = InterceptorChain::default();
+        for name in ["a", "b"] {
+            chain.push(Arc::new(Recorder {
+                name,
+                log: log.clone(),
+            }));
+        }
+
+        let mut context = RequestContext {
+            operation: "Op".to_string(),
+            soap_action: None,
+            endpoint: "http://example.com".to_string(),
+            headers: Vec::new(),
+            correlation_id: None,
+        };
+        let mut request = SoapRequest {
+            endpoint: "http://example.com".to_string(),
+            operation: "Op".to_string(),
+            soap_version: SoapVersion::Soap11,
+            http_headers: vec![("x-trace".to_string(), "none".to_string())],
+            envelope: String::new(),
+        };
+        let mut response = RawResponse {
+            status: 200,
+            http_headers: Vec::new(),
+            body: String::new(),
+        };
+        let parsed = ParsedResponse {
+            status: 200,
+            http_headers: Vec::new(),
+            headers: SoapHeaders::default(),
+            elapsed: Duration::ZERO,
+        };
+        chain.before_serialize(&mut context).await.unwrap();
+        chain.before_send(&mut request).await.unwrap();
+        chain.after_receive(&request, &mut response).await.unwrap();
+        chain.after_parse(&context, &parsed).await.unwrap();
+        chain.on_error(&context, &SoapError::HttpStatus(503)).await;
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "serialize a",
+                "serialize b",
+                "send a",
+                "send b",
+                "receive b",
+                "receive a",
+                "parse b",
+                "parse a",
+                "error b",
+                "error a"
+            ]
+        );
+        // set_header replaces case-insensitively
+        assert_eq!(request.http_headers.len(), 1);
+        assert_eq!(request.header("X-TRACE"), Some("b"));
+    }
+}
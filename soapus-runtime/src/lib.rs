@@ -6,12 +6,28 @@
 //! ## Features
 //!
 //! - **SOAP 1.1 & 1.2** - Full support for both SOAP versions
-//! - **Async/Await** - Built on `tokio` and `reqwest` for modern async Rust
+//! - **Async/Await** - Executor-agnostic: runs on tokio, smol, async-std or any other executor
+//! - **Pluggable Transports** - HTTP via `reqwest` by default, a tokio-free `std` HTTP transport, custom or in-memory transports
 //! - **Type-Safe** - Generic over request/response types with serde
 //! - **Envelope Building** - Automatic SOAP envelope construction with namespaces
 //! - **Error Handling** - Comprehensive error types for all failure modes
-//! - **SOAP Fault Detection** - Automatic parsing and handling of SOAP faults
+//! - **SOAP Fault Detection** - Structured SOAP 1.1 and 1.2 faults (codes, subcodes, reasons, detail)
+//! - **SOAP Headers** - Persistent and per-call header blocks, response headers alongside the body
+//! - **Interceptors** - Hooks before serialization, before sending, after receiving and on errors
+//! - **Retries** - Exponential backoff with jitter, idempotency-aware retry policies
+//! - **Circuit Breaker** - Fail fast on unhealthy endpoints, optional limit of requests in flight
+//! - **Batches** - Many calls with bounded concurrency and a rate limit, results in item order
+//! - **HTTP Authentication** - Basic, Digest, NTLMv2 and refreshable bearer tokens
+//! - **WS-Security** - UsernameToken (text or digest), timestamps, X.509 signatures and XML encryption, optional
+//! - **WS-Addressing** - Action, To, MessageID, ReplyTo/FaultTo headers and `RelatesTo` correlation, optional
+//! - **MTOM/XOP** - Binary content as `multipart/related` attachments in requests and responses, optional
+//! - **SOAP with Attachments** - MIME attachments alongside the envelope, `swaRef` references, optional
+//! - **Streaming** - Large binary content streamed from and to `AsyncRead`s without buffering envelopes, optional
+//! - **HTTP Compression** - Transparent gzip/deflate/br response decompression and gzip request compression, optional
 //! - **Configurable** - Builder pattern for timeouts, custom HTTP clients, etc.
+//! - **Per-call Options** - Timeout, endpoint, HTTP and SOAP headers, correlation ID and cancellation of single calls
+//! - **Sessions** - Opt-in cookie jar for session-based services, session reset, export and import
+//! - **Blocking Calls** - Calls from synchronous code without an async runtime, optional
 //! - **Observability** - Optional tracing and metrics support
 //!
 //! ## Basic Usage
@@ -42,7 +58,7 @@
 //!         .call_with_soap_action(
 //!             "MyOperation",
 //!             Some("http://example.com/MyOperation"),
-//!             Some("http://tempuri.org/"),
+//!             Some("http://tempuri.org/"),
 //!             true,
 //!             &MyRequest { field: "value".to_string() },
 //!         )
@@ -68,22 +84,93 @@
 //! ## Features
 //!
 //! - `tracing` (default) - Structured logging and distributed tracing support
+//! - `reqwest` (default) - HTTP transport based on `reqwest` (implies `tokio`)
+//! - `tokio` - tokio timers inside a tokio runtime; required by `reqwest`, `streaming` and `blocking`
+//! - `std-http` - HTTP/1.1 transport on `std::net` without tokio, for any executor
 //! - `opentelemetry` - OpenTelemetry/Jaeger integration for distributed tracing
 //! - `metrics` - Prometheus metrics collection
+//! - `auth` - HTTP Basic, Digest, NTLM and bearer authentication
+//! - `ws-security` - WS-Security UsernameToken, Timestamp, X.509 signatures and XML encryption
+//! - `ws-addressing` - WS-Addressing 1.0 headers and reply correlation
+//! - `mtom` - MTOM/XOP attachments for binary content
+//! - `swa` - SOAP with Attachments (implies `mtom`)
+//! - `streaming` - Streamed requests and responses for large binary content (implies `mtom`)
+//! - `compression` - gzip/deflate/br response decompression and gzip request compression
+//! - `blocking` - Blocking calls without an async runtime, used by generated blocking clients
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(rustdoc::broken_intra_doc_links)]
 // Note: missing_docs is intentionally not enabled for internal structures
 
+#[cfg(feature = "ws-addressing")]
+pub mod addressing;
+#[cfg(feature = "auth")]
+pub mod auth;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "ws-security")]
+pub mod c14n;
+pub mod circuit_breaker;
 pub mod client;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "ws-security")]
+pub mod dsig;
 pub mod envelope;
 pub mod error;
+mod executor;
+pub mod fault;
+pub mod header;
+pub mod interceptor;
+mod mime;
+#[cfg(feature = "mtom")]
+pub mod mtom;
+pub mod options;
+mod random;
+pub mod retry;
+pub mod session;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+#[cfg(feature = "swa")]
+pub mod swa;
+pub mod transport;
+#[cfg(feature = "ws-security")]
+pub mod ws_security;
+#[cfg(feature = "ws-security")]
+pub mod xmlenc;
 
+#[cfg(feature = "ws-addressing")]
+pub use addressing::{RelatesTo, WsAddressing};
+#[cfg(feature = "auth")]
+pub use auth::{HttpAuth, TokenProvider};
+pub use batch::Batch;
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
 pub use client::SoapClient;
-pub use envelope::{SoapEnvelope, SoapVersion};
-pub use error::{SoapError, SoapResult};
+#[cfg(feature = "ws-security")]
+pub use dsig::{X509Certificate, X509Credential};
+pub use envelope::{BodyPart, ResponseParts, SoapEnvelope, SoapVersion};
+pub use error::{Rejection, SoapError, SoapResult};
+pub use fault::{FaultReason, SoapFault};
+pub use header::{HttpMetadata, SoapHeader, SoapHeaders, SoapResponse};
+pub use interceptor::{Interceptor, ParsedResponse, RawResponse, RequestContext, SoapRequest};
+#[cfg(feature = "swa")]
+pub use mtom::Attachment;
+#[cfg(feature = "mtom")]
+pub use mtom::Binary;
+pub use options::{CallOptions, CancellationToken};
+pub use retry::RetryPolicy;
+pub use session::{Cookie, CookieJar};
+#[cfg(feature = "streaming")]
+pub use streaming::BinaryStream;
+#[cfg(feature = "std-http")]
+pub use transport::StdHttpTransport;
+pub use transport::{SoapConnection, SoapTransport};
+#[cfg(feature = "ws-security")]
+pub use ws_security::{PasswordType, UsernameToken, WsSecurity};
 
 // Re-export commonly used types
+pub use async_trait::async_trait;
 pub use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
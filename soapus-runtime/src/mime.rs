@@ -0,0 +1,162 @@
+//! Content types and character sets of HTTP bodies
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// The media type of a `Content-Type` value, without parameters
+#[cfg(feature = "mtom")]
+pub(crate) fn media_type(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+/// A parameter of a `Content-Type` value, unquoted
+pub(crate) fn parameter(content_type: &str, name: &str) -> Option<String> {
+    let mut rest = content_type.split_once(';')?.1;
+    loop {
+        let (key, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (
+                    &quoted[..end],
+                    quoted[end + 1..].split_once(';').map(|(_, n)| n),
+                )
+            }
+            None => match value.split_once(';') {
+                Some((value, next)) => (value.trim(), Some(next)),
+                None => (value.trim(), None),
+            },
+        };
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value.to_string());
+        }
+        rest = next?;
+    }
+}
+
+/// Decode an XML body to text
+///
+/// The encoding is taken from a byte order mark, the `charset` of the
+/// `Content-Type`, the encoding of the XML declaration, in that order, and
+/// defaults to UTF-8. Malformed sequences are replaced with U+FFFD.
+pub(crate) fn decode_xml(content_type: Option<&str>, body: &[u8]) -> String {
+    let (encoding, bom_length) = Encoding::for_bom(body)
+        .or_else(|| {
+            content_type
+                .and_then(|content_type| parameter(content_type, "charset"))
+                .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+                .or_else(|| declared_encoding(body))
+                .map(|encoding| (encoding, 0))
+        })
+        .unwrap_or((UTF_8, 0));
+
+    encoding
+        .decode_without_bom_handling(&body[bom_length..])
+        .0
+        .into_owned()
+}
+
+/// The encoding of an XML declaration, e.g. `<?xml version="1.0" encoding="ISO-8859-1"?>`
+///
+/// UTF-16 without byte order mark is recognized by the `<?` of the declaration.
+fn declared_encoding(body: &[u8]) -> Option<&'static Encoding> {
+    if body.starts_with(b"<\0?\0") {
+        return Some(UTF_16LE);
+    }
+    if body.starts_with(b"\0<\0?") {
+        return Some(UTF_16BE);
+    }
+
+    let declaration = body.strip_prefix(b"<?xml")?;
+    let declaration = &declaration[..declaration.windows(2).position(|w| w == b"?>")?];
+    let declaration = std::str::from_utf8(declaration).ok()?;
+    let value = declaration.split_once("encoding")?.1.trim_start();
+    let value = value.strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let label = value[1..].split(quote).next()?;
+
+    match Encoding::for_label(label.as_bytes())? {
+        // The declaration was readable as ASCII, so the body isn't UTF-16
+        encoding if encoding == UTF_16LE || encoding == UTF_16BE => None,
+        encoding => Some(encoding),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "mtom")]
+    fn test_parameter() {
+        let content_type =
+            "multipart/related; type=\"application/xop+xml\";boundary=b1; start-info=\"a;b\"";
+        assert_eq!(media_type(content_type), "multipart/related");
+        assert_eq!(parameter(content_type, "boundary").as_deref(), Some("b1"));
+        assert_eq!(
+            parameter(content_type, "start-info").as_deref(),
+            Some("a;b")
+        );
+        assert_eq!(
+            parameter(content_type, "Type").as_deref(),
+            Some("application/xop+xml")
+        );
+        assert_eq!(parameter(content_type, "start"), None);
+        assert_eq!(parameter("text/xml", "charset"), None);
+    }
+
+    #[test]
+    fn test_decode_xml_charset() {
+        // "Müller" in ISO-8859-1
+        let body = b"<name>M\xfcller</name>";
+        assert_eq!(
+            decode_xml(Some("text/xml; charset=ISO-8859-1"), body),
+            "<name>Müller</name>"
+        );
+        assert_eq!(
+            decode_xml(Some("text/xml; charset=\"iso-8859-1\""), body),
+            "<name>Müller</name>"
+        );
+        // Without a charset, UTF-8 is assumed
+        assert_eq!(
+            decode_xml(Some("text/xml"), body),
+            "<name>M\u{fffd}ller</name>"
+        );
+        assert_eq!(
+            decode_xml(None, "<name>Müller</name>".as_bytes()),
+            "<name>Müller</name>"
+        );
+    }
+
+    #[test]
+    fn test_decode_xml_declaration() {
+        let body = b"<?xml version=\"1.0\" encoding='ISO-8859-1'?><name>M\xfcller</name>";
+        assert_eq!(
+            decode_xml(Some("text/xml"), body),
+            "<?xml version=\"1.0\" encoding='ISO-8859-1'?><name>Müller</name>"
+        );
+        // The Content-Type takes precedence
+        assert!(decode_xml(Some("text/xml; charset=utf-8"), body).contains('\u{fffd}'));
+    }
+
+    #[test]
+    fn test_decode_xml_utf16() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-16\"?><name>Müller</name>";
+        let little_endian: Vec<u8> = xml.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let big_endian: Vec<u8> = xml.encode_utf16().flat_map(u16::to_be_bytes).collect();
+
+        assert_eq!(decode_xml(None, &little_endian), xml);
+        assert_eq!(decode_xml(Some("text/xml"), &big_endian), xml);
+        assert_eq!(
+            decode_xml(None, &[&[0xff, 0xfe][..], &little_endian].concat()),
+            xml
+        );
+        assert_eq!(
+            decode_xml(
+                Some("text/xml; charset=utf-16"),
+                &[&[0xef, 0xbb, 0xbf][..], xml.as_bytes()].concat()
+            ),
+            xml
+        );
+    }
+}
@@ -0,0 +1,839 @@
+//! MTOM/XOP attachments
+//!
+//! With MTOM (SOAP Message Transmission Optimization Mechanism) binary content
+//! travels as raw MIME parts of a `multipart/related` message instead of
+//! base64 text inside the envelope. The envelope references each part with an
+//! `xop:Include` element.
+//!
+//! Binary fields use [`Binary`]. When MTOM is enabled with
+//! [`SoapClientBuilder::mtom`](crate::client::SoapClientBuilder::mtom), every
+//! `Binary` of a request is sent as an attachment; otherwise it is written as
+//! base64 text. MTOM responses are recognized by their `multipart/related`
+//! content type, whether MTOM is enabled or not, and their `xop:Include`
+//! references are resolved into the bytes of the referenced parts.
+//!
+//! Requires the `mtom` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use serde::{Deserialize, Serialize};
+//! use soapus_runtime::{Binary, SoapClient};
+//!
+//! #[derive(Serialize)]
+//! struct StoreDocument {
+//!     name: String,
+//!     content: Binary,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct StoreDocumentResponse {
+//!     id: String,
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SoapClient::builder("http://example.com/archive")
+//!     .mtom(true)
+//!     .build();
+//!
+//! let request = StoreDocument {
+//!     name: "scan.pdf".to_string(),
+//!     content: Binary::new(std::fs::read("scan.pdf")?),
+//! };
+//! let response: StoreDocumentResponse = client.call("StoreDocument", &request).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use crate::mime::{self, media_type, parameter};
+use crate::random::{hex, random_bytes};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
+use quick_xml::Reader;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Namespace of XOP `Include` elements
+pub const XOP_NS: &str = "http://www.w3.org/2004/08/xop/include";
+
+/// Content-ID of the root part holding the envelope
+pub(crate) const ROOT_CONTENT_ID: &str = "root.message@soapus";
+
+/// Binary content of an `xs:base64Binary` element
+///
+/// Serialized as base64 text, or as MTOM attachment when the client has MTOM
+/// enabled. Deserializes from base64 text as well as from resolved
+/// `xop:Include` references.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Binary(Vec<u8>);
+
+impl Binary {
+    /// Wrap binary content
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self(data.into())
+    }
+
+    /// Get the content
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Take the content
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for Binary {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Binary {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Binary {
+    fn from(data: Vec<u8>) -> Self {
+        Self(data)
+    }
+}
+
+impl From<&[u8]> for Binary {
+    fn from(data: &[u8]) -> Self {
+        Self(data.to_vec())
+    }
+}
+
+impl From<Binary> for Vec<u8> {
+    fn from(binary: Binary) -> Self {
+        binary.0
+    }
+}
+
+impl Serialize for Binary {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let content_id = OUTGOING.with(|outgoing| {
+            outgoing.borrow_mut().as_mut().map(|outgoing| {
+                let content_id = format!("{}.{}", outgoing.attachments.len() + 1, outgoing.suffix);
+                outgoing.attachments.push(Attachment {
+                    content_id: content_id.clone(),
+                    content_type: "application/octet-stream".to_string(),
+                    data: self.0.clone(),
+                });
+                content_id
+            })
+        });
+
+        match content_id {
+            // Replaced by an xop:Include element once the envelope is complete
+            Some(content_id) => serializer.serialize_str(&format!("cid:{}", content_id)),
+            None => serializer.serialize_str(&BASE64.encode(&self.0)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Binary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BinaryVisitor;
+
+        impl Visitor<'_> for BinaryVisitor {
+            type Value = Binary;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("base64 text or an MTOM attachment reference")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Binary, E> {
+                let value = value.trim();
+                // Base64 text never contains ':'
+                if let Some(content_id) = value.strip_prefix("cid:") {
+                    return INCOMING
+                        .with(|incoming| incoming.borrow_mut().remove(content_id))
+                        .map(Binary)
+                        .ok_or_else(|| {
+                            E::custom(format!("Unknown MTOM attachment 'cid:{}'", content_id))
+                        });
+                }
+
+                let text: String = value.split_ascii_whitespace().collect();
+                BASE64
+                    .decode(text)
+                    .map(Binary)
+                    .map_err(|e| E::custom(format!("Invalid base64 content: {}", e)))
+            }
+        }
+
+        deserializer.deserialize_str(BinaryVisitor)
+    }
+}
+
+/// A MIME part of a multipart/related message
+///
+/// With the `swa` feature, attachments are sent alongside a call with
+/// [`CallOptions::attachment`](crate::options::CallOptions::attachment)
+/// and the attachments of a response are available from the
+/// [`SoapResponse`](crate::header::SoapResponse).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub(crate) content_id: String,
+    pub(crate) content_type: String,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Create an attachment with the given Content-ID (without angle brackets)
+    pub fn new(
+        content_id: impl Into<String>,
+        content_type: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            content_id: content_id.into(),
+            content_type: content_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Create an attachment for a message part of a `mime:multipartRelated` binding
+    ///
+    /// The Content-ID is `<part>=<unique id>`, as required by the WS-I
+    /// Attachments Profile.
+    pub fn for_part(part: &str, content_type: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        Self::new(format!("{}={}@soapus", part, id), content_type, data)
+    }
+
+    /// Get the Content-ID, without angle brackets
+    pub fn content_id(&self) -> &str {
+        &self.content_id
+    }
+
+    /// Get the Content-Type
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    /// Get the content
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Take the content
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Get the message part name of a Content-ID of the form `<part>=<id>`
+    pub fn part_name(&self) -> Option<&str> {
+        self.content_id.split_once('=').map(|(part, _)| part)
+    }
+
+    /// Get the `cid:` URL referring to this attachment, e.g. for `swaRef` elements
+    pub fn reference(&self) -> String {
+        format!("cid:{}", percent_encode(&self.content_id))
+    }
+
+    /// Check whether a `cid:` URL or plain Content-ID refers to this attachment
+    pub fn matches(&self, reference: &str) -> bool {
+        match reference.strip_prefix("cid:") {
+            Some(url) => percent_decode(url) == self.content_id,
+            None => strip_angle_brackets(reference) == self.content_id,
+        }
+    }
+}
+
+/// Attachments collected while a request is serialized
+struct Outgoing {
+    suffix: String,
+    attachments: Vec<Attachment>,
+}
+
+thread_local! {
+    static OUTGOING: RefCell<Option<Outgoing>> = const { RefCell::new(None) };
+    static INCOMING: RefCell<HashMap<String, Vec<u8>>> = RefCell::new(HashMap::new());
+}
+
+/// Clears the collected attachments, also when serialization panics
+struct OutgoingGuard;
+
+impl Drop for OutgoingGuard {
+    fn drop(&mut self) {
+        OUTGOING.with(|outgoing| outgoing.borrow_mut().take());
+    }
+}
+
+/// Clears the received attachments once the response is parsed
+struct IncomingGuard;
+
+impl Drop for IncomingGuard {
+    fn drop(&mut self) {
+        INCOMING.with(|incoming| incoming.borrow_mut().clear());
+    }
+}
+
+/// Build an envelope, moving the content of every [`Binary`] into an attachment
+///
+/// Each attachment is referenced from the envelope with an `xop:Include`
+/// element. Binary content that isn't element content (e.g. an attribute)
+/// stays inline as base64 text.
+pub(crate) fn collect(
+    build_envelope: impl FnOnce() -> SoapResult<String>,
+) -> SoapResult<(String, Vec<Attachment>)> {
+    let suffix = format!("{}@soapus", hex(&random_bytes::<16>()?));
+    let _guard = OutgoingGuard;
+    OUTGOING.with(|outgoing| {
+        *outgoing.borrow_mut() = Some(Outgoing {
+            suffix,
+            attachments: Vec::new(),
+        })
+    });
+
+    let mut envelope = build_envelope()?;
+    let collected = OUTGOING
+        .with(|outgoing| outgoing.borrow_mut().take())
+        .map(|outgoing| outgoing.attachments)
+        .unwrap_or_default();
+
+    let mut attachments = Vec::with_capacity(collected.len());
+    for attachment in collected {
+        let reference = format!(">cid:{}<", attachment.content_id);
+        if envelope.contains(&reference) {
+            envelope = envelope.replacen(
+                &reference,
+                &format!(
+                    "><xop:Include xmlns:xop=\"{}\" href=\"cid:{}\"/><",
+                    XOP_NS, attachment.content_id
+                ),
+                1,
+            );
+            attachments.push(attachment);
+        } else {
+            envelope = envelope.replacen(
+                &format!("cid:{}", attachment.content_id),
+                &BASE64.encode(&attachment.data),
+                1,
+            );
+        }
+    }
+
+    Ok((envelope, attachments))
+}
+
+/// Parse a response with its attachments available to [`Binary`]
+pub(crate) fn resolve<T>(attachments: Vec<Attachment>, parse: impl FnOnce() -> T) -> T {
+    if attachments.is_empty() {
+        return parse();
+    }
+
+    let _guard = IncomingGuard;
+    INCOMING.with(|incoming| {
+        incoming.borrow_mut().extend(
+            attachments
+                .into_iter()
+                .map(|attachment| (attachment.content_id, attachment.data)),
+        )
+    });
+    parse()
+}
+
+/// Replace the attachment references of a response by base64 text
+///
+/// Used for responses that are kept as XML, e.g. split into body parts, while
+/// the attachments are available in [`resolve`].
+pub(crate) fn inline(xml: &str) -> String {
+    INCOMING.with(|incoming| {
+        let mut xml = xml.to_string();
+        for (content_id, data) in incoming.borrow_mut().drain() {
+            xml = xml.replace(
+                &format!(">cid:{}<", escape(content_id.as_str())),
+                &format!(">{}<", BASE64.encode(data)),
+            );
+        }
+        xml
+    })
+}
+
+/// Check whether a `Content-Type` is that of a multipart/related message
+pub(crate) fn is_multipart(content_type: &str) -> bool {
+    media_type(content_type).eq_ignore_ascii_case("multipart/related")
+}
+
+/// Check whether a multipart/related `Content-Type` is that of a XOP package
+pub(crate) fn is_xop(content_type: &str) -> bool {
+    parameter(content_type, "type")
+        .is_some_and(|media_type| media_type.eq_ignore_ascii_case("application/xop+xml"))
+}
+
+/// How the envelope is carried in the root part of a multipart/related message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Root {
+    /// As `application/xop+xml`, for MTOM
+    Xop,
+    /// With the `Content-Type` of the plain envelope, for SOAP with Attachments
+    #[cfg_attr(not(feature = "swa"), allow(dead_code))]
+    Plain,
+}
+
+/// Package an envelope and its attachments as multipart/related message
+///
+/// `content_type` is the `Content-Type` of the plain envelope. Returns the
+/// `Content-Type` of the message and its body.
+pub(crate) fn encode(
+    envelope: &str,
+    content_type: &str,
+    root: Root,
+    attachments: &[Attachment],
+) -> SoapResult<(String, Vec<u8>)> {
+    let (message_type, boundary) = message_type(content_type, root)?;
+
+    let size = envelope.len()
+        + attachments
+            .iter()
+            .map(|a| a.data.len() + 200)
+            .sum::<usize>();
+    let mut body = Vec::with_capacity(size + 400);
+    body.extend_from_slice(root_header(&boundary, content_type, root).as_bytes());
+    body.extend_from_slice(envelope.as_bytes());
+    for attachment in attachments {
+        body.extend_from_slice(
+            part_header(&boundary, &attachment.content_type, &attachment.content_id).as_bytes(),
+        );
+        body.extend_from_slice(&attachment.data);
+    }
+    body.extend_from_slice(closing_delimiter(&boundary).as_bytes());
+
+    Ok((message_type, body))
+}
+
+/// The `Content-Type` of a multipart/related message with a new boundary, and the boundary
+///
+/// `content_type` is the `Content-Type` of the plain envelope.
+pub(crate) fn message_type(content_type: &str, root: Root) -> SoapResult<(String, String)> {
+    let boundary = format!("MIMEBoundary_{}", hex(&random_bytes::<16>()?));
+
+    let message_type = match root {
+        Root::Xop => {
+            let mut message_type = format!(
+                "multipart/related; type=\"application/xop+xml\"; boundary=\"{}\"; start=\"<{}>\"; start-info=\"{}\"",
+                boundary,
+                ROOT_CONTENT_ID,
+                media_type(content_type)
+            );
+            if let Some(action) = parameter(content_type, "action") {
+                message_type.push_str(&format!("; action=\"{}\"", action));
+            }
+            message_type
+        }
+        Root::Plain => format!(
+            "multipart/related; type=\"{}\"; boundary=\"{}\"; start=\"<{}>\"",
+            media_type(content_type),
+            boundary,
+            ROOT_CONTENT_ID
+        ),
+    };
+
+    Ok((message_type, boundary))
+}
+
+/// The delimiter and headers of the root part, followed by the envelope
+pub(crate) fn root_header(boundary: &str, content_type: &str, root: Root) -> String {
+    let root_type = match root {
+        Root::Xop => format!(
+            "application/xop+xml; charset=UTF-8; type=\"{}\"",
+            media_type(content_type)
+        ),
+        Root::Plain => content_type.to_string(),
+    };
+    format!(
+        "--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: 8bit\r\nContent-ID: <{}>\r\n\r\n",
+        boundary, root_type, ROOT_CONTENT_ID
+    )
+}
+
+/// The delimiter and headers of an attachment part, followed by its content
+pub(crate) fn part_header(boundary: &str, content_type: &str, content_id: &str) -> String {
+    format!(
+        "\r\n--{}\r\nContent-Type: {}\r\nContent-Transfer-Encoding: binary\r\nContent-ID: <{}>\r\n\r\n",
+        boundary, content_type, content_id
+    )
+}
+
+/// The delimiter ending the message
+pub(crate) fn closing_delimiter(boundary: &str) -> String {
+    format!("\r\n--{}--\r\n", boundary)
+}
+
+/// Split a multipart/related response into its envelope and attachments
+///
+/// `xop:Include` elements in the envelope are replaced by `cid:` references
+/// that [`Binary`] resolves within [`resolve`].
+pub(crate) fn decode(content_type: &str, body: &[u8]) -> SoapResult<(String, Vec<Attachment>)> {
+    let boundary =
+        parameter(content_type, "boundary").ok_or_else(|| invalid("missing boundary parameter"))?;
+    let start = parameter(content_type, "start").map(|id| strip_angle_brackets(&id).to_string());
+
+    let mut parts = split_parts(body, boundary.as_bytes())?;
+    let root = match start {
+        Some(start) => parts
+            .iter()
+            .position(|part| part.content_id == start)
+            .ok_or_else(|| invalid(&format!("no part with Content-ID <{}>", start)))?,
+        None => 0,
+    };
+    let root = parts.remove(root);
+    let envelope = mime::decode_xml(Some(&root.content_type), &root.data);
+
+    Ok((replace_includes(&envelope)?, parts))
+}
+
+/// Split a multipart body at `--boundary` delimiters
+fn split_parts(body: &[u8], boundary: &[u8]) -> SoapResult<Vec<Attachment>> {
+    let mut delimiter = b"--".to_vec();
+    delimiter.extend_from_slice(boundary);
+
+    let mut position = find(body, &delimiter, 0).ok_or_else(|| invalid("missing boundary"))?;
+    let mut close_delimiter = b"\r\n".to_vec();
+    close_delimiter.extend_from_slice(&delimiter);
+
+    let mut parts = Vec::new();
+    loop {
+        let after = position + delimiter.len();
+        if body[after..].starts_with(b"--") {
+            break;
+        }
+        let start = find(body, b"\r\n", after).ok_or_else(|| invalid("truncated part"))? + 2;
+        let end = find(body, &close_delimiter, start)
+            .ok_or_else(|| invalid("missing closing boundary"))?;
+        parts.push(parse_part(&body[start..end])?);
+        position = end + 2;
+    }
+
+    if parts.is_empty() {
+        return Err(invalid("no parts"));
+    }
+    Ok(parts)
+}
+
+/// The headers of a MIME part
+pub(crate) struct PartHeaders {
+    pub(crate) content_id: String,
+    pub(crate) content_type: String,
+    pub(crate) base64_encoded: bool,
+}
+
+impl PartHeaders {
+    /// Parse header lines separated by CRLF
+    pub(crate) fn parse(headers: &[u8]) -> Self {
+        let mut parsed = Self {
+            content_id: String::new(),
+            content_type: "application/octet-stream".to_string(),
+            base64_encoded: false,
+        };
+        for line in String::from_utf8_lossy(headers).split("\r\n") {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-id" => parsed.content_id = strip_angle_brackets(value).to_string(),
+                "content-type" => parsed.content_type = value.to_string(),
+                "content-transfer-encoding" => {
+                    parsed.base64_encoded = value.eq_ignore_ascii_case("base64")
+                }
+                _ => {}
+            }
+        }
+        parsed
+    }
+}
+
+/// Parse the headers and content of a MIME part
+fn parse_part(part: &[u8]) -> SoapResult<Attachment> {
+    let (headers, content) = match find(part, b"\r\n\r\n", 0) {
+        Some(end) => (&part[..end], &part[end + 4..]),
+        // A part without headers
+        None if part.starts_with(b"\r\n") => (&part[..0], &part[2..]),
+        None => return Err(invalid("part without header end")),
+    };
+
+    let PartHeaders {
+        content_id,
+        content_type,
+        base64_encoded,
+    } = PartHeaders::parse(headers);
+
+    let data = if base64_encoded {
+        let text: Vec<u8> = content
+            .iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        BASE64
+            .decode(text)
+            .map_err(|e| invalid(&format!("invalid base64 part: {}", e)))?
+    } else {
+        content.to_vec()
+    };
+
+    Ok(Attachment {
+        content_id,
+        content_type,
+        data,
+    })
+}
+
+/// Replace `xop:Include` elements by the `cid:` reference of their `href`
+pub(crate) fn replace_includes(envelope: &str) -> SoapResult<String> {
+    let mut reader = Reader::from_str(envelope);
+    let mut output = String::with_capacity(envelope.len());
+    let mut copied = 0;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let (href, end_tag) = match reader.read_event().map_err(xml_error)? {
+            Event::Empty(e) if e.local_name().as_ref() == b"Include" => (href(&e)?, None),
+            Event::Start(e) if e.local_name().as_ref() == b"Include" => {
+                (href(&e)?, Some(e.name().as_ref().to_vec()))
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        if let Some(end_tag) = end_tag {
+            reader.read_to_end(QName(&end_tag)).map_err(xml_error)?;
+        }
+        let Some(href) = href else {
+            continue;
+        };
+        let end = reader.buffer_position() as usize;
+
+        output.push_str(&envelope[copied..start]);
+        output.push_str(&escape(format!("cid:{}", href).as_str()));
+        copied = end;
+    }
+    output.push_str(&envelope[copied..]);
+    Ok(output)
+}
+
+/// The percent-decoded Content-ID of an `href="cid:..."` attribute
+fn href(e: &BytesStart) -> SoapResult<Option<String>> {
+    for attr in e.attributes().with_checks(false) {
+        let attr = attr.map_err(xml_error)?;
+        if attr.key.as_ref() == b"href" {
+            let value = attr.unescape_value().map_err(xml_error)?;
+            return Ok(value.strip_prefix("cid:").map(percent_decode));
+        }
+    }
+    Ok(None)
+}
+
+/// Percent-encode a Content-ID for a `cid:` URL (RFC 2392)
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'*+=@".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub(crate) fn strip_angle_brackets(value: &str) -> &str {
+    value
+        .trim()
+        .strip_prefix('<')
+        .and_then(|v| v.strip_suffix('>'))
+        .unwrap_or(value.trim())
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+pub(crate) fn invalid(message: &str) -> SoapError {
+    SoapError::InvalidResponse(format!("Invalid MTOM response: {}", message))
+}
+
+fn xml_error(e: impl fmt::Display) -> SoapError {
+    SoapError::XmlError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Document {
+        name: String,
+        content: Binary,
+    }
+
+    fn document() -> Document {
+        Document {
+            name: "scan.pdf".to_string(),
+            content: Binary::new((0..=255).collect::<Vec<u8>>()),
+        }
+    }
+
+    #[test]
+    fn test_binary_base64() {
+        let xml = quick_xml::se::to_string(&Document {
+            name: "a".to_string(),
+            content: Binary::new(b"hello".to_vec()),
+        })
+        .unwrap();
+        assert_eq!(
+            xml,
+            "<Document><name>a</name><content>aGVsbG8=</content></Document>"
+        );
+
+        let document: Document = quick_xml::de::from_str(
+            "<Document><name>a</name><content>\n  aGVs\n  bG8=\n</content></Document>",
+        )
+        .unwrap();
+        assert_eq!(document.content.as_bytes(), b"hello");
+
+        let error = quick_xml::de::from_str::<Document>(
+            "<Document><name>a</name><content>cid:missing</content></Document>",
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown MTOM attachment 'cid:missing'"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (envelope, attachments) =
+            collect(|| Ok(quick_xml::se::to_string(&document()).unwrap())).unwrap();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].data, document().content.into_vec());
+        assert!(envelope.contains(&format!(
+            "<content><xop:Include xmlns:xop=\"{}\" href=\"cid:{}\"/></content>",
+            XOP_NS, attachments[0].content_id
+        )));
+
+        let (content_type, body) = encode(
+            &envelope,
+            "application/soap+xml; charset=utf-8; action=\"urn:Store\"",
+            Root::Xop,
+            &attachments,
+        )
+        .unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"application/xop+xml\""));
+        assert!(content_type.contains("start-info=\"application/soap+xml\""));
+        assert!(content_type.ends_with("; action=\"urn:Store\""));
+        assert!(find(&body, &attachments[0].data, 0).is_some());
+
+        let (received, parts) = decode(&content_type, &body).unwrap();
+        assert!(received.contains(&format!(
+            "<content>cid:{}</content>",
+            attachments[0].content_id
+        )));
+        let document: Document = resolve(parts, || quick_xml::de::from_str(&received)).unwrap();
+        assert_eq!(document, self::document());
+    }
+
+    #[test]
+    fn test_attribute_stays_inline() {
+        #[derive(Serialize)]
+        struct Thumbnail {
+            #[serde(rename = "@data")]
+            data: Binary,
+        }
+
+        let (envelope, attachments) = collect(|| {
+            Ok(quick_xml::se::to_string(&Thumbnail {
+                data: Binary::new(b"hello".to_vec()),
+            })
+            .unwrap())
+        })
+        .unwrap();
+        assert!(attachments.is_empty());
+        assert_eq!(envelope, "<Thumbnail data=\"aGVsbG8=\"/>");
+    }
+
+    #[test]
+    fn test_decode_foreign_message() {
+        let body = concat!(
+            "preamble\r\n",
+            "--uuid:0ca0e16e\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "Content-ID: <doc%201@example.org>\r\n",
+            "\r\n",
+            "aGVs\r\nbG8=\r\n",
+            "--uuid:0ca0e16e\r\n",
+            "Content-Type: application/xop+xml; charset=UTF-8; type=\"text/xml\"\r\n",
+            "Content-ID: <root.message@cxf.apache.org>\r\n",
+            "\r\n",
+            "<Document><name>a</name><content><inc:Include xmlns:inc=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:doc%25201%40example.org\"></inc:Include></content></Document>\r\n",
+            "--uuid:0ca0e16e--\r\n",
+        );
+        let content_type = "Multipart/Related; boundary=\"uuid:0ca0e16e\"; type=\"application/xop+xml\"; start=\"<root.message@cxf.apache.org>\"; start-info=\"text/xml\"";
+        assert!(is_multipart(content_type));
+
+        let (envelope, parts) = decode(content_type, body.as_bytes()).unwrap();
+        assert_eq!(
+            envelope,
+            "<Document><name>a</name><content>cid:doc%201@example.org</content></Document>"
+        );
+        assert_eq!(parts[0].content_id, "doc%201@example.org");
+
+        // Parts kept as XML get the content inlined
+        let inlined = resolve(parts.clone(), || inline(&envelope));
+        assert!(inlined.contains("<content>aGVsbG8=</content>"));
+
+        let document: Document = resolve(parts, || quick_xml::de::from_str(&envelope)).unwrap();
+        assert_eq!(document.content.as_bytes(), b"hello");
+
+        let error = decode("multipart/related", body.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("missing boundary parameter"));
+        let error = decode("multipart/related; boundary=other", body.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("missing boundary"));
+    }
+}
@@ -0,0 +1,347 @@
+//! Per-call options
+//!
+//! [`CallOptions`] change a single call without touching the shared
+//! [`SoapClient`](crate::SoapClient): timeout, endpoint, extra HTTP and SOAP
+//! headers, a correlation ID, cancellation and (with the `swa` feature) MIME
+//! attachments. They are passed to
+//! [`SoapClient::call_with_options`](crate::client::SoapClient::call_with_options),
+//! [`SoapClient::call_parts_with_options`](crate::client::SoapClient::call_parts_with_options),
+//! `SoapClient::call_streaming` and the `_with_options` methods of generated
+//! clients.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{CallOptions, CancellationToken, SoapClient};
+//! use std::time::Duration;
+//!
+//! # #[derive(serde::Serialize)] struct GetReport {}
+//! # #[derive(serde::Deserialize)] struct GetReportResponse {}
+//! # async fn example(client: std::sync::Arc<SoapClient>) -> Result<(), Box<dyn std::error::Error>> {
+//! let cancel = CancellationToken::new();
+//! let options = CallOptions::new()
+//!     .timeout(Duration::from_secs(120))
+//!     .endpoint("https://reports.example.com/soap")
+//!     .http_header("X-Tenant", "acme")
+//!     .correlation_id("order-4711")
+//!     .cancellation(cancel.clone());
+//!
+//! let response = client
+//!     .call_with_options::<_, GetReportResponse>("GetReport", None, None, true, &GetReport {}, &options)
+//!     .await?;
+//! let report = response.into_body();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use crate::header::SoapHeader;
+#[cfg(feature = "swa")]
+use crate::mtom::Attachment;
+use futures_util::future::{select, Either};
+use std::fmt;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// HTTP header carrying the correlation ID of a call
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-ID";
+
+/// Options for a single call, overriding the client configuration
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) endpoint: Option<String>,
+    pub(crate) http_headers: Vec<(String, String)>,
+    pub(crate) soap_headers: Vec<SoapHeader>,
+    pub(crate) correlation_id: Option<String>,
+    pub(crate) cancellation: Option<CancellationToken>,
+    pub(crate) raw_body: bool,
+    #[cfg(feature = "swa")]
+    pub(crate) attachments: Vec<Attachment>,
+}
+
+impl CallOptions {
+    /// Create options that leave the client configuration unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the timeout of each attempt, instead of the client timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send the call to another endpoint URL
+    ///
+    /// The circuit breaker of the client is shared by all endpoints.
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Add an HTTP header, replacing a header of the same name set by the client
+    pub fn http_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.http_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a SOAP header block, sent after the persistent client headers
+    pub fn soap_header(mut self, header: SoapHeader) -> Self {
+        self.soap_headers.push(header);
+        self
+    }
+
+    /// Set the correlation ID of the call
+    ///
+    /// It is sent in the [`CORRELATION_ID_HEADER`] HTTP header, passed to
+    /// interceptors in [`RequestContext::correlation_id`](crate::RequestContext::correlation_id)
+    /// and logged with the call.
+    pub fn correlation_id(mut self, id: impl Into<String>) -> Self {
+        self.correlation_id = Some(id.into());
+        self
+    }
+
+    /// Abort the call with [`SoapError::Cancelled`] once `token` is cancelled
+    ///
+    /// Cancellation stops the call at any point, including retry delays. A
+    /// request already sent may still have been processed by the server.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Keep the HTTP body of the response, see [`HttpMetadata::raw_body`](crate::HttpMetadata::raw_body)
+    ///
+    /// Off by default, so the body is not held in memory twice.
+    pub fn raw_body(mut self, keep: bool) -> Self {
+        self.raw_body = keep;
+        self
+    }
+
+    /// Send a MIME attachment with the request (SOAP with Attachments)
+    ///
+    /// The envelope refers to it with [`Attachment::reference`], e.g. in
+    /// `swaRef` fields. See the [`swa`](crate::swa) module.
+    #[cfg(feature = "swa")]
+    pub fn attachment(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Send MIME attachments with the request, see [`CallOptions::attachment`]
+    #[cfg(feature = "swa")]
+    pub fn attachments(mut self, attachments: impl IntoIterator<Item = Attachment>) -> Self {
+        self.attachments.extend(attachments);
+        self
+    }
+
+    /// Run a call, aborting it if the call is cancelled
+    pub(crate) async fn cancellable<T>(
+        &self,
+        call: impl Future<Output = SoapResult<T>>,
+    ) -> SoapResult<T> {
+        let Some(token) = &self.cancellation else {
+            return call.await;
+        };
+
+        // The token goes first, so a cancelled call does not complete
+        match select(pin!(token.cancelled()), pin!(call)).await {
+            Either::Left(((), _)) => Err(SoapError::Cancelled),
+            Either::Right((result, _)) => result,
+        }
+    }
+}
+
+/// Signals cancellation to calls, shared by all clones
+///
+/// Works with any executor. Cancelling a token also cancels its child tokens,
+/// but not its parent.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    node: Arc<Node>,
+}
+
+#[derive(Default)]
+struct Node {
+    state: Mutex<TokenState>,
+}
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: bool,
+    /// Wakers of pending [`CancellationToken::cancelled`] futures by their ID
+    waiters: Vec<(u64, Waker)>,
+    children: Vec<Weak<Node>>,
+}
+
+impl Node {
+    fn lock(&self) -> MutexGuard<'_, TokenState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cancel(&self) {
+        let (waiters, children) = {
+            let mut state = self.lock();
+            if state.cancelled {
+                return;
+            }
+            state.cancelled = true;
+            (
+                std::mem::take(&mut state.waiters),
+                std::mem::take(&mut state.children),
+            )
+        };
+        for (_, waker) in waiters {
+            waker.wake();
+        }
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+    }
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a token that is cancelled together with this one
+    pub fn child_token(&self) -> Self {
+        let child = Self::new();
+        let mut state = self.node.lock();
+        if state.cancelled {
+            child.node.lock().cancelled = true;
+        } else {
+            state.children.retain(|node| node.strong_count() > 0);
+            state.children.push(Arc::downgrade(&child.node));
+        }
+        child
+    }
+
+    /// Cancel the token and its child tokens
+    pub fn cancel(&self) {
+        self.node.cancel();
+    }
+
+    /// Check whether the token is cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.node.lock().cancelled
+    }
+
+    /// Wait until the token is cancelled
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Cancelled {
+            token: self,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Future returned by [`CancellationToken::cancelled`]
+#[derive(Debug)]
+pub struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    id: u64,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.token.node.lock();
+        if state.cancelled {
+            return Poll::Ready(());
+        }
+        match state.waiters.iter_mut().find(|(id, _)| *id == self.id) {
+            Some((_, waker)) => waker.clone_from(cx.waker()),
+            None => state.waiters.push((self.id, cx.waker().clone())),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        self.token
+            .node
+            .lock()
+            .waiters
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancellable() {
+        let options = CallOptions::new();
+        assert_eq!(options.cancellable(async { Ok(1) }).await.unwrap(), 1);
+
+        let token = CancellationToken::new();
+        let options = CallOptions::new().cancellation(token.clone());
+        assert_eq!(options.cancellable(async { Ok(2) }).await.unwrap(), 2);
+
+        token.cancel();
+        let result = options
+            .cancellable(std::future::pending::<SoapResult<()>>())
+            .await;
+        assert!(matches!(result, Err(SoapError::Cancelled)));
+    }
+
+    #[test]
+    fn test_cancellation_token() {
+        let parent = CancellationToken::new();
+        let child = parent.child_token();
+        let grandchild = child.child_token();
+
+        child.cancel();
+        assert!(!parent.is_cancelled());
+        assert!(grandchild.is_cancelled());
+
+        parent.cancel();
+        assert!(parent.child_token().is_cancelled());
+    }
+
+    #[test]
+    fn test_cancelled_wakes_waiters() {
+        let token = CancellationToken::new();
+        let options = CallOptions::new().cancellation(token.clone());
+
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            token.cancel();
+        });
+        let result = futures_executor::block_on(
+            options.cancellable(std::future::pending::<SoapResult<()>>()),
+        );
+        assert!(matches!(result, Err(SoapError::Cancelled)));
+        canceller.join().unwrap();
+
+        // Dropped futures no longer wait
+        let token = CancellationToken::new();
+        {
+            let mut cancelled = pin!(token.cancelled());
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(token.node.lock().waiters.len(), 1);
+        }
+        assert!(token.node.lock().waiters.is_empty());
+    }
+}
@@ -0,0 +1,22 @@
+//! Random values for nonces, MIME boundaries and retry jitter
+
+use crate::error::{SoapError, SoapResult};
+
+/// Fill an array with random bytes from the operating system
+pub(crate) fn random_bytes<const N: usize>() -> SoapResult<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| SoapError::InvalidConfig(format!("No random numbers available: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Lowercase hexadecimal digits of `bytes`
+#[cfg(any(
+    feature = "auth",
+    feature = "mtom",
+    feature = "ws-addressing",
+    feature = "ws-security"
+))]
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,329 @@
+//! Retry policies
+//!
+//! A [`RetryPolicy`] set with
+//! [`SoapClientBuilder::retry_policy`](crate::client::SoapClientBuilder::retry_policy)
+//! resends failed requests with exponential backoff. Only sending is retried:
+//! the envelope is serialized once, the `before_send` and `after_receive`
+//! interceptor hooks run for every attempt and `on_error` only for the final
+//! error.
+//!
+//! SOAP operations are not idempotent in general, so requests that may have
+//! reached the server (timeouts, HTTP 5xx, SOAP faults) are only retried for
+//! operations marked as idempotent, see
+//! [`SoapClientBuilder::idempotent_operation`](crate::client::SoapClientBuilder::idempotent_operation).
+//! Connection errors ([`SoapError::ConnectError`] or a failed reqwest connect)
+//! are retried for every operation, the request was never sent in that case.
+//! Other transport errors are not retried.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{RetryPolicy, SoapClient};
+//! use std::time::Duration;
+//!
+//! let client = SoapClient::builder("http://example.com/soap")
+//!     .retry_policy(
+//!         RetryPolicy::new(4)
+//!             .backoff(Duration::from_millis(200), Duration::from_secs(5))
+//!             .retry_on_fault_code("Server.Busy")
+//!             .disable_for("Transfer"),
+//!     )
+//!     .idempotent_operation("GetBalance")
+//!     .build();
+//! ```
+
+use crate::error::SoapError;
+use crate::fault::local_name;
+use crate::random;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// When and how often failed calls are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_on_connect: bool,
+    retry_on_timeout: bool,
+    retry_on_server_error: bool,
+    fault_codes: Vec<String>,
+    retry_non_idempotent: bool,
+    disabled_operations: HashSet<String>,
+}
+
+impl RetryPolicy {
+    /// Create a policy making at most `max_attempts` attempts per call
+    ///
+    /// Retries connection errors, timeouts and HTTP 5xx responses, waiting
+    /// 100ms before the first retry and doubling the delay up to 10s, with jitter.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on_connect: true,
+            retry_on_timeout: true,
+            retry_on_server_error: true,
+            fault_codes: Vec::new(),
+            retry_non_idempotent: false,
+            disabled_operations: HashSet::new(),
+        }
+    }
+
+    /// Create a policy that never retries
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Set the maximum number of attempts, including the first one
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry and the upper limit of the delay
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the factor the delay grows by with every retry
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Randomize delays so clients failing together do not retry together
+    ///
+    /// With jitter a delay is picked between half and all of the backoff.
+    pub fn jitter(mut self, enable: bool) -> Self {
+        self.jitter = enable;
+        self
+    }
+
+    /// Retry requests that could not connect to the server
+    pub fn retry_on_connect(mut self, enable: bool) -> Self {
+        self.retry_on_connect = enable;
+        self
+    }
+
+    /// Retry requests that timed out ([`SoapError::Timeout`] or a reqwest timeout)
+    pub fn retry_on_timeout(mut self, enable: bool) -> Self {
+        self.retry_on_timeout = enable;
+        self
+    }
+
+    /// Retry responses with an HTTP 5xx status that are not SOAP faults
+    pub fn retry_on_server_error(mut self, enable: bool) -> Self {
+        self.retry_on_server_error = enable;
+        self
+    }
+
+    /// Retry SOAP faults with the given code or subcode
+    ///
+    /// Codes are compared without namespace prefix, e.g. `Server.Busy` matches
+    /// a `soap:Server.Busy` fault code.
+    pub fn retry_on_fault_code(mut self, code: impl Into<String>) -> Self {
+        self.fault_codes.push(code.into());
+        self
+    }
+
+    /// Also retry operations that are not marked as idempotent
+    ///
+    /// Only enable this if the service tolerates duplicate requests.
+    pub fn retry_non_idempotent(mut self, enable: bool) -> Self {
+        self.retry_non_idempotent = enable;
+        self
+    }
+
+    /// Never retry the given operation
+    pub fn disable_for(mut self, operation: impl Into<String>) -> Self {
+        self.disabled_operations.insert(operation.into());
+        self
+    }
+
+    /// Get the maximum number of attempts, including the first one
+    pub fn attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Get the delay before retry number `retry` (starting at 1), without jitter
+    pub fn backoff_for(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = self.initial_backoff.as_secs_f64() * factor;
+        if delay.is_finite() && delay < self.max_backoff.as_secs_f64() {
+            Duration::from_secs_f64(delay)
+        } else {
+            self.max_backoff
+        }
+    }
+
+    /// Get the delay to wait before retry number `retry`, including jitter
+    pub(crate) fn delay_for(&self, retry: u32) -> Duration {
+        let backoff = self.backoff_for(retry);
+        if !self.jitter {
+            return backoff;
+        }
+        match random_fraction() {
+            Some(fraction) => backoff / 2 + backoff.mul_f64(fraction / 2.0),
+            None => backoff,
+        }
+    }
+
+    /// Decide whether a failed attempt is retried
+    ///
+    /// `attempt` is the number of the failed attempt, starting at 1.
+    pub(crate) fn should_retry(
+        &self,
+        operation: &str,
+        idempotent: bool,
+        attempt: u32,
+        error: &SoapError,
+    ) -> bool {
+        if attempt >= self.max_attempts || self.disabled_operations.contains(operation) {
+            return false;
+        }
+
+        // The request was never sent, resending it is always safe
+        if is_connect_error(error) {
+            return self.retry_on_connect;
+        }
+
+        if !idempotent && !self.retry_non_idempotent {
+            return false;
+        }
+
+        match error {
+            SoapError::HttpStatus(status) => self.retry_on_server_error && *status >= 500,
+            SoapError::SoapFault(fault) => {
+                let matches = |code: &str| {
+                    self.fault_codes
+                        .iter()
+                        .any(|expected| expected == local_name(code))
+                };
+                matches(&fault.code) || fault.subcodes.iter().any(|code| matches(code))
+            }
+            SoapError::Timeout(_) => self.retry_on_timeout,
+            #[cfg(feature = "reqwest")]
+            SoapError::HttpError(e) if e.is_timeout() => self.retry_on_timeout,
+            _ => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts with the settings of [`RetryPolicy::new`]
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// Check whether the request failed before reaching the server
+fn is_connect_error(error: &SoapError) -> bool {
+    match error {
+        SoapError::ConnectError(_) => true,
+        #[cfg(feature = "reqwest")]
+        SoapError::HttpError(e) => e.is_connect(),
+        _ => false,
+    }
+}
+
+/// A random number in `[0, 1)`, if the system provides randomness
+fn random_fraction() -> Option<f64> {
+    let bytes = random::random_bytes::<8>().ok()?;
+    Some((u64::from_le_bytes(bytes) >> 11) as f64 / (1u64 << 53) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fault::SoapFault;
+
+    fn fault(code: &str, subcodes: &[&str]) -> SoapError {
+        SoapFault {
+            code: code.to_string(),
+            subcodes: subcodes.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(5)
+            .backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .jitter(false);
+
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(3), Duration::from_millis(350));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(200));
+
+        let jittered = RetryPolicy::new(5).delay_for(3);
+        assert!(jittered >= Duration::from_millis(200) && jittered <= Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new(3).retry_on_fault_code("Server.Busy");
+
+        assert!(policy.should_retry("Op", true, 1, &SoapError::HttpStatus(503)));
+        assert!(!policy.should_retry("Op", true, 3, &SoapError::HttpStatus(503)));
+        assert!(!policy.should_retry("Op", true, 1, &SoapError::HttpStatus(404)));
+        assert!(policy.should_retry("Op", true, 1, &fault("soap:Server.Busy", &[])));
+        assert!(policy.should_retry("Op", true, 1, &fault("env:Receiver", &["app:Server.Busy"])));
+        assert!(!policy.should_retry("Op", true, 1, &fault("soap:Server", &[])));
+        assert!(!policy.should_retry(
+            "Op",
+            true,
+            1,
+            &SoapError::DeserializationError("bad".to_string())
+        ));
+    }
+
+    #[test]
+    fn test_transport_errors() {
+        let policy = RetryPolicy::new(3);
+        let timeout = SoapError::Timeout("no response".to_string());
+        let connect = SoapError::ConnectError("connection refused".to_string());
+        let other = SoapError::TransportError("connection reset".to_string());
+
+        // The request may have been processed
+        assert!(policy.should_retry("Op", true, 1, &timeout));
+        assert!(!policy.should_retry("Op", false, 1, &timeout));
+        assert!(!policy
+            .clone()
+            .retry_on_timeout(false)
+            .should_retry("Op", true, 1, &timeout));
+        // The request was never sent
+        assert!(policy.should_retry("Op", false, 1, &connect));
+        assert!(!policy
+            .clone()
+            .retry_on_connect(false)
+            .should_retry("Op", false, 1, &connect));
+        assert!(!policy.should_retry("Op", true, 1, &other));
+        assert!(!policy.should_retry("Op", false, 1, &other));
+    }
+
+    #[test]
+    fn test_idempotency_and_opt_out() {
+        let error = SoapError::HttpStatus(503);
+        let policy = RetryPolicy::new(3).disable_for("Transfer");
+
+        assert!(!policy.should_retry("Op", false, 1, &error));
+        assert!(!policy.should_retry("Transfer", true, 1, &error));
+        assert!(policy
+            .clone()
+            .retry_non_idempotent(true)
+            .should_retry("Op", false, 1, &error));
+        assert!(!RetryPolicy::none().should_retry("Op", true, 1, &error));
+    }
+}
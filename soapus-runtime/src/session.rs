@@ -0,0 +1,720 @@
+//! Cookie-based sessions
+//!
+//! Stateful services bind calls to a session cookie, e.g. ASP.NET `.asmx`
+//! services with `ASP.NET_SessionId` or services that require a login
+//! operation first. A [`CookieJar`] stores the cookies set by responses and
+//! sends them with later requests to the same host.
+//!
+//! Sessions are opt-in: enable them with
+//! [`SoapClientBuilder::cookie_store`](crate::client::SoapClientBuilder::cookie_store)
+//! or pass a jar with
+//! [`SoapClientBuilder::cookie_jar`](crate::client::SoapClientBuilder::cookie_jar).
+//! Cookies work with any transport and are handled below HTTP authentication,
+//! so cookies set by `401` challenges are sent with the answer. All clones of
+//! a client share its jar.
+//!
+//! [`Cookie`] implements `Serialize` and `Deserialize`, so a session can be
+//! exported before a restart and imported afterwards.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::session::{Cookie, CookieJar};
+//! use soapus_runtime::SoapClient;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let saved = std::fs::read_to_string("session.json").unwrap_or_default();
+//! let cookies: Vec<Cookie> = serde_json::from_str(&saved).unwrap_or_default();
+//!
+//! let jar = CookieJar::from_cookies(cookies);
+//! let client = SoapClient::builder("https://crm.example.com/Service.asmx")
+//!     .cookie_jar(jar.clone())
+//!     .build();
+//!
+//! // ... call the login operation and further operations ...
+//!
+//! std::fs::write("session.json", serde_json::to_string(&jar.export())?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::SoapResult;
+use crate::transport::{SoapConnection, SoapTransport, TransportRequest, TransportResponse};
+#[cfg(feature = "streaming")]
+use crate::transport::{StreamingRequest, StreamingResponse};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A cookie set by a response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+    /// The cookie name
+    pub name: String,
+    /// The cookie value
+    pub value: String,
+    /// The host or domain the cookie is sent to
+    pub domain: String,
+    /// Whether the cookie is only sent to `domain` itself, not to its subdomains
+    pub host_only: bool,
+    /// The path prefix the cookie is sent to
+    pub path: String,
+    /// Whether the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether the cookie was marked `HttpOnly`
+    pub http_only: bool,
+    /// Expiry in seconds since the Unix epoch; `None` for session cookies
+    pub expires: Option<u64>,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` header received from `url`
+    ///
+    /// Returns `None` for malformed headers, for cookies of other domains and
+    /// for cookies of public suffixes such as `com` or `co.uk` other than the
+    /// host itself.
+    fn parse(header: &str, url: &Url) -> Option<Self> {
+        let mut attributes = header.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: url.host.clone(),
+            host_only: true,
+            path: url.default_path(),
+            secure: false,
+            http_only: false,
+            expires: None,
+        };
+        let mut max_age = None;
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if !domain_match(&url.host, &domain) {
+                        return None;
+                    }
+                    // A public suffix may only name the host itself (RFC 6265 5.3)
+                    if is_public_suffix(&domain) && domain != url.host {
+                        return None;
+                    }
+                    cookie.host_only = domain == url.host;
+                    cookie.domain = domain;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "expires" => {
+                    if let Some(expires) = parse_http_date(value) {
+                        cookie.expires = Some(expires);
+                    }
+                }
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                _ => {}
+            }
+        }
+        // Max-Age takes precedence over Expires
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(match seconds {
+                ..=0 => 0,
+                _ => now().saturating_add(seconds as u64),
+            });
+        }
+        Some(cookie)
+    }
+
+    /// Whether the cookie has expired at `now` (seconds since the Unix epoch)
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Whether the cookie is sent with a request to `url`
+    fn matches(&self, url: &Url) -> bool {
+        let domain = if self.host_only {
+            url.host == self.domain
+        } else {
+            domain_match(&url.host, &self.domain)
+        };
+        domain && path_match(&url.path, &self.path) && (url.secure || !self.secure)
+    }
+}
+
+/// Cookies of a session, shared by all clones
+///
+/// Expired cookies are dropped; cookies without expiry are kept until the
+/// session is cleared.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+impl CookieJar {
+    /// Create an empty jar
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a jar with exported cookies, e.g. after a restart
+    pub fn from_cookies(cookies: impl IntoIterator<Item = Cookie>) -> Self {
+        let jar = Self::new();
+        jar.import(cookies);
+        jar
+    }
+
+    /// Get all cookies that have not expired, including session cookies
+    pub fn export(&self) -> Vec<Cookie> {
+        let now = now();
+        let mut cookies = self.lock();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+        cookies.clone()
+    }
+
+    /// Add cookies, replacing cookies with the same name, domain and path
+    pub fn import(&self, cookies: impl IntoIterator<Item = Cookie>) {
+        let mut stored = self.lock();
+        for cookie in cookies {
+            insert(&mut stored, cookie);
+        }
+    }
+
+    /// Get the value of a cookie by name, regardless of domain and path
+    pub fn get(&self, name: &str) -> Option<String> {
+        let now = now();
+        self.lock()
+            .iter()
+            .find(|cookie| cookie.name == name && !cookie.is_expired(now))
+            .map(|cookie| cookie.value.clone())
+    }
+
+    /// Whether the jar holds no cookies
+    pub fn is_empty(&self) -> bool {
+        self.export().is_empty()
+    }
+
+    /// Remove all cookies, ending the session
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Store a `Set-Cookie` header received from `url`
+    ///
+    /// Malformed cookies and cookies for other domains are ignored. A cookie
+    /// that has already expired removes the stored cookie of the same name.
+    pub fn set_cookie(&self, url: &str, header: &str) {
+        if let Some(cookie) = Cookie::parse(header, &Url::parse(url)) {
+            insert(&mut self.lock(), cookie);
+        }
+    }
+
+    /// Get the `Cookie` header for a request to `url`, if any cookie matches
+    ///
+    /// Cookies with longer paths come first.
+    pub fn cookie_header(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url);
+        let now = now();
+        let mut cookies = self.lock();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+
+        let mut matching: Vec<&Cookie> = cookies.iter().filter(|c| c.matches(&url)).collect();
+        if matching.is_empty() {
+            return None;
+        }
+        matching.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            matching
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    /// Add the matching cookies to the `Cookie` header of a request
+    fn add_to(&self, url: &str, headers: &mut Vec<(String, String)>) {
+        let Some(cookies) = self.cookie_header(url) else {
+            return;
+        };
+        match headers
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Cookie"))
+        {
+            Some((_, value)) => *value = format!("{}; {}", value, cookies),
+            None => headers.push(("Cookie".to_string(), cookies)),
+        }
+    }
+
+    /// Store the `Set-Cookie` headers of a response
+    fn store(&self, url: &str, headers: &[(String, String)]) {
+        for (_, value) in headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("Set-Cookie"))
+        {
+            self.set_cookie(url, value);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Cookie>> {
+        self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Store a cookie, replacing the cookie with the same name, domain and path
+fn insert(cookies: &mut Vec<Cookie>, cookie: Cookie) {
+    cookies
+        .retain(|c| !(c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path));
+    if !cookie.is_expired(now()) {
+        cookies.push(cookie);
+    }
+}
+
+/// Transport sending and storing the cookies of a [`CookieJar`]
+pub(crate) struct SessionTransport {
+    inner: Arc<dyn SoapTransport>,
+    jar: CookieJar,
+}
+
+impl SessionTransport {
+    pub(crate) fn new(inner: Arc<dyn SoapTransport>, jar: CookieJar) -> Self {
+        Self { inner, jar }
+    }
+}
+
+#[async_trait]
+impl SoapTransport for SessionTransport {
+    async fn send(&self, mut request: TransportRequest) -> SoapResult<TransportResponse> {
+        let endpoint = request.endpoint.clone();
+        self.jar.add_to(&endpoint, &mut request.headers);
+        let response = self.inner.send(request).await?;
+        self.jar.store(&endpoint, &response.headers);
+        Ok(response)
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn send_streaming(&self, mut request: StreamingRequest) -> SoapResult<StreamingResponse> {
+        let endpoint = request.endpoint.clone();
+        self.jar.add_to(&endpoint, &mut request.headers);
+        let response = self.inner.send_streaming(request).await?;
+        self.jar.store(&endpoint, &response.headers);
+        Ok(response)
+    }
+
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        Ok(self.inner.connection()?.map(|inner| {
+            Box::new(SessionConnection {
+                inner,
+                jar: self.jar.clone(),
+            }) as Box<dyn SoapConnection>
+        }))
+    }
+}
+
+/// Sends and stores cookies on a connection of the inner transport
+struct SessionConnection {
+    inner: Box<dyn SoapConnection>,
+    jar: CookieJar,
+}
+
+#[async_trait]
+impl SoapConnection for SessionConnection {
+    async fn send(&mut self, mut request: TransportRequest) -> SoapResult<TransportResponse> {
+        let endpoint = request.endpoint.clone();
+        self.jar.add_to(&endpoint, &mut request.headers);
+        let response = self.inner.send(request).await?;
+        self.jar.store(&endpoint, &response.headers);
+        Ok(response)
+    }
+}
+
+/// The parts of a URL that cookies depend on
+struct Url {
+    secure: bool,
+    host: String,
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> Self {
+        let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+        let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let (authority, rest) = rest.split_at(end);
+        let host_port = authority.rsplit('@').next().unwrap_or(authority);
+        let host = match host_port.strip_prefix('[') {
+            Some(ipv6) => ipv6.split(']').next().unwrap_or(ipv6),
+            None => host_port.split(':').next().unwrap_or(host_port),
+        };
+        let path = rest.split(['?', '#']).next().unwrap_or(rest);
+
+        Self {
+            secure: scheme.eq_ignore_ascii_case("https"),
+            host: host.to_ascii_lowercase(),
+            path: if path.is_empty() { "/" } else { path }.to_string(),
+        }
+    }
+
+    /// The default cookie path: the request path up to its last `/`
+    fn default_path(&self) -> String {
+        match self.path.rfind('/') {
+            Some(0) | None => "/".to_string(),
+            Some(end) => self.path[..end].to_string(),
+        }
+    }
+}
+
+/// Whether `host` is `domain` or one of its subdomains
+///
+/// Public suffixes only match themselves, so a cookie for `com` or `co.uk`
+/// is never sent to the sites below them.
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<std::net::IpAddr>().is_err()
+            && !is_public_suffix(domain))
+}
+
+/// Public suffixes of more than one label, below which unrelated sites are registered
+///
+/// A built-in selection of the second-level registries and shared hosting
+/// domains of the Public Suffix List most likely in front of SOAP services.
+const PUBLIC_SUFFIXES: &[&str] = &[
+    // Second-level registries
+    "ac.jp",
+    "ac.uk",
+    "co.id",
+    "co.il",
+    "co.in",
+    "co.jp",
+    "co.kr",
+    "co.nz",
+    "co.th",
+    "co.uk",
+    "co.za",
+    "com.ar",
+    "com.au",
+    "com.br",
+    "com.cn",
+    "com.co",
+    "com.hk",
+    "com.mx",
+    "com.my",
+    "com.pl",
+    "com.sg",
+    "com.tr",
+    "com.tw",
+    "com.ua",
+    "edu.au",
+    "gov.au",
+    "gov.br",
+    "gov.cn",
+    "gov.in",
+    "gov.uk",
+    "ltd.uk",
+    "me.uk",
+    "ne.jp",
+    "net.au",
+    "net.br",
+    "net.cn",
+    "net.in",
+    "net.nz",
+    "net.uk",
+    "or.jp",
+    "or.kr",
+    "org.au",
+    "org.br",
+    "org.cn",
+    "org.in",
+    "org.mx",
+    "org.nz",
+    "org.uk",
+    "org.za",
+    "plc.uk",
+    // Shared hosting
+    "appspot.com",
+    "azurewebsites.net",
+    "cloudapp.net",
+    "cloudfront.net",
+    "elasticbeanstalk.com",
+    "firebaseapp.com",
+    "github.io",
+    "gitlab.io",
+    "herokuapp.com",
+    "netlify.app",
+    "pages.dev",
+    "s3.amazonaws.com",
+    "vercel.app",
+    "web.app",
+    "workers.dev",
+];
+
+/// Whether cookies must not be set for all of `domain`'s subdomains
+///
+/// Single labels such as `com` or `localhost` are top-level domains or local
+/// hosts and always count as public suffixes.
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || PUBLIC_SUFFIXES.contains(&domain)
+}
+
+/// Whether `path` is `cookie_path` or below it
+fn path_match(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// Parse the date of an `Expires` attribute, e.g. `Wed, 21 Oct 2026 07:28:00 GMT`
+///
+/// Follows the lenient algorithm of RFC 6265, which also accepts the obsolete
+/// RFC 850 and asctime formats.
+fn parse_http_date(value: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let (mut time, mut day, mut month, mut year) = (None, None, None, None);
+
+    for token in value
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != ':')
+        .filter(|token| !token.is_empty())
+    {
+        let digits = token.chars().take_while(char::is_ascii_digit).count();
+        if time.is_none() && token.contains(':') {
+            let parts: Vec<u64> = token.split(':').filter_map(|p| p.parse().ok()).collect();
+            if let [hour, minute, second] = parts[..] {
+                time = Some((hour, minute, second));
+                continue;
+            }
+        }
+        if day.is_none() && (1..=2).contains(&digits) && token.len() == digits {
+            day = token.parse::<u64>().ok();
+        } else if month.is_none() && token.len() >= 3 && digits == 0 {
+            let prefix = token[..3].to_ascii_lowercase();
+            month = MONTHS
+                .iter()
+                .position(|m| *m == prefix)
+                .map(|m| m as u64 + 1);
+        } else if year.is_none() && (2..=4).contains(&digits) && token.len() == digits {
+            year = token.parse::<u64>().ok().map(|year| match year {
+                0..=69 => year + 2000,
+                70..=99 => year + 1900,
+                _ => year,
+            });
+        }
+    }
+
+    let ((hour, minute, second), day, month, year) = (time?, day?, month?, year?);
+    if year < 1970 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interceptor::find_header;
+    use crate::transport::MemoryTransport;
+
+    const ENDPOINT: &str = "https://crm.example.com/services/Service.asmx";
+
+    #[test]
+    fn test_parse_set_cookie() {
+        let url = Url::parse(ENDPOINT);
+        let cookie = Cookie::parse(
+            "ASP.NET_SessionId=abc123; path=/; HttpOnly; SameSite=Lax",
+            &url,
+        )
+        .unwrap();
+        assert_eq!(cookie.name, "ASP.NET_SessionId");
+        assert_eq!(cookie.value, "abc123");
+        assert_eq!(cookie.domain, "crm.example.com");
+        assert!(cookie.host_only);
+        assert_eq!(cookie.path, "/");
+        assert!(cookie.http_only);
+        assert_eq!(cookie.expires, None);
+
+        let cookie = Cookie::parse("token=x; Domain=.example.com; Secure", &url).unwrap();
+        assert_eq!(cookie.domain, "example.com");
+        assert!(!cookie.host_only);
+        assert!(cookie.secure);
+        assert_eq!(cookie.path, "/services");
+
+        assert!(Cookie::parse("token=x; Domain=other.com", &url).is_none());
+        assert!(Cookie::parse("no-value", &url).is_none());
+    }
+
+    #[test]
+    fn test_public_suffix_domains() {
+        let url = Url::parse(ENDPOINT);
+        assert!(Cookie::parse("token=x; Domain=com", &url).is_none());
+        assert!(Cookie::parse("token=x; Domain=.COM", &url).is_none());
+
+        let url = Url::parse("https://shop.example.co.uk/Service.asmx");
+        assert!(Cookie::parse("token=x; Domain=co.uk", &url).is_none());
+        let cookie = Cookie::parse("token=x; Domain=example.co.uk", &url).unwrap();
+        assert!(!cookie.host_only);
+
+        // A public suffix naming the host itself is a host-only cookie
+        let url = Url::parse("http://localhost:8080/Service.asmx");
+        let cookie = Cookie::parse("token=x; Domain=localhost", &url).unwrap();
+        assert!(cookie.host_only);
+        let url = Url::parse("https://github.io/");
+        assert!(
+            Cookie::parse("token=x; Domain=github.io", &url)
+                .unwrap()
+                .host_only
+        );
+
+        // Imported cookies of public suffixes don't reach the sites below them
+        let jar = CookieJar::from_cookies(vec![Cookie {
+            name: "token".to_string(),
+            value: "x".to_string(),
+            domain: "co.uk".to_string(),
+            host_only: false,
+            path: "/".to_string(),
+            secure: false,
+            http_only: false,
+            expires: None,
+        }]);
+        assert_eq!(jar.cookie_header("https://shop.example.co.uk/"), None);
+        assert!(domain_match("shop.example.co.uk", "example.co.uk"));
+        assert!(!domain_match("example.com", "com"));
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(
+            parse_http_date("Wed, 21 Oct 2026 07:28:00 GMT"),
+            Some(1_792_567_680)
+        );
+        assert_eq!(
+            parse_http_date("Wednesday, 21-Oct-26 07:28:00 GMT"),
+            Some(1_792_567_680)
+        );
+        assert_eq!(
+            parse_http_date("Wed Oct 21 07:28:00 2026"),
+            Some(1_792_567_680)
+        );
+        assert_eq!(parse_http_date("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_cookie_header() {
+        let jar = CookieJar::new();
+        jar.set_cookie(ENDPOINT, "ASP.NET_SessionId=abc123; path=/");
+        jar.set_cookie(ENDPOINT, "cart=1; path=/services");
+        jar.set_cookie(ENDPOINT, "secure=1; Secure");
+        jar.set_cookie(ENDPOINT, "shared=1; Domain=example.com; Path=/");
+
+        assert_eq!(
+            jar.cookie_header(ENDPOINT).as_deref(),
+            Some("cart=1; secure=1; ASP.NET_SessionId=abc123; shared=1")
+        );
+        assert_eq!(
+            jar.cookie_header("http://crm.example.com/other").as_deref(),
+            Some("ASP.NET_SessionId=abc123; shared=1")
+        );
+        assert_eq!(
+            jar.cookie_header("http://api.example.com/").as_deref(),
+            Some("shared=1")
+        );
+        assert_eq!(jar.cookie_header("http://example.org/"), None);
+    }
+
+    #[test]
+    fn test_cookie_replacement_and_expiry() {
+        let jar = CookieJar::new();
+        jar.set_cookie(ENDPOINT, "session=1; path=/");
+        jar.set_cookie(ENDPOINT, "session=2; path=/");
+        assert_eq!(jar.get("session").as_deref(), Some("2"));
+
+        jar.set_cookie(ENDPOINT, "session=; path=/; Max-Age=0");
+        assert_eq!(jar.get("session"), None);
+
+        jar.set_cookie(
+            ENDPOINT,
+            "old=1; path=/; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+        );
+        jar.set_cookie(ENDPOINT, "remember=1; path=/; Max-Age=3600");
+        let cookies = jar.export();
+        assert_eq!(cookies.len(), 1);
+        assert!(cookies[0].expires.unwrap() > now());
+    }
+
+    #[test]
+    fn test_export_import() {
+        let jar = CookieJar::new();
+        jar.set_cookie(ENDPOINT, "ASP.NET_SessionId=abc123; path=/; HttpOnly");
+
+        let json = serde_json::to_string(&jar.export()).unwrap();
+        let restored = CookieJar::from_cookies(serde_json::from_str::<Vec<Cookie>>(&json).unwrap());
+        assert_eq!(restored.export(), jar.export());
+        assert_eq!(
+            restored.cookie_header(ENDPOINT).as_deref(),
+            Some("ASP.NET_SessionId=abc123")
+        );
+
+        restored.clear();
+        assert!(restored.is_empty());
+        assert!(!jar.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_transport() {
+        let stub = MemoryTransport::with_handler(|request| {
+            Ok(match find_header(&request.headers, "Cookie") {
+                None => TransportResponse::new(200, "login")
+                    .with_header("Set-Cookie", "ASP.NET_SessionId=abc123; path=/"),
+                Some(_) => TransportResponse::new(200, "ok"),
+            })
+        });
+        let jar = CookieJar::new();
+        let transport = SessionTransport::new(Arc::new(stub.clone()), jar.clone());
+        let request = TransportRequest {
+            endpoint: ENDPOINT.to_string(),
+            headers: vec![("Cookie".to_string(), "lang=de".to_string())],
+            body: Vec::new(),
+            timeout: std::time::Duration::from_secs(1),
+        };
+
+        let mut first = request.clone();
+        first.headers.clear();
+        transport.send(first).await.unwrap();
+        transport.send(request).await.unwrap();
+
+        let requests = stub.requests();
+        assert_eq!(find_header(&requests[0].headers, "Cookie"), None);
+        assert_eq!(
+            find_header(&requests[1].headers, "Cookie"),
+            Some("lang=de; ASP.NET_SessionId=abc123")
+        );
+        assert_eq!(jar.get("ASP.NET_SessionId").as_deref(), Some("abc123"));
+    }
+}
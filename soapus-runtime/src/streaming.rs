@@ -0,0 +1,1511 @@
+//! Streaming of large binary content
+//!
+//! [`SoapClient::call_streaming`](crate::SoapClient::call_streaming) exchanges
+//! messages without holding complete envelopes in memory. Binary fields use
+//! [`BinaryStream`], an [`AsyncRead`] that is consumed while the request is
+//! sent, and read by the application once the response envelope is parsed:
+//!
+//! - Request content is read from its source while the body is sent: as raw
+//!   MTOM parts when MTOM is enabled, otherwise encoded to base64 on the fly.
+//! - Response bodies are parsed incrementally. MTOM parts stay on the
+//!   connection until their `BinaryStream` is read; parts read out of message
+//!   order are buffered. Text content of more than 64 KiB is kept out of the
+//!   envelope; base64 text is decoded as it arrives, so only the decoded
+//!   content is held in memory.
+//!
+//! Some client features don't apply to streamed calls:
+//!
+//! - They are never retried, whatever the
+//!   [`RetryPolicy`](crate::RetryPolicy) of the client, since the content of
+//!   a request can only be read once.
+//! - Interceptors see the envelope with `cid:` references in place of
+//!   streamed content, so WS-Security signatures and encryption don't cover
+//!   it.
+//!
+//! Requires the `streaming` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use serde::{Deserialize, Serialize};
+//! use soapus_runtime::{BinaryStream, CallOptions, SoapClient};
+//!
+//! #[derive(Serialize)]
+//! struct GetDocument {
+//!     id: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct GetDocumentResponse {
+//!     name: String,
+//!     content: BinaryStream,
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SoapClient::builder("http://example.com/archive")
+//!     .mtom(true)
+//!     .build();
+//!
+//! let request = GetDocument { id: "42".to_string() };
+//! let options = CallOptions::new().timeout(std::time::Duration::from_secs(600));
+//! let mut response: GetDocumentResponse = client
+//!     .call_streaming("GetDocument", None, Some("urn:example:archive"), true, &request, &options)
+//!     .await?;
+//!
+//! let mut file = tokio::fs::File::create(&response.name).await?;
+//! tokio::io::copy(&mut response.content, &mut file).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use crate::mime;
+use crate::mtom::{self, Attachment, PartHeaders, Root, XOP_NS};
+use crate::random::{hex, random_bytes};
+use crate::transport::BodyStream;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+/// Text content longer than this is kept out of a response envelope
+const TEXT_THRESHOLD: usize = 64 * 1024;
+
+/// Bytes read or decoded at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Binary content of an `xs:base64Binary` element, read as a stream
+///
+/// In requests, the content is read from the wrapped reader while the request
+/// is sent. In responses, reading yields the decoded content. Only supported
+/// by [`SoapClient::call_streaming`](crate::SoapClient::call_streaming).
+pub struct BinaryStream {
+    reader: Mutex<Option<BodyStream>>,
+}
+
+impl BinaryStream {
+    /// Stream content from a reader, e.g. a file
+    pub fn new(reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        Self::from_box(Box::new(reader))
+    }
+
+    /// Stream content held in memory
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Self {
+        Self::new(io::Cursor::new(data.into()))
+    }
+
+    fn from_box(reader: BodyStream) -> Self {
+        Self {
+            reader: Mutex::new(Some(reader)),
+        }
+    }
+
+    /// Take the reader to send its content
+    fn take(&self) -> Option<BodyStream> {
+        self.reader.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl AsyncRead for BinaryStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let reader = self
+            .get_mut()
+            .reader
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner());
+        match reader {
+            Some(reader) => Pin::new(reader).poll_read(cx, buf),
+            None => Poll::Ready(Err(io::Error::other("The content was already sent"))),
+        }
+    }
+}
+
+impl fmt::Debug for BinaryStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinaryStream").finish_non_exhaustive()
+    }
+}
+
+impl Serialize for BinaryStream {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let content_id = OUTGOING
+            .with(|outgoing| {
+                let mut outgoing = outgoing.borrow_mut();
+                let outgoing = outgoing
+                    .as_mut()
+                    .ok_or("BinaryStream can only be sent with SoapClient::call_streaming")?;
+                let reader = self
+                    .take()
+                    .ok_or("The content of a BinaryStream can only be sent once")?;
+                let content_id =
+                    format!("stream-{}.{}", outgoing.streams.len() + 1, outgoing.suffix);
+                outgoing.streams.push(Stream {
+                    content_id: content_id.clone(),
+                    reader,
+                });
+                Ok(content_id)
+            })
+            .map_err(|e: &str| ser::Error::custom(e))?;
+
+        // Replaced by the content while the request is sent
+        serializer.serialize_str(&format!("cid:{}", content_id))
+    }
+}
+
+impl<'de> Deserialize<'de> for BinaryStream {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StreamVisitor;
+
+        impl Visitor<'_> for StreamVisitor {
+            type Value = BinaryStream;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("base64 text or a reference to streamed content")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<BinaryStream, E> {
+                let value = value.trim();
+                // Base64 text never contains ':'
+                if let Some(content_id) = value.strip_prefix("cid:") {
+                    return INCOMING
+                        .with(|incoming| incoming.borrow_mut().as_mut()?.open(content_id))
+                        .map(BinaryStream::from_box)
+                        .ok_or_else(|| {
+                            E::custom(format!("Unknown attachment 'cid:{}'", content_id))
+                        });
+                }
+
+                let mut decoder = Base64Decoder::default();
+                let mut data = decoder.push(value.as_bytes()).map_err(E::custom)?;
+                data.extend(decoder.finish().map_err(E::custom)?);
+                Ok(BinaryStream::from_bytes(data))
+            }
+        }
+
+        deserializer.deserialize_str(StreamVisitor)
+    }
+}
+
+/// The content of a [`BinaryStream`] in a request
+pub(crate) struct Stream {
+    content_id: String,
+    reader: BodyStream,
+}
+
+/// Streams collected while a request is serialized
+struct Outgoing {
+    suffix: String,
+    streams: Vec<Stream>,
+}
+
+/// Content of a response kept out of its envelope
+struct Incoming {
+    texts: HashMap<String, Text>,
+    /// Texts read as [`BinaryStream`]
+    claimed: HashSet<String>,
+    parts: Option<Arc<Mutex<Multipart>>>,
+}
+
+impl Incoming {
+    fn open(&mut self, content_id: &str) -> Option<BodyStream> {
+        if let Some(text) = self.texts.get(content_id) {
+            self.claimed.insert(content_id.to_string());
+            return Some(match text {
+                Text::Raw(text) => Box::new(TextReader::new(text.clone())),
+                Text::Decoded(data) => Box::new(io::Cursor::new(data.clone())),
+            });
+        }
+
+        Some(Box::new(PartReader {
+            parts: self.parts.clone()?,
+            content_id: content_id.to_string(),
+            pending: Pending::default(),
+        }))
+    }
+}
+
+thread_local! {
+    static OUTGOING: RefCell<Option<Outgoing>> = const { RefCell::new(None) };
+    static INCOMING: RefCell<Option<Incoming>> = const { RefCell::new(None) };
+}
+
+/// Clears the collected streams, also when serialization panics
+struct OutgoingGuard;
+
+impl Drop for OutgoingGuard {
+    fn drop(&mut self) {
+        OUTGOING.with(|outgoing| outgoing.borrow_mut().take());
+    }
+}
+
+/// Clears the streamed response content once the response is parsed
+struct IncomingGuard;
+
+impl Drop for IncomingGuard {
+    fn drop(&mut self) {
+        INCOMING.with(|incoming| incoming.borrow_mut().take());
+    }
+}
+
+/// Build an envelope, collecting the content of every [`BinaryStream`]
+///
+/// The envelope references each stream with a `cid:` placeholder.
+pub(crate) fn collect(
+    build_envelope: impl FnOnce() -> SoapResult<String>,
+) -> SoapResult<(String, Vec<Stream>)> {
+    let suffix = format!("{}@soapus", hex(&random_bytes::<16>()?));
+    let _guard = OutgoingGuard;
+    OUTGOING.with(|outgoing| {
+        *outgoing.borrow_mut() = Some(Outgoing {
+            suffix,
+            streams: Vec::new(),
+        })
+    });
+
+    let envelope = build_envelope()?;
+    let streams = OUTGOING
+        .with(|outgoing| outgoing.borrow_mut().take())
+        .map(|outgoing| outgoing.streams)
+        .unwrap_or_default();
+
+    Ok((envelope, streams))
+}
+
+/// Reference the streams in element content with `xop:Include` elements
+///
+/// Used with MTOM, where these streams are sent as attachment parts.
+pub(crate) fn include(mut envelope: String, streams: &[Stream]) -> String {
+    for stream in streams {
+        envelope = envelope.replacen(
+            &format!(">cid:{}<", stream.content_id),
+            &format!(
+                "><xop:Include xmlns:xop=\"{}\" href=\"cid:{}\"/><",
+                XOP_NS, stream.content_id
+            ),
+            1,
+        );
+    }
+    envelope
+}
+
+/// Assemble the body of a streamed request
+///
+/// With `mtom`, the envelope is the root part of a XOP message, followed by
+/// the attachments and the streams referenced with `xop:Include`. All other
+/// streams are written as base64 text in place of their placeholder.
+/// Returns the `Content-Type` of the body and the body.
+pub(crate) fn body(
+    envelope: &str,
+    content_type: &str,
+    attachments: Vec<Attachment>,
+    streams: Vec<Stream>,
+    mtom: bool,
+) -> SoapResult<(String, BodyStream)> {
+    let mut parts = Vec::new();
+    let mut inline = Vec::new();
+    for stream in streams {
+        if mtom && envelope.contains(&format!("href=\"cid:{}\"", stream.content_id)) {
+            parts.push(stream);
+        } else if let Some(position) = envelope.find(&format!("cid:{}", stream.content_id)) {
+            inline.push((position, stream));
+        }
+    }
+    inline.sort_by_key(|(position, _)| *position);
+
+    let mut envelope_readers: Vec<BodyStream> = Vec::new();
+    let mut copied = 0;
+    for (position, stream) in inline {
+        envelope_readers.push(bytes(&envelope[copied..position]));
+        envelope_readers.push(Box::new(Base64Encoder::new(stream.reader)));
+        copied = position + "cid:".len() + stream.content_id.len();
+    }
+    envelope_readers.push(bytes(&envelope[copied..]));
+
+    if !mtom {
+        return Ok((content_type.to_string(), Concat::boxed(envelope_readers)));
+    }
+
+    let (message_type, boundary) = mtom::message_type(content_type, Root::Xop)?;
+    let mut readers = vec![bytes(&mtom::root_header(
+        &boundary,
+        content_type,
+        Root::Xop,
+    ))];
+    readers.extend(envelope_readers);
+    for attachment in attachments {
+        readers.push(bytes(&mtom::part_header(
+            &boundary,
+            &attachment.content_type,
+            &attachment.content_id,
+        )));
+        readers.push(Box::new(io::Cursor::new(attachment.data)));
+    }
+    for stream in parts {
+        readers.push(bytes(&mtom::part_header(
+            &boundary,
+            "application/octet-stream",
+            &stream.content_id,
+        )));
+        readers.push(stream.reader);
+    }
+    readers.push(bytes(&mtom::closing_delimiter(&boundary)));
+
+    Ok((message_type, Concat::boxed(readers)))
+}
+
+fn bytes(text: &str) -> BodyStream {
+    Box::new(io::Cursor::new(text.as_bytes().to_vec()))
+}
+
+/// A response read up to the end of its envelope
+pub(crate) struct IncomingStream {
+    /// The envelope, with `cid:` references in place of streamed content
+    pub(crate) envelope: String,
+    texts: HashMap<String, Text>,
+    parts: Option<Arc<Mutex<Multipart>>>,
+}
+
+impl IncomingStream {
+    /// Put the text content kept out of the envelope back, e.g. for faults
+    pub(crate) fn inline_texts(&mut self) {
+        self.envelope = inline_texts(&self.envelope, self.texts.drain());
+    }
+
+    /// Parse the envelope with its streamed content available to [`BinaryStream`]
+    ///
+    /// Text content that isn't read as `BinaryStream`, such as a long string,
+    /// is put back into the envelope and the envelope is parsed again.
+    pub(crate) fn parse<T>(self, parse: impl Fn(&str) -> SoapResult<T>) -> SoapResult<T> {
+        let _guard = IncomingGuard;
+        INCOMING.with(|incoming| {
+            *incoming.borrow_mut() = Some(Incoming {
+                texts: self.texts.clone(),
+                claimed: HashSet::new(),
+                parts: self.parts,
+            })
+        });
+
+        let result = parse(&self.envelope);
+        let unclaimed: Vec<_> = INCOMING.with(|incoming| {
+            let incoming = incoming.borrow();
+            let claimed = incoming.as_ref().map(|incoming| &incoming.claimed);
+            self.texts
+                .into_iter()
+                .filter(|(content_id, _)| !claimed.is_some_and(|c| c.contains(content_id)))
+                .collect()
+        });
+        if unclaimed.is_empty() {
+            return result;
+        }
+
+        parse(&inline_texts(&self.envelope, unclaimed))
+    }
+}
+
+fn inline_texts(envelope: &str, texts: impl IntoIterator<Item = (String, Text)>) -> String {
+    let mut envelope = envelope.to_string();
+    for (content_id, text) in texts {
+        let text = match text {
+            Text::Raw(text) => String::from_utf8_lossy(&text).into_owned(),
+            Text::Decoded(data) => BASE64.encode(data),
+        };
+        envelope = envelope.replacen(&format!("cid:{}", content_id), &text, 1);
+    }
+    envelope
+}
+
+/// Read a response body up to the end of its envelope
+///
+/// For `multipart/related` bodies the envelope is the root part; the parts
+/// after it are read later through [`BinaryStream`].
+pub(crate) async fn receive(
+    content_type: Option<&str>,
+    mut body: BodyStream,
+) -> SoapResult<IncomingStream> {
+    let mut extractor = Extractor::new(format!("{}@soapus", hex(&random_bytes::<16>()?)));
+
+    let parts = match content_type {
+        Some(content_type) if mtom::is_multipart(content_type) => {
+            let boundary = mime::parameter(content_type, "boundary")
+                .ok_or_else(|| mtom::invalid("missing boundary parameter"))?;
+            let mut multipart = Multipart::new(body, &boundary);
+
+            let root = match mime::parameter(content_type, "start") {
+                Some(start) => mtom::strip_angle_brackets(&start).to_string(),
+                None => poll_fn(|cx| multipart.poll_first_part(cx))
+                    .await
+                    .map_err(read_error)?,
+            };
+            while let Some(chunk) = poll_fn(|cx| multipart.poll_part(cx, &root))
+                .await
+                .map_err(read_error)?
+            {
+                extractor.push(&chunk);
+            }
+
+            Some(Arc::new(Mutex::new(multipart)))
+        }
+        _ => {
+            let mut chunk = vec![0; CHUNK_SIZE];
+            loop {
+                let read = body.read(&mut chunk).await.map_err(read_error)?;
+                if read == 0 {
+                    break;
+                }
+                extractor.push(&chunk[..read]);
+            }
+            None
+        }
+    };
+
+    let (envelope, texts) = extractor.finish();
+    let envelope = match parts {
+        // The root part is XML in an ASCII compatible encoding, usually UTF-8
+        Some(_) => mtom::replace_includes(&mime::decode_xml(None, &envelope))?,
+        None => mime::decode_xml(content_type, &envelope),
+    };
+
+    Ok(IncomingStream {
+        envelope,
+        texts,
+        parts,
+    })
+}
+
+fn read_error(e: io::Error) -> SoapError {
+    match e.kind() {
+        io::ErrorKind::InvalidData => SoapError::InvalidResponse(e.to_string()),
+        _ => SoapError::TransportError(format!("Failed to read response body: {}", e)),
+    }
+}
+
+fn invalid_data(message: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Text content kept out of a response envelope
+#[derive(Clone)]
+enum Text {
+    /// Text that isn't valid base64, e.g. a long string
+    Raw(Arc<Vec<u8>>),
+    /// The content of base64 text, decoded while it was received
+    Decoded(Arc<[u8]>),
+}
+
+/// Long text being decoded as base64 while it is received
+///
+/// Only text whose line breaks can be restored is decoded: lines of equal
+/// length separated by the same line break, as written by base64 encoders.
+/// Should the text turn out not to be base64, it is restored as it was sent.
+#[derive(Clone)]
+struct Decoding {
+    decoder: Base64Decoder,
+    decoded: Vec<u8>,
+    layout: Layout,
+}
+
+impl Decoding {
+    /// Start decoding `text`, unless it isn't base64 text
+    fn start(text: &[u8]) -> Option<Self> {
+        let mut decoding = Self {
+            decoder: Base64Decoder::default(),
+            decoded: Vec::new(),
+            layout: Layout::default(),
+        };
+        decoding.push(text).then_some(decoding)
+    }
+
+    /// Decode more text; returns false, leaving the state as it was, if it isn't base64
+    fn push(&mut self, text: &[u8]) -> bool {
+        let mut layout = self.layout.clone();
+        if !text.iter().all(|&byte| layout.push(byte)) {
+            return false;
+        }
+        let pending = self.decoder.pending.len();
+        match self.decoder.push(text) {
+            Ok(decoded) => {
+                self.decoded.extend(decoded);
+                self.layout = layout;
+                true
+            }
+            Err(_) => {
+                self.decoder.pending.truncate(pending);
+                false
+            }
+        }
+    }
+
+    /// The text received so far
+    fn into_text(self) -> Vec<u8> {
+        let mut chars = BASE64.encode(&self.decoded).into_bytes();
+        chars.extend(self.decoder.pending);
+        self.layout.restore(&chars)
+    }
+}
+
+/// Line breaks of base64 text
+#[derive(Clone, Default)]
+struct Layout {
+    /// Characters per line, known after the first line
+    width: Option<usize>,
+    line_break: Vec<u8>,
+    /// Characters of the current line
+    column: usize,
+    /// The line break being read
+    breaking: Vec<u8>,
+    /// A line shorter than `width` ended, so no more characters may follow
+    last_line: bool,
+}
+
+impl Layout {
+    /// Add a byte of the text; returns false if the layout isn't regular
+    fn push(&mut self, byte: u8) -> bool {
+        if byte == b'\r' || byte == b'\n' {
+            if self.breaking.is_empty() {
+                match self.width {
+                    _ if self.column == 0 => return false,
+                    None => self.width = Some(self.column),
+                    Some(width) if self.column < width => self.last_line = true,
+                    Some(_) => {}
+                }
+            }
+            self.breaking.push(byte);
+            return self.line_break.is_empty() || self.line_break.starts_with(&self.breaking);
+        }
+        if byte.is_ascii_whitespace() {
+            return false;
+        }
+
+        if !self.breaking.is_empty() {
+            if self.line_break.is_empty() {
+                self.line_break = std::mem::take(&mut self.breaking);
+            } else if self.breaking != self.line_break {
+                return false;
+            }
+            self.breaking.clear();
+            self.column = 0;
+            if self.last_line {
+                return false;
+            }
+        }
+        self.column += 1;
+        self.width.is_none_or(|width| self.column <= width)
+    }
+
+    /// Put the line breaks back into the characters of the text
+    fn restore(&self, chars: &[u8]) -> Vec<u8> {
+        let mut text = match self.width {
+            Some(width) => chars
+                .chunks(width)
+                .collect::<Vec<_>>()
+                .join(&self.line_break[..]),
+            None => chars.to_vec(),
+        };
+        text.extend_from_slice(&self.breaking);
+        text
+    }
+}
+
+/// Copies XML, keeping long text content out of it
+///
+/// Works on arbitrary chunks of the input; only markup boundaries are
+/// recognized, the XML is parsed later. Long base64 text is decoded as it
+/// arrives, other long text is kept as is.
+struct Extractor {
+    xml: Vec<u8>,
+    text: Vec<u8>,
+    /// The current text is long and valid base64 so far
+    decoding: Option<Decoding>,
+    /// The current text is long and not base64
+    raw: bool,
+    /// The markup being copied, `None` within text
+    markup: Option<Markup>,
+    /// Start of the current markup in `xml`
+    markup_start: usize,
+    texts: HashMap<String, Text>,
+    suffix: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Markup {
+    /// After `<`, before the kind of markup is known
+    Open,
+    /// A tag, within the given quote character
+    Tag(Option<u8>),
+    Comment,
+    CData,
+    Instruction,
+}
+
+impl Extractor {
+    fn new(suffix: String) -> Self {
+        Self {
+            xml: Vec::new(),
+            text: Vec::new(),
+            decoding: None,
+            raw: false,
+            markup: None,
+            markup_start: 0,
+            texts: HashMap::new(),
+            suffix,
+        }
+    }
+
+    fn push(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            match self.markup {
+                None => {
+                    let end = data.iter().position(|&b| b == b'<').unwrap_or(data.len());
+                    self.push_text(&data[..end]);
+                    data = &data[end..];
+                    if !data.is_empty() {
+                        self.end_text();
+                        self.markup_start = self.xml.len();
+                        self.xml.push(b'<');
+                        self.markup = Some(Markup::Open);
+                        data = &data[1..];
+                    }
+                }
+                Some(markup) => {
+                    self.xml.push(data[0]);
+                    self.markup = self.next_markup(markup, data[0]);
+                    data = &data[1..];
+                }
+            }
+        }
+    }
+
+    /// The state after `byte` was copied within `markup`
+    fn next_markup(&self, markup: Markup, byte: u8) -> Option<Markup> {
+        let current = &self.xml[self.markup_start..];
+        match markup {
+            Markup::Open if current.starts_with(b"<!--") => Some(Markup::Comment),
+            Markup::Open if current.starts_with(b"<![CDATA[") => Some(Markup::CData),
+            Markup::Open if current.starts_with(b"<?") => Some(Markup::Instruction),
+            Markup::Open if b"<!--".starts_with(current) || b"<![CDATA[".starts_with(current) => {
+                Some(Markup::Open)
+            }
+            Markup::Open => self.next_markup(Markup::Tag(None), byte),
+            Markup::Tag(None) if byte == b'>' => None,
+            Markup::Tag(None) if byte == b'"' || byte == b'\'' => Some(Markup::Tag(Some(byte))),
+            Markup::Tag(Some(quote)) if byte == quote => Some(Markup::Tag(None)),
+            Markup::Comment if current.len() >= 7 && current.ends_with(b"-->") => None,
+            Markup::CData if current.len() >= 12 && current.ends_with(b"]]>") => None,
+            Markup::Instruction if current.len() >= 4 && current.ends_with(b"?>") => None,
+            markup => Some(markup),
+        }
+    }
+
+    fn push_text(&mut self, text: &[u8]) {
+        if let Some(decoding) = self.decoding.as_mut() {
+            if !decoding.push(text) {
+                // Not base64 after all, keep the text as it was sent
+                self.text = self
+                    .decoding
+                    .take()
+                    .map(Decoding::into_text)
+                    .unwrap_or_default();
+                self.text.extend_from_slice(text);
+                self.raw = true;
+            }
+            return;
+        }
+
+        self.text.extend_from_slice(text);
+        if self.text.len() > TEXT_THRESHOLD && !self.raw {
+            match Decoding::start(&self.text) {
+                Some(decoding) => {
+                    self.decoding = Some(decoding);
+                    self.text = Vec::new();
+                }
+                None => self.raw = true,
+            }
+        }
+    }
+
+    fn end_text(&mut self) {
+        let text = match self.decoding.take() {
+            Some(mut decoding) => match decoding.decoder.finish() {
+                Ok(_) => Some(Text::Decoded(decoding.decoded.into())),
+                // Truncated, reported when the text is read
+                Err(_) => Some(Text::Raw(Arc::new(decoding.into_text()))),
+            },
+            None if self.text.len() > TEXT_THRESHOLD => {
+                Some(Text::Raw(Arc::new(std::mem::take(&mut self.text))))
+            }
+            None => None,
+        };
+        self.raw = false;
+
+        match text {
+            Some(text) => {
+                let content_id = format!("text-{}.{}", self.texts.len() + 1, self.suffix);
+                self.xml
+                    .extend_from_slice(format!("cid:{}", content_id).as_bytes());
+                self.texts.insert(content_id, text);
+            }
+            None => self.xml.append(&mut self.text),
+        }
+    }
+
+    fn finish(mut self) -> (Vec<u8>, HashMap<String, Text>) {
+        if self.markup.is_none() {
+            self.end_text();
+        }
+        (self.xml, self.texts)
+    }
+}
+
+/// Decodes base64 text given in arbitrary chunks
+#[derive(Clone, Default)]
+struct Base64Decoder {
+    /// Characters of an incomplete quantum
+    pending: Vec<u8>,
+    /// Padding was decoded, so the text has to end
+    padded: bool,
+}
+
+impl Base64Decoder {
+    fn push(&mut self, text: &[u8]) -> io::Result<Vec<u8>> {
+        self.pending
+            .extend(text.iter().copied().filter(|b| !b.is_ascii_whitespace()));
+        if self.padded && !self.pending.is_empty() {
+            return Err(invalid_data("Invalid base64 content: data after padding"));
+        }
+        let complete = self.pending.len() / 4 * 4;
+        let decoded = BASE64
+            .decode(&self.pending[..complete])
+            .map_err(|e| invalid_data(format!("Invalid base64 content: {}", e)))?;
+        self.padded = complete > 0 && self.pending[complete - 1] == b'=';
+        self.pending.drain(..complete);
+        Ok(decoded)
+    }
+
+    fn finish(&mut self) -> io::Result<Vec<u8>> {
+        if self.pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        Err(invalid_data("Invalid base64 content: truncated"))
+    }
+}
+
+/// Data to hand out to a reader
+#[derive(Default)]
+struct Pending {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Pending {
+    fn set(&mut self, data: Vec<u8>) {
+        self.data = data;
+        self.offset = 0;
+    }
+
+    /// Copy as much as fits into `buf`; returns whether anything was copied
+    fn copy_to(&mut self, buf: &mut ReadBuf<'_>) -> bool {
+        let len = buf.remaining().min(self.data.len() - self.offset);
+        buf.put_slice(&self.data[self.offset..self.offset + len]);
+        self.offset += len;
+        len > 0
+    }
+}
+
+/// Reads readers one after another
+struct Concat {
+    readers: VecDeque<BodyStream>,
+}
+
+impl Concat {
+    fn boxed(readers: Vec<BodyStream>) -> BodyStream {
+        Box::new(Self {
+            readers: readers.into(),
+        })
+    }
+}
+
+impl AsyncRead for Concat {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        while let Some(reader) = self.readers.front_mut() {
+            let filled = buf.filled().len();
+            ready!(Pin::new(reader).poll_read(cx, buf))?;
+            if buf.filled().len() > filled {
+                return Poll::Ready(Ok(()));
+            }
+            self.readers.pop_front();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Encodes the content of a reader as base64 text
+struct Base64Encoder {
+    reader: BodyStream,
+    /// Bytes of an incomplete quantum
+    input: Vec<u8>,
+    output: Pending,
+    done: bool,
+}
+
+impl Base64Encoder {
+    fn new(reader: BodyStream) -> Self {
+        Self {
+            reader,
+            input: Vec::new(),
+            output: Pending::default(),
+            done: false,
+        }
+    }
+}
+
+impl AsyncRead for Base64Encoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        loop {
+            if this.output.copy_to(buf) || this.done {
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut chunk = vec![0; CHUNK_SIZE];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.reader).poll_read(cx, &mut chunk_buf))?;
+            let read = chunk_buf.filled();
+            if read.is_empty() {
+                this.done = true;
+                this.output.set(BASE64.encode(&this.input).into_bytes());
+                this.input.clear();
+            } else {
+                this.input.extend_from_slice(read);
+                let complete = this.input.len() / 3 * 3;
+                this.output
+                    .set(BASE64.encode(&this.input[..complete]).into_bytes());
+                this.input.drain(..complete);
+            }
+        }
+    }
+}
+
+/// Decodes base64 text kept out of a response envelope
+struct TextReader {
+    text: Arc<Vec<u8>>,
+    position: usize,
+    decoder: Base64Decoder,
+    output: Pending,
+}
+
+impl TextReader {
+    fn new(text: Arc<Vec<u8>>) -> Self {
+        Self {
+            text,
+            position: 0,
+            decoder: Base64Decoder::default(),
+            output: Pending::default(),
+        }
+    }
+}
+
+impl AsyncRead for TextReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        while !this.output.copy_to(buf) {
+            if this.position == this.text.len() {
+                let rest = this.decoder.finish()?;
+                if rest.is_empty() {
+                    break;
+                }
+                this.output.set(rest);
+                continue;
+            }
+
+            let end = this.text.len().min(this.position + CHUNK_SIZE);
+            let decoded = this.decoder.push(&this.text[this.position..end])?;
+            this.position = end;
+            this.output.set(decoded);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Reads an attachment part of a multipart/related response
+struct PartReader {
+    parts: Arc<Mutex<Multipart>>,
+    content_id: String,
+    pending: Pending,
+}
+
+impl AsyncRead for PartReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        while !this.pending.copy_to(buf) {
+            let mut parts = this.parts.lock().unwrap_or_else(|e| e.into_inner());
+            match ready!(parts.poll_part(cx, &this.content_id))? {
+                Some(data) => this.pending.set(data),
+                None => break,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A multipart/related body, read part by part
+struct Multipart {
+    body: BodyStream,
+    /// Bytes read but not consumed
+    buffer: Vec<u8>,
+    eof: bool,
+    /// `CRLF--boundary`
+    delimiter: Vec<u8>,
+    /// Whether `buffer` starts with the delimiter after a part
+    at_delimiter: bool,
+    /// The part whose content is read next
+    current: Option<Part>,
+    /// Content of parts skipped to reach a later part
+    skipped: HashMap<String, Vec<u8>>,
+    /// Parts that were read completely
+    finished: HashSet<String>,
+    /// Whether the closing delimiter was read
+    done: bool,
+}
+
+struct Part {
+    content_id: String,
+    decoder: Option<Base64Decoder>,
+}
+
+impl Multipart {
+    fn new(body: BodyStream, boundary: &str) -> Self {
+        Self {
+            body,
+            // The first delimiter needn't follow a line break
+            buffer: b"\r\n".to_vec(),
+            eof: false,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            at_delimiter: false,
+            current: None,
+            skipped: HashMap::new(),
+            finished: HashSet::new(),
+            done: false,
+        }
+    }
+
+    /// Start reading the first part, returning its Content-ID
+    fn poll_first_part(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<String>> {
+        let headers = ready!(self.poll_next_part(cx))?
+            .ok_or_else(|| invalid_data("Invalid MTOM response: no parts"))?;
+        let content_id = headers.content_id.clone();
+        self.current = Some(Part::new(headers));
+        Poll::Ready(Ok(content_id))
+    }
+
+    /// Read the next chunk of a part, skipping the parts before it
+    ///
+    /// Returns `None` once the part is read completely.
+    fn poll_part(
+        &mut self,
+        cx: &mut Context<'_>,
+        content_id: &str,
+    ) -> Poll<io::Result<Option<Vec<u8>>>> {
+        loop {
+            let is_current = self
+                .current
+                .as_ref()
+                .is_some_and(|part| part.content_id == content_id);
+            if let Some(data) = self.skipped.remove(content_id) {
+                if !is_current {
+                    self.finished.insert(content_id.to_string());
+                }
+                if !data.is_empty() {
+                    return Poll::Ready(Ok(Some(data)));
+                }
+                continue;
+            }
+            if self.finished.contains(content_id) {
+                return Poll::Ready(Ok(None));
+            }
+
+            let Some(part) = &mut self.current else {
+                if self.done {
+                    return Poll::Ready(Err(invalid_data(format!(
+                        "Invalid MTOM response: no part with Content-ID <{}>",
+                        content_id
+                    ))));
+                }
+                if let Some(headers) = ready!(self.poll_next_part(cx))? {
+                    self.current = Some(Part::new(headers));
+                }
+                continue;
+            };
+
+            let data = match ready!(poll_content(
+                &mut self.body,
+                &mut self.buffer,
+                &mut self.eof,
+                &self.delimiter,
+                cx
+            ))? {
+                Some(chunk) => part.decode(&chunk)?,
+                None => {
+                    self.at_delimiter = true;
+                    let mut part = self.current.take().expect("current part");
+                    let data = part.finish()?;
+                    if is_current {
+                        self.finished.insert(part.content_id);
+                    } else {
+                        self.skipped
+                            .entry(part.content_id)
+                            .or_default()
+                            .extend(data);
+                        continue;
+                    }
+                    data
+                }
+            };
+
+            if !is_current {
+                let part = self.current.as_ref().expect("current part");
+                self.skipped
+                    .entry(part.content_id.clone())
+                    .or_default()
+                    .extend(data);
+            } else if !data.is_empty() {
+                return Poll::Ready(Ok(Some(data)));
+            }
+        }
+    }
+
+    /// Skip to the next part and read its headers
+    ///
+    /// Returns `None` after the last part.
+    fn poll_next_part(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<PartHeaders>>> {
+        // Skip the preamble
+        while !self.at_delimiter {
+            if ready!(poll_content(
+                &mut self.body,
+                &mut self.buffer,
+                &mut self.eof,
+                &self.delimiter,
+                cx
+            ))?
+            .is_none()
+            {
+                self.at_delimiter = true;
+            }
+        }
+
+        let after = self.delimiter.len();
+        loop {
+            if self.buffer.len() >= after + 2 && self.buffer[after..].starts_with(b"--") {
+                self.done = true;
+                return Poll::Ready(Ok(None));
+            }
+            if let Some(end) = mtom::find(&self.buffer, b"\r\n\r\n", after) {
+                let headers = PartHeaders::parse(&self.buffer[after..end]);
+                self.buffer.drain(..end + 4);
+                self.at_delimiter = false;
+                return Poll::Ready(Ok(Some(headers)));
+            }
+            if self.eof {
+                return Poll::Ready(Err(invalid_data(
+                    "Invalid MTOM response: truncated part headers",
+                )));
+            }
+            ready!(poll_fill(
+                &mut self.body,
+                &mut self.buffer,
+                &mut self.eof,
+                cx
+            ))?;
+        }
+    }
+}
+
+impl Part {
+    fn new(headers: PartHeaders) -> Self {
+        Self {
+            content_id: headers.content_id,
+            decoder: headers.base64_encoded.then(Base64Decoder::default),
+        }
+    }
+
+    fn decode(&mut self, chunk: &[u8]) -> io::Result<Vec<u8>> {
+        match &mut self.decoder {
+            Some(decoder) => decoder.push(chunk),
+            None => Ok(chunk.to_vec()),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<Vec<u8>> {
+        match &mut self.decoder {
+            Some(decoder) => decoder.finish(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Read the next chunk of content before `delimiter`
+///
+/// Returns `None` once `buffer` starts with the delimiter.
+fn poll_content(
+    body: &mut BodyStream,
+    buffer: &mut Vec<u8>,
+    eof: &mut bool,
+    delimiter: &[u8],
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<Option<Vec<u8>>>> {
+    loop {
+        if let Some(position) = mtom::find(buffer, delimiter, 0) {
+            if position == 0 {
+                return Poll::Ready(Ok(None));
+            }
+            return Poll::Ready(Ok(Some(buffer.drain(..position).collect())));
+        }
+
+        // Keep what could be the start of the delimiter
+        let safe = buffer.len().saturating_sub(delimiter.len() - 1);
+        if safe > 0 {
+            return Poll::Ready(Ok(Some(buffer.drain(..safe).collect())));
+        }
+        if *eof {
+            return Poll::Ready(Err(invalid_data(
+                "Invalid MTOM response: missing closing boundary",
+            )));
+        }
+        ready!(poll_fill(body, buffer, eof, cx))?;
+    }
+}
+
+/// Append the next chunk of the body to `buffer`
+fn poll_fill(
+    body: &mut BodyStream,
+    buffer: &mut Vec<u8>,
+    eof: &mut bool,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>> {
+    let mut chunk = vec![0; CHUNK_SIZE];
+    let mut chunk_buf = ReadBuf::new(&mut chunk);
+    ready!(Pin::new(body).poll_read(cx, &mut chunk_buf))?;
+    let read = chunk_buf.filled();
+    *eof = read.is_empty();
+    buffer.extend_from_slice(read);
+    Poll::Ready(Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    /// Yields its content a few bytes at a time
+    struct Trickle {
+        data: Vec<u8>,
+        position: usize,
+    }
+
+    impl Trickle {
+        fn boxed(data: impl Into<Vec<u8>>) -> BodyStream {
+            Box::new(Self {
+                data: data.into(),
+                position: 0,
+            })
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let end = self.data.len().min(self.position + 7);
+            let len = buf.remaining().min(end - self.position);
+            buf.put_slice(&self.data[self.position..self.position + len]);
+            self.position += len;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn content(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    async fn read_all(mut reader: impl AsyncRead + Unpin) -> Vec<u8> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.unwrap();
+        data
+    }
+
+    #[test]
+    fn test_extractor() {
+        let text = "A".repeat(TEXT_THRESHOLD + 1);
+        let xml = format!(
+            "<?xml version=\"1.0\"?><!-- <b>x</b> --><a t=\"1>2\" u='<'><![CDATA[<b>]]><b>small</b><c>{}</c></a>",
+            text
+        );
+
+        let mut extractor = Extractor::new("x@soapus".to_string());
+        for chunk in xml.as_bytes().chunks(5) {
+            extractor.push(chunk);
+        }
+        let (envelope, texts) = extractor.finish();
+
+        assert_eq!(
+            String::from_utf8(envelope).unwrap(),
+            "<?xml version=\"1.0\"?><!-- <b>x</b> --><a t=\"1>2\" u='<'><![CDATA[<b>]]><b>small</b><c>cid:text-1.x@soapus</c></a>"
+        );
+        assert!(
+            matches!(&texts["text-1.x@soapus"], Text::Raw(raw) if raw.as_slice() == text.as_bytes())
+        );
+    }
+
+    /// Extract the single long text of `<a>` sent in chunks of 1000 bytes
+    fn extract(text: &[u8]) -> Text {
+        let xml = [b"<a>", text, b"</a>"].concat();
+        let mut extractor = Extractor::new("x@soapus".to_string());
+        for chunk in xml.chunks(1000) {
+            extractor.push(chunk);
+        }
+        let (envelope, mut texts) = extractor.finish();
+        assert_eq!(envelope, b"<a>cid:text-1.x@soapus</a>");
+        texts.remove("text-1.x@soapus").unwrap()
+    }
+
+    #[test]
+    fn test_extractor_decodes_base64() {
+        let data = content(100_000);
+        let wrapped: Vec<u8> = BASE64
+            .encode(&data)
+            .as_bytes()
+            .chunks(76)
+            .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+            .collect();
+        assert!(matches!(extract(&wrapped), Text::Decoded(decoded) if decoded[..] == data[..]));
+
+        // Not base64 from the start
+        let text = "long text. ".repeat(10_000);
+        assert!(matches!(extract(text.as_bytes()), Text::Raw(raw) if raw[..] == *text.as_bytes()));
+
+        // Not base64 beyond the first 64 KiB: restored as sent
+        let text = format!("{}\n{} end.", "A".repeat(80_000), "B".repeat(80_000));
+        assert!(matches!(extract(text.as_bytes()), Text::Raw(raw) if raw[..] == *text.as_bytes()));
+        let text = format!(
+            "{}\r\n{}\r\nAAA=\r\nAAAA",
+            "A".repeat(80_000),
+            "B".repeat(80_000)
+        );
+        assert!(matches!(extract(text.as_bytes()), Text::Raw(raw) if raw[..] == *text.as_bytes()));
+        // Lines of different length
+        let text = format!("{}\n{}\nAAAA", "A".repeat(80_000), "B".repeat(40_000));
+        assert!(matches!(extract(text.as_bytes()), Text::Raw(raw) if raw[..] == *text.as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn test_base64_streams() {
+        let data = content(100_003);
+        let encoded = read_all(Base64Encoder::new(Trickle::boxed(data.clone()))).await;
+        assert_eq!(encoded, BASE64.encode(&data).into_bytes());
+
+        // Line breaks as written by many servers
+        let wrapped: Vec<u8> = encoded
+            .chunks(76)
+            .flat_map(|line| line.iter().copied().chain(*b"\r\n"))
+            .collect();
+        let decoded = read_all(TextReader::new(Arc::new(wrapped))).await;
+        assert_eq!(decoded, data);
+
+        let error = read_all_result(TextReader::new(Arc::new(b"aGVsbG8".to_vec()))).await;
+        assert!(error.unwrap_err().to_string().contains("truncated"));
+    }
+
+    async fn read_all_result(mut reader: impl AsyncRead + Unpin) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+
+    #[derive(Deserialize)]
+    struct Document {
+        name: String,
+        content: BinaryStream,
+        thumbnail: BinaryStream,
+    }
+
+    #[tokio::test]
+    async fn test_receive_multipart() {
+        let content = content(200_000);
+        let mut body = concat!(
+            "preamble\r\n",
+            "--b1\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "Content-ID: <thumb@example.org>\r\n",
+            "\r\n",
+            "aGVs\r\nbG8=",
+            "\r\n--b1\r\n",
+            "Content-Type: application/xop+xml; type=\"text/xml\"\r\n",
+            "Content-ID: <root@example.org>\r\n",
+            "\r\n",
+            "<Document><name>scan.pdf</name>",
+            "<content><xop:Include xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:doc@example.org\"/></content>",
+            "<thumbnail><xop:Include xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:thumb@example.org\"/></thumbnail>",
+            "</Document>",
+            "\r\n--b1\r\n",
+            "Content-ID: <doc@example.org>\r\n",
+            "\r\n",
+        )
+        .as_bytes()
+        .to_vec();
+        body.extend_from_slice(&content);
+        body.extend_from_slice(b"\r\n--b1--\r\n");
+
+        let incoming = receive(
+            Some("multipart/related; boundary=b1; start=\"<root@example.org>\""),
+            Trickle::boxed(body),
+        )
+        .await
+        .unwrap();
+        assert!(incoming
+            .envelope
+            .contains("<content>cid:doc@example.org</content>"));
+
+        let document: Document = incoming
+            .parse(|xml| {
+                quick_xml::de::from_str(xml)
+                    .map_err(|e| SoapError::DeserializationError(e.to_string()))
+            })
+            .unwrap();
+        assert_eq!(document.name, "scan.pdf");
+        // The thumbnail preceded the root part and was buffered
+        assert_eq!(read_all(document.thumbnail).await, b"hello");
+        assert_eq!(read_all(document.content).await, content);
+    }
+
+    #[tokio::test]
+    async fn test_receive_out_of_order() {
+        let body = concat!(
+            "--b1\r\n\r\n",
+            "<Document><name>a</name><content>cid:one</content><thumbnail>cid:two</thumbnail></Document>",
+            "\r\n--b1\r\nContent-ID: <one>\r\n\r\nfirst",
+            "\r\n--b1\r\nContent-ID: <two>\r\n\r\nsecond",
+            "\r\n--b1--",
+        );
+        let incoming = receive(Some("multipart/related; boundary=b1"), Trickle::boxed(body))
+            .await
+            .unwrap();
+        let document: Document = incoming
+            .parse(|xml| {
+                quick_xml::de::from_str(xml)
+                    .map_err(|e| SoapError::DeserializationError(e.to_string()))
+            })
+            .unwrap();
+
+        // Reading the second part first buffers the first one
+        assert_eq!(read_all(document.thumbnail).await, b"second");
+        assert_eq!(read_all(document.content).await, b"first");
+    }
+
+    #[tokio::test]
+    async fn test_receive_long_text() {
+        #[derive(Deserialize)]
+        struct Note {
+            text: String,
+            content: BinaryStream,
+        }
+
+        let data = content(100_000);
+        let text = "word ".repeat(TEXT_THRESHOLD / 4) + "end";
+        let body = format!(
+            "<Note><text>{}</text><content>{}</content></Note>",
+            text,
+            BASE64.encode(&data)
+        );
+        let incoming = receive(None, Trickle::boxed(body)).await.unwrap();
+        assert!(incoming.envelope.len() < 200);
+
+        // The long string is put back, the binary content stays out
+        let note: Note = incoming
+            .parse(|xml| {
+                assert!(xml.len() < text.len() + 200);
+                quick_xml::de::from_str(xml)
+                    .map_err(|e| SoapError::DeserializationError(e.to_string()))
+            })
+            .unwrap();
+        assert_eq!(note.text, text);
+        assert_eq!(read_all(note.content).await, data);
+    }
+
+    #[tokio::test]
+    async fn test_body() {
+        #[derive(serde::Serialize)]
+        struct Upload {
+            name: String,
+            content: BinaryStream,
+        }
+
+        let upload = Upload {
+            name: "a".to_string(),
+            content: BinaryStream::from_bytes(b"hello".to_vec()),
+        };
+        let (envelope, streams) =
+            collect(|| Ok(quick_xml::se::to_string(&upload).unwrap())).unwrap();
+        assert!(envelope.starts_with("<Upload><name>a</name><content>cid:stream-1."));
+
+        let (content_type, body) = body(&envelope, "text/xml", Vec::new(), streams, false).unwrap();
+        assert_eq!(content_type, "text/xml");
+        assert_eq!(
+            read_all(body).await,
+            b"<Upload><name>a</name><content>aGVsbG8=</content></Upload>"
+        );
+
+        // A stream is only sent once
+        let Err(error) = collect(|| {
+            quick_xml::se::to_string(&upload)
+                .map_err(|e| SoapError::SerializationError(e.to_string()))
+        }) else {
+            panic!("content sent twice");
+        };
+        assert!(error.to_string().contains("can only be sent once"));
+        let error = quick_xml::se::to_string(&upload).unwrap_err();
+        assert!(error.to_string().contains("call_streaming"));
+    }
+}
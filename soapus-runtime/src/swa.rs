@@ -0,0 +1,119 @@
+//! SOAP with Attachments (SwA)
+//!
+//! SwA sends MIME attachments alongside the envelope in a `multipart/related`
+//! message. Unlike MTOM, the envelope keeps its own content type and refers
+//! to attachments by `cid:` URLs, e.g. in `href` attributes or `swaRef`
+//! elements (see [`Attachment::reference`]). Bindings with
+//! `mime:multipartRelated` in the WSDL use SwA.
+//!
+//! Attachments are sent with the [`CallOptions::attachment`] of a call. The
+//! attachments of any `multipart/related` response are available from
+//! [`SoapResponse::attachments`] and [`ResponseParts::attachments`].
+//!
+//! Requires the `swa` feature.
+//!
+//! [`CallOptions::attachment`]: crate::options::CallOptions::attachment
+//! [`SoapResponse::attachments`]: crate::header::SoapResponse::attachments
+//! [`ResponseParts::attachments`]: crate::envelope::ResponseParts::attachments
+//!
+//! # Example
+//!
+//! ```no_run
+//! use serde::{Deserialize, Serialize};
+//! use soapus_runtime::{Attachment, CallOptions, SoapClient};
+//!
+//! #[derive(Serialize)]
+//! struct SubmitClaim {
+//!     #[serde(rename = "claimId")]
+//!     claim_id: String,
+//!     /// `swaRef` to the scanned form
+//!     form: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct SubmitClaimResponse {
+//!     receipt: String,
+//! }
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SoapClient::new("http://example.com/claims");
+//!
+//! let form = Attachment::for_part("form", "image/tiff", std::fs::read("form.tif")?);
+//! let request = SubmitClaim {
+//!     claim_id: "C-42".to_string(),
+//!     form: form.reference(),
+//! };
+//! let options = CallOptions::new().attachment(form);
+//! let response = client
+//!     .call_with_options::<_, SubmitClaimResponse>(
+//!         "SubmitClaim",
+//!         None,
+//!         Some("urn:example:claims"),
+//!         true,
+//!         &request,
+//!         &options,
+//!     )
+//!     .await?;
+//!
+//! let receipt = response.attachment(&response.body().receipt);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::mtom::Attachment;
+
+/// Namespace of the WS-I `swaRef` type
+pub const SWA_REF_NS: &str = "http://ws-i.org/profiles/basic/1.1/xsd";
+
+/// Find the attachment a `cid:` URL or Content-ID refers to
+pub(crate) fn find<'a>(attachments: &'a [Attachment], reference: &str) -> Option<&'a Attachment> {
+    attachments
+        .iter()
+        .find(|attachment| attachment.matches(reference))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mtom;
+
+    #[test]
+    fn test_encode_decode() {
+        let form = Attachment::new("form=1@example.com", "image/tiff", b"II*\0\r\n--".to_vec());
+        let envelope = format!(
+            "<soap:Envelope xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\"><soap:Body><SubmitClaim><form>{}</form></SubmitClaim></soap:Body></soap:Envelope>",
+            form.reference()
+        );
+
+        let (content_type, body) = mtom::encode(
+            &envelope,
+            "text/xml; charset=utf-8",
+            mtom::Root::Plain,
+            std::slice::from_ref(&form),
+        )
+        .unwrap();
+        assert!(content_type.starts_with("multipart/related; type=\"text/xml\"; boundary="));
+        let text = String::from_utf8_lossy(&body);
+        assert!(text.contains("Content-Type: text/xml; charset=utf-8\r\n"));
+        assert!(text.contains("Content-ID: <form=1@example.com>"));
+
+        let (decoded, attachments) = mtom::decode(&content_type, &body).unwrap();
+        assert_eq!(decoded, envelope);
+        assert_eq!(attachments, vec![form.clone()]);
+        assert_eq!(find(&attachments, "cid:form%3D1@example.com"), Some(&form));
+        assert_eq!(find(&attachments, "<form=1@example.com>"), Some(&form));
+        assert_eq!(find(&attachments, "cid:other"), None);
+    }
+
+    #[test]
+    fn test_attachment_reference() {
+        let attachment = Attachment::new("photo=a b@example.com", "image/png", Vec::new());
+        assert_eq!(attachment.reference(), "cid:photo=a%20b@example.com");
+        assert!(attachment.matches(&attachment.reference()));
+        assert_eq!(attachment.part_name(), Some("photo"));
+
+        let generated = Attachment::for_part("photo", "image/png", Vec::new());
+        assert_eq!(generated.part_name(), Some("photo"));
+        assert!(generated.content_id().ends_with("@soapus"));
+    }
+}
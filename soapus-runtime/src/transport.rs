@@ -0,0 +1,1241 @@
+//! Pluggable transports
+//!
+//! A [`SoapTransport`] sends a serialized request and returns the raw
+//! response. [`SoapClient`](crate::SoapClient) handles everything else:
+//! envelopes, interceptors, HTTP status checks and SOAP faults.
+//!
+//! With the `streaming` feature, transports can additionally stream request
+//! and response bodies through [`SoapTransport::send_streaming`].
+//!
+//! Handshakes that authenticate a connection rather than a request, such as
+//! NTLM, send their requests on a [`SoapConnection`] opened with
+//! [`SoapTransport::connection`].
+//!
+//! Three transports are included:
+//!
+//! - [`ReqwestTransport`] - HTTP via `reqwest` and tokio (feature `reqwest`, enabled by default)
+//! - [`StdHttpTransport`] - plain HTTP/1.1 on `std::net`, for any executor and without
+//!   tokio (feature `std-http`)
+//! - [`MemoryTransport`] - canned responses for tests, no network involved
+//!
+//! The client itself does not depend on an executor. Without the `tokio`
+//! feature, e.g. with `default-features = false, features = ["std-http"]`,
+//! it runs on smol, async-std or any other executor.
+//!
+//! # Example
+//!
+//! ```
+//! use soapus_runtime::transport::{MemoryTransport, TransportResponse};
+//! use soapus_runtime::SoapClient;
+//!
+//! let transport = MemoryTransport::new();
+//! transport.push_response(TransportResponse::new(
+//!     200,
+//!     r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body/></soap:Envelope>"#,
+//! ));
+//!
+//! let client = SoapClient::builder("memory://service")
+//!     .transport(transport.clone())
+//!     .build();
+//! ```
+
+use crate::error::{SoapError, SoapResult};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "streaming")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// A serialized request handed to a transport
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransportRequest {
+    /// The endpoint URL
+    pub endpoint: String,
+    /// Request headers, e.g. `Content-Type` and `SOAPAction`
+    pub headers: Vec<(String, String)>,
+    /// The request body
+    pub body: Vec<u8>,
+    /// Maximum time to wait for the response
+    pub timeout: Duration,
+}
+
+/// A raw response returned by a transport
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransportResponse {
+    /// Status code (HTTP semantics)
+    pub status: u16,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+    /// The response body
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    /// Create a response without headers
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a response header
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A body that is read while it is transferred
+#[cfg(feature = "streaming")]
+pub type BodyStream = Box<dyn AsyncRead + Send + Unpin>;
+
+/// A request handed to a transport with a streamed body
+#[cfg(feature = "streaming")]
+pub struct StreamingRequest {
+    /// The endpoint URL
+    pub endpoint: String,
+    /// Request headers, e.g. `Content-Type` and `SOAPAction`
+    pub headers: Vec<(String, String)>,
+    /// The request body, of unknown length
+    pub body: BodyStream,
+    /// Maximum time to wait for the response
+    pub timeout: Duration,
+}
+
+/// A response returned by a transport with a streamed body
+#[cfg(feature = "streaming")]
+pub struct StreamingResponse {
+    /// Status code (HTTP semantics)
+    pub status: u16,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+    /// The response body, read on demand
+    pub body: BodyStream,
+}
+
+#[cfg(feature = "streaming")]
+impl fmt::Debug for StreamingRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingRequest")
+            .field("endpoint", &self.endpoint)
+            .field("headers", &self.headers)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "streaming")]
+impl fmt::Debug for StreamingResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamingResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sends serialized SOAP requests
+///
+/// Implementations only move bytes: non-success status codes are returned as
+/// a normal [`TransportResponse`], errors are reserved for failures to get a
+/// response at all. Report failures to connect as [`SoapError::ConnectError`]
+/// and expired timeouts as [`SoapError::Timeout`], so the retry policy can
+/// tell them apart. Use [`macro@async_trait`](crate::async_trait) on the impl block.
+#[async_trait]
+pub trait SoapTransport: Send + Sync {
+    /// Send a request and wait for the response
+    async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse>;
+
+    /// Send a request with a streamed body and stream the response body
+    ///
+    /// The default implementation reads the request body into memory, calls
+    /// [`send`](SoapTransport::send) and returns the buffered response body.
+    #[cfg(feature = "streaming")]
+    async fn send_streaming(&self, request: StreamingRequest) -> SoapResult<StreamingResponse> {
+        send_buffered(self, request).await
+    }
+
+    /// Open a connection that sends all its requests over one HTTP connection
+    ///
+    /// The default implementation returns `None`: the transport can't keep
+    /// requests on the same connection, so NTLM authentication fails with it.
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        Ok(None)
+    }
+}
+
+/// Requests sent one after another over the same HTTP connection
+///
+/// Opened with [`SoapTransport::connection`]. Use
+/// [`macro@async_trait`](crate::async_trait) on the impl block.
+#[async_trait]
+pub trait SoapConnection: Send {
+    /// Send a request on this connection and wait for the response
+    async fn send(&mut self, request: TransportRequest) -> SoapResult<TransportResponse>;
+}
+
+/// Send a streamed request through [`SoapTransport::send`], buffering both bodies
+#[cfg(feature = "streaming")]
+pub(crate) async fn send_buffered<T: SoapTransport + ?Sized>(
+    transport: &T,
+    mut request: StreamingRequest,
+) -> SoapResult<StreamingResponse> {
+    let mut body = Vec::new();
+    request
+        .body
+        .read_to_end(&mut body)
+        .await
+        .map_err(|e| SoapError::TransportError(format!("Failed to read request body: {}", e)))?;
+
+    let response = transport
+        .send(TransportRequest {
+            endpoint: request.endpoint,
+            headers: request.headers,
+            body,
+            timeout: request.timeout,
+        })
+        .await?;
+
+    Ok(StreamingResponse {
+        status: response.status,
+        headers: response.headers,
+        body: Box::new(std::io::Cursor::new(response.body)),
+    })
+}
+
+impl fmt::Debug for dyn SoapTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SoapTransport")
+    }
+}
+
+#[async_trait]
+impl<T: SoapTransport + ?Sized> SoapTransport for Arc<T> {
+    async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        (**self).send(request).await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn send_streaming(&self, request: StreamingRequest) -> SoapResult<StreamingResponse> {
+        (**self).send_streaming(request).await
+    }
+
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        (**self).connection()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+type ClientBuilder = dyn Fn() -> reqwest::ClientBuilder + Send + Sync;
+
+/// HTTP transport based on `reqwest`
+#[cfg(feature = "reqwest")]
+#[derive(Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    /// Builds the clients of [`SoapTransport::connection`], if known
+    builder: Option<Arc<ClientBuilder>>,
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::default(),
+            builder: Some(Arc::new(reqwest::Client::builder)),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl fmt::Debug for ReqwestTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReqwestTransport")
+            .field("client", &self.client)
+            .field("connections", &self.builder.is_some())
+            .finish()
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    /// Create a transport with a default `reqwest` client
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a transport using a configured `reqwest` client
+    ///
+    /// This allows custom settings such as proxies, TLS configuration or
+    /// connection pool limits. The transport can't open connections for NTLM
+    /// authentication; use [`with_builder`](Self::with_builder) for that.
+    pub fn with_client(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            builder: None,
+        }
+    }
+
+    /// Create a transport from a `reqwest` client configuration
+    ///
+    /// `builder` is called for the shared client and again for every
+    /// connection opened with [`SoapTransport::connection`].
+    pub fn with_builder<F>(builder: F) -> SoapResult<Self>
+    where
+        F: Fn() -> reqwest::ClientBuilder + Send + Sync + 'static,
+    {
+        Ok(Self {
+            client: builder().build()?,
+            builder: Some(Arc::new(builder)),
+        })
+    }
+}
+
+/// Send a request with a `reqwest` client and read the whole response
+#[cfg(feature = "reqwest")]
+async fn send_reqwest(
+    client: &reqwest::Client,
+    request: TransportRequest,
+) -> SoapResult<TransportResponse> {
+    let mut http_request = client
+        .post(&request.endpoint)
+        .timeout(request.timeout)
+        .body(request.body);
+    for (name, value) in &request.headers {
+        http_request = http_request.header(name, value);
+    }
+
+    let response = http_request.send().await?;
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.bytes().await?.to_vec();
+
+    Ok(TransportResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// A `reqwest` client that keeps a single HTTP/1.1 connection
+#[cfg(feature = "reqwest")]
+struct ReqwestConnection {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl SoapConnection for ReqwestConnection {
+    async fn send(&mut self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        send_reqwest(&self.client, request).await
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl SoapTransport for ReqwestTransport {
+    async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        send_reqwest(&self.client, request).await
+    }
+
+    #[cfg(feature = "streaming")]
+    async fn send_streaming(&self, request: StreamingRequest) -> SoapResult<StreamingResponse> {
+        use futures_util::TryStreamExt;
+        use tokio_util::io::{ReaderStream, StreamReader};
+
+        let mut http_request = self
+            .client
+            .post(&request.endpoint)
+            .timeout(request.timeout)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(request.body)));
+        for (name, value) in &request.headers {
+            http_request = http_request.header(name, value);
+        }
+
+        let response = http_request.send().await?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = StreamReader::new(response.bytes_stream().map_err(std::io::Error::other));
+
+        Ok(StreamingResponse {
+            status,
+            headers,
+            body: Box::new(body),
+        })
+    }
+
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        let Some(builder) = &self.builder else {
+            return Ok(None);
+        };
+        // Requests are sent one at a time, so they all reuse the one idle connection
+        let client = builder().pool_max_idle_per_host(1).http1_only().build()?;
+        Ok(Some(Box::new(ReqwestConnection { client })))
+    }
+}
+
+/// HTTP/1.1 transport on `std::net`, independent of any async runtime
+///
+/// Each request is sent over a new connection on a shared pool of at most 64
+/// background threads, so the transport works with any executor and builds
+/// without tokio. Dropping a pending request, e.g. when its call is
+/// cancelled, closes its connection and frees the thread. Connections opened
+/// with [`SoapTransport::connection`] are kept alive between requests. Only plain
+/// `http://` endpoints are supported: for HTTPS, use [`ReqwestTransport`] or
+/// a custom transport, or send through a TLS-terminating proxy. The timeout
+/// applies to connecting and to each read and write. Header names and values
+/// containing line breaks are rejected.
+#[cfg(feature = "std-http")]
+#[derive(Debug, Clone, Default)]
+pub struct StdHttpTransport {
+    _private: (),
+}
+
+#[cfg(feature = "std-http")]
+impl StdHttpTransport {
+    /// Create a transport
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "std-http")]
+#[async_trait]
+impl SoapTransport for StdHttpTransport {
+    async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        let abort = std_http::Abort::new();
+        let _guard = abort.guard();
+        crate::executor::on_thread(move || std_http::send(&request, &abort)).await
+    }
+
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        Ok(Some(Box::new(StdHttpConnection { open: None })))
+    }
+}
+
+/// A kept-alive connection of [`StdHttpTransport`]
+#[cfg(feature = "std-http")]
+struct StdHttpConnection {
+    open: Option<std_http::Connection>,
+}
+
+#[cfg(feature = "std-http")]
+#[async_trait]
+impl SoapConnection for StdHttpConnection {
+    async fn send(&mut self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        let mut open = self.open.take();
+        let abort = std_http::Abort::new();
+        let _guard = abort.guard();
+        let (result, open) = crate::executor::on_thread(move || {
+            let result = std_http::send_on(&mut open, &request, true, &abort);
+            (result, open)
+        })
+        .await;
+        self.open = open;
+        result
+    }
+}
+
+/// A minimal blocking HTTP/1.1 client for [`StdHttpTransport`]
+#[cfg(feature = "std-http")]
+mod std_http {
+    use super::{TransportRequest, TransportResponse};
+    use crate::error::{SoapError, SoapResult};
+    use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+    use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Duration;
+
+    /// Headers set by the transport itself
+    const CONNECTION_HEADERS: [&str; 4] =
+        ["Host", "Content-Length", "Connection", "Transfer-Encoding"];
+
+    /// An open connection and the authority it is connected to
+    pub(super) struct Connection {
+        authority: String,
+        reader: BufReader<TcpStream>,
+    }
+
+    /// Closes the socket of a request when the request is dropped
+    #[derive(Clone, Default)]
+    pub(super) struct Abort {
+        state: Arc<Mutex<AbortState>>,
+    }
+
+    #[derive(Default)]
+    struct AbortState {
+        aborted: bool,
+        /// A handle to the socket while the request is in flight
+        stream: Option<TcpStream>,
+    }
+
+    /// Aborts its request when dropped
+    pub(super) struct AbortGuard(Abort);
+
+    impl Abort {
+        pub(super) fn new() -> Self {
+            Self::default()
+        }
+
+        /// A guard aborting the request when the sending future is dropped
+        pub(super) fn guard(&self) -> AbortGuard {
+            AbortGuard(self.clone())
+        }
+
+        /// Track the socket of the request, failing if it was already aborted
+        fn start(&self, stream: &TcpStream) -> io::Result<()> {
+            let mut state = self.lock();
+            if state.aborted {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "request aborted",
+                ));
+            }
+            state.stream = Some(stream.try_clone()?);
+            Ok(())
+        }
+
+        /// Stop tracking the socket, so a kept-alive connection stays open
+        fn finish(&self) {
+            self.lock().stream = None;
+        }
+
+        fn lock(&self) -> MutexGuard<'_, AbortState> {
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    impl Drop for AbortGuard {
+        fn drop(&mut self) {
+            let mut state = self.0.lock();
+            state.aborted = true;
+            if let Some(stream) = state.stream.take() {
+                // Fails the blocked read or write of the request
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    pub(super) fn send(request: &TransportRequest, abort: &Abort) -> SoapResult<TransportResponse> {
+        send_on(&mut None, request, false, abort)
+    }
+
+    /// Send a request on `connection` if it is open to the same authority, otherwise on a new one
+    ///
+    /// With `keep_alive`, the connection is left in `connection` unless the
+    /// server closes it.
+    pub(super) fn send_on(
+        connection: &mut Option<Connection>,
+        request: &TransportRequest,
+        keep_alive: bool,
+        abort: &Abort,
+    ) -> SoapResult<TransportResponse> {
+        let (authority, path) = split_endpoint(&request.endpoint)?;
+        let timeout = Some(request.timeout).filter(|timeout| !timeout.is_zero());
+        let error = |e: io::Error| {
+            let message = format!("HTTP request to {} failed: {}", request.endpoint, e);
+            match e.kind() {
+                // Read timeouts are reported as `WouldBlock` on Unix
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => SoapError::Timeout(message),
+                _ => SoapError::TransportError(message),
+            }
+        };
+
+        let mut reader = match connection.take() {
+            Some(open) if open.authority == authority => open.reader,
+            _ => {
+                let stream = connect(authority, timeout).map_err(|e| match e.kind() {
+                    io::ErrorKind::TimedOut => error(e),
+                    _ => SoapError::ConnectError(format!(
+                        "Connecting to {} failed: {}",
+                        authority, e
+                    )),
+                })?;
+                BufReader::new(stream)
+            }
+        };
+        let stream = reader.get_ref();
+        stream.set_read_timeout(timeout).map_err(error)?;
+        stream.set_write_timeout(timeout).map_err(error)?;
+
+        abort.start(stream).map_err(error)?;
+        let response = write_request(stream, authority, path, request, keep_alive)
+            .and_then(|()| read_response(&mut reader));
+        abort.finish();
+        let response = response.map_err(error)?;
+        if keep_alive && persistent(&response.headers) {
+            *connection = Some(Connection {
+                authority: authority.to_string(),
+                reader,
+            });
+        }
+        Ok(response)
+    }
+
+    /// Whether the connection can be reused after a response with these headers
+    fn persistent(headers: &[(String, String)]) -> bool {
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.to_ascii_lowercase())
+        };
+        // Without a length, the body ended when the server closed the connection
+        let delimited = header("Content-Length").is_some()
+            || header("Transfer-Encoding").is_some_and(|v| v.contains("chunked"));
+        delimited && !header("Connection").is_some_and(|v| v.contains("close"))
+    }
+
+    /// Split an `http://` endpoint into authority and path
+    fn split_endpoint(endpoint: &str) -> SoapResult<(&str, &str)> {
+        let rest = endpoint
+            .split_once("://")
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("http"))
+            .map(|(_, rest)| rest)
+            .ok_or_else(|| {
+                SoapError::InvalidConfig(format!(
+                    "StdHttpTransport only supports http:// endpoints, not {}",
+                    endpoint
+                ))
+            })?;
+        let rest = rest.split('#').next().unwrap_or(rest);
+        let end = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path) = rest.split_at(end);
+        let authority = authority.rsplit('@').next().unwrap_or(authority);
+        Ok((authority, if path.is_empty() { "/" } else { path }))
+    }
+
+    fn connect(authority: &str, timeout: Option<Duration>) -> io::Result<TcpStream> {
+        // A port follows the last colon, unless it is part of an IPv6 address
+        let address = match authority.rfind(':') {
+            Some(colon) if !authority[colon..].contains(']') => authority.to_string(),
+            _ => format!("{}:80", authority),
+        };
+
+        let mut last_error = None;
+        for address in address.to_socket_addrs()? {
+            let stream = match timeout {
+                Some(timeout) => TcpStream::connect_timeout(&address, timeout),
+                None => TcpStream::connect(address),
+            };
+            match stream {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} did not resolve", authority),
+            )
+        }))
+    }
+
+    fn write_request(
+        stream: &TcpStream,
+        authority: &str,
+        path: &str,
+        request: &TransportRequest,
+        keep_alive: bool,
+    ) -> io::Result<()> {
+        // A line break would end the header early and let the rest pass as another header
+        let line_break = |text: &str| text.contains(['\r', '\n']);
+        if line_break(path) {
+            return Err(invalid_input("request path"));
+        }
+        if let Some((name, _)) = request.headers.iter().find(|(name, value)| {
+            name.is_empty() || name.contains(':') || line_break(name) || line_break(value)
+        }) {
+            return Err(invalid_input(&format!("header {:?}", name)));
+        }
+
+        let mut writer = BufWriter::new(stream);
+        write!(writer, "POST {} HTTP/1.1\r\nHost: {}\r\n", path, authority)?;
+        for (name, value) in &request.headers {
+            if !CONNECTION_HEADERS
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(name))
+            {
+                write!(writer, "{}: {}\r\n", name, value)?;
+            }
+        }
+        write!(
+            writer,
+            "Content-Length: {}\r\nConnection: {}\r\n\r\n",
+            request.body.len(),
+            if keep_alive { "keep-alive" } else { "close" }
+        )?;
+        writer.write_all(&request.body)?;
+        writer.flush()
+    }
+
+    fn read_response(mut reader: impl BufRead) -> io::Result<TransportResponse> {
+        // Interim responses, e.g. `100 Continue`, precede the final one
+        let (status, headers) = loop {
+            let status = read_status(&mut reader)?;
+            let headers = read_headers(&mut reader)?;
+            if status >= 200 {
+                break (status, headers);
+            }
+        };
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        let mut body = Vec::new();
+        if header("Transfer-Encoding").is_some_and(|v| v.to_ascii_lowercase().contains("chunked")) {
+            read_chunked(&mut reader, &mut body)?;
+        } else if let Some(length) = header("Content-Length") {
+            let length = length
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| invalid("Content-Length"))?;
+            reader.take(length).read_to_end(&mut body)?;
+            if (body.len() as u64) < length {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        } else {
+            reader.read_to_end(&mut body)?;
+        }
+
+        Ok(TransportResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    fn read_status(reader: &mut impl BufRead) -> io::Result<u16> {
+        let line = read_line(reader)?;
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(version), Some(status)) if version.starts_with("HTTP/") => {
+                status.parse().map_err(|_| invalid("status line"))
+            }
+            _ => Err(invalid("status line")),
+        }
+    }
+
+    fn read_headers(reader: &mut impl BufRead) -> io::Result<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader)?;
+            if line.is_empty() {
+                return Ok(headers);
+            }
+            let (name, value) = line.split_once(':').ok_or_else(|| invalid("header"))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    fn read_chunked(reader: &mut impl BufRead, body: &mut Vec<u8>) -> io::Result<()> {
+        loop {
+            let line = read_line(reader)?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16).map_err(|_| invalid("chunk size"))?;
+            if size == 0 {
+                // Skip trailers
+                read_headers(reader)?;
+                return Ok(());
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            read_line(reader)?;
+        }
+    }
+
+    /// Read a line without its line break
+    fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        while line.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line).map_err(|_| invalid("line"))
+    }
+
+    fn invalid(what: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid HTTP {}", what))
+    }
+
+    fn invalid_input(what: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("invalid {}", what))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_split_endpoint() {
+            assert_eq!(
+                split_endpoint("http://user@example.com:8080/soap?wsdl#x").unwrap(),
+                ("example.com:8080", "/soap?wsdl")
+            );
+            assert_eq!(
+                split_endpoint("HTTP://example.com").unwrap(),
+                ("example.com", "/")
+            );
+            assert!(matches!(
+                split_endpoint("https://example.com/soap"),
+                Err(SoapError::InvalidConfig(_))
+            ));
+        }
+
+        #[test]
+        fn test_read_response() {
+            let response = read_response(
+                &b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n<Env \r\n3;ext=1\r\n/>x\r\n0\r\nX-Trailer: 1\r\n\r\n"[..],
+            )
+            .unwrap();
+            assert_eq!(response.status, 200);
+            assert_eq!(
+                response.headers[0],
+                ("Content-Type".to_string(), "text/xml".to_string())
+            );
+            assert_eq!(response.body, b"<Env />x");
+
+            let response =
+                read_response(&b"HTTP/1.0 500 Error\r\nContent-Length: 4\r\n\r\nfault"[..])
+                    .unwrap();
+            assert_eq!(response.status, 500);
+            assert_eq!(response.body, b"faul");
+
+            assert!(
+                read_response(&b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nshort"[..]).is_err()
+            );
+            assert!(read_response(&b"garbage\r\n\r\n"[..]).is_err());
+        }
+
+        #[test]
+        fn test_std_http_transport() {
+            use crate::transport::{SoapTransport, StdHttpTransport};
+            use std::net::TcpListener;
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/soap", listener.local_addr().unwrap());
+            let server = std::thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let request_line = read_line(&mut reader).unwrap();
+                let headers = read_headers(&mut reader).unwrap();
+                let mut body = vec![0; 8];
+                reader.read_exact(&mut body).unwrap();
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\npong!")
+                    .unwrap();
+                (request_line, headers, body)
+            });
+
+            let request = TransportRequest {
+                endpoint,
+                headers: vec![
+                    ("SOAPAction".to_string(), "\"Ping\"".to_string()),
+                    ("Content-Length".to_string(), "1".to_string()),
+                ],
+                body: b"<Ping />".to_vec(),
+                timeout: std::time::Duration::from_secs(5),
+            };
+            let response =
+                futures_executor::block_on(StdHttpTransport::new().send(request)).unwrap();
+            assert_eq!(response.status, 200);
+            assert_eq!(response.body, b"pong!");
+
+            let (request_line, headers, body) = server.join().unwrap();
+            assert_eq!(request_line, "POST /soap HTTP/1.1");
+            assert!(headers.contains(&("SOAPAction".to_string(), "\"Ping\"".to_string())));
+            assert!(headers.contains(&("Content-Length".to_string(), "8".to_string())));
+            assert_eq!(body, b"<Ping />");
+        }
+
+        #[test]
+        fn test_std_http_errors() {
+            use crate::transport::{SoapTransport, StdHttpTransport};
+            use std::net::TcpListener;
+
+            let request = |endpoint: String| TransportRequest {
+                endpoint,
+                headers: Vec::new(),
+                body: b"<Ping />".to_vec(),
+                timeout: std::time::Duration::from_millis(200),
+            };
+
+            // Nothing listens on the port once the listener is dropped
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/soap", listener.local_addr().unwrap());
+            drop(listener);
+            let result =
+                futures_executor::block_on(StdHttpTransport::new().send(request(endpoint)));
+            assert!(matches!(result, Err(SoapError::ConnectError(_))));
+
+            // The server accepts, but never answers
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/soap", listener.local_addr().unwrap());
+            let result =
+                futures_executor::block_on(StdHttpTransport::new().send(request(endpoint.clone())));
+            assert!(matches!(result, Err(SoapError::Timeout(_))));
+
+            // Headers must not smuggle in further headers
+            for header in [
+                ("X-Tenant", "acme\r\nX-Admin: 1"),
+                ("X-Admin:", "1"),
+                ("", "1"),
+            ] {
+                let mut request = request(endpoint.clone());
+                request
+                    .headers
+                    .push((header.0.to_string(), header.1.to_string()));
+                let result = futures_executor::block_on(StdHttpTransport::new().send(request));
+                assert!(
+                    matches!(&result, Err(SoapError::TransportError(e)) if e.contains("invalid header")),
+                    "{:?}",
+                    result
+                );
+            }
+            drop(listener);
+        }
+
+        #[test]
+        fn test_std_http_zero_timeout() {
+            use crate::transport::{SoapTransport, StdHttpTransport};
+            use std::net::TcpListener;
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/soap", listener.local_addr().unwrap());
+            let server = std::thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                read_line(&mut reader).unwrap();
+                read_headers(&mut reader).unwrap();
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                    .unwrap();
+            });
+
+            // A zero timeout means no timeout
+            let request = TransportRequest {
+                endpoint,
+                headers: Vec::new(),
+                body: Vec::new(),
+                timeout: Duration::ZERO,
+            };
+            let response =
+                futures_executor::block_on(StdHttpTransport::new().send(request)).unwrap();
+            assert_eq!(response.status, 200);
+            server.join().unwrap();
+        }
+
+        #[test]
+        fn test_std_http_abort() {
+            use crate::transport::{SoapTransport, StdHttpTransport};
+            use std::net::TcpListener;
+            use std::task::{Context, Waker};
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let endpoint = format!("http://{}/soap", listener.local_addr().unwrap());
+            let (accepted, connected) = std::sync::mpsc::channel();
+            let server = std::thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                accepted.send(()).unwrap();
+                // The server never answers, so only the client can end the connection
+                stream
+                    .set_read_timeout(Some(Duration::from_secs(5)))
+                    .unwrap();
+                let mut request = Vec::new();
+                stream.read_to_end(&mut request)
+            });
+
+            let request = TransportRequest {
+                endpoint,
+                headers: Vec::new(),
+                body: b"<Ping />".to_vec(),
+                timeout: Duration::from_secs(30),
+            };
+            let transport = StdHttpTransport::new();
+            let mut send = transport.send(request);
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(send.as_mut().poll(&mut cx).is_pending());
+            connected.recv().unwrap();
+            drop(send);
+
+            // Reading ends with the shutdown of the client, not the timeout
+            assert!(server.join().unwrap().is_ok());
+        }
+    }
+}
+
+type Handler = dyn Fn(&TransportRequest) -> SoapResult<TransportResponse> + Send + Sync;
+
+#[derive(Default)]
+struct MemoryState {
+    responses: VecDeque<TransportResponse>,
+    requests: Vec<TransportRequest>,
+}
+
+/// In-memory transport for tests
+///
+/// Answers requests with queued responses, or with a handler function if no
+/// response is queued. All sent requests are recorded. Clones share the same
+/// queue and records, so a clone can be passed to the client while the
+/// original is kept for assertions.
+#[derive(Clone, Default)]
+pub struct MemoryTransport {
+    state: Arc<Mutex<MemoryState>>,
+    handler: Option<Arc<Handler>>,
+}
+
+impl MemoryTransport {
+    /// Create a transport without responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a transport that answers every request with `handler`
+    pub fn with_handler<F>(handler: F) -> Self
+    where
+        F: Fn(&TransportRequest) -> SoapResult<TransportResponse> + Send + Sync + 'static,
+    {
+        Self {
+            state: Arc::default(),
+            handler: Some(Arc::new(handler)),
+        }
+    }
+
+    /// Queue a response; queued responses are returned in order
+    pub fn push_response(&self, response: TransportResponse) {
+        self.lock().responses.push_back(response);
+    }
+
+    /// Get all requests sent so far
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MemoryState> {
+        // A panicking handler must not poison the records for later assertions
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for MemoryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("MemoryTransport")
+            .field("queued", &state.responses.len())
+            .field("requests", &state.requests.len())
+            .field("handler", &self.handler.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl SoapTransport for MemoryTransport {
+    async fn send(&self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        let queued = {
+            let mut state = self.lock();
+            state.requests.push(request.clone());
+            state.responses.pop_front()
+        };
+
+        match (queued, &self.handler) {
+            (Some(response), _) => Ok(response),
+            (None, Some(handler)) => handler(&request),
+            (None, None) => Err(SoapError::TransportError(format!(
+                "No response queued for request to {}",
+                request.endpoint
+            ))),
+        }
+    }
+
+    fn connection(&self) -> SoapResult<Option<Box<dyn SoapConnection>>> {
+        Ok(Some(Box::new(MemoryConnection(self.clone()))))
+    }
+}
+
+/// Requests on a connection of a [`MemoryTransport`] are answered and recorded like any other
+struct MemoryConnection(MemoryTransport);
+
+#[async_trait]
+impl SoapConnection for MemoryConnection {
+    async fn send(&mut self, request: TransportRequest) -> SoapResult<TransportResponse> {
+        self.0.send(request).await
+    }
+}
+
+/// Transport used when no transport is configured and no HTTP transport is enabled
+#[cfg(not(any(feature = "reqwest", feature = "std-http")))]
+pub(crate) struct NoTransport;
+
+#[cfg(not(any(feature = "reqwest", feature = "std-http")))]
+#[async_trait]
+impl SoapTransport for NoTransport {
+    async fn send(&self, _request: TransportRequest) -> SoapResult<TransportResponse> {
+        Err(SoapError::InvalidConfig(
+            "No transport configured: enable the `reqwest` or `std-http` feature or set SoapClientBuilder::transport"
+                .to_string(),
+        ))
+    }
+}
+
+/// The transport used by clients that do not configure one
+///
+/// `reqwest` is preferred over `std-http` if both are enabled.
+pub(crate) fn default_transport() -> Arc<dyn SoapTransport> {
+    #[cfg(feature = "reqwest")]
+    {
+        Arc::new(ReqwestTransport::new())
+    }
+    #[cfg(all(feature = "std-http", not(feature = "reqwest")))]
+    {
+        Arc::new(StdHttpTransport::new())
+    }
+    #[cfg(not(any(feature = "reqwest", feature = "std-http")))]
+    {
+        Arc::new(NoTransport)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(endpoint: &str) -> TransportRequest {
+        TransportRequest {
+            endpoint: endpoint.to_string(),
+            headers: Vec::new(),
+            body: b"<Envelope/>".to_vec(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_queue() {
+        let transport = MemoryTransport::new();
+        transport.push_response(TransportResponse::new(200, "first").with_header("X-Id", "1"));
+        transport.push_response(TransportResponse::new(500, "second"));
+
+        let first = transport.send(request("memory://a")).await.unwrap();
+        assert_eq!(first.body, b"first");
+        assert_eq!(first.headers, vec![("X-Id".to_string(), "1".to_string())]);
+        assert_eq!(
+            transport.send(request("memory://b")).await.unwrap().status,
+            500
+        );
+        assert!(matches!(
+            transport.send(request("memory://c")).await,
+            Err(SoapError::TransportError(_))
+        ));
+
+        let endpoints: Vec<String> = transport
+            .requests()
+            .into_iter()
+            .map(|r| r.endpoint)
+            .collect();
+        assert_eq!(endpoints, vec!["memory://a", "memory://b", "memory://c"]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_transport_handler() {
+        let transport = MemoryTransport::with_handler(|request| {
+            Ok(TransportResponse::new(200, request.body.clone()))
+        });
+
+        let response = transport.send(request("memory://echo")).await.unwrap();
+        assert_eq!(response.body, b"<Envelope/>");
+    }
+
+    /// Serve a single HTTP request with the given status and body, returning the raw request
+    #[cfg(feature = "reqwest")]
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, std::thread::JoinHandle<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/soap", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text[..header_end]
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn test_reqwest_transport() {
+        let (url, server) = serve_once("404 Not Found", "missing");
+        let transport = ReqwestTransport::new();
+
+        let mut request = request(&url);
+        request
+            .headers
+            .push(("SOAPAction".to_string(), "\"urn:Op\"".to_string()));
+        let response = transport.send(request).await.unwrap();
+
+        // Status codes are passed through, not turned into errors
+        assert_eq!(response.status, 404);
+        assert_eq!(response.body, b"missing");
+        assert!(response
+            .headers
+            .iter()
+            .any(|(name, value)| name == "content-type" && value == "text/xml"));
+
+        let raw = server.join().unwrap();
+        assert!(raw.to_ascii_lowercase().contains("soapaction: \"urn:op\""));
+        assert!(raw.ends_with("<Envelope/>"));
+    }
+}
@@ -0,0 +1,662 @@
+//! WS-Security (OASIS Web Services Security 1.0)
+//!
+//! [`WsSecurity`] adds a `wsse:Security` header block to every request of a
+//! [`SoapClient`](crate::SoapClient). Its content is built anew for every
+//! attempt, including retries, so nonces and timestamps are never reused:
+//!
+//! - **UsernameToken** - username and password, either as `PasswordText` or
+//!   as `PasswordDigest` (`Base64(SHA-1(nonce + created + password))`) with
+//!   `Nonce` and `Created`
+//! - **Timestamp** - a `wsu:Timestamp` with `Created` and `Expires`, limiting
+//!   how long the message is accepted by the service
+//! - **X.509 signature** - an XML signature over `Body` and `Timestamp` made
+//!   with a [`X509Credential`], and verification of signed responses against
+//!   trusted [`X509Certificate`]s (see [`dsig`](crate::dsig))
+//! - **XML encryption** - AES-GCM encryption of `Body` and header blocks for a
+//!   recipient certificate, and decryption of encrypted responses (see
+//!   [`xmlenc`](crate::xmlenc))
+//!
+//! Requires the `ws-security` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{SoapClient, UsernameToken, WsSecurity};
+//! use std::time::Duration;
+//!
+//! let client = SoapClient::builder("http://example.com/soap")
+//!     .ws_security(
+//!         WsSecurity::new()
+//!             .username_token(UsernameToken::digest("alice", "secret"))
+//!             .timestamp(Duration::from_secs(300)),
+//!     )
+//!     .build();
+//! ```
+
+use crate::c14n;
+use crate::dsig::{self, X509Certificate, X509Credential};
+use crate::error::{SoapError, SoapResult};
+use crate::header::SoapHeader;
+use crate::interceptor::{Interceptor, RawResponse, RequestContext, SoapRequest};
+use crate::random::{hex, random_bytes};
+use crate::xmlenc;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use quick_xml::escape::escape;
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Namespace of the `wsse` elements
+pub const WSSE_NS: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd";
+
+/// Namespace of the `wsu` elements and attributes
+pub const WSU_NS: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd";
+
+const USERNAME_TOKEN_PROFILE: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0";
+
+/// Tolerated clock difference when checking the timestamp of signed responses
+pub const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(300);
+
+pub(crate) const BASE64_BINARY: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary";
+
+/// How the password of a [`UsernameToken`] is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordType {
+    /// The password in clear text, only safe over TLS
+    Text,
+    /// A SHA-1 digest of nonce, creation time and password
+    Digest,
+}
+
+/// Username and password sent in a `wsse:UsernameToken`
+#[derive(Clone)]
+pub struct UsernameToken {
+    username: String,
+    password: String,
+    password_type: PasswordType,
+    nonce: bool,
+}
+
+impl UsernameToken {
+    /// Send the password as `PasswordText`
+    pub fn text(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            password_type: PasswordType::Text,
+            nonce: false,
+        }
+    }
+
+    /// Send the password as `PasswordDigest`, always with `Nonce` and `Created`
+    pub fn digest(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            password_type: PasswordType::Digest,
+            nonce: true,
+        }
+    }
+
+    /// Also send `Nonce` and `Created` with a `PasswordText` token
+    ///
+    /// Lets the service detect replayed messages. Digest tokens always include them.
+    pub fn nonce(mut self, enable: bool) -> Self {
+        self.nonce = enable || self.password_type == PasswordType::Digest;
+        self
+    }
+
+    /// Get the username
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Get how the password is sent
+    pub fn password_type(&self) -> PasswordType {
+        self.password_type
+    }
+
+    fn to_xml(&self, created: &str, nonce: &[u8]) -> String {
+        let mut xml = format!(
+            "<wsse:UsernameToken><wsse:Username>{}</wsse:Username>",
+            escape(self.username.as_str())
+        );
+
+        match self.password_type {
+            PasswordType::Text => xml.push_str(&format!(
+                "<wsse:Password Type=\"{}#PasswordText\">{}</wsse:Password>",
+                USERNAME_TOKEN_PROFILE,
+                escape(self.password.as_str())
+            )),
+            PasswordType::Digest => xml.push_str(&format!(
+                "<wsse:Password Type=\"{}#PasswordDigest\">{}</wsse:Password>",
+                USERNAME_TOKEN_PROFILE,
+                password_digest(nonce, created, &self.password)
+            )),
+        }
+
+        if self.nonce {
+            xml.push_str(&format!(
+                "<wsse:Nonce EncodingType=\"{}\">{}</wsse:Nonce><wsu:Created>{}</wsu:Created>",
+                BASE64_BINARY,
+                BASE64.encode(nonce),
+                created
+            ));
+        }
+
+        xml.push_str("</wsse:UsernameToken>");
+        xml
+    }
+}
+
+impl fmt::Debug for UsernameToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UsernameToken")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .field("password_type", &self.password_type)
+            .field("nonce", &self.nonce)
+            .finish()
+    }
+}
+
+/// The `wsse:Security` header sent with every request
+///
+/// Registered with
+/// [`SoapClientBuilder::ws_security`](crate::client::SoapClientBuilder::ws_security),
+/// or as an [`Interceptor`] on an existing client.
+#[derive(Debug, Clone)]
+pub struct WsSecurity {
+    username_token: Option<UsernameToken>,
+    timestamp_ttl: Option<Duration>,
+    must_understand: bool,
+    signing: Option<X509Credential>,
+    trusted: Vec<X509Certificate>,
+    clock_skew: Duration,
+    recipient: Option<X509Certificate>,
+    encrypted_headers: Vec<String>,
+    decryption: Option<X509Credential>,
+}
+
+impl WsSecurity {
+    /// Create an empty security header with `mustUnderstand` set
+    pub fn new() -> Self {
+        Self {
+            username_token: None,
+            timestamp_ttl: None,
+            must_understand: true,
+            signing: None,
+            trusted: Vec::new(),
+            clock_skew: DEFAULT_CLOCK_SKEW,
+            recipient: None,
+            encrypted_headers: Vec::new(),
+            decryption: None,
+        }
+    }
+
+    /// Send a `wsse:UsernameToken`
+    pub fn username_token(mut self, token: UsernameToken) -> Self {
+        self.username_token = Some(token);
+        self
+    }
+
+    /// Send a `wsu:Timestamp` expiring `ttl` after the request is built
+    pub fn timestamp(mut self, ttl: Duration) -> Self {
+        self.timestamp_ttl = Some(ttl);
+        self
+    }
+
+    /// Sign `Body` and `Timestamp` of every request with an X.509 credential
+    ///
+    /// The certificate is sent as `wsse:BinarySecurityToken`.
+    pub fn sign_with(mut self, credential: X509Credential) -> Self {
+        self.signing = Some(credential);
+        self
+    }
+
+    /// Require successful responses to be signed with this certificate
+    ///
+    /// May be called several times to trust more than one certificate.
+    /// Responses without a valid signature fail with
+    /// [`SoapError::SecurityError`]; SOAP faults are not checked. A signed
+    /// `wsu:Timestamp` must not be expired or created in the future.
+    pub fn verify_with(mut self, certificate: X509Certificate) -> Self {
+        self.trusted.push(certificate);
+        self
+    }
+
+    /// Tolerated difference to the service's clock when checking the timestamp
+    /// of signed responses (default [`DEFAULT_CLOCK_SKEW`])
+    pub fn clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Encrypt the `Body` content of every request for the recipient's certificate
+    ///
+    /// Requests are signed before they are encrypted.
+    pub fn encrypt_for(mut self, recipient: X509Certificate) -> Self {
+        self.recipient = Some(recipient);
+        self
+    }
+
+    /// Also encrypt the header block with this local name, as `wsse11:EncryptedHeader`
+    ///
+    /// Only used together with [`encrypt_for`](Self::encrypt_for).
+    pub fn encrypt_header(mut self, name: impl Into<String>) -> Self {
+        self.encrypted_headers.push(name.into());
+        self
+    }
+
+    /// Decrypt encrypted responses with the private key of this credential
+    ///
+    /// Successful responses must be encrypted; SOAP faults may be sent in
+    /// clear text. Responses are decrypted before their signature is verified.
+    pub fn decrypt_with(mut self, credential: X509Credential) -> Self {
+        self.decryption = Some(credential);
+        self
+    }
+
+    /// Set `mustUnderstand` on the header block (default true)
+    pub fn must_understand(mut self, must_understand: bool) -> Self {
+        self.must_understand = must_understand;
+        self
+    }
+
+    /// Build the header block for a request sent now
+    pub fn header(&self) -> SoapResult<SoapHeader> {
+        Ok(self.header_at(SystemTime::now(), &random_bytes::<16>()?))
+    }
+
+    fn header_at(&self, now: SystemTime, nonce: &[u8]) -> SoapHeader {
+        let xml = format!(
+            "<wsse:Security xmlns:wsse=\"{}\" xmlns:wsu=\"{}\">{}</wsse:Security>",
+            WSSE_NS,
+            WSU_NS,
+            self.content_at(now, nonce)
+        );
+        SoapHeader::raw("Security", xml).must_understand(self.must_understand)
+    }
+
+    /// The children of the header block, using the `wsse` and `wsu` prefixes
+    fn content_at(&self, now: SystemTime, nonce: &[u8]) -> String {
+        let created = format_timestamp(now);
+        let mut xml = String::new();
+
+        // Signed parts need an id to be referenced from the signature
+        let id_suffix = hex(nonce);
+        if let Some(ttl) = self.timestamp_ttl {
+            let id = match self.signing {
+                Some(_) => format!(" wsu:Id=\"TS-{}\"", id_suffix),
+                None => String::new(),
+            };
+            xml.push_str(&format!(
+                "<wsu:Timestamp{}><wsu:Created>{}</wsu:Created><wsu:Expires>{}</wsu:Expires></wsu:Timestamp>",
+                id,
+                created,
+                format_timestamp(now + ttl)
+            ));
+        }
+        if let Some(credential) = &self.signing {
+            xml.push_str(&credential.token_xml(&format!("X509-{}", id_suffix)));
+        }
+        if let Some(token) = &self.username_token {
+            xml.push_str(&token.to_xml(&created, nonce));
+        }
+        xml
+    }
+}
+
+/// Check for a `wsse:Security` header block, e.g. one set on the client
+fn is_security_header(header: &SoapHeader) -> bool {
+    header.name() == "Security"
+        && c14n::parse(header.xml()).is_ok_and(|root| root.is(WSSE_NS, "Security"))
+}
+
+impl Default for WsSecurity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Interceptor for WsSecurity {
+    async fn before_serialize(&self, context: &mut RequestContext) -> SoapResult<()> {
+        context.headers.retain(|header| !is_security_header(header));
+        context.headers.push(self.header()?);
+        Ok(())
+    }
+
+    async fn before_send(&self, request: &mut SoapRequest) -> SoapResult<()> {
+        // The envelope is built once per call, but each attempt needs a fresh nonce and timestamp
+        let root = c14n::parse(&request.envelope)?;
+        // An empty element was not built by `before_serialize`
+        if let Some(security) = dsig::security(&root)
+            .ok()
+            .filter(|security| security.outer_end > security.end)
+        {
+            let content = self.content_at(SystemTime::now(), &random_bytes::<16>()?);
+            request
+                .envelope
+                .replace_range(security.content_start..security.end, &content);
+        }
+
+        let id_suffix = hex(&random_bytes::<16>()?);
+        if let Some(credential) = &self.signing {
+            let body_id = format!("id-{}", id_suffix);
+            request.envelope = dsig::sign_envelope(&request.envelope, credential, &body_id)?;
+        }
+        if let Some(recipient) = &self.recipient {
+            request.envelope = xmlenc::encrypt_envelope(
+                &request.envelope,
+                recipient,
+                &self.encrypted_headers,
+                &id_suffix,
+            )?;
+        }
+        Ok(())
+    }
+
+    async fn after_receive(
+        &self,
+        _request: &SoapRequest,
+        response: &mut RawResponse,
+    ) -> SoapResult<()> {
+        let success = (200..300).contains(&response.status);
+        if let Some(credential) = &self.decryption {
+            match xmlenc::decrypt_envelope(&response.body, credential) {
+                Ok(Some(decrypted)) => response.body = decrypted,
+                Ok(None) if success => {
+                    return Err(SoapError::SecurityError(
+                        "Response is not encrypted".to_string(),
+                    ))
+                }
+                Ok(None) => {}
+                Err(SoapError::SecurityError(message)) if !success => {
+                    return Err(SoapError::SecurityError(format!(
+                        "{} (SOAP fault response, HTTP {})",
+                        message, response.status
+                    )))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.trusted.is_empty() && success {
+            dsig::verify_envelope(
+                &response.body,
+                &self.trusted,
+                SystemTime::now(),
+                self.clock_skew,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// `Base64(SHA-1(nonce + created + password))`
+fn password_digest(nonce: &[u8], created: &str, password: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(nonce);
+    hasher.update(created.as_bytes());
+    hasher.update(password.as_bytes());
+    BASE64.encode(hasher.finalize())
+}
+
+/// Format a time as UTC `xsd:dateTime` with milliseconds, e.g. `2026-10-18T12:00:00.000Z`
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Parse an `xsd:dateTime`, e.g. `2026-10-18T12:00:00.000Z`
+///
+/// Times without a time zone are taken as UTC.
+pub(crate) fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let (date, time) = text.trim().split_once('T')?;
+    let mut date = date.splitn(3, '-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(sign) = time.rfind(['+', '-']) {
+        let (hours, minutes) = time[sign + 1..].split_once(':')?;
+        let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        let offset = if time[sign..].starts_with('-') {
+            -offset
+        } else {
+            offset
+        };
+        (&time[..sign], offset)
+    } else {
+        (time, 0)
+    };
+    let mut time = time.splitn(3, ':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let seconds = time.next()?;
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    let seconds: i64 = seconds.parse().ok()?;
+    let nanos = match fraction {
+        "" => 0,
+        digits => format!("{:0<9.9}", digits).parse::<u32>().ok()?,
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 24
+        || minute > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + seconds - offset;
+    let since_epoch = Duration::new(u64::try_from(secs).ok()?, nanos);
+    UNIX_EPOCH.checked_add(since_epoch)
+}
+
+/// Convert a (year, month, day) date into days since 1970-01-01
+///
+/// The inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let day_of_year = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Convert days since 1970-01-01 into a (year, month, day) date
+///
+/// Howard Hinnant's algorithm for the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::SoapVersion;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            format_timestamp(at(1_709_251_199) + Duration::from_millis(999)),
+            "2024-02-29T23:59:59.999Z"
+        );
+        assert_eq!(
+            format_timestamp(at(1_792_324_800)),
+            "2026-10-18T12:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2026-10-18T12:00:00.000Z"),
+            Some(at(1_792_324_800))
+        );
+        assert_eq!(
+            parse_timestamp("2026-10-18T14:00:00+02:00"),
+            Some(at(1_792_324_800))
+        );
+        assert_eq!(
+            parse_timestamp("2026-10-18T12:00:00"),
+            Some(at(1_792_324_800))
+        );
+        assert_eq!(
+            parse_timestamp("1970-01-01T00:00:01.5Z"),
+            Some(UNIX_EPOCH + Duration::from_millis(1500))
+        );
+        assert_eq!(
+            parse_timestamp(&format_timestamp(at(1_000_000_000))),
+            Some(at(1_000_000_000))
+        );
+        assert_eq!(parse_timestamp("2026-13-18T12:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn test_password_digest() {
+        let nonce: Vec<u8> = (0..16).collect();
+        let security = WsSecurity::new().username_token(UsernameToken::digest("alice", "secret"));
+        let xml = security
+            .header_at(at(1_792_324_800), &nonce)
+            .to_xml(SoapVersion::Soap11);
+
+        assert!(xml.starts_with("<wsse:Security soap:mustUnderstand=\"1\" xmlns:wsse="));
+        assert!(xml.contains("<wsse:Username>alice</wsse:Username>"));
+        assert!(xml.contains("#PasswordDigest\">j0HGm5Sad+JlRaGzWRfwZ1b979U=</wsse:Password>"));
+        assert!(xml.contains(">AAECAwQFBgcICQoLDA0ODw==</wsse:Nonce>"));
+        assert!(xml.contains("<wsu:Created>2026-10-18T12:00:00.000Z</wsu:Created>"));
+        assert!(!xml.contains("secret"));
+    }
+
+    #[test]
+    fn test_password_text_and_timestamp() {
+        let security = WsSecurity::new()
+            .username_token(UsernameToken::text("bob", "p<w"))
+            .timestamp(Duration::from_secs(300))
+            .must_understand(false);
+        let xml = security
+            .header_at(at(1_792_324_800), &[0; 16])
+            .to_xml(SoapVersion::Soap12);
+
+        assert!(xml.starts_with("<wsse:Security xmlns:wsse="));
+        assert!(xml.contains(
+            "<wsu:Timestamp><wsu:Created>2026-10-18T12:00:00.000Z</wsu:Created><wsu:Expires>2026-10-18T12:05:00.000Z</wsu:Expires></wsu:Timestamp>"
+        ));
+        assert!(xml.contains("#PasswordText\">p&lt;w</wsse:Password>"));
+        assert!(!xml.contains("wsse:Nonce"));
+
+        let with_nonce = UsernameToken::text("bob", "pw").nonce(true);
+        assert!(with_nonce.to_xml("now", &[0; 16]).contains("wsse:Nonce"));
+    }
+
+    #[tokio::test]
+    async fn test_header_regenerated_per_request() {
+        let security = WsSecurity::new().username_token(UsernameToken::digest("alice", "secret"));
+        let other = SoapHeader::raw("Security", "<Security xmlns=\"urn:other\"/>");
+        let mut context = RequestContext {
+            operation: "Op".to_string(),
+            soap_action: None,
+            endpoint: "http://example.com".to_string(),
+            headers: vec![other.clone()],
+            correlation_id: None,
+        };
+
+        security.before_serialize(&mut context).await.unwrap();
+        let first = context.headers[1].to_xml(SoapVersion::Soap11);
+        security.before_serialize(&mut context).await.unwrap();
+
+        // Only the WS-Security header is replaced
+        assert_eq!(context.headers.len(), 2);
+        assert_eq!(context.headers[0], other);
+        assert_ne!(context.headers[1].to_xml(SoapVersion::Soap11), first);
+    }
+
+    #[tokio::test]
+    async fn test_header_refreshed_per_attempt() {
+        use crate::retry::RetryPolicy;
+        use crate::transport::{MemoryTransport, TransportResponse};
+        use crate::SoapClient;
+
+        #[derive(serde::Serialize)]
+        struct Ping {}
+
+        let transport = MemoryTransport::new();
+        transport.push_response(TransportResponse::new(503, "Service Unavailable"));
+        transport.push_response(TransportResponse::new(
+            200,
+            r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body><Pong/></soap:Body></soap:Envelope>"#,
+        ));
+        let client = SoapClient::builder("memory://service")
+            .transport(transport.clone())
+            .ws_security(
+                WsSecurity::new()
+                    .username_token(UsernameToken::digest("alice", "secret"))
+                    .timestamp(Duration::from_secs(300)),
+            )
+            .retry_policy(RetryPolicy::new(3).backoff(Duration::ZERO, Duration::ZERO))
+            .idempotent_operation("Ping")
+            .build();
+
+        client.call_raw("Ping", &Ping {}).await.unwrap();
+
+        let nonces: Vec<String> = transport
+            .requests()
+            .iter()
+            .map(|request| {
+                let envelope = String::from_utf8(request.body.clone()).unwrap();
+                assert_eq!(envelope.matches("<wsse:Security").count(), 1);
+                assert!(envelope.contains("soap:mustUnderstand=\"1\""));
+                let root = c14n::parse(&envelope).unwrap();
+                let token = dsig::security(&root)
+                    .unwrap()
+                    .child(WSSE_NS, "UsernameToken")
+                    .unwrap();
+                token.child(WSSE_NS, "Nonce").unwrap().text()
+            })
+            .collect();
+        assert_eq!(nonces.len(), 2);
+        assert_ne!(nonces[0], nonces[1]);
+    }
+}
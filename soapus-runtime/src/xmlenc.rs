@@ -0,0 +1,505 @@
+//! XML encryption for WS-Security
+//!
+//! Encrypts outgoing envelopes and decrypts encrypted responses following
+//! WS-Security and [XML Encryption 1.1](https://www.w3.org/TR/xmlenc-core1/):
+//!
+//! - the content of `soap:Body` (and optionally header blocks, wrapped in
+//!   `wsse11:EncryptedHeader`) is replaced by `xenc:EncryptedData`, encrypted
+//!   with AES-256-GCM under a random content key
+//! - the content key is wrapped with RSA-OAEP for the recipient certificate in
+//!   a `xenc:EncryptedKey` at the start of the `wsse:Security` header, which
+//!   lists the encrypted parts in its `xenc:ReferenceList` and identifies the
+//!   certificate by its SHA-1 thumbprint
+//!
+//! Responses are accepted with AES-128-GCM or AES-256-GCM content and content
+//! keys wrapped with `rsa-oaep-mgf1p` or `xmlenc11#rsa-oaep` (SHA-1 or SHA-256).
+//!
+//! Encryption and decryption are enabled on [`WsSecurity`](crate::WsSecurity)
+//! with [`encrypt_for`](crate::WsSecurity::encrypt_for) and
+//! [`decrypt_with`](crate::WsSecurity::decrypt_with). Requires the
+//! `ws-security` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use soapus_runtime::{SoapClient, WsSecurity, X509Certificate, X509Credential};
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let partner = X509Certificate::from_pem(&std::fs::read_to_string("partner.crt")?)?;
+//! let credential = X509Credential::from_pem(
+//!     &std::fs::read_to_string("client.crt")?,
+//!     &std::fs::read_to_string("client.key")?,
+//! )?;
+//!
+//! let client = SoapClient::builder("https://partner.example.com/soap")
+//!     .ws_security(
+//!         WsSecurity::new()
+//!             .encrypt_for(partner)
+//!             .decrypt_with(credential),
+//!     )
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::c14n::{self, Element, Namespaces, Node};
+use crate::dsig::{self, X509Certificate, X509Credential, DS_NS};
+use crate::error::{SoapError, SoapResult};
+use crate::ws_security::{BASE64_BINARY, WSSE_NS};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rsa::Oaep;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Namespace of the XML encryption elements
+pub const XENC_NS: &str = "http://www.w3.org/2001/04/xmlenc#";
+
+const XENC11_NS: &str = "http://www.w3.org/2009/xmlenc11#";
+const WSSE11_NS: &str = "http://docs.oasis-open.org/wss/oasis-wss-wssecurity-secext-1.1.xsd";
+
+const AES128_GCM: &str = "http://www.w3.org/2009/xmlenc11#aes128-gcm";
+const AES256_GCM: &str = "http://www.w3.org/2009/xmlenc11#aes256-gcm";
+const RSA_OAEP_MGF1P: &str = "http://www.w3.org/2001/04/xmlenc#rsa-oaep-mgf1p";
+const RSA_OAEP: &str = "http://www.w3.org/2009/xmlenc11#rsa-oaep";
+const MGF1_SHA256: &str = "http://www.w3.org/2009/xmlenc11#mgf1sha256";
+const DIGEST_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+const DIGEST_SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+const TYPE_CONTENT: &str = "http://www.w3.org/2001/04/xmlenc#Content";
+const TYPE_ELEMENT: &str = "http://www.w3.org/2001/04/xmlenc#Element";
+const THUMBPRINT_SHA1: &str =
+    "http://docs.oasis-open.org/wss/oasis-wss-soap-message-security-1.1#ThumbprintSHA1";
+
+/// Length of the AES-GCM initialization vector prepended to the cipher text
+const GCM_IV_LENGTH: usize = 12;
+
+/// Encrypt the `Body` content and the named header blocks of an envelope
+///
+/// The envelope must contain a `wsse:Security` header, which receives the
+/// `xenc:EncryptedKey`. Ids are derived from `id_suffix`.
+pub(crate) fn encrypt_envelope(
+    envelope: &str,
+    recipient: &X509Certificate,
+    header_names: &[String],
+    id_suffix: &str,
+) -> SoapResult<String> {
+    let root = c14n::parse(envelope)?;
+    let root_scope = root.scope(&Namespaces::new());
+    let security = dsig::security(&root)?;
+
+    let mut content_key = [0u8; 32];
+    getrandom::getrandom(&mut content_key).map_err(dsig::security_error)?;
+
+    // Replacements of byte ranges, applied back to front
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let mut references = Vec::new();
+
+    if let Some(header) = root
+        .elements()
+        .find(|e| e.local_name == "Header" && e.namespace == root.namespace)
+    {
+        let header_scope = header.scope(&root_scope);
+        for block in header.elements() {
+            if std::ptr::eq(block, security) || !header_names.contains(&block.local_name) {
+                continue;
+            }
+
+            let id = format!("EH-{}-{}", references.len() + 1, id_suffix);
+            let plaintext = block.canonicalize(&header_scope, &[]);
+            let data = encrypted_data(&id, TYPE_ELEMENT, &content_key, &plaintext)?;
+
+            // mustUnderstand and actor/role move to the wrapper
+            let mut attributes = String::new();
+            if !root.prefix.is_empty() {
+                for attribute in &block.attributes {
+                    if attribute.namespace == root.namespace {
+                        attributes.push_str(&format!(
+                            " {}:{}=\"{}\"",
+                            root.prefix,
+                            attribute.local_name,
+                            quick_xml::escape::escape(attribute.value.as_str())
+                        ));
+                    }
+                }
+            }
+            edits.push((
+                block.start,
+                block.outer_end,
+                format!(
+                    "<wsse11:EncryptedHeader xmlns:wsse11=\"{}\"{}>{}</wsse11:EncryptedHeader>",
+                    WSSE11_NS, attributes, data
+                ),
+            ));
+            references.push(id);
+        }
+    }
+
+    let (body, body_scope) = root
+        .find(&Namespaces::new(), &|e| {
+            e.local_name == "Body" && e.namespace == root.namespace
+        })
+        .ok_or_else(|| SoapError::SecurityError("Missing Body".to_string()))?;
+    let body_scope = body.scope(&body_scope);
+    let mut plaintext = String::new();
+    for node in &body.children {
+        match node {
+            Node::Element(element) => plaintext.push_str(&element.canonicalize(&body_scope, &[])),
+            Node::Text(text) => plaintext.push_str(&c14n::escape_text(text)),
+            Node::ProcessingInstruction(_) => {}
+        }
+    }
+    let id = format!("ED-{}", id_suffix);
+    edits.push((
+        body.content_start,
+        body.end,
+        encrypted_data(&id, TYPE_CONTENT, &content_key, &plaintext)?,
+    ));
+    references.push(id);
+
+    let mut rng = rsa::rand_core::OsRng;
+    let wrapped_key = recipient
+        .public_key
+        .encrypt(&mut rng, Oaep::new::<Sha1>(), &content_key)
+        .map_err(dsig::security_error)?;
+    let reference_list: String = references
+        .iter()
+        .map(|id| format!("<xenc:DataReference URI=\"#{}\"/>", id))
+        .collect();
+    edits.push((
+        security.content_start,
+        security.content_start,
+        format!(
+            "<xenc:EncryptedKey xmlns:xenc=\"{}\" Id=\"EK-{}\"><xenc:EncryptionMethod Algorithm=\"{}\"><ds:DigestMethod xmlns:ds=\"{}\" Algorithm=\"{}\"/></xenc:EncryptionMethod><ds:KeyInfo xmlns:ds=\"{}\"><wsse:SecurityTokenReference><wsse:KeyIdentifier EncodingType=\"{}\" ValueType=\"{}\">{}</wsse:KeyIdentifier></wsse:SecurityTokenReference></ds:KeyInfo><xenc:CipherData><xenc:CipherValue>{}</xenc:CipherValue></xenc:CipherData><xenc:ReferenceList>{}</xenc:ReferenceList></xenc:EncryptedKey>",
+            XENC_NS,
+            id_suffix,
+            RSA_OAEP_MGF1P,
+            DS_NS,
+            DIGEST_SHA1,
+            DS_NS,
+            BASE64_BINARY,
+            THUMBPRINT_SHA1,
+            BASE64.encode(Sha1::digest(recipient.der())),
+            BASE64.encode(wrapped_key),
+            reference_list
+        ),
+    ));
+
+    Ok(apply(envelope, edits))
+}
+
+/// Decrypt all parts of an envelope encrypted for `credential`
+///
+/// Returns `Ok(None)` if the envelope contains no encrypted data.
+pub(crate) fn decrypt_envelope(
+    envelope: &str,
+    credential: &X509Credential,
+) -> SoapResult<Option<String>> {
+    let root = c14n::parse(envelope)?;
+    let encrypted_keys: Vec<&Element> = dsig::security(&root)
+        .map(|security| {
+            security
+                .elements()
+                .filter(|e| e.is(XENC_NS, "EncryptedKey"))
+                .collect()
+        })
+        .unwrap_or_default();
+    if encrypted_keys.is_empty() {
+        if root.count(&|e| e.is(XENC_NS, "EncryptedData")) > 0 {
+            return Err(decryption_error(
+                "the message has encrypted data but no EncryptedKey",
+            ));
+        }
+        return Ok(None);
+    }
+
+    let mut edits = Vec::new();
+    for encrypted_key in encrypted_keys {
+        let content_key = unwrap_key(encrypted_key, credential)?;
+        let references = encrypted_key
+            .child(XENC_NS, "ReferenceList")
+            .ok_or_else(|| decryption_error("EncryptedKey has no ReferenceList"))?;
+
+        for reference in references
+            .elements()
+            .filter(|e| e.is(XENC_NS, "DataReference"))
+        {
+            let uri = reference.attribute("", "URI").unwrap_or("");
+            let id = uri.trim_start_matches('#');
+            if root.count(&|e| e.id() == Some(id)) != 1 {
+                return Err(decryption_error(&format!(
+                    "reference '{}' is missing or not unique",
+                    uri
+                )));
+            }
+            let (data, _) = root
+                .find(&Namespaces::new(), &|e| {
+                    e.is(XENC_NS, "EncryptedData") && e.id() == Some(id)
+                })
+                .ok_or_else(|| {
+                    decryption_error(&format!("'{}' is not an EncryptedData element", uri))
+                })?;
+            let plaintext = decrypt_data(data, &content_key)
+                .map_err(|e| decryption_error(&format!("'{}': {}", uri, e)))?;
+
+            // An encrypted header block replaces its EncryptedHeader wrapper
+            let wrapper = root.find(&Namespaces::new(), &|e| {
+                e.is(WSSE11_NS, "EncryptedHeader")
+                    && e.elements().any(|child| std::ptr::eq(child, data))
+            });
+            let (start, end) = match wrapper {
+                Some((wrapper, _)) => (wrapper.start, wrapper.outer_end),
+                None => (data.start, data.outer_end),
+            };
+            edits.push((start, end, plaintext));
+        }
+        edits.push((encrypted_key.start, encrypted_key.outer_end, String::new()));
+    }
+
+    let decrypted = apply(envelope, edits);
+    if c14n::parse(&decrypted)?.count(&|e| e.is(XENC_NS, "EncryptedData")) > 0 {
+        return Err(decryption_error(
+            "encrypted data is not referenced by an EncryptedKey",
+        ));
+    }
+    Ok(Some(decrypted))
+}
+
+/// Build an `xenc:EncryptedData` element with AES-256-GCM
+fn encrypted_data(id: &str, data_type: &str, key: &[u8], plaintext: &str) -> SoapResult<String> {
+    let mut iv = [0u8; GCM_IV_LENGTH];
+    getrandom::getrandom(&mut iv).map_err(dsig::security_error)?;
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(dsig::security_error)?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&iv), plaintext.as_bytes())
+        .map_err(dsig::security_error)?;
+
+    let mut value = iv.to_vec();
+    value.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "<xenc:EncryptedData xmlns:xenc=\"{}\" Id=\"{}\" Type=\"{}\"><xenc:EncryptionMethod Algorithm=\"{}\"/><xenc:CipherData><xenc:CipherValue>{}</xenc:CipherValue></xenc:CipherData></xenc:EncryptedData>",
+        XENC_NS,
+        id,
+        data_type,
+        AES256_GCM,
+        BASE64.encode(value)
+    ))
+}
+
+/// Decrypt the content key of an `xenc:EncryptedKey` with the private key
+fn unwrap_key(encrypted_key: &Element, credential: &X509Credential) -> SoapResult<Vec<u8>> {
+    // A key for another certificate can't be unwrapped, say so instead of failing in RSA
+    let thumbprint = encrypted_key
+        .child(DS_NS, "KeyInfo")
+        .and_then(|e| e.child(WSSE_NS, "SecurityTokenReference"))
+        .and_then(|e| e.child(WSSE_NS, "KeyIdentifier"))
+        .filter(|e| e.attribute("", "ValueType") == Some(THUMBPRINT_SHA1));
+    if let Some(thumbprint) = thumbprint {
+        let expected = Sha1::digest(credential.certificate().der());
+        if dsig::decode_base64(&thumbprint.text())? != expected.as_slice() {
+            return Err(decryption_error(
+                "the content key is encrypted for a different certificate",
+            ));
+        }
+    }
+
+    let method = encrypted_key.child(XENC_NS, "EncryptionMethod");
+    let algorithm = method
+        .and_then(|e| e.attribute("", "Algorithm"))
+        .unwrap_or("");
+    let digest = method
+        .and_then(|e| e.child(DS_NS, "DigestMethod"))
+        .and_then(|e| e.attribute("", "Algorithm"))
+        .unwrap_or(DIGEST_SHA1);
+    let mgf_sha256 = method
+        .and_then(|e| e.child(XENC11_NS, "MGF"))
+        .and_then(|e| e.attribute("", "Algorithm"))
+        == Some(MGF1_SHA256);
+
+    let padding = match (algorithm, digest, mgf_sha256) {
+        (RSA_OAEP_MGF1P | RSA_OAEP, DIGEST_SHA1, false) => Oaep::new::<Sha1>(),
+        (RSA_OAEP_MGF1P | RSA_OAEP, DIGEST_SHA256, false) => {
+            Oaep::new_with_mgf_hash::<Sha256, Sha1>()
+        }
+        (RSA_OAEP, DIGEST_SHA1, true) => Oaep::new_with_mgf_hash::<Sha1, Sha256>(),
+        (RSA_OAEP, DIGEST_SHA256, true) => Oaep::new::<Sha256>(),
+        _ => {
+            return Err(decryption_error(&format!(
+                "unsupported key transport '{}'",
+                algorithm
+            )))
+        }
+    };
+
+    let wrapped = encrypted_key
+        .child(XENC_NS, "CipherData")
+        .and_then(|e| e.child(XENC_NS, "CipherValue"))
+        .ok_or_else(|| decryption_error("EncryptedKey has no CipherValue"))?;
+    credential
+        .private_key
+        .decrypt(padding, &dsig::decode_base64(&wrapped.text())?)
+        .map_err(|e| decryption_error(&format!("cannot decrypt the content key: {}", e)))
+}
+
+/// Decrypt an `xenc:EncryptedData` element with AES-GCM
+fn decrypt_data(data: &Element, key: &[u8]) -> Result<String, String> {
+    let algorithm = data
+        .child(XENC_NS, "EncryptionMethod")
+        .and_then(|e| e.attribute("", "Algorithm"))
+        .unwrap_or("");
+    let value = data
+        .child(XENC_NS, "CipherData")
+        .and_then(|e| e.child(XENC_NS, "CipherValue"))
+        .ok_or("no CipherValue")?;
+    let value = dsig::decode_base64(&value.text()).map_err(|e| e.to_string())?;
+    if value.len() < GCM_IV_LENGTH {
+        return Err("cipher value too short".to_string());
+    }
+    let (iv, ciphertext) = value.split_at(GCM_IV_LENGTH);
+    let nonce = Nonce::from_slice(iv);
+
+    let plaintext = match algorithm {
+        AES128_GCM => Aes128Gcm::new_from_slice(key)
+            .map_err(|_| "content key is not an AES-128 key".to_string())?
+            .decrypt(nonce, ciphertext),
+        AES256_GCM => Aes256Gcm::new_from_slice(key)
+            .map_err(|_| "content key is not an AES-256 key".to_string())?
+            .decrypt(nonce, ciphertext),
+        other => return Err(format!("unsupported encryption method '{}'", other)),
+    }
+    .map_err(|_| "authentication of the cipher text failed".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|_| "decrypted data is not UTF-8".to_string())
+}
+
+/// Replace byte ranges, which must not overlap
+fn apply(xml: &str, mut edits: Vec<(usize, usize, String)>) -> String {
+    let mut xml = xml.to_string();
+    edits.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+    for (start, end, replacement) in edits {
+        xml.replace_range(start..end, &replacement);
+    }
+    xml
+}
+
+fn decryption_error(message: &str) -> SoapError {
+    SoapError::SecurityError(format!("Decryption failed: {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsig::tests::{credential, other_credential, verify};
+    use crate::envelope::{SoapEnvelope, SoapVersion};
+    use crate::header::SoapHeader;
+    use crate::ws_security::WsSecurity;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct GetSalary {
+        #[serde(rename = "EmployeeId")]
+        employee_id: String,
+    }
+
+    fn envelope() -> String {
+        SoapEnvelope::build_with_soap_headers(
+            &[
+                WsSecurity::new().header().unwrap(),
+                SoapHeader::raw(
+                    "Session",
+                    "<s:Session xmlns:s=\"urn:session\">abc</s:Session>",
+                )
+                .must_understand(true),
+            ],
+            &GetSalary {
+                employee_id: "E-1".to_string(),
+            },
+            SoapVersion::Soap11,
+            Some("http://example.com/payroll"),
+            true,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let credential = credential();
+        let plain = envelope();
+        let encrypted = encrypt_envelope(
+            &plain,
+            credential.certificate(),
+            &["Session".to_string()],
+            "1",
+        )
+        .unwrap();
+
+        assert!(!encrypted.contains("E-1"));
+        assert!(!encrypted.contains("abc"));
+        assert!(encrypted
+            .contains("<xenc:DataReference URI=\"#EH-1-1\"/><xenc:DataReference URI=\"#ED-1\"/>"));
+        assert!(encrypted.contains("<wsse11:EncryptedHeader xmlns:wsse11=\"http://docs.oasis-open.org/wss/oasis-wss-wssecurity-secext-1.1.xsd\" soap:mustUnderstand=\"1\">"));
+
+        let decrypted = decrypt_envelope(&encrypted, &credential).unwrap().unwrap();
+        assert!(!decrypted.contains("xenc:"));
+        assert!(decrypted.contains("abc</s:Session>"));
+        assert_eq!(
+            c14n::canonicalize(&decrypted, &[]).unwrap(),
+            c14n::canonicalize(&plain, &[]).unwrap()
+        );
+
+        assert!(decrypt_envelope(&plain, &credential).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decryption_errors() {
+        let credential = credential();
+        let encrypted = encrypt_envelope(&envelope(), credential.certificate(), &[], "1").unwrap();
+
+        let error = decrypt_envelope(&encrypted, &other_credential()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("encrypted for a different certificate"));
+
+        // Flip a character of the body cipher text
+        let start = encrypted.rfind("<xenc:CipherValue>").unwrap() + 40;
+        let mut tampered = encrypted.clone();
+        let flipped = if &tampered[start..start + 1] == "A" {
+            "B"
+        } else {
+            "A"
+        };
+        tampered.replace_range(start..start + 1, flipped);
+        let error = decrypt_envelope(&tampered, &credential).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "WS-Security error: Decryption failed: '#ED-1': authentication of the cipher text failed"
+        );
+
+        let unreferenced = encrypted.replace("<xenc:DataReference URI=\"#ED-1\"/>", "");
+        let error = decrypt_envelope(&unreferenced, &credential).unwrap_err();
+        assert!(error.to_string().contains("not referenced"));
+    }
+
+    #[test]
+    fn test_signed_then_encrypted() {
+        let credential = credential();
+        let security = WsSecurity::new().sign_with(credential.clone());
+        let envelope = SoapEnvelope::build_with_soap_headers(
+            &[security.header().unwrap()],
+            &GetSalary {
+                employee_id: "E-1".to_string(),
+            },
+            SoapVersion::Soap12,
+            None,
+            false,
+        )
+        .unwrap();
+        let signed = dsig::sign_envelope(&envelope, &credential, "id-body").unwrap();
+        let encrypted = encrypt_envelope(&signed, credential.certificate(), &[], "1").unwrap();
+
+        assert!(verify(&encrypted, &[credential.certificate().clone()]).is_err());
+        let decrypted = decrypt_envelope(&encrypted, &credential).unwrap().unwrap();
+        verify(&decrypted, &[credential.certificate().clone()]).unwrap();
+    }
+}